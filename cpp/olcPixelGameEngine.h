@@ -491,6 +491,17 @@ namespace olc
   // | Auxilliary components internal to engine                                     |
   // O------------------------------------------------------------------------------O
 
+  // How decals are blended with what is already drawn
+  enum class DecalMode
+  {
+    NORMAL,
+    ADDITIVE,
+    MULTIPLICATIVE,
+    STENCIL,
+    ILLUMINATE,
+    WIREFRAME,
+  };
+
   struct DecalInstance
   {
     olc::Decal* decal = nullptr;
@@ -498,6 +509,7 @@ namespace olc
     olc::vf2d uv[4] = {{ 0.0f, 0.0f}, {0.0f, 1.0f}, {1.0f, 1.0f}, {1.0f, 0.0f}};
     float w[4] = { 1, 1, 1, 1 };
     olc::Pixel tint;
+    olc::DecalMode mode = olc::DecalMode::NORMAL;
   };
 
   struct LayerDesc
@@ -666,6 +678,9 @@ namespace olc
     // selected area is (ox,oy) to (ox+w,oy+h)
     void DrawPartialSprite(int32_t x, int32_t y, Sprite *sprite, int32_t ox, int32_t oy, int32_t w, int32_t h, uint32_t scale = 1, uint8_t flip = olc::Sprite::NONE);
     void DrawPartialSprite(const olc::vi2d& pos, Sprite *sprite, const olc::vi2d& sourcepos, const olc::vi2d& size, uint32_t scale = 1, uint8_t flip = olc::Sprite::NONE);
+    // Changes the blend mode of decals drawn after this call
+    void SetDecalMode(const olc::DecalMode& mode);
+    olc::DecalMode GetDecalMode();
     // Draws a whole decal, with optional scale and tinting
     void DrawDecal(const olc::vf2d& pos, olc::Decal *decal, const olc::vf2d& scale = { 1.0f,1.0f }, const olc::Pixel& tint = olc::WHITE);
     // Draws a region of a decal, with optional scale and tinting
@@ -700,6 +715,7 @@ namespace olc
   private: // Inner mysterious workings
     Sprite*     pDrawTarget           = nullptr;
     Pixel::Mode  nPixelMode            = Pixel::NORMAL;
+    olc::DecalMode nDecalMode          = olc::DecalMode::NORMAL;
     float    fBlendFactor          = 1.0f;
     olc::vi2d  vScreenSize           = { 256, 240 };
     olc::vf2d  vInvScreenSize        = { 1.0f / 256.0f, 1.0f / 240.0f };
@@ -1526,7 +1542,7 @@ namespace olc
     int x0 = 0;
     int y0 = radius;
     int d = 3 - 2 * radius;
-    if (!radius) return;
+    if (!radius) { Draw(x, y, p); return; }
 
     auto drawline = [&](int sx, int ex, int ny)
     {
@@ -1828,7 +1844,7 @@ namespace olc
       vScreenSpacePos.y - (2.0f * source_size.y * vInvScreenSize.y) * scale.y
     };
 
-    DecalInstance di; di.decal = decal;  di.tint = tint;
+    DecalInstance di; di.decal = decal;  di.tint = tint; di.mode = nDecalMode;
 
     di.pos[0] = { vScreenSpacePos.x, vScreenSpacePos.y };
     di.pos[1] = { vScreenSpacePos.x, vScreenSpaceDim.y };
@@ -1842,6 +1858,12 @@ namespace olc
     vLayers[nTargetLayer].vecDecalInstance.push_back(di);
   }
 
+  void PixelGameEngine::SetDecalMode(const olc::DecalMode& mode)
+  { nDecalMode = mode; }
+
+  olc::DecalMode PixelGameEngine::GetDecalMode()
+  { return nDecalMode; }
+
   void PixelGameEngine::DrawDecal(const olc::vf2d& pos, olc::Decal *decal, const olc::vf2d& scale, const olc::Pixel& tint)
   {
     olc::vf2d vScreenSpacePos =
@@ -1858,7 +1880,7 @@ namespace olc
 
     DecalInstance di;
     di.decal = decal;
-    di.tint = tint;
+    di.tint = tint; di.mode = nDecalMode;
     di.pos[0] = { vScreenSpacePos.x, vScreenSpacePos.y };
     di.pos[1] = { vScreenSpacePos.x, vScreenSpaceDim.y };
     di.pos[2] = { vScreenSpaceDim.x, vScreenSpaceDim.y };
@@ -1870,7 +1892,7 @@ namespace olc
   {
     DecalInstance di;
     di.decal = decal;
-    di.tint = tint;
+    di.tint = tint; di.mode = nDecalMode;
     di.pos[0] = (olc::vf2d(0.0f, 0.0f) - center) * scale;
     di.pos[1] = (olc::vf2d(0.0f, float(decal->sprite->height)) - center) * scale;
     di.pos[2] = (olc::vf2d(float(decal->sprite->width), float(decal->sprite->height)) - center) * scale;
@@ -1889,7 +1911,7 @@ namespace olc
   {
     DecalInstance di;
     di.decal = decal;
-    di.tint = tint;
+    di.tint = tint; di.mode = nDecalMode;
     di.pos[0] = (olc::vf2d(0.0f, 0.0f) - center) * scale;
     di.pos[1] = (olc::vf2d(0.0f, source_size.y) - center) * scale;
     di.pos[2] = (olc::vf2d(source_size.x, source_size.y) - center) * scale;
@@ -1914,7 +1936,7 @@ namespace olc
   {
     DecalInstance di;
     di.decal = decal;
-    di.tint = tint;
+    di.tint = tint; di.mode = nDecalMode;
     olc::vf2d center;
     float rd = ((pos[2].x - pos[0].x) * (pos[3].y - pos[1].y) - (pos[3].x - pos[1].x) * (pos[2].y - pos[0].y));
     if (rd != 0)
@@ -1945,7 +1967,7 @@ namespace olc
     // http://www.reedbeta.com/blog/quadrilateral-interpolation-part-1/
     DecalInstance di;
     di.decal = decal;
-    di.tint = tint;
+    di.tint = tint; di.mode = nDecalMode;
     olc::vf2d center;
     float rd = ((pos[2].x - pos[0].x) * (pos[3].y - pos[1].y) - (pos[3].x - pos[1].x) * (pos[2].y - pos[0].y));
     if (rd != 0)
@@ -2378,6 +2400,32 @@ namespace olc
     X11::Window*         olc_Window = nullptr;
     X11::XVisualInfo*            olc_VisualInfo = nullptr;
   #endif
+    olc::DecalMode nDecalMode = olc::DecalMode::NORMAL;
+
+    void SetDecalMode(const olc::DecalMode& mode)
+    {
+      if (mode == nDecalMode) return;
+      switch (mode)
+      {
+      case olc::DecalMode::NORMAL:
+      case olc::DecalMode::WIREFRAME:
+        glBlendFunc(GL_SRC_ALPHA, GL_ONE_MINUS_SRC_ALPHA);
+        break;
+      case olc::DecalMode::ADDITIVE:
+        glBlendFunc(GL_SRC_ALPHA, GL_ONE);
+        break;
+      case olc::DecalMode::MULTIPLICATIVE:
+        glBlendFunc(GL_DST_COLOR, GL_ONE_MINUS_SRC_ALPHA);
+        break;
+      case olc::DecalMode::STENCIL:
+        glBlendFunc(GL_ZERO, GL_SRC_ALPHA);
+        break;
+      case olc::DecalMode::ILLUMINATE:
+        glBlendFunc(GL_ONE_MINUS_SRC_ALPHA, GL_SRC_ALPHA);
+        break;
+      }
+      nDecalMode = mode;
+    }
 
   public:
     void PrepareDevice() override
@@ -2470,10 +2518,12 @@ namespace olc
     {
       glEnable(GL_BLEND);
       glBlendFunc(GL_SRC_ALPHA, GL_ONE_MINUS_SRC_ALPHA);
+      nDecalMode = olc::DecalMode::NORMAL;
     }
 
     void DrawLayerQuad(const olc::vf2d& offset, const olc::vf2d& scale, const olc::Pixel tint) override
     {
+      SetDecalMode(olc::DecalMode::NORMAL);
       glBegin(GL_QUADS);
       glColor4ub(tint.r, tint.g, tint.b, tint.a);
       glTexCoord2f(0.0f * scale.x + offset.x, 1.0f * scale.y + offset.y);
@@ -2489,8 +2539,18 @@ namespace olc
 
     void DrawDecalQuad(const olc::DecalInstance& decal) override
     {
-      glBindTexture(GL_TEXTURE_2D, decal.decal->id);
-      glBegin(GL_QUADS);
+      SetDecalMode(decal.mode);
+      // Wireframes are drawn in the tint colour, without the texture
+      if (decal.mode == olc::DecalMode::WIREFRAME)
+      {
+        glBindTexture(GL_TEXTURE_2D, 0);
+        glBegin(GL_LINE_LOOP);
+      }
+      else
+      {
+        glBindTexture(GL_TEXTURE_2D, decal.decal->id);
+        glBegin(GL_QUADS);
+      }
       glColor4ub(decal.tint.r, decal.tint.g, decal.tint.b, decal.tint.a);
       glTexCoord4f(decal.uv[0].x, decal.uv[0].y, 0.0f, decal.w[0]); glVertex2f(decal.pos[0].x, decal.pos[0].y);
       glTexCoord4f(decal.uv[1].x, decal.uv[1].y, 0.0f, decal.w[1]); glVertex2f(decal.pos[1].x, decal.pos[1].y);
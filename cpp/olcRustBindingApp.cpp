@@ -64,8 +64,16 @@ RCode start(const char* name, void* binding, int32_t screen_w, int32_t screen_h,
 
 Sprite SpriteNullConstructor() { return TO_SPRITE(new olc::Sprite()); }
 Sprite SpriteConstructor(int32_t w, int32_t h) { return TO_SPRITE(new olc::Sprite(w, h)); }
-void SpriteDestructor(Sprite* s) { delete s->olc_sprite; }
+void SpriteDestructor(Sprite* s) {
+  // Never leave the engine drawing into a freed sprite
+  if (app.GetDrawTarget() == TO_OLC_SPRITE(s)) {
+    Sprite primary = { nullptr };
+    SetDrawTargetSprite(&primary);
+  }
+  delete s->olc_sprite;
+}
 RCode SpriteLoadFromFile(Sprite* s, const char* image_file) { return TO_RCODE(TO_OLC_SPRITE(s)->LoadFromFile(image_file)); }
+RCode SpriteSaveToPGESprFile(Sprite* s, const char* image_file) { return TO_RCODE(TO_OLC_SPRITE(s)->SaveToPGESprFile(image_file)); }
 int32_t SpriteWidth(Sprite* s) { return TO_OLC_SPRITE(s)->width; }
 int32_t SpriteHeight(Sprite* s) { return TO_OLC_SPRITE(s)->height; }
 bool SpriteHasData(Sprite* s) { return TO_OLC_SPRITE(s)->pColData != nullptr; }
@@ -75,10 +83,15 @@ Pixel SpriteGetPixel(Sprite* s, int32_t x, int32_t y) { return TO_PIXEL(TO_OLC_S
 bool  SpriteSetPixel(Sprite* s, int32_t x, int32_t y, Pixel p) { return TO_OLC_SPRITE(s)->SetPixel(x, y, TO_OLC_PIXEL(p)); }
 Pixel SpriteSample(Sprite* s, float x, float y) { return TO_PIXEL(TO_OLC_SPRITE(s)->Sample(x, y)); }
 Pixel SpriteSampleBL(Sprite* s, float u, float v) { return TO_PIXEL(TO_OLC_SPRITE(s)->SampleBL(u, v)); }
+Pixel* SpriteGetData(Sprite* s) { return reinterpret_cast<Pixel*>(TO_OLC_SPRITE(s)->GetData()); }
 
 Decal DecalConstructor(Sprite* s) { return TO_DECAL(new olc::Decal(TO_OLC_SPRITE(s))); }
 int32_t DecalId(Decal* d) { return TO_OLC_DECAL(d)->id; }
 Vf2d DecalScale(Decal* d) { return TO_VF2D(TO_OLC_DECAL(d)->vUVScale); }
+void DecalUpdate(Decal* d, Sprite* s) {
+  TO_OLC_DECAL(d)->sprite = TO_OLC_SPRITE(s);
+  TO_OLC_DECAL(d)->Update();
+}
 void DecalDestructor(Decal* d) { delete d->olc_decal; }
 
 bool IsFocused() { return app.IsFocused(); }
@@ -92,20 +105,30 @@ int32_t ScreenWidth() { return app.ScreenWidth(); }
 int32_t ScreenHeight() { return app.ScreenHeight(); }
 int32_t GetDrawTargetWidth() { return app.GetDrawTargetWidth(); }
 int32_t GetDrawTargetHeight() { return app.GetDrawTargetHeight(); }
+Sprite GetDrawTargetSprite() { return TO_SPRITE(app.GetDrawTarget()); }
 void SetScreenSize(int w, int h) { app.SetScreenSize(w, h); }
+void SetDrawTargetSprite(Sprite* target) {
+  // Stands in for the primary screen before the engine has created its layers,
+  // drawing onto an empty sprite is a no-op.
+  static olc::Sprite noTarget;
+  if (TO_OLC_SPRITE(target) || !app.GetLayers().empty()) {
+    app.SetDrawTarget(TO_OLC_SPRITE(target));
+  } else {
+    app.SetDrawTarget(&noTarget);
+  }
+}
 uint32_t GetFPS() { return app.GetFPS(); }
 
 void SetDrawTarget(uint8_t layer) { app.SetDrawTarget(layer); }
 void SetPrimaryDrawTarget() { app.SetDrawTarget(nullptr); }
-LayerDesc GetDrawTarget(uint8_t layer) {
-  if (layer < app.GetLayers().size()) {
-    return TO_LAYER_DESC(layer, &app.GetLayers()[layer]);
-  } else {
-    LayerDesc l;
-    return l;
+bool GetDrawTarget(uint8_t layer, LayerDesc* desc) {
+  if (layer >= app.GetLayers().size()) {
+    return false;
   }
+  *desc = TO_LAYER_DESC(layer, &app.GetLayers()[layer]);
+  return true;
 }
-LayerDesc GetPrimaryDrawTarget() { return GetDrawTarget(0); }
+bool GetPrimaryDrawTarget(LayerDesc* desc) { return GetDrawTarget(0, desc); }
 void EnableLayer(uint8_t layer, bool b) { app.EnableLayer(layer, b); }
 void SetLayerOffset(uint8_t layer, float x, float y) { app.SetLayerOffset(layer, x, y); }
 void SetLayerScale(uint8_t layer, float x, float y) { app.SetLayerScale(layer, x, y); }
@@ -196,6 +219,9 @@ void DrawStringDecal(const Vf2d& pos, const char* sText, const Pixel col, const
   app.DrawStringDecal(TO_OLC_VF2D(pos), sText, TO_OLC_PIXEL(col), TO_OLC_VF2D(scale));
 }
 
+void SetDecalMode(DecalMode m) { app.SetDecalMode(m); }
+DecalMode GetDecalMode() { return app.GetDecalMode(); }
+
 void DrawString(int32_t x, int32_t y, const char* sText, Pixel col, uint32_t scale) {
   app.DrawString(x, y, sText, TO_OLC_PIXEL(col), scale);
 }
@@ -36,6 +36,8 @@ typedef struct {
 
 typedef olc::Pixel::Mode PixelMode;
 
+typedef olc::DecalMode DecalMode;
+
 typedef struct {
   // Set once during the frame the event occurs
   bool pressed;
@@ -159,10 +161,13 @@ RCode start(const char* name, void* binding, int32_t screen_w, int32_t screen_h,
 Sprite SpriteNullConstructor();
 // Creates a new sprite with dimensions
 Sprite SpriteConstructor(int32_t w, int32_t h);
-// Calls destructor on the underlying olc sprite
+// Calls destructor on the underlying olc sprite, the primary screen becomes the draw target
+// if the sprite was the draw target
 void SpriteDestructor(Sprite* s);
 // Loads image into the sprite
 RCode SpriteLoadFromFile(Sprite* s, const char* image_file);
+// Saves the sprite into PGE .spr file
+RCode SpriteSaveToPGESprFile(Sprite* s, const char* image_file);
 // Returns sprite width
 int32_t SpriteWidth(Sprite* s);
 // Returns sprite height
@@ -181,6 +186,8 @@ bool  SpriteSetPixel(Sprite* s, int32_t x, int32_t y, Pixel p);
 Pixel SpriteSample(Sprite* s, float x, float y);
 // Sprite sample BL for (u, v)
 Pixel SpriteSampleBL(Sprite* s, float u, float v);
+// Returns pointer to sprite pixel data, width * height pixels in row-major order
+Pixel* SpriteGetData(Sprite* s);
 
 // Decal constructor
 Decal DecalConstructor(Sprite* s);
@@ -188,6 +195,8 @@ Decal DecalConstructor(Sprite* s);
 int32_t DecalId(Decal* d);
 // Returns (u, v) scale of the decal
 Vf2d DecalScale(Decal* d);
+// Points the decal at the sprite and uploads the sprite pixels
+void DecalUpdate(Decal* d, Sprite* s);
 // Decal destructor
 void DecalDestructor(Decal* d);
 
@@ -212,20 +221,21 @@ int32_t ScreenHeight();
 int32_t GetDrawTargetWidth();
 // Returns the height of the currently selected drawing target in "pixels"
 int32_t GetDrawTargetHeight();
-// Returns the currently active draw target
-// TODO olc::Sprite* GetDrawTarget();
+// Returns the currently active draw target, the sprite is not owned by the caller
+Sprite GetDrawTargetSprite();
 // Resize the primary screen sprite
 void SetScreenSize(int w, int h);
 // Specify which Sprite should be the target of drawing functions, use nullptr
 // to specify the primary screen
-// TODO void SetDrawTarget(Sprite *target);
+void SetDrawTargetSprite(Sprite* target);
 // Gets the current Frames Per Second
 uint32_t GetFPS();
 
 void SetDrawTarget(uint8_t layer);
 void SetPrimaryDrawTarget();
-LayerDesc GetDrawTarget(uint8_t layer);
-LayerDesc GetPrimaryDrawTarget();
+// Writes the layer description into desc, returns false if there is no such layer
+bool GetDrawTarget(uint8_t layer, LayerDesc* desc);
+bool GetPrimaryDrawTarget(LayerDesc* desc);
 void EnableLayer(uint8_t layer, bool b);
 void SetLayerOffset(uint8_t layer, float x, float y);
 void SetLayerScale(uint8_t layer, float x, float y);
@@ -280,6 +290,9 @@ void DrawRotatedDecal(const Vf2d& pos, Decal* decal, const float angle, const Vf
 void DrawPartialRotatedDecal(const Vf2d& pos, Decal* decal, const float angle, const Vf2d& center, const Vf2d& source_pos, const Vf2d& source_size, const Vf2d& scale, const Pixel& tint);
 
 void DrawStringDecal(const Vf2d& pos, const char* sText, const Pixel col, const Vf2d& scale);
+// Changes the blend mode of decals drawn after this call
+void SetDecalMode(DecalMode m);
+DecalMode GetDecalMode();
 void DrawString(int32_t x, int32_t y, const char* sText, Pixel col, uint32_t scale);
 // Clears entire draw target to Pixel
 void Clear(Pixel p);
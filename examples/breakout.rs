@@ -65,7 +65,7 @@ impl Breakout {
     self.ball_x = ball_coords.x as f32;
     self.ball_y = ball_coords.y as f32;
 
-    let angle: f32 = START_ANGLES[olc::c_rand() as usize % START_ANGLES.len()];
+    let angle: f32 = START_ANGLES[olc::random::rand() as usize % START_ANGLES.len()];
     self.ball_dx = angle.cos();
     self.ball_dy = angle.sin();
 
@@ -1,6 +1,7 @@
 extern crate olc_pixel_game_engine;
 
 use crate::olc_pixel_game_engine as olc;
+use olc::sprite_sheet::SpriteSheet;
 
 // Tile size is 32x32 in dungeon.png.
 const TILE_SIZE: olc::Vi2d = olc::Vi2d { x: 32, y: 32 };
@@ -15,7 +16,8 @@ const EAST: usize = 5;
 #[derive(Clone, Debug, Default)]
 struct Cell {
   wall: bool,
-  id: [olc::Vi2d; 6]
+  // Column and row of the tile of each face in the sprite sheet.
+  id: [(usize, usize); 6]
 }
 
 struct World {
@@ -51,13 +53,13 @@ struct Vec3d {
 #[derive(Clone, Debug)]
 struct Quad {
   points: [Vec3d; 4],
-  tile: olc::Vi2d
+  tile: (usize, usize)
 }
 
 struct WarpedDungeon {
   world: World,
-  rend_all_walls: Option<olc::Decal>,
-  rend_select: Option<olc::Decal>,
+  rend_all_walls: Option<SpriteSheet<olc::Decal>>,
+  rend_select: Option<SpriteSheet<olc::Decal>>,
   camera_pos: olc::Vf2d,
   camera_angle: f32,
   camera_angle_target: f32,
@@ -70,20 +72,21 @@ struct WarpedDungeon {
 
 impl olc::Application for WarpedDungeon {
   fn on_user_create(&mut self) -> Result<(), olc::Error> {
-    self.rend_all_walls =
-      Some(olc::Decal::new(olc::Sprite::from_image("examples/dungeon.png").unwrap()));
-    self.rend_select =
-      Some(olc::Decal::new(olc::Sprite::from_image("examples/cursor.png").unwrap()));
+    let no_gap = olc::Vi2d::new(0, 0);
+    let walls = olc::Decal::new(olc::Sprite::from_image("examples/dungeon.png")?);
+    self.rend_all_walls = Some(SpriteSheet::from_grid(walls, TILE_SIZE, no_gap, no_gap));
+    let select = olc::Decal::new(olc::Sprite::from_image("examples/cursor.png")?);
+    self.rend_select = Some(SpriteSheet::from_grid(select, TILE_SIZE, no_gap, no_gap));
 
     for x in 0..self.world.size.x {
       for y in 0..self.world.size.y {
         self.world.get_cell_mut(olc::Vi2d { x, y }).wall = false;
-        self.world.get_cell_mut(olc::Vi2d { x, y }).id[FLOOR] = olc::Vi2d { x: 0, y: 0 } * TILE_SIZE;
-        self.world.get_cell_mut(olc::Vi2d { x, y }).id[TOP] = olc::Vi2d { x: 2, y: 0 } * TILE_SIZE;
-        self.world.get_cell_mut(olc::Vi2d { x, y }).id[NORTH] = olc::Vi2d { x: 6, y: 6 } * TILE_SIZE;
-        self.world.get_cell_mut(olc::Vi2d { x, y }).id[SOUTH] = olc::Vi2d { x: 6, y: 6 } * TILE_SIZE;
-        self.world.get_cell_mut(olc::Vi2d { x, y }).id[WEST] = olc::Vi2d { x: 6, y: 6 } * TILE_SIZE;
-        self.world.get_cell_mut(olc::Vi2d { x, y }).id[EAST] = olc::Vi2d { x: 6, y: 6 } * TILE_SIZE;
+        self.world.get_cell_mut(olc::Vi2d { x, y }).id[FLOOR] = (0, 0);
+        self.world.get_cell_mut(olc::Vi2d { x, y }).id[TOP] = (2, 0);
+        self.world.get_cell_mut(olc::Vi2d { x, y }).id[NORTH] = (6, 6);
+        self.world.get_cell_mut(olc::Vi2d { x, y }).id[SOUTH] = (6, 6);
+        self.world.get_cell_mut(olc::Vi2d { x, y }).id[WEST] = (6, 6);
+        self.world.get_cell_mut(olc::Vi2d { x, y }).id[EAST] = (6, 6);
       }
     }
 
@@ -94,8 +97,11 @@ impl olc::Application for WarpedDungeon {
     // Edit mode - Selection from tile sprite sheet
     if olc::get_key(olc::Key::TAB).held {
       let mouse = olc::Vi2d { x: olc::get_mouse_x(), y: olc::get_mouse_y() };
-      olc::draw_sprite(0, 0, self.rend_all_walls.as_ref().unwrap().sprite());
-      olc::draw_rect(self.tile_cursor.x * TILE_SIZE.x, self.tile_cursor.y * TILE_SIZE.y, TILE_SIZE.x, TILE_SIZE.y, olc::YELLOW);
+      let walls = self.rend_all_walls.as_ref().unwrap();
+      olc::draw_sprite(0, 0, walls.image().sprite());
+      if let Some(f) = walls.frame(self.selected_tile()) {
+        olc::draw_rect(f.pos.x, f.pos.y, f.size.x, f.size.y, olc::YELLOW);
+      }
       if olc::get_mouse(0).pressed {
         self.tile_cursor = mouse / TILE_SIZE;
       }
@@ -147,12 +153,12 @@ impl olc::Application for WarpedDungeon {
     if olc::get_key(olc::Key::M).pressed { self.camera_angle_target = 3.14159 * 1.75; }
 
     // Numeric keys apply selected tile to specific face
-    if olc::get_key(olc::Key::K1).pressed { self.world.get_cell_mut(self.cursor).id[NORTH] = self.tile_cursor * TILE_SIZE; }
-    if olc::get_key(olc::Key::K2).pressed { self.world.get_cell_mut(self.cursor).id[EAST] = self.tile_cursor * TILE_SIZE; }
-    if olc::get_key(olc::Key::K3).pressed { self.world.get_cell_mut(self.cursor).id[SOUTH] = self.tile_cursor * TILE_SIZE; }
-    if olc::get_key(olc::Key::K4).pressed { self.world.get_cell_mut(self.cursor).id[WEST] = self.tile_cursor * TILE_SIZE; }
-    if olc::get_key(olc::Key::K5).pressed { self.world.get_cell_mut(self.cursor).id[FLOOR] = self.tile_cursor * TILE_SIZE; }
-    if olc::get_key(olc::Key::K6).pressed { self.world.get_cell_mut(self.cursor).id[TOP] = self.tile_cursor * TILE_SIZE; }
+    if olc::get_key(olc::Key::K1).pressed { self.world.get_cell_mut(self.cursor).id[NORTH] = self.selected_tile(); }
+    if olc::get_key(olc::Key::K2).pressed { self.world.get_cell_mut(self.cursor).id[EAST] = self.selected_tile(); }
+    if olc::get_key(olc::Key::K3).pressed { self.world.get_cell_mut(self.cursor).id[SOUTH] = self.selected_tile(); }
+    if olc::get_key(olc::Key::K4).pressed { self.world.get_cell_mut(self.cursor).id[WEST] = self.selected_tile(); }
+    if olc::get_key(olc::Key::K5).pressed { self.world.get_cell_mut(self.cursor).id[FLOOR] = self.selected_tile(); }
+    if olc::get_key(olc::Key::K6).pressed { self.world.get_cell_mut(self.cursor).id[TOP] = self.selected_tile(); }
 
     // Smooth camera
 		self.camera_angle += (self.camera_angle_target - self.camera_angle) * 10.0 * elapsed_time;
@@ -192,17 +198,7 @@ impl olc::Application for WarpedDungeon {
     olc::clear(olc::BLACK);
 
     for quad in &quads {
-      olc::draw_partial_warped_decal(
-        self.rend_all_walls.as_ref().unwrap(),
-        &[
-          olc::Vf2d::new(quad.points[0].x, quad.points[0].y),
-          olc::Vf2d::new(quad.points[1].x, quad.points[1].y),
-          olc::Vf2d::new(quad.points[2].x, quad.points[2].y),
-          olc::Vf2d::new(quad.points[3].x, quad.points[3].y)
-        ],
-        &olc::Vf2d { x: quad.tile.x as f32, y: quad.tile.y as f32 },
-        &olc::Vf2d { x: TILE_SIZE.x as f32, y: TILE_SIZE.y as f32 } // TODO: fix this inconsistency
-      );
+      self.rend_all_walls.as_ref().unwrap().draw_frame_warped_decal(&quad.corners(), quad.tile);
     }
 
     // 6) Draw selection "tile cube"
@@ -216,18 +212,9 @@ impl olc::Application for WarpedDungeon {
       &mut quads
     );
 
+    // The cursor image is a single tile drawn on every face.
     for quad in &quads {
-      olc::draw_partial_warped_decal(
-        self.rend_select.as_ref().unwrap(),
-        &[
-          olc::Vf2d::new(quad.points[0].x, quad.points[0].y),
-          olc::Vf2d::new(quad.points[1].x, quad.points[1].y),
-          olc::Vf2d::new(quad.points[2].x, quad.points[2].y),
-          olc::Vf2d::new(quad.points[3].x, quad.points[3].y)
-        ],
-        &olc::Vf2d { x: quad.tile.x as f32, y: quad.tile.y as f32 },
-        &olc::Vf2d { x: TILE_SIZE.x as f32, y: TILE_SIZE.y as f32 } // TODO: fix this inconsistency
-      );
+      self.rend_select.as_ref().unwrap().draw_frame_warped_decal(&quad.corners(), 0);
     }
 
     Ok(())
@@ -238,7 +225,23 @@ impl olc::Application for WarpedDungeon {
   }
 }
 
+impl Quad {
+  // Projected corners of the quad on the screen.
+  fn corners(&self) -> [olc::Vf2d; 4] {
+    let mut corners = [olc::Vf2d::new(0.0, 0.0); 4];
+    for (corner, point) in corners.iter_mut().zip(self.points.iter()) {
+      *corner = olc::Vf2d::new(point.x, point.y);
+    }
+    corners
+  }
+}
+
 impl WarpedDungeon {
+  // Column and row of the tile selected in edit mode.
+  fn selected_tile(&self) -> (usize, usize) {
+    (self.tile_cursor.x.max(0) as usize, self.tile_cursor.y.max(0) as usize)
+  }
+
   fn create_cube(cell: olc::Vi2d, angle: f32, pitch: f32, scale: f32, camera: Vec3d) -> [Vec3d; 8] {
     let mut unit_cube = [Vec3d::default(); 8];
     let mut rot_cube = [Vec3d::default(); 8];
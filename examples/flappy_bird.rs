@@ -99,11 +99,11 @@ fn is_collision(v: &[bool], x: f32, y: f32, w: f32, h: f32) -> bool {
 }
 
 fn get_random_gate() -> Option<(i32, i32)> {
-  if olc::c_rand() % 10 == 0 {
+  if olc::random::rand() % 10 == 0 {
     None
   } else {
-    let y = 10 + (olc::c_rand() % 20);
-    let gap = 30 + (olc::c_rand() % 30);
+    let y = 10 + (olc::random::rand() % 20);
+    let gap = 30 + (olc::random::rand() % 30);
     Some((y, gap))
   }
 }
@@ -13,9 +13,9 @@ impl olc::Application for ExampleProgram {
     for x in 0..olc::screen_width() {
       for y in 0..olc::screen_height() {
         let p = olc::Pixel::rgb(
-          (olc::c_rand() % 255) as u8,
-          (olc::c_rand() % 255) as u8,
-          (olc::c_rand() % 255) as u8
+          (olc::random::rand() % 255) as u8,
+          (olc::random::rand() % 255) as u8,
+          (olc::random::rand() % 255) as u8
         );
         olc::draw(x, y, p);
       }
@@ -1,6 +1,7 @@
 extern crate olc_pixel_game_engine;
 
 use crate::olc_pixel_game_engine as olc;
+use olc::tilemap::{Orientation, Tile, Tilemap, Tileset};
 
 // Number of tiles in world
 const WORLD_SIZE: (i32, i32) = (14, 10);
@@ -11,14 +12,17 @@ const ORIGIN: (i32, i32) = (5, 1);
 
 struct IsometricTiles {
   sprite: olc::Sprite,
-  world: Vec<i32>
+  world: Tilemap<olc::Sprite>,
+  // Tiles a cell cycles through when clicked
+  tiles: Vec<Tile>
 }
 
 impl IsometricTiles {
   pub fn new() -> Self {
     Self {
       sprite: olc::Sprite::new(),
-      world: Vec::new()
+      world: Tilemap::new(olc::Vi2d::new(WORLD_SIZE.0, WORLD_SIZE.1), olc::Vi2d::new(TILE_SIZE.0, TILE_SIZE.1), Orientation::Isometric),
+      tiles: Vec::new()
     }
   }
 }
@@ -27,8 +31,15 @@ impl olc::Application for IsometricTiles {
   fn on_user_create(&mut self) -> Result<(), olc::Error> {
     // Load sprites
     self.sprite = olc::Sprite::from_image("examples/isometric_demo.png")?;
+    // Flat tiles are in the first row, tall tiles (trees, beach and water) below
+    let zero = olc::Vi2d::new(0, 0);
+    let size = olc::Vi2d::new(TILE_SIZE.0, TILE_SIZE.1);
+    let flat = self.world.add_tileset(Tileset::new("flat", self.sprite.clone(), size, zero, zero));
+    let tall = self.world.add_tileset(Tileset::new("tall", self.sprite.clone(), size * olc::Vi2d::new(1, 2), olc::Vi2d::new(0, TILE_SIZE.1), zero));
+    // Invisible, visible, tree, spooky tree, beach and water
+    self.tiles = vec![Tile::new(flat + 1), Tile::new(flat + 2), Tile::new(tall), Tile::new(tall + 1), Tile::new(tall + 2), Tile::new(tall + 3)];
     // Create the world
-    self.world = vec![0; WORLD_SIZE.0 as usize * WORLD_SIZE.1 as usize];
+    self.world.add_tile_layer("ground").fill(self.tiles[0]);
     Ok(())
   }
 
@@ -67,10 +78,11 @@ impl olc::Application for IsometricTiles {
 
     // Handle mouse click to toggle if a tile is visible or not
     if olc::get_mouse(0).pressed {
-      if selected.0 >= 0 && selected.0 < WORLD_SIZE.0 && selected.1 >= 0 && selected.1 < WORLD_SIZE.1 {
-        let idx = (selected.1 * WORLD_SIZE.0 + selected.0) as usize;
-        self.world[idx] += 1;
-        self.world[idx] %= 6;
+      let cell = olc::Vi2d::new(selected.0, selected.1);
+      let tiles = &self.tiles;
+      if let Some(ground) = self.world.tile_layer_mut("ground") {
+        let next = tiles.iter().position(|&t| t == ground.get(cell)).map_or(0, |i| (i + 1) % tiles.len());
+        ground.set(cell, tiles[next]);
       }
     }
 
@@ -85,43 +97,8 @@ impl olc::Application for IsometricTiles {
       )
     }
 
-    // (0, 0) is at top, defined by ORIGIN, so draw from top to bottom
-    // to ensure tiles closest to camera are drawn last
-    for y in 0..WORLD_SIZE.1 {
-      for x in 0..WORLD_SIZE.0 {
-        // Convert cell coordinate to world space
-
-        let world_coords = to_screen(x, y);
-
-        match self.world[(y * WORLD_SIZE.0 + x) as usize] {
-          0 => {
-            // Invisble Tile
-            olc::draw_partial_sprite(world_coords.0, world_coords.1, &self.sprite, 1 * TILE_SIZE.0, 0, TILE_SIZE.0, TILE_SIZE.1);
-          },
-          1 => {
-            // Visible Tile
-            olc::draw_partial_sprite(world_coords.0, world_coords.1, &self.sprite, 2 * TILE_SIZE.0, 0, TILE_SIZE.0, TILE_SIZE.1);
-          },
-          2 => {
-            // Tree
-            olc::draw_partial_sprite(world_coords.0, world_coords.1 - TILE_SIZE.1, &self.sprite, 0 * TILE_SIZE.0, 1 * TILE_SIZE.1, TILE_SIZE.0, TILE_SIZE.1 * 2);
-          },
-          3 => {
-            // Spooky Tree
-            olc::draw_partial_sprite(world_coords.0, world_coords.1 - TILE_SIZE.1, &self.sprite, 1 * TILE_SIZE.0, 1 * TILE_SIZE.1, TILE_SIZE.0, TILE_SIZE.1 * 2);
-          },
-          4 => {
-            // Beach
-            olc::draw_partial_sprite(world_coords.0, world_coords.1 - TILE_SIZE.1, &self.sprite, 2 * TILE_SIZE.0, 1 * TILE_SIZE.1, TILE_SIZE.0, TILE_SIZE.1 * 2);
-          },
-          5 => {
-            // Water
-            olc::draw_partial_sprite(world_coords.0, world_coords.1 - TILE_SIZE.1, &self.sprite, 3 * TILE_SIZE.0, 1 * TILE_SIZE.1, TILE_SIZE.0, TILE_SIZE.1 * 2);
-          },
-          _ => { }
-        }
-      }
-    }
+    // (0, 0) is at top, defined by ORIGIN, the map places the left most cell at x = 0
+    self.world.draw(olc::Vi2d::new(to_screen(0, WORLD_SIZE.1 - 1).0, to_screen(0, 0).1));
 
     // Draw Selected Cell - Has varying alpha components
     olc::set_pixel_mode(olc::PixelMode::ALPHA);
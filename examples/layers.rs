@@ -17,15 +17,14 @@ impl olc::Application for Layers {
   }
 
   fn on_user_update(&mut self, _elapsed_time: f32) -> Result<(), olc::Error> {
-    let l1 = olc::layer::get_draw_target(1);
+    let l1 = olc::layer::get_draw_target(1).unwrap();
     // println!("layer 1: {:?}", l1);
-    let l2 = olc::layer::get_draw_target(2);
+    let l2 = olc::layer::get_draw_target(2).unwrap();
     // println!("layer 2: {:?}", l2);
-    let l3 = olc::layer::get_draw_target(3);
+    let l3 = olc::layer::get_draw_target(3).unwrap();
     // println!("layer 3: {:?}", l3);
     // non-existent layer
-    let _l4 = olc::layer::get_draw_target(4);
-    // println!("layer 4: {:?}", l4);
+    assert!(olc::layer::get_draw_target(4).is_none());
 
     olc::layer::enable_layer(l1.id, true);
     olc::layer::set_draw_target(l1.id);
@@ -48,7 +47,7 @@ impl olc::Application for Layers {
     olc::layer::set_primary_draw_target();
     olc::clear(olc::Pixel::rgba(128, 0, 0, 100));
     olc::draw_string(10, 10, &"Layer 0", olc::WHITE)?;
-    olc::draw_string(90, 10, &format!("{}", olc::layer::get_primary_draw_target().get_pixel(0, 0)), olc::WHITE)?;
+    olc::draw_string(90, 10, &format!("{}", olc::layer::get_primary_draw_target().unwrap().get_pixel(0, 0)), olc::WHITE)?;
 
     Ok(())
   }
@@ -13,7 +13,7 @@ const CHAR_HEIGHT: f32 = 10.0;
 // Random ASCII
 #[inline]
 fn rand_character() -> char {
-  let random_char = (olc::c_rand() % 93 + 33) as u8;
+  let random_char = (olc::random::rand() % 93 + 33) as u8;
   random_char as char
 }
 
@@ -31,13 +31,13 @@ impl Stream {
   }
 
   fn prepare(&mut self) {
-    self.column = (olc::c_rand() % (olc::screen_width() / CHAR_WIDTH as i32)) as f32;
+    self.column = (olc::random::rand() % (olc::screen_width() / CHAR_WIDTH as i32)) as f32;
     self.pos_delta = 0.0;
     self.pos = 0.0;
-    self.speed = (olc::c_rand() % 50 + 15) as f32;
+    self.speed = (olc::random::rand() % 50 + 15) as f32;
     self.text.clear();
 
-    let len = olc::c_rand() % 80 + 10;
+    let len = olc::random::rand() % 80 + 10;
     for _ in 0..len {
       self.text.push(rand_character());
     }
@@ -99,7 +99,7 @@ impl olc::Application for Matrix {
         )?;
 
         // Occasionally glitch a character
-        if olc::c_rand() % 1000 < 5 {
+        if olc::random::rand() % 1000 < 5 {
           unsafe {
             let bytes = stream.text.as_bytes_mut();
             bytes[i] = rand_character() as u8;
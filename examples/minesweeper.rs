@@ -2,6 +2,7 @@ extern crate olc_pixel_game_engine;
 
 use std::time::SystemTime;
 use crate::olc_pixel_game_engine as olc;
+use olc::sprite_sheet::SpriteSheet;
 
 const UNKNOWN: i32 = -1;
 const MINE: i32 = -2;
@@ -14,7 +15,7 @@ struct MineSweeper {
   mines: i32,
   board: Vec<(i32, bool)>,
   game_over: i32,
-  sprite: olc::Sprite,
+  tiles: SpriteSheet<olc::Sprite>,
 }
 
 impl MineSweeper {
@@ -26,7 +27,7 @@ impl MineSweeper {
       mines: 15,
       board: Vec::new(),
       game_over: 0,
-      sprite: olc::Sprite::new(),
+      tiles: SpriteSheet::new(olc::Sprite::new()),
     }
   }
 
@@ -41,7 +42,7 @@ impl MineSweeper {
 
     let mut left = self.mines;
     while left > 0 {
-      let idx = olc::c_rand() as usize % mine_pos.len();
+      let idx = olc::random::rand() as usize % mine_pos.len();
       let (x, y) = mine_pos[idx];
       self.board[(y * self.width + x) as usize] = (MINE, false);
       if idx < mine_pos.len() - 1 {
@@ -102,8 +103,10 @@ impl olc::Application for MineSweeper {
   fn on_user_create(&mut self) -> Result<(), olc::Error> {
     let secs = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
     println!("secs: {}", secs);
-    olc::c_srand(secs as u32);
-    self.sprite = olc::Sprite::from_image("examples/minesweeper.png")?;
+    olc::random::srand(secs);
+    let sprite = olc::Sprite::from_image("examples/minesweeper.png")?;
+    // 8x8 tiles: hidden, empty, mine, blown mine and flag in the first row, numbers 1 to 8 in the second.
+    self.tiles = SpriteSheet::from_grid(sprite, olc::Vi2d::new(8, 8), olc::Vi2d::new(0, 0), olc::Vi2d::new(0, 0));
     self.gen_board();
 
     Ok(())
@@ -151,33 +154,20 @@ impl olc::Application for MineSweeper {
       for y in 0..self.height {
         let (v, safe) = self.get(x, y);
 
-        if v == MINE && self.game_over == 1 {
-          olc::draw_partial_sprite(x * 8, y * 8, &self.sprite, 16, 0, 8, 8);
+        let tile = if v == MINE && self.game_over == 1 {
+          (2, 0)
         } else if v == BLOWN && self.game_over == 1 {
-          olc::draw_partial_sprite(x * 8, y * 8, &self.sprite, 24, 0, 8, 8);
+          (3, 0)
         } else if safe {
-          olc::draw_partial_sprite(x * 8, y * 8, &self.sprite, 32, 0, 8, 8);
+          (4, 0)
         } else if v == 0 {
-          olc::draw_partial_sprite(x * 8, y * 8, &self.sprite, 8, 0, 8, 8);
-        } else if v == 1 {
-          olc::draw_partial_sprite(x * 8, y * 8, &self.sprite, 0, 8, 8, 8);
-        } else if v == 2 {
-          olc::draw_partial_sprite(x * 8, y * 8, &self.sprite, 8, 8, 8, 8);
-        } else if v == 3 {
-          olc::draw_partial_sprite(x * 8, y * 8, &self.sprite, 16, 8, 8, 8);
-        } else if v == 4 {
-          olc::draw_partial_sprite(x * 8, y * 8, &self.sprite, 24, 8, 8, 8);
-        } else if v == 5 {
-          olc::draw_partial_sprite(x * 8, y * 8, &self.sprite, 32, 8, 8, 8);
-        } else if v == 6 {
-          olc::draw_partial_sprite(x * 8, y * 8, &self.sprite, 40, 8, 8, 8);
-        } else if v == 7 {
-          olc::draw_partial_sprite(x * 8, y * 8, &self.sprite, 48, 8, 8, 8);
-        } else if v == 8 {
-          olc::draw_partial_sprite(x * 8, y * 8, &self.sprite, 56, 8, 8, 8);
+          (1, 0)
+        } else if (1..=8).contains(&v) {
+          (v as usize - 1, 1)
         } else {
-          olc::draw_partial_sprite(x * 8, y * 8, &self.sprite, 0, 0, 8, 8);
-        }
+          (0, 0)
+        };
+        self.tiles.draw_frame(olc::Vi2d::new(x * 8, y * 8), tile);
       }
     }
 
@@ -90,7 +90,7 @@ impl olc::Application for SnakeGame {
 
     self.snake.push_front(olc::Vi2d::new(0, 0));
 
-    self.point = olc::Vi2d::new(olc::c_rand() % GAME_W, olc::c_rand() % GAME_H);
+    self.point = olc::Vi2d::new(olc::random::rand() % GAME_W, olc::random::rand() % GAME_H);
     self.game_over = false;
 
     Ok(())
@@ -134,7 +134,7 @@ impl olc::Application for SnakeGame {
         if pos.x == self.point.x && pos.y == self.point.y {
           // Eat the point and generate a new one
           self.snake.push_back(olc::Vi2d::new(self.point.x, self.point.y));
-          self.point = olc::Vi2d::new(olc::c_rand() % GAME_W, olc::c_rand() % GAME_H);
+          self.point = olc::Vi2d::new(olc::random::rand() % GAME_W, olc::random::rand() % GAME_H);
 
           // Increase the difficulty/speed
           if self.snake.len() % 4 == 0 {
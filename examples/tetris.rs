@@ -129,8 +129,8 @@ impl Tetris {
     self.can_rotate = true;
     self.game_over = false;
 
-    self.next_piece = (olc::c_rand() % 7) as usize;
-    self.next_r = olc::c_rand() % 4;
+    self.next_piece = (olc::random::rand() % 7) as usize;
+    self.next_r = olc::random::rand() % 4;
   }
 
   fn create_new_piece(&mut self) {
@@ -139,8 +139,8 @@ impl Tetris {
     self.y = 0;
     self.r = self.next_r;
 
-    self.next_piece = (olc::c_rand() % 7) as usize;
-    self.next_r = olc::c_rand() % 4;
+    self.next_piece = (olc::random::rand() % 7) as usize;
+    self.next_r = olc::random::rand() % 4;
 
     self.num_pieces += 1;
   }
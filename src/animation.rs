@@ -0,0 +1,485 @@
+//! Frame based sprite animation.
+//!
+//! An [`Animation`](Animation) is a list of frames of a
+//! [`SpriteSheet`](crate::sprite_sheet::SpriteSheet), each shown for its own duration, and
+//! [`Tag`](Tag)s naming sub-ranges of the frames such as "idle" or "run". An
+//! [`Animator`](Animator) plays an animation, or one of its tags, in a [`PlayMode`](PlayMode) and
+//! draws the current frame. Animators implement [`Playable`](crate::tween::Playable), so they are
+//! advanced by `elapsed_time` like tweens and can be part of a sequence.
+//!
+//! Animations can be imported from the JSON files exported by Aseprite, using the same file to
+//! slice the sprite sheet:
+//!
+//! ```no_run
+//! # extern crate olc_pixel_game_engine;
+//! # use crate::olc_pixel_game_engine as olc;
+//! use std::rc::Rc;
+//! use olc::animation::{Animation, Animator, PlayMode};
+//! use olc::sprite_sheet::SpriteSheet;
+//! use olc::tween::Playable;
+//!
+//! let json = std::fs::read_to_string("player.json").unwrap();
+//! let image = olc::Sprite::from_image("player.png").unwrap();
+//! let sheet = SpriteSheet::from_descriptor(olc::Decal::new(image), &json).unwrap();
+//! let animation = Rc::new(Animation::from_aseprite(&json).unwrap());
+//!
+//! let mut player = Animator::new(animation, PlayMode::Loop);
+//! player.play("run");
+//!
+//! // In `on_user_update`:
+//! # let elapsed_time = 0.016;
+//! player.update(elapsed_time);
+//! player.draw_decal(&sheet, &olc::Vf2d::new(10.0, 10.0));
+//! ```
+
+use std::rc::Rc;
+
+use crate::json::{self, Value};
+use crate::sprite_sheet::SpriteSheet;
+use crate::tween::Playable;
+use crate::{Decal, Error, Pixel, Sprite, SpriteFlip, Vf2d, Vi2d};
+
+// Shortest frame duration, keeps zero length frames from stalling the animator.
+const MIN_DURATION: f32 = 0.001;
+
+/// How an animation continues after its last frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PlayMode {
+  /// Starts again from the first frame.
+  #[default]
+  Loop,
+  /// Plays backwards to the first frame, then forwards again.
+  PingPong,
+  /// Stops on the last frame.
+  Once
+}
+
+/// Frame of an animation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AnimationFrame {
+  /// Index of the frame in the sprite sheet.
+  pub frame: usize,
+  /// Time the frame is shown for, in seconds.
+  pub duration: f32
+}
+
+/// Named range of animation frames, from `from` to `to` inclusive.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tag {
+  pub name: String,
+  pub from: usize,
+  pub to: usize,
+  pub mode: PlayMode,
+  /// Plays the range from `to` to `from`.
+  pub reverse: bool
+}
+
+/// Frames and tags of an animation, shared by any number of [`Animator`](Animator)s.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Animation {
+  frames: Vec<AnimationFrame>,
+  tags: Vec<Tag>
+}
+
+impl Animation {
+  /// Creates an animation without frames.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Creates an animation showing each of the sprite sheet frames for `duration` seconds.
+  pub fn from_frames(frames: impl IntoIterator<Item = usize>, duration: f32) -> Self {
+    let mut animation = Self::new();
+    for frame in frames {
+      animation.add_frame(frame, duration);
+    }
+    animation
+  }
+
+  /// Imports frame durations and tags from the JSON file exported by Aseprite.
+  /// Frame `i` of the animation shows frame `i` of the sprite sheet sliced from the same file.
+  /// A tag that repeats once plays in [`PlayMode::Once`](PlayMode::Once).
+  /// Returns error if the file is invalid.
+  pub fn from_aseprite(json: &str) -> Result<Self, Error> {
+    let error = |msg: &str| Error { msg: format!("Failed to import the animation: {}", msg) };
+    let root = json::parse(json)?;
+    let frames = root.get("frames").ok_or_else(|| error("missing 'frames'"))?;
+    let frames: Vec<&Value> = match (frames.as_object(), frames.as_array()) {
+      (Some(members), _) => members.iter().map(|(_, v)| v).collect(),
+      (_, Some(items)) => items.iter().collect(),
+      _ => return Err(error("'frames' must be an object or an array"))
+    };
+    let mut animation = Self::new();
+    for (i, frame) in frames.into_iter().enumerate() {
+      let duration = frame.get("duration")
+        .and_then(Value::as_f64)
+        .ok_or_else(|| error(&format!("frame {} has no 'duration'", i)))?;
+      animation.add_frame(i, duration as f32 / 1000.0);
+    }
+
+    let tags = root.get("meta").and_then(|m| m.get("frameTags")).and_then(Value::as_array).unwrap_or_default();
+    for tag in tags {
+      let name = tag.get("name").and_then(Value::as_str).ok_or_else(|| error("tag has no 'name'"))?;
+      let index = |key: &str| {
+        tag.get(key)
+          .and_then(Value::as_i64)
+          .filter(|&v| v >= 0)
+          .map(|v| v as usize)
+          .ok_or_else(|| error(&format!("tag '{}' has an invalid '{}'", name, key)))
+      };
+      let (from, to) = (index("from")?, index("to")?);
+      let direction = tag.get("direction").and_then(Value::as_str).unwrap_or("forward");
+      let (mode, reverse) = match direction {
+        "forward" => (PlayMode::Loop, false),
+        "reverse" => (PlayMode::Loop, true),
+        "pingpong" => (PlayMode::PingPong, false),
+        "pingpong_reverse" => (PlayMode::PingPong, true),
+        d => return Err(error(&format!("tag '{}' has an unknown direction '{}'", name, d)))
+      };
+      // Newer versions store the repeat count as a string.
+      let once = tag.get("repeat").is_some_and(|r| r.as_str() == Some("1") || r.as_i64() == Some(1));
+      let mode = if once && mode == PlayMode::Loop { PlayMode::Once } else { mode };
+      if !animation.add_tag(Tag { name: name.to_string(), from, to, mode, reverse }) {
+        return Err(error(&format!("tag '{}' is out of range", name)));
+      }
+    }
+    Ok(animation)
+  }
+
+  /// Imports the animation from the Aseprite JSON file, see
+  /// [`from_aseprite`](Animation::from_aseprite).
+  /// Returns error if the file could not be read or is invalid.
+  pub fn from_aseprite_file(path: &str) -> Result<Self, Error> {
+    let json = std::fs::read_to_string(path)
+      .map_err(|e| Error { msg: format!("Failed to import the animation: '{}': {}", path, e) })?;
+    Self::from_aseprite(&json)
+  }
+
+  /// Appends the sprite sheet frame shown for `duration` seconds and returns its index.
+  pub fn add_frame(&mut self, frame: usize, duration: f32) -> usize {
+    self.frames.push(AnimationFrame { frame, duration: duration.max(MIN_DURATION) });
+    self.frames.len() - 1
+  }
+
+  /// Adds the tag, replacing any tag with the same name.
+  /// Returns false if the range is empty or out of bounds.
+  pub fn add_tag(&mut self, tag: Tag) -> bool {
+    if tag.from > tag.to || tag.to >= self.frames.len() {
+      return false;
+    }
+    self.tags.retain(|t| t.name != tag.name);
+    self.tags.push(tag);
+    true
+  }
+
+  /// Returns the tag.
+  pub fn tag(&self, name: &str) -> Option<&Tag> {
+    self.tags.iter().find(|t| t.name == name)
+  }
+
+  /// Returns all frames.
+  pub fn frames(&self) -> &[AnimationFrame] {
+    &self.frames
+  }
+
+  /// Returns all tags.
+  pub fn tags(&self) -> &[Tag] {
+    &self.tags
+  }
+
+  /// Returns the time to play all frames once, in seconds.
+  pub fn duration(&self) -> f32 {
+    self.frames.iter().map(|f| f.duration).sum()
+  }
+}
+
+/// Plays an [`Animation`](Animation), tracks the current frame and draws it.
+#[derive(Clone, Debug)]
+pub struct Animator {
+  animation: Rc<Animation>,
+  tag: Option<String>,
+  from: usize,
+  to: usize,
+  mode: PlayMode,
+  reverse: bool,
+  position: usize,
+  forward: bool,
+  time: f32,
+  speed: f32,
+  finished: bool
+}
+
+impl Animator {
+  /// Creates an animator playing all frames of the animation in the mode.
+  pub fn new(animation: Rc<Animation>, mode: PlayMode) -> Self {
+    let to = animation.frames.len().saturating_sub(1);
+    let mut animator = Self {
+      animation,
+      tag: None,
+      from: 0,
+      to,
+      mode,
+      reverse: false,
+      position: 0,
+      forward: true,
+      time: 0.0,
+      speed: 1.0,
+      finished: false
+    };
+    animator.reset();
+    animator
+  }
+
+  /// Plays the tag from its start, unless it is already playing and has not finished.
+  /// Returns false if the animation has no such tag.
+  pub fn play(&mut self, tag: &str) -> bool {
+    if self.tag.as_deref() == Some(tag) && !self.finished {
+      return true;
+    }
+    let t = match self.animation.tag(tag) {
+      Some(t) => t.clone(),
+      None => return false
+    };
+    self.tag = Some(t.name);
+    self.from = t.from;
+    self.to = t.to;
+    self.mode = t.mode;
+    self.reverse = t.reverse;
+    self.reset();
+    true
+  }
+
+  /// Plays all frames of the animation from the start in the mode.
+  pub fn play_all(&mut self, mode: PlayMode) {
+    self.tag = None;
+    self.from = 0;
+    self.to = self.animation.frames.len().saturating_sub(1);
+    self.mode = mode;
+    self.reverse = false;
+    self.reset();
+  }
+
+  /// Returns name of the playing tag, `None` when playing all frames.
+  pub fn tag(&self) -> Option<&str> {
+    self.tag.as_deref()
+  }
+
+  /// Returns the play mode.
+  pub fn mode(&self) -> PlayMode {
+    self.mode
+  }
+
+  /// Sets the playback speed, 1.0 is normal speed.
+  pub fn set_speed(&mut self, speed: f32) {
+    self.speed = speed.max(0.0);
+  }
+
+  /// Returns the playback speed.
+  pub fn speed(&self) -> f32 {
+    self.speed
+  }
+
+  /// Returns the animation.
+  pub fn animation(&self) -> &Rc<Animation> {
+    &self.animation
+  }
+
+  /// Returns index of the current animation frame.
+  pub fn position(&self) -> usize {
+    self.position
+  }
+
+  /// Returns index of the current sprite sheet frame, `None` if the animation has no frames.
+  pub fn frame(&self) -> Option<usize> {
+    self.animation.frames.get(self.position).map(|f| f.frame)
+  }
+
+  /// Draws the current frame from the sheet with its top left corner at `pos`.
+  /// Returns false if there is nothing to draw.
+  pub fn draw<T: AsRef<Sprite>>(&self, sheet: &SpriteSheet<T>, pos: Vi2d) -> bool {
+    self.draw_ext(sheet, pos, 1, SpriteFlip::NONE)
+  }
+
+  /// Draws the current frame from the sheet scaled and flipped.
+  /// Returns false if there is nothing to draw.
+  pub fn draw_ext<T: AsRef<Sprite>>(&self, sheet: &SpriteSheet<T>, pos: Vi2d, scale: u32, flip: SpriteFlip) -> bool {
+    self.frame().is_some_and(|f| sheet.draw_frame_ext(pos, f, scale, flip))
+  }
+
+  /// Draws the current frame from the sheet as a decal with its top left corner at `pos`.
+  /// Returns false if there is nothing to draw.
+  pub fn draw_decal(&self, sheet: &SpriteSheet<Decal>, pos: &Vf2d) -> bool {
+    self.frame().is_some_and(|f| sheet.draw_frame_decal(pos, f))
+  }
+
+  /// Draws the current frame from the sheet as a scaled and tinted decal.
+  /// Returns false if there is nothing to draw.
+  pub fn draw_decal_ext(&self, sheet: &SpriteSheet<Decal>, pos: &Vf2d, scale: &Vf2d, tint: &Pixel) -> bool {
+    self.frame().is_some_and(|f| sheet.draw_frame_decal_ext(pos, f, scale, tint))
+  }
+
+  // Moves to the next frame, returns false once a `Once` animation is on its last frame.
+  fn advance(&mut self) -> bool {
+    let last = if self.forward { self.to } else { self.from };
+    if self.position != last {
+      self.position = if self.forward { self.position + 1 } else { self.position - 1 };
+      return true;
+    }
+    match self.mode {
+      PlayMode::Once => false,
+      PlayMode::Loop => {
+        self.position = if self.forward { self.from } else { self.to };
+        true
+      }
+      PlayMode::PingPong => {
+        self.forward = !self.forward;
+        if self.from != self.to {
+          self.position = if self.forward { self.position + 1 } else { self.position - 1 };
+        }
+        true
+      }
+    }
+  }
+}
+
+impl Playable for Animator {
+  fn update(&mut self, elapsed_time: f32) -> f32 {
+    if self.finished {
+      return elapsed_time;
+    }
+    if self.animation.frames.is_empty() {
+      self.finished = true;
+      return elapsed_time;
+    }
+    self.time += elapsed_time * self.speed;
+    loop {
+      let duration = self.animation.frames[self.position].duration;
+      if self.time < duration {
+        return 0.0;
+      }
+      self.time -= duration;
+      if !self.advance() {
+        self.finished = true;
+        return self.time / self.speed.max(f32::EPSILON);
+      }
+    }
+  }
+
+  fn is_finished(&self) -> bool {
+    self.finished
+  }
+
+  fn reset(&mut self) {
+    self.forward = !self.reverse;
+    self.position = if self.reverse { self.to } else { self.from };
+    self.time = 0.0;
+    self.finished = false;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn positions(animator: &mut Animator, steps: usize, dt: f32) -> Vec<usize> {
+    (0..steps).map(|_| {
+      animator.update(dt);
+      animator.position()
+    }).collect()
+  }
+
+  #[test]
+  fn test_modes() {
+    let animation = Rc::new(Animation::from_frames(10..14, 0.1));
+    let mut animator = Animator::new(animation.clone(), PlayMode::Loop);
+    assert_eq!(animator.frame(), Some(10));
+    assert_eq!(positions(&mut animator, 6, 0.1), [1, 2, 3, 0, 1, 2]);
+
+    animator.play_all(PlayMode::PingPong);
+    assert_eq!(positions(&mut animator, 8, 0.1), [1, 2, 3, 2, 1, 0, 1, 2]);
+
+    animator.play_all(PlayMode::Once);
+    assert_eq!(positions(&mut animator, 2, 0.1), [1, 2]);
+    // Finishes on the last frame and returns the time left over.
+    let left = animator.update(0.25);
+    assert!(animator.is_finished());
+    assert_eq!(animator.frame(), Some(13));
+    assert!((left - 0.05).abs() < 1e-5);
+    animator.reset();
+    assert_eq!(animator.position(), 0);
+    assert!(!animator.is_finished());
+  }
+
+  #[test]
+  fn test_durations_and_speed() {
+    let mut animation = Animation::new();
+    animation.add_frame(0, 0.5);
+    animation.add_frame(1, 0.25);
+    animation.add_frame(2, 0.0);
+    assert!((animation.duration() - 0.751).abs() < 1e-5);
+    let mut animator = Animator::new(Rc::new(animation), PlayMode::Loop);
+    assert_eq!(positions(&mut animator, 3, 0.25), [0, 1, 2]);
+    // The zero length frame is shown for the shortest duration.
+    animator.update(0.125);
+    assert_eq!(animator.position(), 0);
+    animator.set_speed(2.0);
+    assert_eq!(positions(&mut animator, 2, 0.125), [0, 1]);
+  }
+
+  #[test]
+  fn test_tags() {
+    let mut animation = Animation::from_frames(0..6, 1.0);
+    assert!(animation.add_tag(Tag { name: "run".into(), from: 2, to: 4, mode: PlayMode::Loop, reverse: true }));
+    assert!(!animation.add_tag(Tag { name: "bad".into(), from: 4, to: 6, mode: PlayMode::Loop, reverse: false }));
+    let mut animator = Animator::new(Rc::new(animation), PlayMode::Loop);
+    assert!(!animator.play("jump"));
+    assert!(animator.play("run"));
+    assert_eq!(animator.tag(), Some("run"));
+    assert_eq!(animator.position(), 4);
+    assert_eq!(positions(&mut animator, 4, 1.0), [3, 2, 4, 3]);
+    // Playing the same tag again does not restart it.
+    assert!(animator.play("run"));
+    assert_eq!(animator.position(), 3);
+  }
+
+  #[test]
+  fn test_replay_finished_tag() {
+    let mut animation = Animation::from_frames(0..6, 1.0);
+    assert!(animation.add_tag(Tag { name: "hit".into(), from: 1, to: 3, mode: PlayMode::Once, reverse: false }));
+    let mut animator = Animator::new(Rc::new(animation), PlayMode::Loop);
+    assert!(animator.play("hit"));
+    animator.update(5.0);
+    assert!(animator.is_finished());
+    assert_eq!(animator.position(), 3);
+    // A finished tag starts over when played again.
+    assert!(animator.play("hit"));
+    assert!(!animator.is_finished());
+    assert_eq!(animator.position(), 1);
+  }
+
+  #[test]
+  fn test_aseprite() {
+    let json = r#"{ "frames": [
+        { "filename": "knight 0", "frame": { "x": 0, "y": 0, "w": 16, "h": 16 }, "duration": 100 },
+        { "filename": "knight 1", "frame": { "x": 16, "y": 0, "w": 16, "h": 16 }, "duration": 150 },
+        { "filename": "knight 2", "frame": { "x": 32, "y": 0, "w": 16, "h": 16 }, "duration": 100 }
+      ],
+      "meta": { "app": "https://www.aseprite.org/", "frameTags": [
+        { "name": "idle", "from": 0, "to": 1, "direction": "pingpong" },
+        { "name": "attack", "from": 1, "to": 2, "direction": "forward", "repeat": "1" },
+        { "name": "back", "from": 0, "to": 2, "direction": "reverse" }
+      ] } }"#;
+    let animation = Animation::from_aseprite(json).unwrap();
+    assert_eq!(animation.frames()[1], AnimationFrame { frame: 1, duration: 0.15 });
+    assert_eq!(animation.tag("idle").map(|t| t.mode), Some(PlayMode::PingPong));
+    assert_eq!(animation.tag("attack").map(|t| t.mode), Some(PlayMode::Once));
+    assert_eq!(animation.tag("back").map(|t| (t.mode, t.reverse)), Some((PlayMode::Loop, true)));
+    let sheet = SpriteSheet::from_descriptor(Sprite::with_dims(48, 16), json).unwrap();
+    assert_eq!(sheet.len(), animation.frames().len());
+
+    assert!(Animation::from_aseprite("{}").is_err());
+    assert!(Animation::from_aseprite(r#"{"frames": [{"frame": {}}]}"#).is_err());
+    let bad_tag = r#"{"frames": [{"duration": 1}], "meta": {"frameTags": [{"name": "a", "from": 0, "to": 3}]}}"#;
+    assert!(Animation::from_aseprite(bad_tag).is_err());
+  }
+}
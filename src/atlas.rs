@@ -0,0 +1,401 @@
+//! Texture atlas packing.
+//!
+//! [`AtlasBuilder`](AtlasBuilder) packs many small sprites into one or more large pages with a
+//! skyline packer, so a single [`Decal`](crate::Decal) per page can draw every sprite and the
+//! decals batch together. Each added sprite gets an [`AtlasHandle`](AtlasHandle) that identifies
+//! its [`Region`](Region) of the atlas.
+//!
+//! Sprites can be separated by transparent padding and their edge pixels extruded outwards, which
+//! prevents neighbouring sprites from bleeding in when decals are scaled or filtered.
+//!
+//! ```no_run
+//! # extern crate olc_pixel_game_engine;
+//! # use crate::olc_pixel_game_engine as olc;
+//! use olc::atlas::AtlasBuilder;
+//!
+//! let coin = olc::Sprite::from_image("coin.png").unwrap();
+//! let gem = olc::Sprite::from_image("gem.png").unwrap();
+//!
+//! let mut builder = AtlasBuilder::new(olc::Vi2d::new(512, 512)).padding(1).extrude(1);
+//! let coin_id = builder.add(&coin);
+//! let gem_id = builder.add(&gem);
+//! // Decals can only be created once the engine is running, e.g. in `on_user_create`.
+//! let atlas = builder.build().unwrap().into_decals();
+//!
+//! atlas.draw_decal(&olc::Vf2d::new(10.0, 10.0), coin_id);
+//! atlas.draw_decal(&olc::Vf2d::new(30.0, 10.0), gem_id);
+//! ```
+
+use crate::{Decal, Error, Pixel, Sprite, SpriteFlip, Vf2d, Vi2d, BLANK};
+
+/// Identifies a sprite added to an [`AtlasBuilder`](AtlasBuilder).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AtlasHandle(usize);
+
+impl AtlasHandle {
+  /// Returns the order in which the sprite was added, starting from 0.
+  pub fn index(self) -> usize {
+    self.0
+  }
+}
+
+/// Location of a sprite in the atlas.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Region {
+  /// Index of the page.
+  pub page: usize,
+  /// Top left corner on the page in pixels.
+  pub pos: Vi2d,
+  /// Size in pixels.
+  pub size: Vi2d,
+  /// Top left corner in normalised texture coordinates.
+  pub uv_pos: Vf2d,
+  /// Size in normalised texture coordinates.
+  pub uv_size: Vf2d
+}
+
+/// Collects sprites and packs them into an [`Atlas`](Atlas).
+#[derive(Clone, Debug)]
+pub struct AtlasBuilder<'a> {
+  page_size: Vi2d,
+  padding: i32,
+  extrude: i32,
+  sprites: Vec<&'a Sprite>
+}
+
+impl<'a> AtlasBuilder<'a> {
+  /// Creates a builder producing pages of `page_size` pixels, without padding and extrusion.
+  pub fn new(page_size: Vi2d) -> Self {
+    Self { page_size, padding: 0, extrude: 0, sprites: Vec::new() }
+  }
+
+  /// Sets the number of transparent pixels between sprites and around the page edges.
+  pub fn padding(mut self, padding: i32) -> Self {
+    self.padding = padding.max(0);
+    self
+  }
+
+  /// Sets the number of times the edge pixels of each sprite are repeated outwards.
+  pub fn extrude(mut self, extrude: i32) -> Self {
+    self.extrude = extrude.max(0);
+    self
+  }
+
+  /// Adds the sprite and returns its handle.
+  pub fn add(&mut self, sprite: &'a Sprite) -> AtlasHandle {
+    self.sprites.push(sprite);
+    AtlasHandle(self.sprites.len() - 1)
+  }
+
+  /// Returns the number of added sprites.
+  pub fn len(&self) -> usize {
+    self.sprites.len()
+  }
+
+  /// Returns true if no sprites were added.
+  pub fn is_empty(&self) -> bool {
+    self.sprites.is_empty()
+  }
+
+  /// Packs the sprites into as few pages as possible.
+  /// Returns error if a sprite, with its padding and extrusion, does not fit into a page.
+  pub fn build(&self) -> Result<Atlas<Sprite>, Error> {
+    let border = 2 * self.extrude + self.padding;
+    // Pack the tallest sprites first, which keeps the skyline flat.
+    let mut order: Vec<usize> = (0..self.sprites.len()).collect();
+    order.sort_by_key(|&i| (-self.sprites[i].height(), -self.sprites[i].width()));
+
+    let mut skylines: Vec<Skyline> = Vec::new();
+    let mut regions = vec![Region::default(); self.sprites.len()];
+    for i in order {
+      let sprite = self.sprites[i];
+      let size = Vi2d::new(sprite.width(), sprite.height());
+      if size.x <= 0 || size.y <= 0 {
+        continue;
+      }
+      let cell = size + Vi2d::new(border, border);
+      let placed = skylines.iter_mut().enumerate().find_map(|(page, s)| s.insert(cell).map(|pos| (page, pos)));
+      let (page, pos) = match placed {
+        Some(placed) => placed,
+        None => {
+          let mut skyline = Skyline::new(self.page_size - Vi2d::new(self.padding, self.padding));
+          let pos = skyline.insert(cell).ok_or_else(|| Error {
+            msg: format!("Failed to build the atlas: sprite {} of size {}x{} does not fit into a {}x{} page",
+              i, size.x, size.y, self.page_size.x, self.page_size.y)
+          })?;
+          skylines.push(skyline);
+          (skylines.len() - 1, pos)
+        }
+      };
+      let pos = pos + Vi2d::new(self.padding + self.extrude, self.padding + self.extrude);
+      regions[i] = Region {
+        page,
+        pos,
+        size,
+        uv_pos: Vf2d::new(pos.x as f32 / self.page_size.x as f32, pos.y as f32 / self.page_size.y as f32),
+        uv_size: Vf2d::new(size.x as f32 / self.page_size.x as f32, size.y as f32 / self.page_size.y as f32)
+      };
+    }
+
+    let mut pages: Vec<Sprite> = (0..skylines.len()).map(|_| Sprite::with_dims(self.page_size.x, self.page_size.y)).collect();
+    for page in pages.iter_mut() {
+      page.fill(BLANK);
+    }
+    for (sprite, region) in self.sprites.iter().zip(regions.iter()) {
+      if region.size.x > 0 && region.size.y > 0 {
+        copy_extruded(&mut pages[region.page], sprite, region.pos, self.extrude);
+      }
+    }
+    Ok(Atlas { pages, regions })
+  }
+}
+
+// Copies the sprite to `pos` and repeats its edge pixels `extrude` times around it.
+fn copy_extruded(page: &mut Sprite, sprite: &Sprite, pos: Vi2d, extrude: i32) {
+  let (w, h, page_w) = (sprite.width(), sprite.height(), page.width());
+  let src = sprite.pixels();
+  let dst = page.pixels_mut();
+  for y in -extrude..h + extrude {
+    let row = ((pos.y + y) * page_w) as usize;
+    let src_row = (y.clamp(0, h - 1) * w) as usize;
+    for x in -extrude..w + extrude {
+      dst[row + (pos.x + x) as usize] = src[src_row + x.clamp(0, w - 1) as usize];
+    }
+  }
+}
+
+// Skyline bottom-left packer, the skyline is a list of segments (x, y, width) covering the page.
+#[derive(Debug)]
+struct Skyline {
+  size: Vi2d,
+  segments: Vec<(i32, i32, i32)>
+}
+
+impl Skyline {
+  fn new(size: Vi2d) -> Self {
+    Self { size, segments: vec![(0, 0, size.x.max(0))] }
+  }
+
+  // Returns the lowest y at which a rectangle of `size` fits with its left edge at segment `i`.
+  fn fit(&self, i: usize, size: Vi2d) -> Option<i32> {
+    let x = self.segments[i].0;
+    if x + size.x > self.size.x {
+      return None;
+    }
+    let mut y = 0;
+    let mut remaining = size.x;
+    for &(_, sy, sw) in &self.segments[i..] {
+      if remaining <= 0 {
+        break;
+      }
+      y = y.max(sy);
+      remaining -= sw;
+    }
+    Some(y).filter(|y| y + size.y <= self.size.y)
+  }
+
+  // Places the rectangle where its top is lowest, then leftmost, and returns its position.
+  fn insert(&mut self, size: Vi2d) -> Option<Vi2d> {
+    let (i, y) = (0..self.segments.len())
+      .filter_map(|i| self.fit(i, size).map(|y| (i, y)))
+      .min_by_key(|&(i, y)| (y + size.y, self.segments[i].0))?;
+    let x = self.segments[i].0;
+    // Replace the covered segments with the new one, keeping the uncovered part of the last.
+    let end = x + size.x;
+    let mut j = i;
+    while j < self.segments.len() && self.segments[j].0 < end {
+      j += 1;
+    }
+    let last = self.segments[j - 1];
+    self.segments.splice(i..j, std::iter::once((x, y + size.y, size.x)));
+    if last.0 + last.2 > end {
+      self.segments.insert(i + 1, (end, last.1, last.0 + last.2 - end));
+    }
+    // Merge neighbours of the same height.
+    self.segments.dedup_by(|b, a| {
+      if a.1 == b.1 {
+        a.2 += b.2;
+        true
+      } else {
+        false
+      }
+    });
+    Some(Vi2d::new(x, y))
+  }
+}
+
+/// Packed pages and the regions of the sprites, `T` is either [`Sprite`](crate::Sprite) or
+/// [`Decal`](crate::Decal).
+#[derive(Debug)]
+pub struct Atlas<T> {
+  pages: Vec<T>,
+  regions: Vec<Region>
+}
+
+impl<T> Atlas<T> {
+  /// Returns the pages.
+  pub fn pages(&self) -> &[T] {
+    &self.pages
+  }
+
+  /// Returns the region of the sprite, `None` if the handle belongs to another atlas.
+  pub fn region(&self, handle: AtlasHandle) -> Option<&Region> {
+    self.regions.get(handle.0)
+  }
+
+  /// Returns the regions of all sprites, in the order they were added.
+  pub fn regions(&self) -> &[Region] {
+    &self.regions
+  }
+}
+
+impl Atlas<Sprite> {
+  /// Creates a decal for each page.
+  pub fn into_decals(self) -> Atlas<Decal> {
+    Atlas { pages: self.pages.into_iter().map(Decal::new).collect(), regions: self.regions }
+  }
+}
+
+impl<T: AsRef<Sprite>> Atlas<T> {
+  /// Draws the sprite with its top left corner at `pos`.
+  /// Returns false if the handle belongs to another atlas.
+  pub fn draw(&self, pos: Vi2d, handle: AtlasHandle) -> bool {
+    self.draw_ext(pos, handle, 1, SpriteFlip::NONE)
+  }
+
+  /// Draws the sprite scaled and flipped.
+  /// Returns false if the handle belongs to another atlas.
+  pub fn draw_ext(&self, pos: Vi2d, handle: AtlasHandle, scale: u32, flip: SpriteFlip) -> bool {
+    match self.region(handle) {
+      Some(r) => {
+        let page = self.pages.get(r.page).map(AsRef::as_ref);
+        if let Some(page) = page {
+          crate::draw_partial_sprite_ext(pos.x, pos.y, page, r.pos.x, r.pos.y, r.size.x, r.size.y, scale, flip);
+        }
+        true
+      }
+      None => false
+    }
+  }
+}
+
+impl Atlas<Decal> {
+  /// Draws the sprite as a decal with its top left corner at `pos`.
+  /// Returns false if the handle belongs to another atlas.
+  pub fn draw_decal(&self, pos: &Vf2d, handle: AtlasHandle) -> bool {
+    self.draw_decal_ext(pos, handle, &Vf2d::new(1.0, 1.0), &crate::WHITE)
+  }
+
+  /// Draws the sprite as a scaled and tinted decal.
+  /// Returns false if the handle belongs to another atlas.
+  pub fn draw_decal_ext(&self, pos: &Vf2d, handle: AtlasHandle, scale: &Vf2d, tint: &Pixel) -> bool {
+    match self.region(handle) {
+      Some(r) => {
+        if let Some(page) = self.pages.get(r.page) {
+          crate::draw_partial_decal_ext(pos, page, &r.pos.into(), &r.size.into(), scale, tint);
+        }
+        true
+      }
+      None => false
+    }
+  }
+
+  /// Draws the sprite as a decal rotated by `angle` radians around `center`, relative to the sprite.
+  /// Returns false if the handle belongs to another atlas.
+  pub fn draw_rotated_decal(&self, pos: &Vf2d, handle: AtlasHandle, angle: f32, center: &Vf2d, scale: &Vf2d, tint: &Pixel) -> bool {
+    match self.region(handle) {
+      Some(r) => {
+        if let Some(page) = self.pages.get(r.page) {
+          crate::draw_partial_rotated_decal_ext(pos, page, angle, center, &r.pos.into(), &r.size.into(), scale, tint);
+        }
+        true
+      }
+      None => false
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn solid(w: i32, h: i32, id: u8) -> Sprite {
+    let mut sprite = Sprite::with_dims(w, h);
+    for (i, p) in sprite.pixels_mut().iter_mut().enumerate() {
+      *p = Pixel::rgba(id, i as u8, 0, 255);
+    }
+    sprite
+  }
+
+  // Returns the rectangle covered by the region including its padding and extrusion.
+  fn cell(r: &Region, padding: i32, extrude: i32) -> (Vi2d, Vi2d) {
+    let e = Vi2d::new(extrude, extrude);
+    (r.pos - e, r.size + e * 2 + Vi2d::new(padding, padding))
+  }
+
+  #[test]
+  fn test_pack_without_overlap() {
+    let sprites: Vec<Sprite> = (0..40).map(|i| solid(3 + i % 7, 2 + (i * 5) % 9, i as u8)).collect();
+    let mut builder = AtlasBuilder::new(Vi2d::new(64, 64)).padding(1).extrude(1);
+    let handles: Vec<AtlasHandle> = sprites.iter().map(|s| builder.add(s)).collect();
+    let atlas = builder.build().unwrap();
+    assert!(atlas.pages().len() <= 2);
+    let regions: Vec<Region> = handles.iter().map(|&h| *atlas.region(h).unwrap()).collect();
+    for (i, a) in regions.iter().enumerate() {
+      let (pos, size) = cell(a, 1, 1);
+      assert!(pos.x >= 1 && pos.y >= 1 && pos.x + size.x <= 64 && pos.y + size.y <= 64);
+      for b in &regions[i + 1..] {
+        let (bpos, bsize) = cell(b, 1, 1);
+        let apart = a.page != b.page || pos.x + size.x <= bpos.x || bpos.x + bsize.x <= pos.x ||
+          pos.y + size.y <= bpos.y || bpos.y + bsize.y <= pos.y;
+        assert!(apart, "{:?} overlaps {:?}", a, b);
+      }
+      // The sprite is copied as it is.
+      let page = &atlas.pages()[a.page];
+      assert_eq!(page.crop(a.pos, a.size).pixels(), sprites[i].pixels());
+      assert_eq!(a.uv_pos, Vf2d::new(a.pos.x as f32 / 64.0, a.pos.y as f32 / 64.0));
+    }
+  }
+
+  #[test]
+  fn test_extrude_and_padding() {
+    let sprite = solid(2, 2, 9);
+    let mut builder = AtlasBuilder::new(Vi2d::new(8, 8)).padding(1).extrude(2);
+    let handle = builder.add(&sprite);
+    let atlas = builder.build().unwrap();
+    let region = atlas.region(handle).unwrap();
+    assert_eq!(region.pos, Vi2d::new(3, 3));
+    let page = &atlas.pages()[0];
+    // Corners are extruded diagonally and the padding stays transparent.
+    assert_eq!(page.get_pixel(1, 1), sprite.get_pixel(0, 0));
+    assert_eq!(page.get_pixel(6, 1), sprite.get_pixel(1, 0));
+    assert_eq!(page.get_pixel(3, 6), sprite.get_pixel(0, 1));
+    assert_eq!(page.get_pixel(0, 0), BLANK);
+    assert_eq!(page.get_pixel(7, 3), BLANK);
+  }
+
+  #[test]
+  fn test_pages_and_errors() {
+    let sprites: Vec<Sprite> = (0..5).map(|i| solid(10, 10, i)).collect();
+    let mut builder = AtlasBuilder::new(Vi2d::new(20, 20));
+    for s in &sprites {
+      builder.add(s);
+    }
+    let atlas = builder.build().unwrap();
+    assert_eq!(atlas.pages().len(), 2);
+    assert_eq!(atlas.regions().iter().filter(|r| r.page == 1).count(), 1);
+
+    let big = solid(20, 20, 0);
+    let mut builder = AtlasBuilder::new(Vi2d::new(20, 20)).padding(1);
+    builder.add(&big);
+    assert!(builder.build().is_err());
+
+    let empty = Sprite::new();
+    let mut builder = AtlasBuilder::new(Vi2d::new(4, 4));
+    let handle = builder.add(&empty);
+    let atlas = builder.build().unwrap();
+    assert!(atlas.pages().is_empty());
+    assert_eq!(atlas.region(handle).unwrap().size, Vi2d::new(0, 0));
+    assert!(atlas.region(AtlasHandle(1)).is_none());
+  }
+}
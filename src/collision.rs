@@ -0,0 +1,410 @@
+//! Pixel-perfect collision between sprites.
+//!
+//! A [`CollisionMask`](CollisionMask) stores which pixels of a sprite are solid, one bit per
+//! pixel, derived from the alpha channel. Masks are placed in the world with
+//! [`at`](CollisionMask::at), optionally flipped like
+//! [`draw_sprite_ext`](crate::draw_sprite_ext), or through a
+//! [`Transform2D`](crate::transform::Transform2D) with
+//! [`transformed`](CollisionMask::transformed). Placements are tested against each other for
+//! any overlap, the first contact point or the region where they overlap.
+//!
+//! Placements without a transform are compared 64 pixels at a time. Transformed placements are
+//! sampled at the centre of each world pixel where their bounding boxes intersect.
+//!
+//! ```no_run
+//! # extern crate olc_pixel_game_engine;
+//! # use crate::olc_pixel_game_engine as olc;
+//! use olc::collision::CollisionMask;
+//!
+//! let ship = CollisionMask::from_sprite(&olc::Sprite::from_image("ship.png").unwrap());
+//! let rock = CollisionMask::from_sprite(&olc::Sprite::from_image("rock.png").unwrap());
+//!
+//! let hit = ship.at(olc::Vi2d::new(40, 60)).contact(&rock.at(olc::Vi2d::new(52, 70)));
+//! if let Some(point) = hit {
+//!   olc::draw_circle(point.x, point.y, 4, olc::RED);
+//! }
+//! ```
+
+use crate::sprite_sheet::Frame;
+use crate::transform::Transform2D;
+use crate::{Sprite, SpriteFlip, Vf2d, Vi2d};
+
+/// Solid pixels of a sprite packed into 64 bit words, row by row.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CollisionMask {
+  width: i32,
+  height: i32,
+  stride: usize,
+  // Bit `i` of word `w` of a row is the pixel at x = w * 64 + i.
+  bits: Vec<u64>,
+  // Rows mirrored horizontally, for flipped placements.
+  mirrored: Vec<u64>
+}
+
+impl CollisionMask {
+  /// Creates a mask of the size without solid pixels.
+  pub fn new(width: i32, height: i32) -> Self {
+    let (width, height) = (width.max(0), height.max(0));
+    let stride = (width as usize).div_ceil(64);
+    let len = stride * height as usize;
+    Self { width, height, stride, bits: vec![0; len], mirrored: vec![0; len] }
+  }
+
+  /// Creates a mask of the sprite where pixels with non-zero alpha are solid.
+  pub fn from_sprite(sprite: &Sprite) -> Self {
+    let frame = Frame::new(Vi2d::new(0, 0), Vi2d::new(sprite.width(), sprite.height()));
+    Self::from_sprite_ext(sprite, frame, 1)
+  }
+
+  /// Creates a mask of the region of the sprite, such as a frame of a sprite sheet, where
+  /// pixels with alpha of at least `threshold` are solid. Pixels outside of the sprite are empty.
+  pub fn from_sprite_ext(sprite: &Sprite, frame: Frame, threshold: u8) -> Self {
+    let mut mask = Self::new(frame.size.x, frame.size.y);
+    let threshold = threshold.max(1);
+    for y in 0..mask.height {
+      for x in 0..mask.width {
+        let (sx, sy) = (frame.pos.x + x, frame.pos.y + y);
+        if sx >= 0 && sy >= 0 && sx < sprite.width() && sy < sprite.height() && sprite.get_pixel(sx, sy).a >= threshold {
+          mask.set(x, y, true);
+        }
+      }
+    }
+    mask
+  }
+
+  pub fn width(&self) -> i32 {
+    self.width
+  }
+
+  pub fn height(&self) -> i32 {
+    self.height
+  }
+
+  /// Returns true if the pixel is solid, false outside of the mask.
+  pub fn get(&self, x: i32, y: i32) -> bool {
+    if x < 0 || y < 0 || x >= self.width || y >= self.height {
+      return false;
+    }
+    let row = y as usize * self.stride;
+    self.bits[row + x as usize / 64] & (1 << (x % 64)) != 0
+  }
+
+  /// Sets the pixel solid or empty.
+  /// Returns false if the pixel is outside of the mask.
+  pub fn set(&mut self, x: i32, y: i32, solid: bool) -> bool {
+    if x < 0 || y < 0 || x >= self.width || y >= self.height {
+      return false;
+    }
+    let row = y as usize * self.stride;
+    let update = |bits: &mut Vec<u64>, x: i32| {
+      let (word, bit) = (&mut bits[row + x as usize / 64], 1u64 << (x % 64));
+      if solid { *word |= bit } else { *word &= !bit }
+    };
+    update(&mut self.bits, x);
+    update(&mut self.mirrored, self.width - 1 - x);
+    true
+  }
+
+  /// Returns the number of solid pixels.
+  pub fn count(&self) -> usize {
+    self.bits.iter().map(|w| w.count_ones() as usize).sum()
+  }
+
+  /// Places the mask with its top left corner at `pos`.
+  pub fn at(&self, pos: Vi2d) -> Placement<'_> {
+    self.at_ext(pos, SpriteFlip::NONE)
+  }
+
+  /// Places the mask with its top left corner at `pos`, flipped within its bounds.
+  pub fn at_ext(&self, pos: Vi2d, flip: SpriteFlip) -> Placement<'_> {
+    Placement { mask: self, pos, flip, transform: None }
+  }
+
+  /// Places the mask through the transform from mask pixels to world pixels, as drawn by
+  /// [`draw_sprite_transformed`](crate::draw_sprite_transformed).
+  pub fn transformed(&self, transform: &Transform2D) -> Placement<'_> {
+    Placement { mask: self, pos: Vi2d::new(0, 0), flip: SpriteFlip::NONE, transform: Some(*transform) }
+  }
+
+  fn row(&self, y: i32, mirrored: bool) -> &[u64] {
+    let start = y as usize * self.stride;
+    let bits = if mirrored { &self.mirrored } else { &self.bits };
+    &bits[start..start + self.stride]
+  }
+}
+
+// Returns 64 pixels of the row starting at `x`, pixels outside of the row are empty.
+fn window(row: &[u64], x: i32) -> u64 {
+  let (word, shift) = (x.div_euclid(64), x.rem_euclid(64) as u32);
+  let get = |i: i32| if i >= 0 && (i as usize) < row.len() { row[i as usize] } else { 0 };
+  let lo = get(word) >> shift;
+  let hi = if shift == 0 { 0 } else { get(word + 1) << (64 - shift) };
+  lo | hi
+}
+
+/// Region where two placements overlap.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Overlap {
+  /// Top left corner of the bounding box of the overlapping pixels.
+  pub pos: Vi2d,
+  /// Size of the bounding box.
+  pub size: Vi2d,
+  /// Number of overlapping pixels.
+  pub count: usize
+}
+
+/// Collision mask placed in the world, see [`CollisionMask::at`](CollisionMask::at).
+#[derive(Clone, Copy, Debug)]
+pub struct Placement<'a> {
+  mask: &'a CollisionMask,
+  pos: Vi2d,
+  flip: SpriteFlip,
+  transform: Option<Transform2D>
+}
+
+impl<'a> Placement<'a> {
+  /// Returns the mask.
+  pub fn mask(&self) -> &'a CollisionMask {
+    self.mask
+  }
+
+  /// Returns true if any solid pixels of the placements overlap.
+  pub fn overlaps(&self, other: &Placement<'_>) -> bool {
+    self.contact(other).is_some()
+  }
+
+  /// Returns the first overlapping pixel, scanning the world top to bottom and left to right.
+  pub fn contact(&self, other: &Placement<'_>) -> Option<Vi2d> {
+    let mut contact = None;
+    self.scan(other, |x, y, bits| {
+      contact = Some(Vi2d::new(x + bits.trailing_zeros() as i32, y));
+      false
+    });
+    contact
+  }
+
+  /// Returns the bounding box and the number of the overlapping pixels, `None` if the
+  /// placements do not overlap.
+  pub fn overlap(&self, other: &Placement<'_>) -> Option<Overlap> {
+    let (mut lo, mut hi, mut count) = (Vi2d::new(i32::MAX, i32::MAX), Vi2d::new(i32::MIN, i32::MIN), 0);
+    self.scan(other, |x, y, bits| {
+      lo = lo.min(Vi2d::new(x + bits.trailing_zeros() as i32, y));
+      hi = hi.max(Vi2d::new(x + 63 - bits.leading_zeros() as i32, y));
+      count += bits.count_ones() as usize;
+      true
+    });
+    if count == 0 {
+      return None;
+    }
+    Some(Overlap { pos: lo, size: hi - lo + Vi2d::new(1, 1), count })
+  }
+
+  // World bounding box, top left inclusive and bottom right exclusive.
+  fn bounds(&self) -> (Vi2d, Vi2d) {
+    let size = Vi2d::new(self.mask.width, self.mask.height);
+    match self.transform {
+      None => (self.pos, self.pos + size),
+      Some(t) => {
+        let size = Vf2d::from(size);
+        let corners = [Vf2d::new(0.0, 0.0), Vf2d::new(size.x, 0.0), Vf2d::new(0.0, size.y), size];
+        let (mut lo, mut hi) = (t.forward(corners[0]), t.forward(corners[0]));
+        for c in corners.iter().skip(1) {
+          lo = lo.min(t.forward(*c));
+          hi = hi.max(t.forward(*c));
+        }
+        (lo.floor().into(), Vi2d::new(hi.x.ceil() as i32, hi.y.ceil() as i32))
+      }
+    }
+  }
+
+  // Transform from mask pixels to world pixels, including position and flip.
+  fn world_transform(&self) -> Transform2D {
+    if let Some(t) = self.transform {
+      return t;
+    }
+    let mut t = Transform2D::new();
+    match self.flip {
+      SpriteFlip::HORIZ => {
+        t.scale(-1.0, 1.0);
+        t.translate(self.mask.width as f32, 0.0);
+      }
+      SpriteFlip::VERT => {
+        t.scale(1.0, -1.0);
+        t.translate(0.0, self.mask.height as f32);
+      }
+      SpriteFlip::NONE => {}
+    }
+    t.translate(self.pos.x as f32, self.pos.y as f32);
+    t
+  }
+
+  // Returns the row of the mask covering world row `y`, for placements without a transform.
+  fn world_row(&self, y: i32) -> &'a [u64] {
+    let y = y - self.pos.y;
+    let y = if self.flip == SpriteFlip::VERT { self.mask.height - 1 - y } else { y };
+    self.mask.row(y, self.flip == SpriteFlip::HORIZ)
+  }
+
+  // Calls `f` with up to 64 overlapping pixels at a time, starting at world (x, y), in scan
+  // order until it returns false.
+  fn scan(&self, other: &Placement<'_>, mut f: impl FnMut(i32, i32, u64) -> bool) {
+    let (a_lo, a_hi) = self.bounds();
+    let (b_lo, b_hi) = other.bounds();
+    let (lo, hi) = (a_lo.max(b_lo), a_hi.min(b_hi));
+    if lo.x >= hi.x || lo.y >= hi.y {
+      return;
+    }
+    if self.transform.is_none() && other.transform.is_none() {
+      for y in lo.y..hi.y {
+        let (a, b) = (self.world_row(y), other.world_row(y));
+        for x in (lo.x..hi.x).step_by(64) {
+          let mut bits = window(a, x - self.pos.x) & window(b, x - other.pos.x);
+          if hi.x - x < 64 {
+            bits &= (1 << (hi.x - x)) - 1;
+          }
+          if bits != 0 && !f(x, y, bits) {
+            return;
+          }
+        }
+      }
+      return;
+    }
+    let (a, b) = match (self.world_transform().inverse(), other.world_transform().inverse()) {
+      (Some(a), Some(b)) => (a, b),
+      _ => return
+    };
+    let solid = |mask: &CollisionMask, t: &Transform2D, p: Vf2d| {
+      let p = t.forward(p).floor();
+      mask.get(p.x as i32, p.y as i32)
+    };
+    for y in lo.y..hi.y {
+      for x in lo.x..hi.x {
+        let p = Vf2d::new(x as f32 + 0.5, y as f32 + 0.5);
+        if solid(self.mask, &a, p) && solid(other.mask, &b, p) && !f(x, y, 1) {
+          return;
+        }
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{Pixel, BLANK, WHITE};
+
+  // Mask from rows of '#' for solid and '.' for empty pixels.
+  fn mask(rows: &[&str]) -> CollisionMask {
+    let mut mask = CollisionMask::new(rows[0].len() as i32, rows.len() as i32);
+    for (y, row) in rows.iter().enumerate() {
+      for (x, c) in row.chars().enumerate() {
+        mask.set(x as i32, y as i32, c == '#');
+      }
+    }
+    mask
+  }
+
+  // Overlap computed one pixel at a time.
+  fn brute_force(a: &CollisionMask, pa: Vi2d, fa: SpriteFlip, b: &CollisionMask, pb: Vi2d, fb: SpriteFlip) -> Option<Overlap> {
+    let local = |m: &CollisionMask, p: Vi2d, f: SpriteFlip, x: i32, y: i32| {
+      let (x, y) = (x - p.x, y - p.y);
+      match f {
+        SpriteFlip::HORIZ => m.get(m.width() - 1 - x, y),
+        SpriteFlip::VERT => m.get(x, m.height() - 1 - y),
+        SpriteFlip::NONE => m.get(x, y)
+      }
+    };
+    let mut pixels = Vec::new();
+    for y in -200..200 {
+      for x in -200..200 {
+        if local(a, pa, fa, x, y) && local(b, pb, fb, x, y) {
+          pixels.push(Vi2d::new(x, y));
+        }
+      }
+    }
+    let first = *pixels.first()?;
+    let lo = pixels.iter().fold(first, |lo, p| lo.min(*p));
+    let hi = pixels.iter().fold(first, |hi, p| hi.max(*p));
+    Some(Overlap { pos: lo, size: hi - lo + Vi2d::new(1, 1), count: pixels.len() })
+  }
+
+  #[test]
+  fn test_mask() {
+    let mut sprite = Sprite::with_dims(70, 2);
+    sprite.pixels_mut().fill(BLANK);
+    sprite.set_pixel(0, 0, WHITE);
+    sprite.set_pixel(66, 1, Pixel::rgba(255, 255, 255, 100));
+    let m = CollisionMask::from_sprite(&sprite);
+    assert_eq!((m.width(), m.height(), m.count()), (70, 2, 2));
+    assert!(m.get(0, 0) && m.get(66, 1) && !m.get(1, 0) && !m.get(70, 1) && !m.get(-1, 0));
+    let m = CollisionMask::from_sprite_ext(&sprite, Frame::new(Vi2d::new(60, 0), Vi2d::new(20, 2)), 128);
+    assert_eq!((m.width(), m.count()), (20, 0));
+    let m = CollisionMask::from_sprite_ext(&sprite, Frame::new(Vi2d::new(60, 0), Vi2d::new(20, 2)), 100);
+    assert!(m.get(6, 1));
+    assert!(!CollisionMask::new(4, 4).set(4, 0, true));
+  }
+
+  #[test]
+  fn test_overlap() {
+    let l = mask(&["#..", "#..", "###"]);
+    let dot = mask(&["#"]);
+    let at = |x, y| Vi2d::new(x, y);
+    assert!(l.at(at(10, 10)).overlaps(&dot.at(at(10, 11))));
+    assert!(!l.at(at(10, 10)).overlaps(&dot.at(at(11, 11))));
+    assert!(l.at_ext(at(10, 10), SpriteFlip::HORIZ).overlaps(&dot.at(at(12, 11))));
+    assert!(l.at_ext(at(10, 10), SpriteFlip::VERT).overlaps(&dot.at(at(12, 10))));
+
+    let overlap = l.at(at(0, 0)).overlap(&l.at(at(-1, -1))).unwrap();
+    assert_eq!(overlap, Overlap { pos: at(0, 1), size: at(1, 1), count: 1 });
+    assert_eq!(l.at(at(0, 0)).contact(&l.at(at(-1, -1))), Some(at(0, 1)));
+    assert_eq!(l.at(at(0, 0)).overlap(&l.at(at(3, 0))), None);
+  }
+
+  #[test]
+  fn test_overlap_matches_pixels() {
+    // Irregular masks wider than a word, at offsets crossing word boundaries.
+    let mut seed = 12345u32;
+    let mut random = || {
+      seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+      seed >> 16
+    };
+    let mut a = CollisionMask::new(100, 7);
+    let mut b = CollisionMask::new(70, 5);
+    for m in [&mut a, &mut b].iter_mut() {
+      for y in 0..m.height() {
+        for x in 0..m.width() {
+          m.set(x, y, random() % 7 == 0);
+        }
+      }
+    }
+    let flips = [SpriteFlip::NONE, SpriteFlip::HORIZ, SpriteFlip::VERT];
+    for i in 0..60 {
+      let pb = Vi2d::new(random() as i32 % 200 - 90, random() as i32 % 14 - 6);
+      let (fa, fb) = (flips[i % 3], flips[i / 3 % 3]);
+      let expected = brute_force(&a, Vi2d::new(3, 0), fa, &b, pb, fb);
+      assert_eq!(a.at_ext(Vi2d::new(3, 0), fa).overlap(&b.at_ext(pb, fb)), expected, "{} {:?} {:?}", pb, fa, fb);
+      // The same placements through transforms.
+      let (ta, tb) = (a.at_ext(Vi2d::new(3, 0), fa).world_transform(), b.at_ext(pb, fb).world_transform());
+      assert_eq!(a.transformed(&ta).overlap(&b.transformed(&tb)), expected);
+      assert_eq!(a.transformed(&ta).overlap(&b.at_ext(pb, fb)), expected);
+    }
+  }
+
+  #[test]
+  fn test_transformed() {
+    let bar = mask(&["####"]);
+    let dot = mask(&["#"]);
+    // Bar rotated a quarter turn around its left end and scaled twice, covering (9..11, 10..18).
+    let mut t = Transform2D::new();
+    t.rotate(std::f32::consts::FRAC_PI_2);
+    t.scale(2.0, 2.0);
+    t.translate(11.0, 10.0);
+    let bar = bar.transformed(&t);
+    assert!(bar.overlaps(&dot.at(Vi2d::new(10, 17))));
+    assert!(!bar.overlaps(&dot.at(Vi2d::new(11, 12))));
+    assert_eq!(bar.overlap(&mask(&["####"; 20]).at(Vi2d::new(8, 0))), Some(Overlap { pos: Vi2d::new(9, 10), size: Vi2d::new(2, 8), count: 16 }));
+    let mut degenerate = Transform2D::new();
+    degenerate.scale(0.0, 1.0);
+    assert!(!dot.transformed(&degenerate).overlaps(&dot.at(Vi2d::new(0, 0))));
+  }
+}
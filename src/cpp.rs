@@ -32,6 +32,25 @@ pub enum PixelMode {
   CUSTOM
 }
 
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[allow(non_camel_case_types)]
+/// Mirror of `olc::DecalMode`, how decals are blended with what is already drawn.
+pub enum DecalMode {
+  /// `olc::DecalMode::NORMAL` = Alpha blending.
+  NORMAL,
+  /// `olc::DecalMode::ADDITIVE` = Adds the colour weighted by alpha, for light and fire.
+  ADDITIVE,
+  /// `olc::DecalMode::MULTIPLICATIVE` = Multiplies by the colour, for shadows and light maps.
+  MULTIPLICATIVE,
+  /// `olc::DecalMode::STENCIL` = Keeps what is drawn where the decal is opaque, clears the rest.
+  STENCIL,
+  /// `olc::DecalMode::ILLUMINATE` = Reveals what is drawn where the decal is transparent.
+  ILLUMINATE,
+  /// `olc::DecalMode::WIREFRAME` = Draws the outline of the decal quad in the tint colour.
+  WIREFRAME
+}
+
 /// Generic 2D vector type. See [`Vf2d`](Vf2d) and [`Vi2d`](Vi2d) for more information.
 ///
 /// Don't use this generic struct directly.
@@ -47,28 +66,33 @@ pub struct V2d<T> {
 
 /// Mirror of `olc::vi2d`. A 2D integer vector type.
 /// Implements `std::ops::Add`, `std::ops::Sub`, `std::ops::Mul`, and `std::ops::Div` as well as
-/// all their assignment equivalents.
+/// all their assignment equivalents. `Mul` and `Div` also accept a scalar, `Neg` negates both
+/// components. Integer division truncates towards zero and panics on division by zero, same as
+/// `i32`.
 ///
 /// Example usage:
 /// ```
 /// # extern crate olc_pixel_game_engine;
 /// # use crate::olc_pixel_game_engine::Vi2d;
 /// let mut a = Vi2d::new(1, 2) + Vi2d { x: 3, y: 4 };
-/// a *= (2i32, 2i32).into();
+/// a *= Vi2d::from((2, 2));
 /// assert_eq!(a, Vi2d::new(8, 12));
+/// assert_eq!(-a / 4, Vi2d::new(-2, -3));
 /// ```
 pub type Vi2d = V2d<i32>;
 /// Mirror of `olc::vf2d`. A 2D float vector type.
 /// Implements `std::ops::Add`, `std::ops::Sub`, `std::ops::Mul`, and `std::ops::Div` as well as
-/// all their assignment equivalents.
+/// all their assignment equivalents. `Mul` and `Div` also accept a scalar, `Neg` negates both
+/// components.
 ///
 /// Example usage:
 /// ```
 /// # extern crate olc_pixel_game_engine;
 /// # use crate::olc_pixel_game_engine::Vf2d;
 /// let mut a = Vf2d::new(10.0, 20.0) - Vf2d { x: 5.0, y: 5.0 };
-/// a /= (5f32, 5f32).into();
+/// a /= Vf2d::from((5.0, 5.0));
 /// assert_eq!(a, Vf2d::new(1.0, 3.0));
+/// assert_eq!(2.0 * a, Vf2d::new(2.0, 6.0));
 /// ```
 pub type Vf2d = V2d<c_float>;
 
@@ -145,21 +169,14 @@ pub struct Decal {
   olc_decal: *const c_void
 }
 
-/// Mirror of the `olc::LayerDesc`. Contains layer description, must be treated as read-only since
-/// no modifications to the object are propagated back to the engine.
-/// Does not support Clone and Copy, used as a container for layer information.
+// Internal mirror of the `olc::LayerDesc`, filled in by `GetDrawTarget`.
+// The sprite is owned by the engine.
 #[repr(C)]
-#[derive(Debug, PartialEq)]
 pub struct LayerDesc {
-  /// Layer id.
   pub id: u8,
-  /// Layer offset.
   pub offset: Vf2d,
-  /// Layer scale.
   pub scale: Vf2d,
-  /// Layer tint.
   pub tint: Pixel,
-  /// Whether or not this layer is enabled to be rendered.
   pub shown: bool,
   pub sprite: Sprite // layer backing sprite
 }
@@ -182,6 +199,7 @@ extern "C" {
   pub fn SpriteConstructor(w: i32, h: i32) -> Sprite;
   pub fn SpriteDestructor(s: &Sprite);
   pub fn SpriteLoadFromFile(s: &Sprite, image_file: *const c_char) -> RCode;
+  pub fn SpriteSaveToPGESprFile(s: &Sprite, image_file: *const c_char) -> RCode;
   pub fn SpriteWidth(s: &Sprite) -> i32;
   pub fn SpriteHeight(s: &Sprite) -> i32;
   pub fn SpriteHasData(s: &Sprite) -> bool;
@@ -191,10 +209,12 @@ extern "C" {
   pub fn SpriteSetPixel(s: &Sprite, x: i32, y: i32, p: Pixel) -> bool;
   pub fn SpriteSample(s: &Sprite, x: c_float, y: c_float) -> Pixel;
   pub fn SpriteSampleBL(s: &Sprite, u: c_float, v: c_float) -> Pixel;
+  pub fn SpriteGetData(s: &Sprite) -> *mut Pixel;
 
   pub fn DecalConstructor(s: &Sprite) -> Decal;
   pub fn DecalId(d: &Decal) -> i32;
   pub fn DecalScale(d: &Decal) -> Vf2d;
+  pub fn DecalUpdate(d: &Decal, s: &Sprite);
   pub fn DecalDestructor(d: &Decal);
 
   // olcPixelGameEngine API
@@ -219,16 +239,20 @@ extern "C" {
   pub fn GetDrawTargetWidth() -> i32;
   // Returns the height of the currently selected drawing target in "pixels"
   pub fn GetDrawTargetHeight() -> i32;
+  // Returns the currently active draw target, the sprite is not owned by the caller
+  pub fn GetDrawTargetSprite() -> Sprite;
   // Resize the primary screen sprite
   pub fn SetScreenSize(w: i32, h: i32);
+  // Specify which Sprite should be the target of drawing functions, null for the primary screen
+  pub fn SetDrawTargetSprite(target: &Sprite);
   // Gets the current Frames Per Second
   pub fn GetFPS() -> u32;
 
   pub fn CreateLayer() -> u32;
   pub fn SetPrimaryDrawTarget();
   pub fn SetDrawTarget(layer: u8);
-  pub fn GetDrawTarget(layer: u8) -> LayerDesc;
-  pub fn GetPrimaryDrawTarget() -> LayerDesc;
+  pub fn GetDrawTarget(layer: u8, desc: *mut LayerDesc) -> bool;
+  pub fn GetPrimaryDrawTarget(desc: *mut LayerDesc) -> bool;
   pub fn EnableLayer(layer: u8, b: bool);
   pub fn SetLayerOffset(layer: u8, x: c_float, y: c_float);
   pub fn SetLayerScale(layer: u8, x: c_float, y: c_float);
@@ -282,6 +306,8 @@ extern "C" {
 
   pub fn DrawStringDecal(pos: &Vf2d, sText: *const c_char, col: Pixel, scale: &Vf2d);
   pub fn DrawString(x: i32, y: i32, sText: *const c_char, col: Pixel, scale: u32);
+  pub fn SetDecalMode(m: DecalMode);
+  pub fn GetDecalMode() -> DecalMode;
 
   // Clears entire draw target to Pixel
   pub fn Clear(p: Pixel);
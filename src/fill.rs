@@ -0,0 +1,330 @@
+//! Fill styles for the filled drawing primitives.
+//!
+//! A [`FillStyle`](FillStyle) describes how each pixel of a filled shape is coloured: a solid
+//! colour, a linear or radial gradient with any number of colour stops, or a repeating pattern
+//! taken from a sprite. Styles are passed to [`fill_rect_with_style`](crate::fill_rect_with_style),
+//! [`fill_circle_with_style`](crate::fill_circle_with_style) and
+//! [`fill_triangle_with_style`](crate::fill_triangle_with_style), which draw onto the current
+//! draw target and respect the current pixel mode.
+//!
+//! Gradient and pattern coordinates are in draw target space, so adjacent shapes sharing a style
+//! line up seamlessly.
+//!
+//! ```no_run
+//! # extern crate olc_pixel_game_engine;
+//! # use crate::olc_pixel_game_engine as olc;
+//! use olc::fill::{FillStyle, Gradient};
+//!
+//! // Sky background from dark blue at the top to cyan at the horizon.
+//! let sky = FillStyle::LinearGradient {
+//!   start: olc::Vf2d::new(0.0, 0.0),
+//!   end: olc::Vf2d::new(0.0, 100.0),
+//!   gradient: Gradient::new(olc::DARK_BLUE, olc::CYAN)
+//! };
+//! olc::fill_rect_with_style(0, 0, 200, 100, &sky);
+//! ```
+
+use crate::{Pixel, Sprite, Vf2d, Vi2d};
+
+/// Colour at a position along a gradient, `offset` is between 0.0 and 1.0.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColourStop {
+  pub offset: f32,
+  pub colour: Pixel
+}
+
+impl ColourStop {
+  /// Creates a new colour stop.
+  pub fn new(offset: f32, colour: Pixel) -> Self {
+    Self { offset, colour }
+  }
+}
+
+/// Colour gradient defined by a list of colour stops.
+/// Positions before the first stop or after the last stop use the colour of that stop.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient {
+  stops: Vec<ColourStop>
+}
+
+impl Gradient {
+  /// Creates a gradient between two colours.
+  pub fn new(start: Pixel, end: Pixel) -> Self {
+    Self::with_stops(vec![ColourStop::new(0.0, start), ColourStop::new(1.0, end)])
+  }
+
+  /// Creates a gradient from colour stops, stops are sorted by offset.
+  ///
+  /// Panics if `stops` is empty.
+  pub fn with_stops(mut stops: Vec<ColourStop>) -> Self {
+    assert!(!stops.is_empty(), "Expected at least one colour stop");
+    stops.sort_by(|a, b| a.offset.partial_cmp(&b.offset).unwrap_or(std::cmp::Ordering::Equal));
+    Self { stops }
+  }
+
+  /// Returns colour stops sorted by offset.
+  pub fn stops(&self) -> &[ColourStop] {
+    &self.stops
+  }
+
+  /// Returns colour at position `t` along the gradient.
+  pub fn colour_at(&self, t: f32) -> Pixel {
+    let first = self.stops[0];
+    if t <= first.offset {
+      return first.colour;
+    }
+    for pair in self.stops.windows(2) {
+      let (a, b) = (pair[0], pair[1]);
+      if t <= b.offset {
+        let span = b.offset - a.offset;
+        let k = if span > 0.0 { (t - a.offset) / span } else { 1.0 };
+        return crate::pixel_lerp(a.colour, b.colour, k);
+      }
+    }
+    self.stops[self.stops.len() - 1].colour
+  }
+}
+
+/// Describes how filled primitives colour each pixel.
+#[derive(Clone, Debug)]
+pub enum FillStyle<'a> {
+  /// Single colour, same as the regular filled primitives.
+  Solid(Pixel),
+  /// Gradient along the line from `start` to `end`, constant across it.
+  LinearGradient { start: Vf2d, end: Vf2d, gradient: Gradient },
+  /// Gradient from `center` (offset 0.0) to the circle of `radius` (offset 1.0).
+  RadialGradient { center: Vf2d, radius: f32, gradient: Gradient },
+  /// Sprite repeated in both directions, with its top left corner placed at `offset`.
+  Pattern { sprite: &'a Sprite, offset: Vi2d }
+}
+
+impl FillStyle<'_> {
+  /// Returns colour of the pixel at (x, y) in draw target space.
+  /// Gradients are sampled at the pixel centre.
+  pub fn colour_at(&self, x: i32, y: i32) -> Pixel {
+    match self {
+      FillStyle::Solid(p) => *p,
+      FillStyle::LinearGradient { start, end, gradient } => {
+        let dir = *end - *start;
+        let len2 = dir.mag2();
+        if len2 <= 0.0 {
+          return gradient.colour_at(0.0);
+        }
+        let p = Vf2d::new(x as f32 + 0.5, y as f32 + 0.5) - *start;
+        gradient.colour_at(p.dot(dir) / len2)
+      },
+      FillStyle::RadialGradient { center, radius, gradient } => {
+        if *radius <= 0.0 {
+          return gradient.colour_at(1.0);
+        }
+        let p = Vf2d::new(x as f32 + 0.5, y as f32 + 0.5) - *center;
+        gradient.colour_at(p.mag() / radius)
+      },
+      FillStyle::Pattern { sprite, offset } => {
+        let (w, h) = (sprite.width(), sprite.height());
+        if w <= 0 || h <= 0 {
+          return crate::BLANK;
+        }
+        let i = (y - offset.y).rem_euclid(h) * w + (x - offset.x).rem_euclid(w);
+        sprite.pixels().get(i as usize).copied().unwrap_or(crate::BLANK)
+      }
+    }
+  }
+}
+
+//----------------------------------
+// Rasterisation
+//----------------------------------
+
+// Draws the horizontal span [sx, ex] on row y, clipped to the draw target.
+fn span(sx: i32, ex: i32, y: i32, style: &FillStyle) {
+  if y < 0 || y >= crate::get_draw_target_height() {
+    return;
+  }
+  let sx = sx.max(0);
+  let ex = ex.min(crate::get_draw_target_width() - 1);
+  for x in sx..=ex {
+    crate::draw(x, y, style.colour_at(x, y));
+  }
+}
+
+pub(crate) fn fill_rect(x: i32, y: i32, w: i32, h: i32, style: &FillStyle) {
+  let ex = x.saturating_add(w).saturating_sub(1);
+  let ey = y.saturating_add(h).min(crate::get_draw_target_height());
+  for j in y.max(0)..ey {
+    span(x, ex, j, style);
+  }
+}
+
+pub(crate) fn fill_circle(x: i32, y: i32, radius: i32, style: &FillStyle) {
+  // A radius of 0 still draws the centre pixel, like `olc::FillCircle`.
+  if radius < 0 {
+    return;
+  }
+  // Same midpoint algorithm as `olc::FillCircle`, but each scanline is only drawn once,
+  // so that blending does not accumulate on overlapping spans.
+  let mut half_widths = vec![-1; (2 * radius + 1) as usize];
+  let mut widen = |dy: i32, half: i32| {
+    let row = &mut half_widths[(dy + radius) as usize];
+    *row = (*row).max(half);
+  };
+  let (mut x0, mut y0) = (0, radius);
+  let mut d = 3 - 2 * radius;
+  while y0 >= x0 {
+    widen(-y0, x0);
+    widen(-x0, y0);
+    widen(y0, x0);
+    widen(x0, y0);
+    if d < 0 {
+      d += 4 * x0 + 6;
+      x0 += 1;
+    } else {
+      d += 4 * (x0 - y0) + 10;
+      x0 += 1;
+      y0 -= 1;
+    }
+  }
+  for (i, half) in half_widths.iter().enumerate() {
+    if *half >= 0 {
+      span(x - half, x + half, y + i as i32 - radius, style);
+    }
+  }
+}
+
+pub(crate) fn fill_triangle(x1: i32, y1: i32, x2: i32, y2: i32, x3: i32, y3: i32, style: &FillStyle) {
+  // Integer edge functions, wide enough for any pair of i32 points.
+  let points = [(x1 as i128, y1 as i128), (x2 as i128, y2 as i128), (x3 as i128, y3 as i128)];
+  let ((ax, ay), (bx, by), (cx, cy)) = (points[0], points[1], points[2]);
+  let area = (bx - ax) * (cy - ay) - (by - ay) * (cx - ax);
+  if area == 0 {
+    return;
+  }
+  let sign = area.signum();
+  let min_x = x1.min(x2).min(x3).max(0);
+  let max_x = x1.max(x2).max(x3).min(crate::get_draw_target_width() - 1);
+  let min_y = y1.min(y2).min(y3).max(0);
+  let max_y = y1.max(y2).max(y3).min(crate::get_draw_target_height() - 1);
+  'rows: for y in min_y..=max_y {
+    // Pixel (x, y) is covered when it lies inside or on the edges of the triangle. Each edge
+    // function is linear in x, so the covered pixels of a row are the span where all three
+    // are non-negative.
+    let (mut sx, mut ex) = (min_x as i128, max_x as i128);
+    for (i, &(px, py)) in points.iter().enumerate() {
+      let (qx, qy) = points[(i + 1) % 3];
+      // Edge function a * x + b.
+      let a = -(qy - py) * sign;
+      let b = ((qx - px) * (y as i128 - py) + (qy - py) * px) * sign;
+      if a > 0 {
+        sx = sx.max(-(b.div_euclid(a)));
+      } else if a < 0 {
+        ex = ex.min(b.div_euclid(-a));
+      } else if b < 0 {
+        continue 'rows;
+      }
+    }
+    if sx <= ex {
+      span(sx as i32, ex as i32, y, style);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_gradient_colour_at() {
+    let g = Gradient::new(crate::BLACK, crate::WHITE);
+    assert_eq!(g.colour_at(-1.0), crate::BLACK);
+    assert_eq!(g.colour_at(0.0), crate::BLACK);
+    assert_eq!(g.colour_at(1.0), crate::WHITE);
+    assert_eq!(g.colour_at(2.0), crate::WHITE);
+    assert_eq!(g.colour_at(0.5), Pixel::rgb(128, 128, 128));
+  }
+
+  #[test]
+  fn test_gradient_multiple_stops() {
+    let g = Gradient::with_stops(vec![
+      ColourStop::new(1.0, crate::BLUE),
+      ColourStop::new(0.0, crate::RED),
+      ColourStop::new(0.5, crate::GREEN)
+    ]);
+    assert_eq!(g.stops()[1].colour, crate::GREEN);
+    assert_eq!(g.colour_at(0.5), crate::GREEN);
+    assert_eq!(g.colour_at(0.75), Pixel::rgb(0, 128, 128));
+  }
+
+  #[test]
+  fn test_linear_gradient_style() {
+    let style = FillStyle::LinearGradient {
+      start: Vf2d::new(0.0, 0.0),
+      end: Vf2d::new(10.0, 0.0),
+      gradient: Gradient::new(crate::BLACK, crate::WHITE)
+    };
+    assert_eq!(style.colour_at(0, 5), style.colour_at(0, 100));
+    assert!(style.colour_at(2, 0).r < style.colour_at(8, 0).r);
+    assert_eq!(style.colour_at(20, 0), crate::WHITE);
+  }
+
+  #[test]
+  fn test_radial_gradient_style() {
+    let style = FillStyle::RadialGradient {
+      center: Vf2d::new(5.0, 5.0),
+      radius: 5.0,
+      gradient: Gradient::new(crate::WHITE, crate::BLACK)
+    };
+    assert!(style.colour_at(5, 5).r > 200);
+    assert_eq!(style.colour_at(20, 20), crate::BLACK);
+  }
+
+  #[test]
+  fn test_fill_clipped() {
+    let _lock = crate::tests::engine_lock();
+    let style = FillStyle::LinearGradient {
+      start: Vf2d::new(0.0, 0.0),
+      end: Vf2d::new(1.0, 0.0),
+      gradient: Gradient::new(crate::RED, crate::RED)
+    };
+    let mut sprite = Sprite::with_dims(4, 4);
+    let covered = |sprite: &Sprite| -> Vec<bool> { sprite.pixels().iter().map(|&p| p == crate::RED).collect() };
+    sprite.fill(crate::BLANK);
+    crate::with_draw_target(&mut sprite, || fill_triangle(0, 0, 3, 0, 0, 3, &style));
+    let expected: Vec<bool> = (0..16).map(|i| i % 4 + i / 4 <= 3).collect();
+    assert_eq!(covered(&sprite), expected);
+    // Winding does not matter.
+    sprite.fill(crate::BLANK);
+    crate::with_draw_target(&mut sprite, || fill_triangle(0, 3, 3, 0, 0, 0, &style));
+    assert_eq!(covered(&sprite), expected);
+    // Huge shapes only visit the pixels of the draw target.
+    sprite.fill(crate::BLANK);
+    crate::with_draw_target(&mut sprite, || fill_triangle(-1_000_000, -1_000_000, 1_000_000, -1_000_000, 0, 1_000_000, &style));
+    assert!(covered(&sprite).iter().all(|&c| c));
+    sprite.fill(crate::BLANK);
+    crate::with_draw_target(&mut sprite, || fill_rect(1, 2, i32::MAX, i32::MAX, &style));
+    let expected: Vec<bool> = (0..16).map(|i| i % 4 >= 1 && i / 4 >= 2).collect();
+    assert_eq!(covered(&sprite), expected);
+    sprite.fill(crate::BLANK);
+    crate::with_draw_target(&mut sprite, || fill_circle(1, 2, 0, &style));
+    assert_eq!(covered(&sprite).iter().position(|&c| c), Some(9));
+    assert_eq!(covered(&sprite).iter().filter(|&&c| c).count(), 1);
+    let mut solid = Sprite::with_dims(4, 4);
+    solid.fill(crate::BLANK);
+    crate::with_draw_target(&mut solid, || crate::fill_circle(1, 2, 0, crate::RED));
+    assert_eq!(covered(&solid), covered(&sprite));
+    sprite.fill(crate::BLANK);
+    crate::with_draw_target(&mut sprite, || fill_rect(i32::MIN, i32::MIN, i32::MAX, i32::MAX, &style));
+    assert!(covered(&sprite).iter().all(|&c| !c));
+  }
+
+  #[test]
+  fn test_pattern_style() {
+    let mut sprite = Sprite::with_dims(2, 2);
+    sprite.set_pixel(0, 0, crate::RED);
+    sprite.set_pixel(1, 1, crate::BLUE);
+    let style = FillStyle::Pattern { sprite: &sprite, offset: Vi2d::new(1, 1) };
+    assert_eq!(style.colour_at(1, 1), crate::RED);
+    assert_eq!(style.colour_at(3, 5), crate::RED);
+    assert_eq!(style.colour_at(0, 0), crate::BLUE);
+    assert_eq!(style.colour_at(-2, -2), crate::BLUE);
+  }
+}
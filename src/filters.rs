@@ -0,0 +1,464 @@
+//! Image filters for sprites, computed on the CPU.
+//!
+//! Colour adjustments such as [`greyscale`](greyscale) or [`saturation`](saturation) modify a
+//! sprite in place, clone the sprite first to keep the original. Filters that combine
+//! neighbouring pixels, such as blurs, convolutions, outlines and drop shadows, return a new
+//! sprite of the same size.
+//!
+//! Blurs weight colours by alpha, so transparent pixels do not darken the edges of shapes.
+//! Convolutions repeat the edge pixels of the sprite, while outlines and shadows are clipped
+//! to the sprite, use [`pad`](pad) to make room for them.
+//!
+//! ```no_run
+//! # extern crate olc_pixel_game_engine;
+//! # use crate::olc_pixel_game_engine as olc;
+//! use olc::filters;
+//!
+//! let icon = olc::Sprite::from_image("icon.png").unwrap();
+//! // Disabled variant of a button icon.
+//! let mut disabled = icon.clone();
+//! filters::greyscale(&mut disabled);
+//! filters::brightness(&mut disabled, -0.2);
+//! // Highlighted variant with a yellow stroke and a soft shadow.
+//! let highlighted = filters::outline(&filters::pad(&icon, 4), 2, olc::YELLOW);
+//! let highlighted = filters::drop_shadow(&highlighted, olc::Vi2d::new(2, 2), 1.5, olc::Pixel::rgba(0, 0, 0, 128));
+//! ```
+
+use crate::{Error, Pixel, Sprite, Vi2d, BLANK};
+
+// Colour premultiplied by alpha, channels from 0.0 to 255.0.
+type Rgba = [f32; 4];
+
+fn to_u8(v: f32) -> u8 {
+  v.round().clamp(0.0, 255.0) as u8
+}
+
+fn size(sprite: &Sprite) -> (usize, usize) {
+  (sprite.width().max(0) as usize, sprite.height().max(0) as usize)
+}
+
+fn luma(c: [f32; 3]) -> f32 {
+  0.299 * c[0] + 0.587 * c[1] + 0.114 * c[2]
+}
+
+// Composites `fg` over `bg`.
+fn over(fg: Pixel, bg: Pixel) -> Pixel {
+  let (fa, ba) = (fg.a as f32 / 255.0, bg.a as f32 / 255.0 * (1.0 - fg.a as f32 / 255.0));
+  let a = fa + ba;
+  if a <= 0.0 {
+    return BLANK;
+  }
+  let mix = |f: u8, b: u8| to_u8((f as f32 * fa + b as f32 * ba) / a);
+  Pixel::rgba(mix(fg.r, bg.r), mix(fg.g, bg.g), mix(fg.b, bg.b), to_u8(a * 255.0))
+}
+
+fn premultiplied(sprite: &Sprite) -> Vec<Rgba> {
+  sprite.pixels().iter().map(|p| {
+    let a = p.a as f32 / 255.0;
+    [p.r as f32 * a, p.g as f32 * a, p.b as f32 * a, p.a as f32]
+  }).collect()
+}
+
+fn from_premultiplied(width: usize, height: usize, data: &[Rgba]) -> Sprite {
+  let mut sprite = Sprite::with_dims(width as i32, height as i32);
+  for (p, c) in sprite.pixels_mut().iter_mut().zip(data) {
+    let a = to_u8(c[3]);
+    *p = if a == 0 {
+      BLANK
+    } else {
+      let scale = 255.0 / c[3];
+      Pixel::rgba(to_u8(c[0] * scale), to_u8(c[1] * scale), to_u8(c[2] * scale), a)
+    };
+  }
+  sprite
+}
+
+// Convolves rows or columns with the kernel centred on each pixel, repeating the edge pixels.
+fn convolve_1d(data: &[Rgba], width: usize, height: usize, weights: &[f32], horizontal: bool) -> Vec<Rgba> {
+  let radius = (weights.len() / 2) as isize;
+  let mut out = vec![[0.0; 4]; data.len()];
+  for y in 0..height {
+    for x in 0..width {
+      let mut acc = [0.0; 4];
+      for (i, w) in weights.iter().enumerate() {
+        let d = i as isize - radius;
+        let (sx, sy) = if horizontal {
+          ((x as isize + d).clamp(0, width as isize - 1) as usize, y)
+        } else {
+          (x, (y as isize + d).clamp(0, height as isize - 1) as usize)
+        };
+        let s = data[sy * width + sx];
+        for c in 0..4 {
+          acc[c] += s[c] * w;
+        }
+      }
+      out[y * width + x] = acc;
+    }
+  }
+  out
+}
+
+// Blurs premultiplied colour with the kernel applied to rows and then to columns.
+fn separable(data: &[Rgba], width: usize, height: usize, weights: &[f32]) -> Vec<Rgba> {
+  let rows = convolve_1d(data, width, height, weights, true);
+  convolve_1d(&rows, width, height, weights, false)
+}
+
+// Largest radius of blur kernels, wider kernels would only allocate memory.
+const MAX_RADIUS: u32 = 1024;
+
+fn gaussian_weights(sigma: f32) -> Vec<f32> {
+  if sigma.is_nan() || sigma <= 0.0 {
+    return vec![1.0];
+  }
+  // Weights further than 3 sigma from the centre are negligible.
+  let radius = (sigma * 3.0).ceil().min(MAX_RADIUS as f32) as i32;
+  let weights: Vec<f32> = (-radius..=radius).map(|x| (-(x * x) as f32 / (2.0 * sigma * sigma)).exp()).collect();
+  let sum: f32 = weights.iter().sum();
+  weights.iter().map(|w| w / sum).collect()
+}
+
+/// Convolution kernel, a grid of weights with odd width and height centred on the pixel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Kernel {
+  width: usize,
+  height: usize,
+  weights: Vec<f32>
+}
+
+impl Kernel {
+  /// Creates a kernel from the weights in row-major order, `width * height` long. Weights are
+  /// used as given, they are not normalised.
+  /// Returns error if the width or height is even or the number of weights does not match.
+  pub fn new(width: usize, height: usize, weights: &[f32]) -> Result<Self, Error> {
+    if width.is_multiple_of(2) || height.is_multiple_of(2) {
+      return Err(Error { msg: format!("Failed to create the kernel: {}x{} is not odd", width, height) });
+    }
+    if weights.len() != width * height {
+      return Err(Error { msg: format!("Failed to create the kernel: expected {} weights, got {}", width * height, weights.len()) });
+    }
+    Ok(Self { width, height, weights: weights.to_vec() })
+  }
+
+  /// Averages the pixels within `radius` pixels horizontally and vertically, the radius is
+  /// limited to 1024.
+  pub fn box_blur(radius: u32) -> Self {
+    let size = 2 * radius.min(MAX_RADIUS) as usize + 1;
+    Self { width: size, height: size, weights: vec![1.0 / (size * size) as f32; size * size] }
+  }
+
+  /// Gaussian blur with the standard deviation `sigma` in pixels.
+  pub fn gaussian(sigma: f32) -> Self {
+    let w = gaussian_weights(sigma);
+    Self { width: w.len(), height: w.len(), weights: w.iter().flat_map(|a| w.iter().map(move |b| a * b)).collect() }
+  }
+
+  /// Sharpens edges, `amount` of 0.0 leaves the image unchanged.
+  pub fn sharpen(amount: f32) -> Self {
+    let a = -amount;
+    Self { width: 3, height: 3, weights: vec![0.0, a, 0.0, a, 1.0 + 4.0 * amount, a, 0.0, a, 0.0] }
+  }
+
+  /// Laplacian edge detection, uniform areas turn black.
+  pub fn edge_detect() -> Self {
+    Self { width: 3, height: 3, weights: vec![-1.0, -1.0, -1.0, -1.0, 8.0, -1.0, -1.0, -1.0, -1.0] }
+  }
+
+  /// Embossing lit from the top left.
+  pub fn emboss() -> Self {
+    Self { width: 3, height: 3, weights: vec![-2.0, -1.0, 0.0, -1.0, 1.0, 1.0, 0.0, 1.0, 2.0] }
+  }
+
+  pub fn width(&self) -> usize {
+    self.width
+  }
+
+  pub fn height(&self) -> usize {
+    self.height
+  }
+
+  /// Returns the weights in row-major order.
+  pub fn weights(&self) -> &[f32] {
+    &self.weights
+  }
+}
+
+/// Returns the sprite blurred by averaging the pixels within `radius` pixels horizontally and
+/// vertically, the radius is limited to 1024.
+pub fn box_blur(sprite: &Sprite, radius: u32) -> Sprite {
+  let (width, height) = size(sprite);
+  let size = 2 * radius.min(MAX_RADIUS) as usize + 1;
+  let data = separable(&premultiplied(sprite), width, height, &vec![1.0 / size as f32; size]);
+  from_premultiplied(width, height, &data)
+}
+
+/// Returns the sprite blurred with a Gaussian of the standard deviation `sigma` in pixels.
+pub fn gaussian_blur(sprite: &Sprite, sigma: f32) -> Sprite {
+  let (width, height) = size(sprite);
+  let data = separable(&premultiplied(sprite), width, height, &gaussian_weights(sigma));
+  from_premultiplied(width, height, &data)
+}
+
+/// Returns the sprite with sharpened edges, see [`Kernel::sharpen`](Kernel::sharpen).
+pub fn sharpen(sprite: &Sprite, amount: f32) -> Sprite {
+  convolve(sprite, &Kernel::sharpen(amount))
+}
+
+/// Returns the sprite with the colour channels convolved with the kernel, alpha is kept.
+pub fn convolve(sprite: &Sprite, kernel: &Kernel) -> Sprite {
+  let (width, height) = size(sprite);
+  let (rx, ry) = ((kernel.width / 2) as isize, (kernel.height / 2) as isize);
+  let src = sprite.pixels();
+  let mut out = sprite.clone();
+  for (i, p) in out.pixels_mut().iter_mut().enumerate() {
+    let (x, y) = ((i % width) as isize, (i / width) as isize);
+    let mut acc = [0.0; 3];
+    for (k, w) in kernel.weights.iter().enumerate() {
+      let sx = (x + (k % kernel.width) as isize - rx).clamp(0, width as isize - 1) as usize;
+      let sy = (y + (k / kernel.width) as isize - ry).clamp(0, height as isize - 1) as usize;
+      let s = src[sy * width + sx];
+      acc[0] += s.r as f32 * w;
+      acc[1] += s.g as f32 * w;
+      acc[2] += s.b as f32 * w;
+    }
+    *p = Pixel::rgba(to_u8(acc[0]), to_u8(acc[1]), to_u8(acc[2]), p.a);
+  }
+  out
+}
+
+/// Returns the sprite with a transparent border of `border` pixels on each side.
+pub fn pad(sprite: &Sprite, border: u32) -> Sprite {
+  let (width, height) = size(sprite);
+  let b = border as usize;
+  let mut out = Sprite::with_dims((width + 2 * b) as i32, (height + 2 * b) as i32);
+  out.pixels_mut().fill(BLANK);
+  if width > 0 {
+    for (src, dst) in sprite.rows().zip(out.rows_mut().skip(b)) {
+      dst[b..b + width].copy_from_slice(src);
+    }
+  }
+  out
+}
+
+/// Returns the sprite with a stroke of `colour` around its opaque areas, reaching `width`
+/// pixels out. The stroke is drawn behind the sprite and follows the alpha of its edges.
+pub fn outline(sprite: &Sprite, width: u32, colour: Pixel) -> Sprite {
+  let (w, h) = size(sprite);
+  let r = width.min(MAX_RADIUS) as isize;
+  let disc: Vec<(isize, isize)> = (-r..=r)
+    .flat_map(|dy| (-r..=r).map(move |dx| (dx, dy)))
+    .filter(|(dx, dy)| dx * dx + dy * dy <= r * r)
+    .collect();
+  let src = sprite.pixels();
+  let mut out = sprite.clone();
+  for (i, p) in out.pixels_mut().iter_mut().enumerate() {
+    if p.a == 255 {
+      continue;
+    }
+    let (x, y) = ((i % w) as isize, (i / w) as isize);
+    let coverage = disc.iter()
+      .filter(|(dx, dy)| (0..w as isize).contains(&(x + dx)) && (0..h as isize).contains(&(y + dy)))
+      .map(|(dx, dy)| src[(y + dy) as usize * w + (x + dx) as usize].a)
+      .max()
+      .unwrap_or(0);
+    let stroke = Pixel::rgba(colour.r, colour.g, colour.b, (coverage as u32 * colour.a as u32 / 255) as u8);
+    *p = over(*p, stroke);
+  }
+  out
+}
+
+/// Returns the sprite over its shadow, a silhouette in `colour` moved by `offset` and blurred
+/// with a Gaussian of the standard deviation `blur` in pixels.
+pub fn drop_shadow(sprite: &Sprite, offset: Vi2d, blur: f32, colour: Pixel) -> Sprite {
+  let (width, height) = size(sprite);
+  let src = sprite.pixels();
+  let mut data = vec![[0.0; 4]; src.len()];
+  for (i, c) in data.iter_mut().enumerate() {
+    let (x, y) = ((i % width) as i32 - offset.x, (i / width) as i32 - offset.y);
+    if x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height {
+      let a = src[y as usize * width + x as usize].a as f32 * colour.a as f32 / 255.0;
+      *c = [colour.r as f32 * a / 255.0, colour.g as f32 * a / 255.0, colour.b as f32 * a / 255.0, a];
+    }
+  }
+  let shadow = from_premultiplied(width, height, &separable(&data, width, height, &gaussian_weights(blur)));
+  let mut out = shadow;
+  for (p, s) in out.pixels_mut().iter_mut().zip(src) {
+    *p = over(*s, *p);
+  }
+  out
+}
+
+// Maps the colour channels of every pixel, alpha is kept.
+fn map_colour(sprite: &mut Sprite, f: impl Fn([f32; 3]) -> [f32; 3]) {
+  for p in sprite.pixels_mut() {
+    let [r, g, b] = f([p.r as f32, p.g as f32, p.b as f32]);
+    *p = Pixel::rgba(to_u8(r), to_u8(g), to_u8(b), p.a);
+  }
+}
+
+/// Adds `amount` to the colour channels, from -1.0 (black) to 1.0 (white).
+pub fn brightness(sprite: &mut Sprite, amount: f32) {
+  let d = amount * 255.0;
+  map_colour(sprite, |[r, g, b]| [r + d, g + d, b + d]);
+}
+
+/// Scales the difference of the colour channels from mid grey by `factor`, 1.0 leaves the
+/// sprite unchanged and 0.0 turns it grey.
+pub fn contrast(sprite: &mut Sprite, factor: f32) {
+  let f = |c: f32| (c - 127.5) * factor + 127.5;
+  map_colour(sprite, |[r, g, b]| [f(r), f(g), f(b)]);
+}
+
+/// Scales the difference of the colours from their grey level by `factor`, 1.0 leaves the
+/// sprite unchanged, 0.0 turns it grey and larger values make colours more vivid.
+pub fn saturation(sprite: &mut Sprite, factor: f32) {
+  map_colour(sprite, |c| {
+    let l = luma(c);
+    [l + (c[0] - l) * factor, l + (c[1] - l) * factor, l + (c[2] - l) * factor]
+  });
+}
+
+/// Converts the colours to their grey level.
+pub fn greyscale(sprite: &mut Sprite) {
+  map_colour(sprite, |c| [luma(c); 3]);
+}
+
+/// Converts the colours to sepia tones.
+pub fn sepia(sprite: &mut Sprite) {
+  map_colour(sprite, |[r, g, b]| [
+    0.393 * r + 0.769 * g + 0.189 * b,
+    0.349 * r + 0.686 * g + 0.168 * b,
+    0.272 * r + 0.534 * g + 0.131 * b
+  ]);
+}
+
+/// Replaces pixels that differ from `from` by at most `tolerance` in each channel, including
+/// alpha, with `to`.
+/// Returns the number of replaced pixels.
+pub fn replace_colour(sprite: &mut Sprite, from: Pixel, to: Pixel, tolerance: u8) -> usize {
+  let close = |a: u8, b: u8| a.abs_diff(b) <= tolerance;
+  let mut count = 0;
+  for p in sprite.pixels_mut() {
+    if close(p.r, from.r) && close(p.g, from.g) && close(p.b, from.b) && close(p.a, from.a) {
+      *p = to;
+      count += 1;
+    }
+  }
+  count
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{BLUE, RED, WHITE};
+
+  fn sprite(width: i32, height: i32, pixels: &[Pixel]) -> Sprite {
+    let mut sprite = Sprite::with_dims(width, height);
+    sprite.pixels_mut().copy_from_slice(pixels);
+    sprite
+  }
+
+  // Transparent sprite with a single opaque pixel in the centre.
+  fn dot(size: i32, p: Pixel) -> Sprite {
+    let mut sprite = sprite(size, size, &vec![BLANK; (size * size) as usize]);
+    sprite.set_pixel(size / 2, size / 2, p);
+    sprite
+  }
+
+  #[test]
+  fn test_colour_adjustments() {
+    let pixels = [Pixel::rgba(200, 100, 50, 128), Pixel::rgb(0, 128, 255)];
+    let mut s = sprite(2, 1, &pixels);
+    brightness(&mut s, 0.2);
+    assert_eq!(s.pixels(), [Pixel::rgba(251, 151, 101, 128), Pixel::rgb(51, 179, 255)]);
+    let mut s = sprite(2, 1, &pixels);
+    contrast(&mut s, 0.0);
+    assert!(s.pixels().iter().all(|p| (p.r, p.g, p.b) == (128, 128, 128)));
+    let mut s = sprite(2, 1, &pixels);
+    saturation(&mut s, 1.0);
+    assert_eq!(s.pixels(), pixels);
+    saturation(&mut s, 0.0);
+    let mut grey = sprite(2, 1, &pixels);
+    greyscale(&mut grey);
+    assert_eq!(s.pixels(), grey.pixels());
+    assert_eq!(grey.pixels(), [Pixel::rgba(124, 124, 124, 128), Pixel::rgb(104, 104, 104)]);
+    let mut s = sprite(1, 1, &[WHITE]);
+    sepia(&mut s);
+    assert_eq!(s.pixels(), [Pixel::rgb(255, 255, 239)]);
+
+    let mut s = sprite(4, 1, &[RED, Pixel::rgb(250, 3, 0), Pixel::rgba(255, 0, 0, 200), BLUE]);
+    assert_eq!(replace_colour(&mut s, RED, WHITE, 5), 2);
+    assert_eq!(s.pixels(), [WHITE, WHITE, Pixel::rgba(255, 0, 0, 200), BLUE]);
+  }
+
+  #[test]
+  fn test_blur() {
+    // Colour of the dot spreads without darkening, alpha is conserved.
+    for blurred in [box_blur(&dot(5, RED), 1), gaussian_blur(&dot(5, RED), 0.8)].iter() {
+      assert_eq!(blurred.get_pixel(1, 2), Pixel::rgba(255, 0, 0, blurred.get_pixel(1, 2).a));
+      assert!(blurred.get_pixel(1, 2).a > 0 && blurred.get_pixel(2, 2).a < 255);
+      assert_eq!(blurred.get_pixel(1, 2), blurred.get_pixel(3, 2));
+      assert_eq!(blurred.get_pixel(2, 1), blurred.get_pixel(2, 3));
+      let total: u32 = blurred.pixels().iter().map(|p| p.a as u32).sum();
+      assert!((250..=260).contains(&total), "{}", total);
+    }
+    assert_eq!(box_blur(&dot(5, RED), 1).get_pixel(2, 2).a, 28);
+    assert_eq!(box_blur(&dot(5, RED), 1).get_pixel(0, 0), BLANK);
+    assert_eq!(gaussian_blur(&dot(3, RED), 0.0).pixels(), dot(3, RED).pixels());
+    assert_eq!(box_blur(&Sprite::with_dims(0, 0), 2).width(), 0);
+    // Huge radii are capped instead of allocating a kernel for them.
+    assert_eq!(Kernel::box_blur(u32::MAX).width(), 2049);
+    let flat = box_blur(&sprite(2, 1, &[RED, RED]), u32::MAX);
+    assert_eq!(flat.pixels(), [RED, RED]);
+  }
+
+  #[test]
+  fn test_convolve() {
+    assert!(Kernel::new(2, 1, &[1.0, 1.0]).is_err());
+    assert!(Kernel::new(3, 1, &[1.0, 1.0]).is_err());
+    let identity = Kernel::new(1, 3, &[0.0, 1.0, 0.0]).unwrap();
+    assert_eq!((identity.width(), identity.height()), (1, 3));
+    let s = sprite(2, 2, &[RED, BLUE, Pixel::rgba(1, 2, 3, 4), WHITE]);
+    assert_eq!(convolve(&s, &identity).pixels(), s.pixels());
+
+    let uniform = sprite(3, 3, &[Pixel::rgba(100, 150, 200, 90); 9]);
+    assert_eq!(sharpen(&uniform, 2.0).pixels(), uniform.pixels());
+    assert!(convolve(&uniform, &Kernel::edge_detect()).pixels().iter().all(|&p| p == Pixel::rgba(0, 0, 0, 90)));
+    let blurred = convolve(&uniform, &Kernel::gaussian(1.0));
+    assert_eq!(blurred.pixels(), uniform.pixels());
+    assert!((Kernel::box_blur(1).weights().iter().sum::<f32>() - 1.0).abs() < 1e-6);
+
+    // Sharpening increases the difference of neighbours.
+    let s = sprite(3, 1, &[Pixel::rgb(100, 100, 100), Pixel::rgb(150, 150, 150), Pixel::rgb(100, 100, 100)]);
+    let sharp = sharpen(&s, 1.0);
+    assert_eq!(sharp.get_pixel(1, 0), Pixel::rgb(250, 250, 250));
+    assert_eq!(sharp.get_pixel(0, 0), Pixel::rgb(50, 50, 50));
+  }
+
+  #[test]
+  fn test_outline_and_shadow() {
+    let padded = pad(&sprite(1, 1, &[RED]), 2);
+    assert_eq!((padded.width(), padded.height()), (5, 5));
+    assert_eq!(padded.pixels(), dot(5, RED).pixels());
+
+    let outlined = outline(&padded, 1, BLUE);
+    assert_eq!(outlined.get_pixel(2, 2), RED);
+    for (x, y) in [(1, 2), (3, 2), (2, 1), (2, 3)].iter() {
+      assert_eq!(outlined.get_pixel(*x, *y), BLUE);
+    }
+    assert_eq!(outlined.get_pixel(1, 1), BLANK);
+    assert_eq!(outline(&padded, 2, BLUE).get_pixel(1, 1), BLUE);
+    // Semi-transparent edges blend over the stroke.
+    let edge = outline(&sprite(2, 1, &[Pixel::rgba(255, 0, 0, 128), BLANK]), 1, Pixel::rgba(0, 0, 255, 128));
+    assert_eq!(edge.pixels(), [Pixel::rgba(204, 0, 51, 160), Pixel::rgba(0, 0, 255, 64)]);
+
+    let shadow = Pixel::rgba(0, 0, 0, 128);
+    let shadowed = drop_shadow(&padded, Vi2d::new(1, 1), 0.0, shadow);
+    assert_eq!(shadowed.get_pixel(2, 2), RED);
+    assert_eq!(shadowed.get_pixel(3, 3), shadow);
+    assert_eq!(shadowed.get_pixel(1, 1), BLANK);
+    let soft = drop_shadow(&padded, Vi2d::new(1, 0), 1.0, shadow);
+    assert_eq!(soft.get_pixel(2, 2), RED);
+    assert!(soft.get_pixel(3, 2).a < 128 && soft.get_pixel(3, 3).a > 0 && soft.get_pixel(4, 2).a > 0);
+  }
+}
@@ -0,0 +1,746 @@
+//! 2D geometry over [`Vf2d`](crate::Vf2d).
+//!
+//! Provides [`Rect`](Rect), [`Circle`](Circle), [`Line`](Line), [`Triangle`](Triangle) and
+//! [`Polygon`](Polygon) shapes that implement the [`Shape`](Shape) trait with containment,
+//! overlap, intersection, closest point and envelope queries, and a [`Ray`](Ray) that can be cast
+//! against any shape. Shapes can be drawn with the existing drawing routines.
+//!
+//! Lines are treated as segments, all other shapes are treated as filled, so a circle contains its
+//! centre and overlaps any shape fully inside it. Intersections are computed between the shape
+//! boundaries and return the contact points.
+//!
+//! ```
+//! # extern crate olc_pixel_game_engine;
+//! # use crate::olc_pixel_game_engine as olc;
+//! use olc::geometry::{Circle, Rect, Shape};
+//!
+//! let paddle = Rect::new(olc::Vf2d::new(10.0, 90.0), olc::Vf2d::new(30.0, 5.0));
+//! let ball = Circle::new(olc::Vf2d::new(20.0, 87.0), 4.0);
+//! assert!(ball.overlaps(&paddle));
+//! assert_eq!(ball.intersects(&paddle).len(), 2);
+//! ```
+
+use crate::{Pixel, Vf2d};
+
+// Tolerance used for comparisons of floating point coordinates.
+const EPSILON: f32 = 1e-4;
+
+mod private {
+  use super::{Circle, Line};
+  use crate::Vf2d;
+
+  // Boundary representation of a shape used to compute intersections.
+  pub enum Boundary {
+    Segments(Vec<Line>),
+    Circle(Circle)
+  }
+
+  pub trait Sealed {
+    // Returns the boundary of the shape.
+    fn boundary(&self) -> Boundary;
+    // Returns any point that belongs to the shape.
+    fn anchor(&self) -> Vf2d;
+  }
+}
+
+use private::Boundary;
+
+/// Common queries supported by all bounded shapes.
+/// This trait is sealed and can't be implemented outside of this crate.
+pub trait Shape: private::Sealed {
+  /// Returns true if the point is inside the shape or on its boundary.
+  fn contains(&self, p: Vf2d) -> bool;
+
+  /// Returns the point of the shape closest to `p`, which is `p` itself if it is contained.
+  fn closest_point(&self, p: Vf2d) -> Vf2d;
+
+  /// Returns axis aligned bounding rectangle of the shape.
+  fn envelope_r(&self) -> Rect;
+
+  /// Returns a bounding circle of the shape, not necessarily the smallest one.
+  fn envelope_c(&self) -> Circle {
+    let r = self.envelope_r();
+    Circle::new(r.middle(), r.size.mag() / 2.0)
+  }
+
+  /// Draws outline of the shape.
+  fn draw(&self, p: Pixel);
+
+  /// Returns true if the shapes share at least one point.
+  fn overlaps<S: Shape>(&self, other: &S) -> bool where Self: Sized {
+    if !self.envelope_r().overlaps_rect(&other.envelope_r()) {
+      return false;
+    }
+    other.contains(self.anchor()) || self.contains(other.anchor()) ||
+      !intersect_boundaries(&self.boundary(), &other.boundary()).is_empty()
+  }
+
+  /// Returns points where the boundaries of the shapes intersect.
+  fn intersects<S: Shape>(&self, other: &S) -> Vec<Vf2d> where Self: Sized {
+    intersect_boundaries(&self.boundary(), &other.boundary())
+  }
+
+  /// Returns true if `other` is entirely inside this shape.
+  fn contains_shape<S: Shape>(&self, other: &S) -> bool where Self: Sized {
+    match (self.boundary(), other.boundary()) {
+      (_, Boundary::Segments(edges)) => edges.iter().all(|e| segment_inside(self, e)),
+      (Boundary::Circle(s), Boundary::Circle(c)) => (c.pos - s.pos).mag() + c.radius <= s.radius + EPSILON,
+      (Boundary::Segments(edges), Boundary::Circle(c)) => self.contains(c.pos) &&
+        edges.iter().all(|e| (e.closest_point(c.pos) - c.pos).mag() >= c.radius - EPSILON)
+    }
+  }
+}
+
+/// Axis aligned rectangle with top left corner at `pos`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Rect {
+  pub pos: Vf2d,
+  pub size: Vf2d
+}
+
+impl Rect {
+  /// Creates a new rectangle.
+  pub fn new(pos: Vf2d, size: Vf2d) -> Self {
+    Self { pos, size }
+  }
+
+  /// Creates the smallest rectangle containing both points.
+  pub fn from_points(a: Vf2d, b: Vf2d) -> Self {
+    let pos = a.min(b);
+    Self { pos, size: a.max(b) - pos }
+  }
+
+  /// Returns centre of the rectangle.
+  pub fn middle(&self) -> Vf2d {
+    self.pos + self.size * 0.5
+  }
+
+  /// Returns the four corners in clockwise order starting from the top left one.
+  pub fn corners(&self) -> [Vf2d; 4] {
+    [
+      self.pos,
+      Vf2d::new(self.pos.x + self.size.x, self.pos.y),
+      self.pos + self.size,
+      Vf2d::new(self.pos.x, self.pos.y + self.size.y)
+    ]
+  }
+
+  /// Returns the four edges in clockwise order starting from the top one.
+  pub fn edges(&self) -> [Line; 4] {
+    let c = self.corners();
+    [Line::new(c[0], c[1]), Line::new(c[1], c[2]), Line::new(c[2], c[3]), Line::new(c[3], c[0])]
+  }
+
+  /// Returns area of the rectangle.
+  pub fn area(&self) -> f32 {
+    self.size.x * self.size.y
+  }
+
+  /// Fast overlap test between two axis aligned rectangles.
+  pub fn overlaps_rect(&self, other: &Rect) -> bool {
+    self.pos.x <= other.pos.x + other.size.x && other.pos.x <= self.pos.x + self.size.x &&
+      self.pos.y <= other.pos.y + other.size.y && other.pos.y <= self.pos.y + self.size.y
+  }
+
+  /// Fills the rectangle.
+  pub fn fill(&self, p: Pixel) {
+    crate::fill_rect(self.pos.x as i32, self.pos.y as i32, self.size.x as i32, self.size.y as i32, p)
+  }
+}
+
+impl private::Sealed for Rect {
+  fn boundary(&self) -> Boundary {
+    Boundary::Segments(self.edges().to_vec())
+  }
+
+  fn anchor(&self) -> Vf2d {
+    self.pos
+  }
+}
+
+impl Shape for Rect {
+  fn contains(&self, p: Vf2d) -> bool {
+    p.x >= self.pos.x - EPSILON && p.x <= self.pos.x + self.size.x + EPSILON &&
+      p.y >= self.pos.y - EPSILON && p.y <= self.pos.y + self.size.y + EPSILON
+  }
+
+  fn closest_point(&self, p: Vf2d) -> Vf2d {
+    p.clamp(self.pos, self.pos + self.size)
+  }
+
+  fn envelope_r(&self) -> Rect {
+    *self
+  }
+
+  fn draw(&self, p: Pixel) {
+    crate::draw_rect(self.pos.x as i32, self.pos.y as i32, self.size.x as i32, self.size.y as i32, p)
+  }
+}
+
+/// Circle with centre at `pos`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Circle {
+  pub pos: Vf2d,
+  pub radius: f32
+}
+
+impl Circle {
+  /// Creates a new circle.
+  pub fn new(pos: Vf2d, radius: f32) -> Self {
+    Self { pos, radius }
+  }
+
+  /// Returns area of the circle.
+  pub fn area(&self) -> f32 {
+    std::f32::consts::PI * self.radius * self.radius
+  }
+
+  /// Fills the circle.
+  pub fn fill(&self, p: Pixel) {
+    crate::fill_circle(self.pos.x as i32, self.pos.y as i32, self.radius as i32, p)
+  }
+}
+
+impl private::Sealed for Circle {
+  fn boundary(&self) -> Boundary {
+    Boundary::Circle(*self)
+  }
+
+  fn anchor(&self) -> Vf2d {
+    self.pos
+  }
+}
+
+impl Shape for Circle {
+  fn contains(&self, p: Vf2d) -> bool {
+    (p - self.pos).mag2() <= (self.radius + EPSILON) * (self.radius + EPSILON)
+  }
+
+  fn closest_point(&self, p: Vf2d) -> Vf2d {
+    if self.contains(p) {
+      p
+    } else {
+      self.pos + (p - self.pos).norm() * self.radius
+    }
+  }
+
+  fn envelope_r(&self) -> Rect {
+    let r = Vf2d::new(self.radius, self.radius);
+    Rect::new(self.pos - r, r * 2.0)
+  }
+
+  fn envelope_c(&self) -> Circle {
+    *self
+  }
+
+  fn draw(&self, p: Pixel) {
+    crate::draw_circle(self.pos.x as i32, self.pos.y as i32, self.radius as i32, p)
+  }
+}
+
+/// Line segment between `start` and `end`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Line {
+  pub start: Vf2d,
+  pub end: Vf2d
+}
+
+impl Line {
+  /// Creates a new line segment.
+  pub fn new(start: Vf2d, end: Vf2d) -> Self {
+    Self { start, end }
+  }
+
+  /// Returns vector from `start` to `end`.
+  pub fn vector(&self) -> Vf2d {
+    self.end - self.start
+  }
+
+  /// Returns length of the segment.
+  pub fn length(&self) -> f32 {
+    self.vector().mag()
+  }
+
+  /// Returns point at parameter `t`, where 0.0 is `start` and 1.0 is `end`.
+  pub fn point_at(&self, t: f32) -> Vf2d {
+    self.start + self.vector() * t
+  }
+
+  /// Returns which side of the line the point is on: positive on one side, negative on the
+  /// other side and zero if the point is on the line.
+  pub fn side(&self, p: Vf2d) -> f32 {
+    self.vector().cross(p - self.start)
+  }
+}
+
+impl private::Sealed for Line {
+  fn boundary(&self) -> Boundary {
+    Boundary::Segments(vec![*self])
+  }
+
+  fn anchor(&self) -> Vf2d {
+    self.start
+  }
+}
+
+impl Shape for Line {
+  fn contains(&self, p: Vf2d) -> bool {
+    (self.closest_point(p) - p).mag2() <= EPSILON * EPSILON
+  }
+
+  fn closest_point(&self, p: Vf2d) -> Vf2d {
+    let d = self.vector();
+    let len2 = d.mag2();
+    if len2 == 0.0 {
+      return self.start;
+    }
+    let t = ((p - self.start).dot(d) / len2).clamp(0.0, 1.0);
+    self.point_at(t)
+  }
+
+  fn envelope_r(&self) -> Rect {
+    Rect::from_points(self.start, self.end)
+  }
+
+  fn draw(&self, p: Pixel) {
+    crate::draw_line(self.start.x as i32, self.start.y as i32, self.end.x as i32, self.end.y as i32, p)
+  }
+}
+
+/// Triangle defined by three points.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Triangle {
+  pub pos: [Vf2d; 3]
+}
+
+impl Triangle {
+  /// Creates a new triangle.
+  pub fn new(a: Vf2d, b: Vf2d, c: Vf2d) -> Self {
+    Self { pos: [a, b, c] }
+  }
+
+  /// Returns the three edges of the triangle.
+  pub fn edges(&self) -> [Line; 3] {
+    let p = self.pos;
+    [Line::new(p[0], p[1]), Line::new(p[1], p[2]), Line::new(p[2], p[0])]
+  }
+
+  /// Returns area of the triangle.
+  pub fn area(&self) -> f32 {
+    let p = self.pos;
+    ((p[1] - p[0]).cross(p[2] - p[0]) / 2.0).abs()
+  }
+
+  /// Fills the triangle.
+  pub fn fill(&self, p: Pixel) {
+    let v = self.pos;
+    crate::fill_triangle(v[0].x as i32, v[0].y as i32, v[1].x as i32, v[1].y as i32, v[2].x as i32, v[2].y as i32, p)
+  }
+}
+
+impl private::Sealed for Triangle {
+  fn boundary(&self) -> Boundary {
+    Boundary::Segments(self.edges().to_vec())
+  }
+
+  fn anchor(&self) -> Vf2d {
+    self.pos[0]
+  }
+}
+
+impl Shape for Triangle {
+  fn contains(&self, p: Vf2d) -> bool {
+    let [e0, e1, e2] = self.edges();
+    let (s0, s1, s2) = (e0.side(p), e1.side(p), e2.side(p));
+    let eps = EPSILON * self.area().sqrt().max(1.0);
+    (s0 >= -eps && s1 >= -eps && s2 >= -eps) || (s0 <= eps && s1 <= eps && s2 <= eps)
+  }
+
+  fn closest_point(&self, p: Vf2d) -> Vf2d {
+    if self.contains(p) { p } else { closest_on_edges(&self.edges(), p) }
+  }
+
+  fn envelope_r(&self) -> Rect {
+    envelope_of(&self.pos)
+  }
+
+  fn draw(&self, p: Pixel) {
+    let v = self.pos;
+    crate::draw_triangle(v[0].x as i32, v[0].y as i32, v[1].x as i32, v[1].y as i32, v[2].x as i32, v[2].y as i32, p)
+  }
+}
+
+/// Closed polygon defined by its vertices, may be concave.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Polygon {
+  pub points: Vec<Vf2d>
+}
+
+impl Polygon {
+  /// Creates a new polygon.
+  pub fn new(points: Vec<Vf2d>) -> Self {
+    Self { points }
+  }
+
+  /// Returns edges of the polygon, including the edge from the last point to the first one.
+  pub fn edges(&self) -> Vec<Line> {
+    let n = self.points.len();
+    (0..n).map(|i| Line::new(self.points[i], self.points[(i + 1) % n])).collect()
+  }
+
+  /// Returns area of the polygon.
+  pub fn area(&self) -> f32 {
+    (self.edges().iter().map(|e| e.start.cross(e.end)).sum::<f32>() / 2.0).abs()
+  }
+}
+
+impl private::Sealed for Polygon {
+  fn boundary(&self) -> Boundary {
+    Boundary::Segments(self.edges())
+  }
+
+  fn anchor(&self) -> Vf2d {
+    self.points.first().copied().unwrap_or_default()
+  }
+}
+
+impl Shape for Polygon {
+  fn contains(&self, p: Vf2d) -> bool {
+    let edges = self.edges();
+    if edges.iter().any(|e| e.contains(p)) {
+      return true;
+    }
+    // Even-odd rule, count crossings of a horizontal ray cast to the right.
+    let mut inside = false;
+    for e in edges.iter() {
+      let (a, b) = (e.start, e.end);
+      if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) * (b.x - a.x) / (b.y - a.y) {
+        inside = !inside;
+      }
+    }
+    inside
+  }
+
+  fn closest_point(&self, p: Vf2d) -> Vf2d {
+    if self.contains(p) { p } else { closest_on_edges(&self.edges(), p) }
+  }
+
+  fn envelope_r(&self) -> Rect {
+    envelope_of(&self.points)
+  }
+
+  fn draw(&self, p: Pixel) {
+    for e in self.edges() {
+      e.draw(p);
+    }
+  }
+}
+
+/// Half-line starting at `origin` and extending in `direction`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Ray {
+  pub origin: Vf2d,
+  pub direction: Vf2d
+}
+
+impl Ray {
+  /// Creates a new ray, direction does not have to be normalised.
+  pub fn new(origin: Vf2d, direction: Vf2d) -> Self {
+    Self { origin, direction }
+  }
+
+  /// Returns points where the ray crosses the boundary of the shape,
+  /// sorted by distance from the origin.
+  pub fn intersects<S: Shape>(&self, shape: &S) -> Vec<Vf2d> {
+    let mut res = Vec::new();
+    match shape.boundary() {
+      Boundary::Segments(edges) => {
+        for e in edges {
+          param_intersect_segment(self.origin, self.direction, 0.0, f32::INFINITY, &e, &mut res);
+        }
+      },
+      Boundary::Circle(c) => param_intersect_circle(self.origin, self.direction, 0.0, f32::INFINITY, &c, &mut res)
+    }
+    let mut res = dedup(res);
+    res.sort_by(|a, b| {
+      let (da, db) = ((*a - self.origin).mag2(), (*b - self.origin).mag2());
+      da.partial_cmp(&db).unwrap_or(std::cmp::Ordering::Equal)
+    });
+    res
+  }
+
+  /// Returns the first point where the ray hits the boundary of the shape.
+  pub fn first_hit<S: Shape>(&self, shape: &S) -> Option<Vf2d> {
+    self.intersects(shape).first().copied()
+  }
+
+  /// Returns true if the point lies on the ray.
+  pub fn contains(&self, p: Vf2d) -> bool {
+    let d = p - self.origin;
+    d.mag2() <= EPSILON * EPSILON ||
+      (self.direction.norm_or_zero().cross(d).abs() <= EPSILON && d.dot(self.direction) >= 0.0)
+  }
+
+  /// Draws the ray up to the edge of the current draw target.
+  pub fn draw(&self, p: Pixel) {
+    let reach = (crate::get_draw_target_width() + crate::get_draw_target_height()) as f32 * 2.0;
+    let end = self.origin + self.direction.norm_or_zero() * reach;
+    Line::new(self.origin, end).draw(p)
+  }
+}
+
+//----------------------------------
+// Internal helpers
+//----------------------------------
+
+#[inline]
+fn close(a: Vf2d, b: Vf2d) -> bool {
+  (a - b).mag2() <= EPSILON * EPSILON
+}
+
+fn dedup(points: Vec<Vf2d>) -> Vec<Vf2d> {
+  let mut res: Vec<Vf2d> = Vec::with_capacity(points.len());
+  for p in points {
+    if !res.iter().any(|q| close(*q, p)) {
+      res.push(p);
+    }
+  }
+  res
+}
+
+// Returns true if the segment is entirely inside the shape: every piece of the segment between
+// the points where it crosses the shape boundary must be inside the shape.
+fn segment_inside<S: Shape + ?Sized>(shape: &S, seg: &Line) -> bool {
+  let mut cuts = vec![0.0, 1.0];
+  let len2 = seg.vector().mag2();
+  if len2 > 0.0 {
+    for p in intersect_boundaries(&shape.boundary(), &Boundary::Segments(vec![*seg])) {
+      cuts.push((p - seg.start).dot(seg.vector()) / len2);
+    }
+  }
+  cuts.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+  shape.contains(seg.start) && shape.contains(seg.end) &&
+    cuts.windows(2).all(|w| shape.contains(seg.point_at((w[0] + w[1]) / 2.0)))
+}
+
+fn envelope_of(points: &[Vf2d]) -> Rect {
+  match points.split_first() {
+    Some((first, rest)) => {
+      let (lo, hi) = rest.iter().fold((*first, *first), |(lo, hi), p| (lo.min(*p), hi.max(*p)));
+      Rect::from_points(lo, hi)
+    },
+    None => Rect::default()
+  }
+}
+
+fn closest_on_edges(edges: &[Line], p: Vf2d) -> Vf2d {
+  edges.iter()
+    .map(|e| e.closest_point(p))
+    .min_by(|a, b| (*a - p).mag2().partial_cmp(&(*b - p).mag2()).unwrap_or(std::cmp::Ordering::Equal))
+    .unwrap_or(p)
+}
+
+// Intersects parametric line `origin + t * dir` for t in [t_min, t_max] with a segment.
+fn param_intersect_segment(origin: Vf2d, dir: Vf2d, t_min: f32, t_max: f32, seg: &Line, out: &mut Vec<Vf2d>) {
+  let s = seg.vector();
+  let qp = seg.start - origin;
+  let rxs = dir.cross(s);
+  if rxs.abs() <= f32::EPSILON {
+    // Parallel, only collinear lines have intersections: report the ends of the overlap.
+    let len2 = dir.mag2();
+    if qp.cross(dir).abs() > EPSILON || len2 == 0.0 {
+      return;
+    }
+    let t0 = qp.dot(dir) / len2;
+    let t1 = t0 + s.dot(dir) / len2;
+    let lo = t0.min(t1).max(t_min);
+    let hi = t0.max(t1).min(t_max);
+    if lo <= hi {
+      out.push(origin + dir * lo);
+      if hi.is_finite() {
+        out.push(origin + dir * hi);
+      }
+    }
+    return;
+  }
+  let t = qp.cross(s) / rxs;
+  let u = qp.cross(dir) / rxs;
+  let (eps_t, eps_u) = (EPSILON / dir.mag().max(EPSILON), EPSILON / s.mag().max(EPSILON));
+  if t >= t_min - eps_t && t <= t_max + eps_t && u >= -eps_u && u <= 1.0 + eps_u {
+    out.push(origin + dir * t);
+  }
+}
+
+// Intersects parametric line `origin + t * dir` for t in [t_min, t_max] with a circle.
+fn param_intersect_circle(origin: Vf2d, dir: Vf2d, t_min: f32, t_max: f32, c: &Circle, out: &mut Vec<Vf2d>) {
+  let f = origin - c.pos;
+  let a = dir.mag2();
+  if a == 0.0 {
+    return;
+  }
+  let b = 2.0 * f.dot(dir);
+  let cc = f.mag2() - c.radius * c.radius;
+  let disc = b * b - 4.0 * a * cc;
+  if disc < 0.0 {
+    return;
+  }
+  let sq = disc.sqrt();
+  let eps = EPSILON / dir.mag();
+  for t in [(-b - sq) / (2.0 * a), (-b + sq) / (2.0 * a)].iter() {
+    if *t >= t_min - eps && *t <= t_max + eps {
+      out.push(origin + dir * *t);
+    }
+  }
+}
+
+fn intersect_circles(a: &Circle, b: &Circle, out: &mut Vec<Vf2d>) {
+  let d = b.pos - a.pos;
+  let dist = d.mag();
+  if dist == 0.0 || dist > a.radius + b.radius + EPSILON || dist < (a.radius - b.radius).abs() - EPSILON {
+    return;
+  }
+  let along = (a.radius * a.radius - b.radius * b.radius + dist * dist) / (2.0 * dist);
+  let h = (a.radius * a.radius - along * along).max(0.0).sqrt();
+  let mid = a.pos + d * (along / dist);
+  let offset = d.perp() * (h / dist);
+  out.push(mid + offset);
+  out.push(mid - offset);
+}
+
+fn intersect_boundaries(a: &Boundary, b: &Boundary) -> Vec<Vf2d> {
+  let mut res = Vec::new();
+  match (a, b) {
+    (Boundary::Segments(ea), Boundary::Segments(eb)) => {
+      for x in ea {
+        for y in eb {
+          param_intersect_segment(x.start, x.vector(), 0.0, 1.0, y, &mut res);
+        }
+      }
+    },
+    (Boundary::Segments(edges), Boundary::Circle(c)) | (Boundary::Circle(c), Boundary::Segments(edges)) => {
+      for e in edges {
+        param_intersect_circle(e.start, e.vector(), 0.0, 1.0, c, &mut res);
+      }
+    },
+    (Boundary::Circle(x), Boundary::Circle(y)) => intersect_circles(x, y, &mut res)
+  }
+  dedup(res)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn v(x: f32, y: f32) -> Vf2d {
+    Vf2d::new(x, y)
+  }
+
+  #[test]
+  fn test_rect_contains_and_closest() {
+    let r = Rect::new(v(0.0, 0.0), v(10.0, 5.0));
+    assert!(r.contains(v(10.0, 5.0)));
+    assert!(!r.contains(v(10.5, 5.0)));
+    assert_eq!(r.closest_point(v(20.0, -3.0)), v(10.0, 0.0));
+    assert_eq!(r.middle(), v(5.0, 2.5));
+  }
+
+  #[test]
+  fn test_circle_contains_and_closest() {
+    let c = Circle::new(v(0.0, 0.0), 2.0);
+    assert!(c.contains(v(1.0, 1.0)));
+    assert!(!c.contains(v(2.0, 2.0)));
+    assert_eq!(c.closest_point(v(4.0, 0.0)), v(2.0, 0.0));
+    assert_eq!(c.envelope_r(), Rect::new(v(-2.0, -2.0), v(4.0, 4.0)));
+  }
+
+  #[test]
+  fn test_line_closest_and_intersects() {
+    let a = Line::new(v(0.0, 0.0), v(10.0, 10.0));
+    let b = Line::new(v(0.0, 10.0), v(10.0, 0.0));
+    assert_eq!(a.closest_point(v(10.0, 0.0)), v(5.0, 5.0));
+    assert_eq!(a.intersects(&b), vec![v(5.0, 5.0)]);
+    let c = Line::new(v(0.0, 1.0), v(10.0, 11.0));
+    assert!(a.intersects(&c).is_empty());
+    assert!(!a.overlaps(&c));
+  }
+
+  #[test]
+  fn test_collinear_lines() {
+    let a = Line::new(v(0.0, 0.0), v(10.0, 0.0));
+    let b = Line::new(v(5.0, 0.0), v(15.0, 0.0));
+    assert_eq!(a.intersects(&b), vec![v(5.0, 0.0), v(10.0, 0.0)]);
+  }
+
+  #[test]
+  fn test_circle_rect_intersects() {
+    let r = Rect::new(v(0.0, 0.0), v(10.0, 10.0));
+    let c = Circle::new(v(5.0, 0.0), 2.0);
+    let mut points = c.intersects(&r);
+    points.sort_by(|a, b| a.x.partial_cmp(&b.x).unwrap());
+    assert_eq!(points, vec![v(3.0, 0.0), v(7.0, 0.0)]);
+    assert!(c.overlaps(&r));
+  }
+
+  #[test]
+  fn test_circle_circle() {
+    let a = Circle::new(v(0.0, 0.0), 5.0);
+    let b = Circle::new(v(8.0, 0.0), 5.0);
+    let points = a.intersects(&b);
+    assert_eq!(points.len(), 2);
+    assert!(points.iter().all(|p| (p.x - 4.0).abs() < 1e-4 && (p.y.abs() - 3.0).abs() < 1e-4));
+    assert!(!a.overlaps(&Circle::new(v(20.0, 0.0), 5.0)));
+  }
+
+  #[test]
+  fn test_overlaps_containment() {
+    let outer = Rect::new(v(0.0, 0.0), v(100.0, 100.0));
+    let inner = Circle::new(v(50.0, 50.0), 5.0);
+    assert!(inner.intersects(&outer).is_empty());
+    assert!(inner.overlaps(&outer));
+    assert!(outer.overlaps(&inner));
+    assert!(outer.contains_shape(&inner));
+    assert!(!inner.contains_shape(&outer));
+  }
+
+  #[test]
+  fn test_triangle() {
+    let t = Triangle::new(v(0.0, 0.0), v(10.0, 0.0), v(0.0, 10.0));
+    assert!(t.contains(v(2.0, 2.0)));
+    assert!(t.contains(v(5.0, 5.0)));
+    assert!(!t.contains(v(6.0, 6.0)));
+    assert_eq!(t.area(), 50.0);
+    assert_eq!(t.closest_point(v(10.0, 10.0)), v(5.0, 5.0));
+    assert_eq!(t.envelope_r(), Rect::new(v(0.0, 0.0), v(10.0, 10.0)));
+  }
+
+  #[test]
+  fn test_polygon_concave() {
+    // U shape with the opening at the top.
+    let p = Polygon::new(vec![
+      v(0.0, 0.0), v(3.0, 0.0), v(3.0, 6.0), v(6.0, 6.0), v(6.0, 0.0), v(9.0, 0.0), v(9.0, 9.0), v(0.0, 9.0)
+    ]);
+    assert!(p.contains(v(1.0, 1.0)));
+    assert!(!p.contains(v(4.5, 3.0)));
+    assert!(p.contains(v(4.5, 8.0)));
+    assert_eq!(p.area(), 63.0);
+    assert!(!p.overlaps(&Circle::new(v(4.5, 2.0), 1.0)));
+    assert!(p.overlaps(&Circle::new(v(4.5, 2.0), 2.0)));
+  }
+
+  #[test]
+  fn test_ray() {
+    let ray = Ray::new(v(0.0, 5.0), v(1.0, 0.0));
+    let r = Rect::new(v(10.0, 0.0), v(10.0, 10.0));
+    assert_eq!(ray.intersects(&r), vec![v(10.0, 5.0), v(20.0, 5.0)]);
+    assert_eq!(ray.first_hit(&Circle::new(v(30.0, 5.0), 2.0)), Some(v(28.0, 5.0)));
+    assert_eq!(Ray::new(v(0.0, 5.0), v(-1.0, 0.0)).first_hit(&r), None);
+    assert!(ray.contains(v(100.0, 5.0)));
+    assert!(!ray.contains(v(-1.0, 5.0)));
+  }
+
+  #[test]
+  fn test_envelope_c() {
+    let r = Rect::new(v(0.0, 0.0), v(6.0, 8.0));
+    assert_eq!(r.envelope_c(), Circle::new(v(3.0, 4.0), 5.0));
+  }
+}
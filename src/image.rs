@@ -0,0 +1,196 @@
+//! Image file formats implemented in Rust.
+//!
+//! These codecs work on [`Sprite`](crate::Sprite) pixel data directly and do not depend on the
+//! platform image loader used by [`Sprite::from_image`](crate::Sprite::from_image).
+//! [`decode`](decode) detects the format from the magic bytes at the start of the data, so file
+//! extensions are not consulted.
+//!
+//! ```no_run
+//! # extern crate olc_pixel_game_engine;
+//! # use crate::olc_pixel_game_engine as olc;
+//! use olc::image::{self, Format};
+//!
+//! let data = std::fs::read("level.qoi").unwrap();
+//! assert_eq!(Format::detect(&data), Some(Format::Qoi));
+//! let sprite = olc::Sprite::from_bytes(&data).unwrap();
+//! std::fs::write("level.tga", image::encode(&sprite, Format::Tga).unwrap()).unwrap();
+//! ```
+
+use crate::{Error, Pixel, Sprite};
+
+pub mod bmp;
+mod deflate;
+pub mod png;
+pub mod pnm;
+pub mod qoi;
+pub mod tga;
+
+/// Supported image formats.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+  /// Portable Network Graphics.
+  Png,
+  /// Windows bitmap, uncompressed or run-length encoded.
+  Bmp,
+  /// Truevision TGA, uncompressed or RLE.
+  Tga,
+  /// Quite OK Image format.
+  Qoi,
+  /// Portable pixmap, P3 or P6. Alpha is not stored.
+  Ppm,
+  /// Portable greymap, P2 or P5. Colours are stored as luminance and alpha is not stored.
+  Pgm
+}
+
+impl Format {
+  /// Detects the format of the image data from its magic bytes.
+  /// TGA files have no signature, so they are recognised by a plausible header instead.
+  pub fn detect(data: &[u8]) -> Option<Format> {
+    if data.starts_with(&[0x89, b'P', b'N', b'G']) {
+      Some(Format::Png)
+    } else if data.starts_with(b"BM") {
+      Some(Format::Bmp)
+    } else if data.starts_with(b"qoif") {
+      Some(Format::Qoi)
+    } else if data.starts_with(b"P3") || data.starts_with(b"P6") {
+      Some(Format::Ppm)
+    } else if data.starts_with(b"P2") || data.starts_with(b"P5") {
+      Some(Format::Pgm)
+    } else if tga::is_tga(data) {
+      Some(Format::Tga)
+    } else {
+      None
+    }
+  }
+}
+
+/// Decodes image data in any of the supported formats into a new sprite.
+/// Returns error if the format is not recognised or the data is invalid.
+pub fn decode(data: &[u8]) -> Result<Sprite, Error> {
+  match Format::detect(data) {
+    Some(Format::Png) => png::decode(data),
+    Some(Format::Bmp) => bmp::decode(data),
+    Some(Format::Tga) => tga::decode(data),
+    Some(Format::Qoi) => qoi::decode(data),
+    Some(Format::Ppm) | Some(Format::Pgm) => pnm::decode(data),
+    None => Err(Error { msg: "Failed to decode the image: unknown format".to_string() })
+  }
+}
+
+/// Encodes the sprite in the format.
+/// Returns error if the sprite has no data.
+pub fn encode(sprite: &Sprite, format: Format) -> Result<Vec<u8>, Error> {
+  match format {
+    Format::Png => png::encode(sprite),
+    Format::Bmp => bmp::encode(sprite),
+    Format::Tga => tga::encode(sprite),
+    Format::Qoi => qoi::encode(sprite),
+    Format::Ppm => pnm::encode_ppm(sprite),
+    Format::Pgm => pnm::encode_pgm(sprite)
+  }
+}
+
+// Largest image accepted by the decoders, guards against allocating memory for corrupt headers.
+const MAX_PIXELS: u64 = 1 << 28;
+
+// Creates a sprite for decoding, returns error for empty or unreasonably large dimensions.
+fn new_sprite(width: u64, height: u64, format: &str) -> Result<Sprite, Error> {
+  if width == 0 || height == 0 || width > i32::MAX as u64 || height > i32::MAX as u64 ||
+      width * height > MAX_PIXELS {
+    return Err(Error { msg: format!("Failed to decode {}: unsupported image size {}x{}", format, width, height) });
+  }
+  Ok(Sprite::with_dims(width as i32, height as i32))
+}
+
+// Treats 32-bit images whose alpha channel is entirely zero as opaque, many writers leave it unset.
+fn opaque_if_transparent(pixels: &mut [Pixel]) {
+  if pixels.iter().all(|p| p.a == 0) {
+    pixels.iter_mut().for_each(|p| p.a = 255);
+  }
+}
+
+// Returns error for sprites that cannot be encoded.
+fn check_encodable(sprite: &Sprite, format: &str) -> Result<(), Error> {
+  if sprite.pixels().is_empty() {
+    return Err(Error { msg: format!("Failed to encode {}: sprite has no data", format) });
+  }
+  Ok(())
+}
+
+// Sequential little and big endian reader over a byte slice.
+struct Reader<'a> {
+  data: &'a [u8],
+  pos: usize
+}
+
+impl<'a> Reader<'a> {
+  fn new(data: &'a [u8]) -> Self {
+    Self { data, pos: 0 }
+  }
+
+  fn at(data: &'a [u8], pos: usize) -> Self {
+    Self { data, pos }
+  }
+
+  fn bytes(&mut self, n: usize) -> Option<&'a [u8]> {
+    let b = self.data.get(self.pos..self.pos.checked_add(n)?)?;
+    self.pos += n;
+    Some(b)
+  }
+
+  fn u8(&mut self) -> Option<u8> {
+    self.bytes(1).map(|b| b[0])
+  }
+
+  fn u16_le(&mut self) -> Option<u16> {
+    self.bytes(2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+  }
+
+  fn u32_le(&mut self) -> Option<u32> {
+    self.bytes(4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+  }
+
+  fn i32_le(&mut self) -> Option<i32> {
+    self.u32_le().map(|v| v as i32)
+  }
+
+  fn u32_be(&mut self) -> Option<u32> {
+    self.bytes(4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn test_sprite() -> Sprite {
+    let mut sprite = Sprite::with_dims(7, 5);
+    for (i, p) in sprite.pixels_mut().iter_mut().enumerate() {
+      *p = Pixel::rgba((i * 9) as u8, (i * 5) as u8, 200 - i as u8, if i % 3 == 0 { 255 } else { 128 });
+    }
+    sprite
+  }
+
+  #[test]
+  fn test_detect_and_roundtrip() {
+    let sprite = test_sprite();
+    for format in [Format::Png, Format::Bmp, Format::Tga, Format::Qoi].iter() {
+      let data = encode(&sprite, *format).unwrap();
+      assert_eq!(Format::detect(&data), Some(*format));
+      assert_eq!(decode(&data).unwrap().pixels(), sprite.pixels(), "{:?}", format);
+    }
+    for format in [Format::Ppm, Format::Pgm].iter() {
+      let data = encode(&sprite, *format).unwrap();
+      assert_eq!(Format::detect(&data), Some(*format));
+      assert!(decode(&data).unwrap().pixels().iter().all(|p| p.a == 255));
+    }
+  }
+
+  #[test]
+  fn test_unknown_format() {
+    assert_eq!(Format::detect(b"GIF89a"), None);
+    assert!(decode(b"GIF89a").is_err());
+    assert!(decode(&[]).is_err());
+    assert!(encode(&Sprite::new(), Format::Qoi).is_err());
+  }
+}
@@ -0,0 +1,371 @@
+//! BMP encoding and decoding.
+//!
+//! Sprites are written as 32-bit images with a version 4 header so alpha is preserved.
+//! The decoder supports every header version, 1, 4, 8, 16, 24 and 32 bits per pixel,
+//! uncompressed, RLE4, RLE8 and bit field images, stored either bottom-up or top-down.
+
+use crate::{Error, Pixel, Sprite, BLANK};
+
+use super::Reader;
+
+const BI_RGB: u32 = 0;
+const BI_RLE8: u32 = 1;
+const BI_RLE4: u32 = 2;
+const BI_BITFIELDS: u32 = 3;
+const BI_ALPHABITFIELDS: u32 = 6;
+
+const FILE_HEADER_SIZE: u32 = 14;
+const V4_HEADER_SIZE: u32 = 108;
+
+/// Encodes the sprite as a 32-bit BMP image.
+pub fn encode(sprite: &Sprite) -> Result<Vec<u8>, Error> {
+  super::check_encodable(sprite, "BMP")?;
+  let (width, height) = (sprite.width() as u32, sprite.height() as u32);
+  let image_size = width * height * 4;
+  let offset = FILE_HEADER_SIZE + V4_HEADER_SIZE;
+  let mut out = Vec::with_capacity((offset + image_size) as usize);
+  out.extend_from_slice(b"BM");
+  out.extend_from_slice(&(offset + image_size).to_le_bytes());
+  out.extend_from_slice(&0u32.to_le_bytes());
+  out.extend_from_slice(&offset.to_le_bytes());
+  out.extend_from_slice(&V4_HEADER_SIZE.to_le_bytes());
+  out.extend_from_slice(&width.to_le_bytes());
+  out.extend_from_slice(&height.to_le_bytes());
+  out.extend_from_slice(&1u16.to_le_bytes());
+  out.extend_from_slice(&32u16.to_le_bytes());
+  out.extend_from_slice(&BI_BITFIELDS.to_le_bytes());
+  out.extend_from_slice(&image_size.to_le_bytes());
+  // 72 DPI in both directions, no palette.
+  for v in [2835u32, 2835, 0, 0].iter() {
+    out.extend_from_slice(&v.to_le_bytes());
+  }
+  for mask in [0x00FF_0000u32, 0x0000_FF00, 0x0000_00FF, 0xFF00_0000].iter() {
+    out.extend_from_slice(&mask.to_le_bytes());
+  }
+  // sRGB colour space, the endpoints and gamma that follow are unused.
+  out.extend_from_slice(b"BGRs");
+  out.resize(offset as usize, 0);
+  for row in sprite.rows().rev() {
+    for p in row {
+      out.extend_from_slice(&[p.b, p.g, p.r, p.a]);
+    }
+  }
+  Ok(out)
+}
+
+fn decode_error(msg: &str) -> Error {
+  Error { msg: format!("Failed to decode BMP: {}", msg) }
+}
+
+fn truncated() -> Error {
+  decode_error("file is truncated")
+}
+
+// A colour channel stored in a bit field.
+#[derive(Clone, Copy)]
+struct Channel {
+  shift: u32,
+  bits: u32
+}
+
+impl Channel {
+  fn from_mask(mask: u32) -> Result<Self, Error> {
+    if mask == 0 {
+      return Ok(Self { shift: 0, bits: 0 });
+    }
+    let shift = mask.trailing_zeros();
+    let bits = (mask >> shift).trailing_ones();
+    if mask >> shift >> bits != 0 {
+      return Err(decode_error(&format!("bit field mask {:#x} is not contiguous", mask)));
+    }
+    Ok(Self { shift, bits })
+  }
+
+  // Extracts the channel from the value and scales it to 8 bits, `default` if the channel is absent.
+  fn get(self, value: u32, default: u8) -> u8 {
+    if self.bits == 0 {
+      return default;
+    }
+    let max = (1u64 << self.bits) - 1;
+    let v = (value >> self.shift) as u64 & max;
+    ((v * 255 + max / 2) / max) as u8
+  }
+}
+
+struct Header {
+  width: usize,
+  height: usize,
+  top_down: bool,
+  bpp: u16,
+  compression: u32,
+  masks: [u32; 4],
+  palette: Vec<Pixel>
+}
+
+impl Header {
+  fn parse(data: &[u8]) -> Result<Self, Error> {
+    let mut r = Reader::at(data, 10);
+    let offset = r.u32_le().ok_or_else(truncated)?;
+    let size = r.u32_le().ok_or_else(truncated)?;
+    let (width, height, bpp, compression, colours, entry_size);
+    if size == 12 {
+      width = r.u16_le().ok_or_else(truncated)? as i32;
+      height = r.u16_le().ok_or_else(truncated)? as i32;
+      r.u16_le().ok_or_else(truncated)?;
+      bpp = r.u16_le().ok_or_else(truncated)?;
+      compression = BI_RGB;
+      colours = 0;
+      entry_size = 3;
+    } else if size >= 40 {
+      width = r.i32_le().ok_or_else(truncated)?;
+      height = r.i32_le().ok_or_else(truncated)?;
+      r.u16_le().ok_or_else(truncated)?;
+      bpp = r.u16_le().ok_or_else(truncated)?;
+      compression = r.u32_le().ok_or_else(truncated)?;
+      r.bytes(12).ok_or_else(truncated)?;
+      colours = r.u32_le().ok_or_else(truncated)?;
+      entry_size = 4;
+    } else {
+      return Err(decode_error(&format!("unsupported header size {}", size)));
+    }
+
+    // Masks are part of the newer headers, or follow the 40 byte header.
+    let mut masks = match (bpp, compression) {
+      (16, BI_RGB) => [0x7C00, 0x03E0, 0x001F, 0],
+      (32, BI_RGB) => [0x00FF_0000, 0x0000_FF00, 0x0000_00FF, 0xFF00_0000],
+      _ => [0; 4]
+    };
+    let mut palette_pos = FILE_HEADER_SIZE as usize + size as usize;
+    if compression == BI_BITFIELDS || compression == BI_ALPHABITFIELDS {
+      if bpp != 16 && bpp != 32 {
+        return Err(decode_error(&format!("bit fields with {} bits per pixel", bpp)));
+      }
+      let count = if compression == BI_ALPHABITFIELDS || size >= 56 { 4 } else { 3 };
+      let mut m = Reader::at(data, 54);
+      for mask in masks.iter_mut().take(count) {
+        *mask = m.u32_le().ok_or_else(truncated)?;
+      }
+      if size == 40 {
+        palette_pos += count * 4;
+      }
+    } else if compression != BI_RGB &&
+        !(compression == BI_RLE8 && bpp == 8) && !(compression == BI_RLE4 && bpp == 4) {
+      return Err(decode_error(&format!("unsupported compression {} with {} bits per pixel", compression, bpp)));
+    }
+    if ![1, 4, 8, 16, 24, 32].contains(&bpp) {
+      return Err(decode_error(&format!("unsupported bit depth {}", bpp)));
+    }
+
+    let mut palette = Vec::new();
+    if bpp <= 8 {
+      let count = if colours == 0 || colours > 1 << bpp { 1 << bpp } else { colours as usize };
+      // Some writers omit unused entries, keep what fits before the pixel data.
+      let available = (offset as usize).saturating_sub(palette_pos) / entry_size;
+      let mut p = Reader::at(data, palette_pos);
+      for _ in 0..count.min(available) {
+        let e = p.bytes(entry_size).ok_or_else(truncated)?;
+        palette.push(Pixel::rgb(e[2], e[1], e[0]));
+      }
+      if palette.is_empty() {
+        return Err(decode_error("missing palette"));
+      }
+    }
+
+    if height == i32::MIN {
+      return Err(decode_error("invalid height"));
+    }
+    if width <= 0 || height == 0 {
+      return Err(decode_error(&format!("unsupported image size {}x{}", width, height)));
+    }
+    Ok(Self {
+      width: width as usize,
+      height: height.unsigned_abs() as usize,
+      top_down: height < 0,
+      bpp,
+      compression,
+      masks,
+      palette
+    })
+  }
+
+  fn colour(&self, index: usize) -> Result<Pixel, Error> {
+    self.palette.get(index).copied().ok_or_else(|| decode_error("palette index out of range"))
+  }
+
+  // Index of the first pixel of the `y`th row stored in the file.
+  fn row_start(&self, y: usize) -> usize {
+    if self.top_down { y * self.width } else { (self.height - 1 - y) * self.width }
+  }
+}
+
+/// Decodes BMP data into a new sprite.
+/// Returns error if the data is not a valid or supported BMP image.
+pub fn decode(data: &[u8]) -> Result<Sprite, Error> {
+  if !data.starts_with(b"BM") {
+    return Err(decode_error("missing BMP signature"));
+  }
+  let header = Header::parse(data)?;
+  let mut sprite = super::new_sprite(header.width as u64, header.height as u64, "BMP")?;
+  let offset = Reader::at(data, 10).u32_le().ok_or_else(truncated)? as usize;
+  let pixels = data.get(offset..).ok_or_else(truncated)?;
+  let mut out = vec![BLANK; header.width * header.height];
+  match header.compression {
+    BI_RLE8 | BI_RLE4 => decode_rle(&header, pixels, &mut out)?,
+    _ => decode_rows(&header, pixels, &mut out)?
+  }
+  if header.bpp == 32 && header.compression == BI_RGB {
+    super::opaque_if_transparent(&mut out);
+  }
+  sprite.copy_from_slice(&out);
+  Ok(sprite)
+}
+
+fn decode_rows(header: &Header, data: &[u8], out: &mut [Pixel]) -> Result<(), Error> {
+  let bpp = header.bpp as usize;
+  let stride = (header.width * bpp).div_ceil(32) * 4;
+  let mut channels = [Channel { shift: 0, bits: 0 }; 4];
+  for (c, &mask) in channels.iter_mut().zip(header.masks.iter()) {
+    *c = Channel::from_mask(mask)?;
+  }
+  for y in 0..header.height {
+    let row = data.get(y * stride..(y + 1) * stride).ok_or_else(truncated)?;
+    let start = header.row_start(y);
+    for (x, p) in out[start..start + header.width].iter_mut().enumerate() {
+      *p = match bpp {
+        1 | 4 | 8 => {
+          let bit = x * bpp;
+          let index = (row[bit / 8] >> (8 - bpp - bit % 8)) & ((1u16 << bpp) - 1) as u8;
+          header.colour(index as usize)?
+        }
+        24 => Pixel::rgb(row[x * 3 + 2], row[x * 3 + 1], row[x * 3]),
+        _ => {
+          let v = if bpp == 16 {
+            u16::from_le_bytes([row[x * 2], row[x * 2 + 1]]) as u32
+          } else {
+            u32::from_le_bytes([row[x * 4], row[x * 4 + 1], row[x * 4 + 2], row[x * 4 + 3]])
+          };
+          Pixel::rgba(channels[0].get(v, 0), channels[1].get(v, 0), channels[2].get(v, 0), channels[3].get(v, 255))
+        }
+      };
+    }
+  }
+  Ok(())
+}
+
+// Decodes run-length encoded data, pixels skipped by the encoding are left transparent.
+fn decode_rle(header: &Header, data: &[u8], out: &mut [Pixel]) -> Result<(), Error> {
+  let four = header.compression == BI_RLE4;
+  let mut r = Reader::new(data);
+  let (mut x, mut y) = (0usize, 0usize);
+  let mut put = |x: usize, y: usize, index: u8| -> Result<(), Error> {
+    if x < header.width && y < header.height {
+      out[header.row_start(y) + x] = header.colour(index as usize)?;
+    }
+    Ok(())
+  };
+  loop {
+    let count = r.u8().ok_or_else(truncated)? as usize;
+    let value = r.u8().ok_or_else(truncated)?;
+    if count > 0 {
+      for i in 0..count {
+        let index = if !four { value } else if i % 2 == 0 { value >> 4 } else { value & 0x0F };
+        put(x + i, y, index)?;
+      }
+      x += count;
+      continue;
+    }
+    match value {
+      0 => {
+        x = 0;
+        y += 1;
+      }
+      1 => break,
+      2 => {
+        x += r.u8().ok_or_else(truncated)? as usize;
+        y += r.u8().ok_or_else(truncated)? as usize;
+      }
+      n => {
+        let n = n as usize;
+        let len = if four { n.div_ceil(2) } else { n };
+        let bytes = r.bytes(len).ok_or_else(truncated)?;
+        for i in 0..n {
+          let index = if !four { bytes[i] } else if i % 2 == 0 { bytes[i / 2] >> 4 } else { bytes[i / 2] & 0x0F };
+          put(x + i, y, index)?;
+        }
+        x += n;
+        // Absolute runs are padded to a 16-bit boundary.
+        if len % 2 == 1 {
+          r.u8().ok_or_else(truncated)?;
+        }
+      }
+    }
+    if y >= header.height {
+      break;
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Builds an 8-bit BMP with a 40 byte header and the palette entries.
+  fn build(width: i32, height: i32, compression: u32, palette: &[Pixel], pixels: &[u8]) -> Vec<u8> {
+    let offset = 54 + palette.len() as u32 * 4;
+    let mut out = Vec::new();
+    out.extend_from_slice(b"BM");
+    out.extend_from_slice(&(offset + pixels.len() as u32).to_le_bytes());
+    out.extend_from_slice(&0u32.to_le_bytes());
+    out.extend_from_slice(&offset.to_le_bytes());
+    out.extend_from_slice(&40u32.to_le_bytes());
+    out.extend_from_slice(&width.to_le_bytes());
+    out.extend_from_slice(&height.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes());
+    out.extend_from_slice(&8u16.to_le_bytes());
+    out.extend_from_slice(&compression.to_le_bytes());
+    out.extend_from_slice(&[0; 12]);
+    out.extend_from_slice(&(palette.len() as u32).to_le_bytes());
+    out.extend_from_slice(&0u32.to_le_bytes());
+    for p in palette {
+      out.extend_from_slice(&[p.b, p.g, p.r, 0]);
+    }
+    out.extend_from_slice(pixels);
+    out
+  }
+
+  #[test]
+  fn test_roundtrip() {
+    let mut sprite = Sprite::with_dims(5, 3);
+    for (i, p) in sprite.pixels_mut().iter_mut().enumerate() {
+      *p = Pixel::rgba(i as u8 * 10, 255 - i as u8, 7, i as u8 * 17);
+    }
+    let data = encode(&sprite).unwrap();
+    assert_eq!(data.len(), 14 + 108 + 5 * 3 * 4);
+    assert_eq!(decode(&data).unwrap().pixels(), sprite.pixels());
+  }
+
+  #[test]
+  fn test_decode_palette_and_rle8() {
+    let palette = [crate::RED, crate::GREEN, crate::BLUE];
+    // Bottom-up 3x2, the bottom row first and each row padded to 4 bytes.
+    let data = build(3, 2, BI_RGB, &palette, &[2, 2, 2, 0, 0, 1, 0, 0]);
+    let sprite = decode(&data).unwrap();
+    assert_eq!(sprite.pixels(), &[crate::RED, crate::GREEN, crate::RED, crate::BLUE, crate::BLUE, crate::BLUE]);
+
+    // Top-down 4x3: a run, an absolute run, a delta skipping one pixel, then end of bitmap.
+    let rle = [4, 1, 0, 0, 0, 3, 2, 0, 1, 0, 0, 0, 0, 2, 1, 0, 2, 1, 0, 1];
+    let sprite = decode(&build(4, -3, BI_RLE8, &palette, &rle)).unwrap();
+    let (r, g, b) = (crate::RED, crate::GREEN, crate::BLUE);
+    assert_eq!(sprite.pixels(), &[g, g, g, g, b, r, g, BLANK, BLANK, g, g, BLANK]);
+  }
+
+  #[test]
+  fn test_decode_errors() {
+    assert!(decode(b"BM").is_err());
+    let data = build(3, 2, BI_RGB, &[crate::RED], &[0; 8]);
+    assert!(decode(&data[..data.len() - 1]).is_err());
+    assert!(decode(&build(0, 2, BI_RGB, &[crate::RED], &[])).is_err());
+    assert!(decode(&build(2, 1, BI_RGB, &[crate::RED], &[0, 1, 0, 0])).is_err());
+  }
+}
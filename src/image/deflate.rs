@@ -0,0 +1,457 @@
+// DEFLATE (RFC 1951) compression and decompression wrapped in the zlib format (RFC 1950),
+// as used by PNG.
+//
+// The compressor uses LZ77 with hash chains and the fixed Huffman codes, which is simple and
+// compresses sprite data well since it is dominated by repeated runs. The decompressor supports
+// all block types.
+
+const WINDOW: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const MAX_CHAIN: usize = 64;
+const HASH_BITS: u32 = 15;
+
+pub(crate) const LENGTH_BASE: [u16; 29] = [
+  3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+  163, 195, 227, 258
+];
+pub(crate) const LENGTH_EXTRA: [u8; 29] = [
+  0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0
+];
+pub(crate) const DIST_BASE: [u16; 30] = [
+  1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049,
+  3073, 4097, 6145, 8193, 12289, 16385, 24577
+];
+pub(crate) const DIST_EXTRA: [u8; 30] = [
+  0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13
+];
+
+// Writes bits least significant first, as required by DEFLATE.
+struct BitWriter {
+  out: Vec<u8>,
+  acc: u32,
+  count: u32
+}
+
+impl BitWriter {
+  fn new(out: Vec<u8>) -> Self {
+    Self { out, acc: 0, count: 0 }
+  }
+
+  fn bits(&mut self, value: u32, count: u32) {
+    self.acc |= value << self.count;
+    self.count += count;
+    while self.count >= 8 {
+      self.out.push(self.acc as u8);
+      self.acc >>= 8;
+      self.count -= 8;
+    }
+  }
+
+  // Huffman codes are defined most significant bit first.
+  fn code(&mut self, code: u32, len: u32) {
+    self.bits(code.reverse_bits() >> (32 - len), len)
+  }
+
+  fn finish(mut self) -> Vec<u8> {
+    if self.count > 0 {
+      self.out.push(self.acc as u8);
+    }
+    self.out
+  }
+}
+
+fn fixed_literal(w: &mut BitWriter, sym: u32) {
+  match sym {
+    0..=143 => w.code(0x30 + sym, 8),
+    144..=255 => w.code(0x190 + sym - 144, 9),
+    256..=279 => w.code(sym - 256, 7),
+    _ => w.code(0xC0 + sym - 280, 8)
+  }
+}
+
+fn fixed_match(w: &mut BitWriter, len: usize, dist: usize) {
+  let li = LENGTH_BASE.iter().rposition(|b| *b as usize <= len).unwrap_or(0);
+  fixed_literal(w, 257 + li as u32);
+  w.bits((len - LENGTH_BASE[li] as usize) as u32, LENGTH_EXTRA[li] as u32);
+  let di = DIST_BASE.iter().rposition(|b| *b as usize <= dist).unwrap_or(0);
+  w.code(di as u32, 5);
+  w.bits((dist - DIST_BASE[di] as usize) as u32, DIST_EXTRA[di] as u32);
+}
+
+fn hash(data: &[u8], i: usize) -> usize {
+  let v = (data[i] as u32) << 16 | (data[i + 1] as u32) << 8 | data[i + 2] as u32;
+  (v.wrapping_mul(2654435761) >> (32 - HASH_BITS)) as usize
+}
+
+// Compresses data into a single raw DEFLATE block with fixed Huffman codes.
+pub(crate) fn deflate(data: &[u8]) -> Vec<u8> {
+  let mut w = BitWriter::new(Vec::with_capacity(data.len() / 2 + 16));
+  w.bits(1, 1); // final block
+  w.bits(1, 2); // fixed Huffman codes
+  let mut head = vec![usize::MAX; 1 << HASH_BITS];
+  let mut prev = vec![usize::MAX; WINDOW];
+  let insert = |i: usize, head: &mut [usize], prev: &mut [usize]| {
+    if i + MIN_MATCH <= data.len() {
+      let h = hash(data, i);
+      prev[i % WINDOW] = head[h];
+      head[h] = i;
+    }
+  };
+  let mut i = 0;
+  while i < data.len() {
+    let mut best_len = 0;
+    let mut best_dist = 0;
+    if i + MIN_MATCH <= data.len() {
+      let max = (data.len() - i).min(MAX_MATCH);
+      let mut candidate = head[hash(data, i)];
+      let mut chain = 0;
+      while candidate != usize::MAX && i - candidate <= WINDOW && chain < MAX_CHAIN {
+        let len = data[candidate..].iter().zip(&data[i..i + max]).take_while(|(a, b)| a == b).count();
+        if len > best_len {
+          best_len = len;
+          best_dist = i - candidate;
+          if len == max {
+            break;
+          }
+        }
+        let next = prev[candidate % WINDOW];
+        // Entries older than the window may have been overwritten by newer positions.
+        if next == usize::MAX || next >= candidate {
+          break;
+        }
+        candidate = next;
+        chain += 1;
+      }
+    }
+    if best_len >= MIN_MATCH {
+      fixed_match(&mut w, best_len, best_dist);
+      for j in i..i + best_len {
+        insert(j, &mut head, &mut prev);
+      }
+      i += best_len;
+    } else {
+      fixed_literal(&mut w, data[i] as u32);
+      insert(i, &mut head, &mut prev);
+      i += 1;
+    }
+  }
+  fixed_literal(&mut w, 256);
+  w.finish()
+}
+
+pub(crate) fn adler32(data: &[u8]) -> u32 {
+  let (mut a, mut b) = (1u32, 0u32);
+  for chunk in data.chunks(5552) {
+    for v in chunk {
+      a += *v as u32;
+      b += a;
+    }
+    a %= 65521;
+    b %= 65521;
+  }
+  b << 16 | a
+}
+
+// Compresses data into a zlib stream.
+pub(crate) fn zlib_compress(data: &[u8]) -> Vec<u8> {
+  let mut out = vec![0x78, 0x01];
+  out.extend_from_slice(&deflate(data));
+  out.extend_from_slice(&adler32(data).to_be_bytes());
+  out
+}
+
+//----------------------------------
+// Decompression
+//----------------------------------
+
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+// Reads bits least significant first.
+struct BitReader<'a> {
+  data: &'a [u8],
+  pos: usize,
+  acc: u64,
+  count: u32
+}
+
+impl<'a> BitReader<'a> {
+  fn new(data: &'a [u8]) -> Self {
+    Self { data, pos: 0, acc: 0, count: 0 }
+  }
+
+  fn bits(&mut self, n: u32) -> Result<u32, &'static str> {
+    while self.count < n {
+      let byte = *self.data.get(self.pos).ok_or("unexpected end of compressed data")?;
+      self.acc |= (byte as u64) << self.count;
+      self.pos += 1;
+      self.count += 8;
+    }
+    let v = (self.acc & ((1u64 << n) - 1)) as u32;
+    self.acc >>= n;
+    self.count -= n;
+    Ok(v)
+  }
+
+  // Drops bits up to the next byte boundary, all buffered bits are consumed afterwards.
+  fn align(&mut self) {
+    self.acc = 0;
+    self.count = 0;
+  }
+}
+
+// Canonical Huffman code, decoded one bit at a time.
+struct Huffman {
+  counts: [u16; 16],
+  symbols: Vec<u16>
+}
+
+impl Huffman {
+  fn new(lengths: &[u8]) -> Result<Self, &'static str> {
+    let mut counts = [0u16; 16];
+    for len in lengths {
+      counts[*len as usize] += 1;
+    }
+    counts[0] = 0;
+    // Incomplete codes are allowed, for example a single distance code.
+    let mut left = 1i32;
+    for count in counts.iter().skip(1) {
+      left = left * 2 - *count as i32;
+      if left < 0 {
+        return Err("invalid Huffman code lengths");
+      }
+    }
+    let mut offsets = [0u16; 16];
+    for len in 1..15 {
+      offsets[len + 1] = offsets[len] + counts[len];
+    }
+    let mut symbols = vec![0u16; lengths.len()];
+    for (sym, len) in lengths.iter().enumerate() {
+      if *len != 0 {
+        symbols[offsets[*len as usize] as usize] = sym as u16;
+        offsets[*len as usize] += 1;
+      }
+    }
+    Ok(Self { counts, symbols })
+  }
+
+  fn decode(&self, r: &mut BitReader) -> Result<u16, &'static str> {
+    let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+    for len in 1..16 {
+      code |= r.bits(1)? as i32;
+      let count = self.counts[len] as i32;
+      if code - first < count {
+        return Ok(self.symbols[(index + code - first) as usize]);
+      }
+      index += count;
+      first = (first + count) << 1;
+      code <<= 1;
+    }
+    Err("invalid Huffman code")
+  }
+}
+
+fn fixed_tables() -> (Huffman, Huffman) {
+  let mut lengths = [0u8; 288];
+  for (i, len) in lengths.iter_mut().enumerate() {
+    *len = match i {
+      0..=143 => 8,
+      144..=255 => 9,
+      256..=279 => 7,
+      _ => 8
+    };
+  }
+  // Both tables are complete, so construction cannot fail.
+  let lit = Huffman::new(&lengths).unwrap_or(Huffman { counts: [0; 16], symbols: vec![] });
+  let dist = Huffman::new(&[5u8; 30]).unwrap_or(Huffman { counts: [0; 16], symbols: vec![] });
+  (lit, dist)
+}
+
+fn dynamic_tables(r: &mut BitReader) -> Result<(Huffman, Huffman), &'static str> {
+  let hlit = r.bits(5)? as usize + 257;
+  let hdist = r.bits(5)? as usize + 1;
+  let hclen = r.bits(4)? as usize + 4;
+  if hlit > 286 || hdist > 30 {
+    return Err("invalid dynamic block header");
+  }
+  let mut code_lengths = [0u8; 19];
+  for i in CODE_LENGTH_ORDER.iter().take(hclen) {
+    code_lengths[*i] = r.bits(3)? as u8;
+  }
+  let code = Huffman::new(&code_lengths)?;
+  let mut lengths = Vec::with_capacity(hlit + hdist);
+  while lengths.len() < hlit + hdist {
+    let sym = code.decode(r)?;
+    let (value, repeat) = match sym {
+      0..=15 => (sym as u8, 1),
+      16 => (*lengths.last().ok_or("repeated code length without a previous one")?, 3 + r.bits(2)?),
+      17 => (0, 3 + r.bits(3)?),
+      _ => (0, 11 + r.bits(7)?)
+    };
+    if lengths.len() + repeat as usize > hlit + hdist {
+      return Err("too many code lengths");
+    }
+    lengths.extend(std::iter::repeat_n(value, repeat as usize));
+  }
+  if lengths[256] == 0 {
+    return Err("missing end of block code");
+  }
+  Ok((Huffman::new(&lengths[..hlit])?, Huffman::new(&lengths[hlit..])?))
+}
+
+fn inflate_block(r: &mut BitReader, out: &mut Vec<u8>, lit: &Huffman, dist: &Huffman, limit: usize) -> Result<(), &'static str> {
+  loop {
+    let sym = lit.decode(r)? as usize;
+    if sym < 256 {
+      out.push(sym as u8);
+    } else if sym == 256 {
+      return Ok(());
+    } else {
+      let li = sym - 257;
+      if li >= LENGTH_BASE.len() {
+        return Err("invalid length code");
+      }
+      let len = LENGTH_BASE[li] as usize + r.bits(LENGTH_EXTRA[li] as u32)? as usize;
+      let di = dist.decode(r)? as usize;
+      if di >= DIST_BASE.len() {
+        return Err("invalid distance code");
+      }
+      let d = DIST_BASE[di] as usize + r.bits(DIST_EXTRA[di] as u32)? as usize;
+      if d > out.len() {
+        return Err("distance too far back");
+      }
+      let start = out.len() - d;
+      for k in 0..len {
+        out.push(out[start + k]);
+      }
+    }
+    if out.len() > limit {
+      return Err("decompressed data is larger than expected");
+    }
+  }
+}
+
+// Decompresses raw DEFLATE data, producing at most `limit` bytes.
+// Returns the data and the number of compressed bytes consumed.
+pub(crate) fn inflate(data: &[u8], limit: usize) -> Result<(Vec<u8>, usize), &'static str> {
+  let mut r = BitReader::new(data);
+  let mut out = Vec::new();
+  loop {
+    let last = r.bits(1)? == 1;
+    match r.bits(2)? {
+      0 => {
+        r.align();
+        let len = r.bits(16)?;
+        let nlen = r.bits(16)?;
+        if len != !nlen & 0xFFFF {
+          return Err("invalid stored block length");
+        }
+        let end = r.pos + len as usize;
+        let bytes = r.data.get(r.pos..end).ok_or("unexpected end of compressed data")?;
+        out.extend_from_slice(bytes);
+        r.pos = end;
+      },
+      1 => {
+        let (lit, dist) = fixed_tables();
+        inflate_block(&mut r, &mut out, &lit, &dist, limit)?;
+      },
+      2 => {
+        let (lit, dist) = dynamic_tables(&mut r)?;
+        inflate_block(&mut r, &mut out, &lit, &dist, limit)?;
+      },
+      _ => return Err("invalid block type")
+    }
+    if out.len() > limit {
+      return Err("decompressed data is larger than expected");
+    }
+    if last {
+      return Ok((out, r.pos));
+    }
+  }
+}
+
+// Decompresses a zlib stream, producing at most `limit` bytes.
+pub(crate) fn zlib_decompress(data: &[u8], limit: usize) -> Result<Vec<u8>, &'static str> {
+  if data.len() < 2 {
+    return Err("zlib stream is too short");
+  }
+  let (cmf, flg) = (data[0], data[1]);
+  if cmf & 0x0F != 8 || cmf >> 4 > 7 || !(cmf as u16 * 256 + flg as u16).is_multiple_of(31) {
+    return Err("invalid zlib header");
+  }
+  if flg & 0x20 != 0 {
+    return Err("zlib preset dictionaries are not supported");
+  }
+  let (out, used) = inflate(&data[2..], limit)?;
+  let trailer = data.get(2 + used..2 + used + 4).ok_or("missing zlib checksum")?;
+  if u32::from_be_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]) != adler32(&out) {
+    return Err("zlib checksum mismatch");
+  }
+  Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_adler32() {
+    assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    assert_eq!(adler32(b""), 1);
+  }
+
+  #[test]
+  fn test_deflate_compresses_runs() {
+    let data = vec![7u8; 10000];
+    let compressed = zlib_compress(&data);
+    assert!(compressed.len() < 200);
+    assert_eq!(&compressed[..2], &[0x78, 0x01]);
+    assert_eq!((compressed[0] as u16 * 256 + compressed[1] as u16) % 31, 0);
+  }
+
+  #[test]
+  fn test_inflate_roundtrip() {
+    let mut data = Vec::new();
+    for i in 0..70000u32 {
+      data.push((i % 251) as u8 ^ (i / 1000) as u8);
+    }
+    for input in [&data[..], b"", b"a", b"abcabcabcabcabcabc"].iter() {
+      let compressed = zlib_compress(input);
+      assert_eq!(&zlib_decompress(&compressed, input.len()).unwrap(), input);
+    }
+  }
+
+  #[test]
+  fn test_inflate_dynamic_and_stored() {
+    // Produced by zlib at level 9, uses a dynamic Huffman block.
+    let compressed = [
+      0x78, 0xda, 0xb5, 0xca, 0xc1, 0x11, 0x80, 0x20, 0x0c, 0x05, 0xd1, 0x56, 0x7e, 0x05, 0x56, 0x43,
+      0x03, 0x01, 0x23, 0xa2, 0x81, 0x20, 0x8a, 0xa2, 0xd5, 0xeb, 0x89, 0x0e, 0x9c, 0xd9, 0xdb, 0x5b,
+      0x33, 0x33, 0xb6, 0x1a, 0xdc, 0x0a, 0x5b, 0xf4, 0x4a, 0x98, 0xb4, 0x61, 0xa9, 0x31, 0xef, 0xd0,
+      0x93, 0x0b, 0x8e, 0x8f, 0x85, 0x9e, 0x1b, 0xa3, 0xfa, 0x01, 0xe6, 0xb7, 0x99, 0xac, 0xeb, 0x41,
+      0xc5, 0x21, 0x87, 0xc6, 0x02, 0x4f, 0x91, 0xc1, 0xc9, 0x87, 0xc4, 0x2f, 0x44, 0x62, 0x3c, 0xfe
+    ];
+    let dog = b"The quick brown fox jumps over the lazy dog. ";
+    let mut expected = Vec::new();
+    for _ in 0..3 {
+      expected.extend_from_slice(dog);
+    }
+    expected.extend_from_slice(b"abcabcabcabc olc pixel game engine");
+    assert_eq!(zlib_decompress(&compressed, 1000).unwrap(), expected);
+    assert!(zlib_decompress(&compressed, 100).is_err());
+
+    let stored = [0x78, 0x01, 0x01, 0x03, 0x00, 0xfc, 0xff, b'o', b'l', b'c', 0x02, 0x8b, 0x01, 0x3f];
+    assert_eq!(zlib_decompress(&stored, 10).unwrap(), b"olc");
+  }
+
+  #[test]
+  fn test_inflate_corrupt() {
+    let mut compressed = zlib_compress(b"hello hello hello");
+    assert!(zlib_decompress(&compressed[..compressed.len() - 5], 100).is_err());
+    let n = compressed.len();
+    compressed[n - 1] ^= 1;
+    assert_eq!(zlib_decompress(&compressed, 100), Err("zlib checksum mismatch"));
+    assert!(zlib_decompress(&[0x78, 0x02], 100).is_err());
+    assert!(zlib_decompress(&[0x78, 0x01, 0x07], 100).is_err());
+  }
+}
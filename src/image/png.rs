@@ -0,0 +1,477 @@
+//! PNG encoding and decoding.
+//!
+//! Sprites are written as 8-bit RGBA images, each row uses the filter that compresses best.
+//! The decoder supports every standard colour type and bit depth, transparency chunks and
+//! interlacing, ancillary chunks such as gamma are ignored.
+
+use crate::{Error, Pixel, Sprite};
+
+use super::deflate;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+fn crc_table() -> [u32; 256] {
+  let mut table = [0u32; 256];
+  for (n, entry) in table.iter_mut().enumerate() {
+    let mut c = n as u32;
+    for _ in 0..8 {
+      c = if c & 1 != 0 { 0xEDB8_8320 ^ (c >> 1) } else { c >> 1 };
+    }
+    *entry = c;
+  }
+  table
+}
+
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+  let table = crc_table();
+  !data.iter().fold(!0u32, |c, b| table[((c ^ *b as u32) & 0xFF) as usize] ^ (c >> 8))
+}
+
+fn chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+  out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+  let start = out.len();
+  out.extend_from_slice(kind);
+  out.extend_from_slice(data);
+  let crc = crc32(&out[start..]);
+  out.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+  let p = a as i16 + b as i16 - c as i16;
+  let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+  if pa <= pb && pa <= pc { a } else if pb <= pc { b } else { c }
+}
+
+// Filters a row with the given filter type, `prev` is the previous unfiltered row.
+fn filter_row(kind: u8, row: &[u8], prev: &[u8], out: &mut Vec<u8>) {
+  out.push(kind);
+  for i in 0..row.len() {
+    let a = if i >= 4 { row[i - 4] } else { 0 };
+    let b = prev[i];
+    let c = if i >= 4 { prev[i - 4] } else { 0 };
+    let predicted = match kind {
+      0 => 0,
+      1 => a,
+      2 => b,
+      3 => ((a as u16 + b as u16) / 2) as u8,
+      _ => paeth(a, b, c)
+    };
+    out.push(row[i].wrapping_sub(predicted));
+  }
+}
+
+/// Encodes `width` x `height` pixels in row-major order as a PNG image.
+pub fn encode_pixels(width: u32, height: u32, pixels: &[Pixel]) -> Result<Vec<u8>, Error> {
+  if width == 0 || height == 0 || pixels.len() != width as usize * height as usize {
+    return Err(Error { msg: format!("Failed to encode PNG: invalid image size {}x{}", width, height) });
+  }
+  let stride = width as usize * 4;
+  let bytes: Vec<u8> = pixels.iter().flat_map(|p| [p.r, p.g, p.b, p.a]).collect();
+  let mut raw = Vec::with_capacity((stride + 1) * height as usize);
+  let zero = vec![0u8; stride];
+  let mut candidate = Vec::with_capacity(stride + 1);
+  let mut best = Vec::with_capacity(stride + 1);
+  for y in 0..height as usize {
+    let row = &bytes[y * stride..(y + 1) * stride];
+    let prev = if y > 0 { &bytes[(y - 1) * stride..y * stride] } else { &zero[..] };
+    // Usual heuristic: pick the filter with the smallest sum of absolute signed residuals.
+    let mut best_score = u64::MAX;
+    for kind in 0..5 {
+      candidate.clear();
+      filter_row(kind, row, prev, &mut candidate);
+      let score: u64 = candidate[1..].iter().map(|v| (*v as i8).unsigned_abs() as u64).sum();
+      if score < best_score {
+        best_score = score;
+        std::mem::swap(&mut best, &mut candidate);
+      }
+    }
+    raw.extend_from_slice(&best);
+  }
+
+  let mut header = Vec::with_capacity(13);
+  header.extend_from_slice(&width.to_be_bytes());
+  header.extend_from_slice(&height.to_be_bytes());
+  header.extend_from_slice(&[8, 6, 0, 0, 0]); // 8-bit RGBA, deflate, adaptive filter, no interlace
+
+  let mut out = SIGNATURE.to_vec();
+  chunk(&mut out, b"IHDR", &header);
+  chunk(&mut out, b"IDAT", &deflate::zlib_compress(&raw));
+  chunk(&mut out, b"IEND", &[]);
+  Ok(out)
+}
+
+/// Encodes the sprite as a PNG image.
+pub fn encode(sprite: &Sprite) -> Result<Vec<u8>, Error> {
+  encode_pixels(sprite.width().max(0) as u32, sprite.height().max(0) as u32, sprite.pixels())
+}
+
+//----------------------------------
+// Decoding
+//----------------------------------
+
+// Adam7 passes: x start, y start, x step, y step.
+const ADAM7: [(usize, usize, usize, usize); 7] =
+  [(0, 0, 8, 8), (4, 0, 8, 8), (0, 4, 4, 8), (2, 0, 4, 4), (0, 2, 2, 4), (1, 0, 2, 2), (0, 1, 1, 2)];
+
+fn decode_error(msg: &str) -> Error {
+  Error { msg: format!("Failed to decode PNG: {}", msg) }
+}
+
+struct Header {
+  width: usize,
+  height: usize,
+  depth: u8,
+  colour: u8,
+  interlaced: bool
+}
+
+impl Header {
+  fn parse(data: &[u8]) -> Result<Self, Error> {
+    if data.len() != 13 {
+      return Err(decode_error("invalid IHDR chunk"));
+    }
+    let width = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+    let height = u32::from_be_bytes([data[4], data[5], data[6], data[7]]);
+    let (depth, colour) = (data[8], data[9]);
+    if width == 0 || height == 0 || width > i32::MAX as u32 || height > i32::MAX as u32 ||
+        width as u64 * height as u64 > super::MAX_PIXELS {
+      return Err(decode_error(&format!("unsupported image size {}x{}", width, height)));
+    }
+    let valid = match colour {
+      0 => [1, 2, 4, 8, 16].contains(&depth),
+      3 => [1, 2, 4, 8].contains(&depth),
+      2 | 4 | 6 => [8, 16].contains(&depth),
+      _ => false
+    };
+    if !valid {
+      return Err(decode_error(&format!("invalid colour type {} with bit depth {}", colour, depth)));
+    }
+    if data[10] != 0 || data[11] != 0 || data[12] > 1 {
+      return Err(decode_error("unsupported compression, filter or interlace method"));
+    }
+    Ok(Self { width: width as usize, height: height as usize, depth, colour, interlaced: data[12] == 1 })
+  }
+
+  fn channels(&self) -> usize {
+    match self.colour {
+      2 => 3,
+      4 => 2,
+      6 => 4,
+      _ => 1
+    }
+  }
+
+  // Bytes per complete pixel used by the filters, at least one.
+  fn filter_bpp(&self) -> usize {
+    (self.channels() * self.depth as usize).div_ceil(8)
+  }
+
+  fn stride(&self, width: usize) -> usize {
+    (width * self.channels() * self.depth as usize).div_ceil(8)
+  }
+
+  // Returns (x start, y start, x step, y step, width, height) of each pass with data.
+  fn passes(&self) -> Vec<(usize, usize, usize, usize, usize, usize)> {
+    if !self.interlaced {
+      return vec![(0, 0, 1, 1, self.width, self.height)];
+    }
+    ADAM7
+      .iter()
+      .map(|&(x0, y0, dx, dy)| {
+        let w = (self.width + dx - 1 - x0) / dx;
+        let h = (self.height + dy - 1 - y0) / dy;
+        (x0, y0, dx, dy, if self.width > x0 { w } else { 0 }, if self.height > y0 { h } else { 0 })
+      })
+      .filter(|p| p.4 > 0 && p.5 > 0)
+      .collect()
+  }
+}
+
+// Reverses the row filters in place, rows are `stride + 1` bytes with a leading filter type.
+fn unfilter(data: &mut [u8], stride: usize, rows: usize, bpp: usize) -> Result<(), Error> {
+  for y in 0..rows {
+    let (done, rest) = data.split_at_mut(y * (stride + 1));
+    let prev = if y > 0 { &done[done.len() - stride..] } else { &[][..] };
+    let (kind, row) = rest[..stride + 1].split_first_mut().ok_or_else(|| decode_error("empty row"))?;
+    for i in 0..stride {
+      let a = if i >= bpp { row[i - bpp] } else { 0 };
+      let b = if y > 0 { prev[i] } else { 0 };
+      let c = if i >= bpp && y > 0 { prev[i - bpp] } else { 0 };
+      let predicted = match *kind {
+        0 => 0,
+        1 => a,
+        2 => b,
+        3 => ((a as u16 + b as u16) / 2) as u8,
+        4 => paeth(a, b, c),
+        k => return Err(decode_error(&format!("invalid filter type {}", k)))
+      };
+      row[i] = row[i].wrapping_add(predicted);
+    }
+  }
+  Ok(())
+}
+
+// Returns sample `index` of the row, 16-bit samples are returned in full.
+fn sample(row: &[u8], index: usize, depth: u8) -> u16 {
+  match depth {
+    16 => u16::from_be_bytes([row[index * 2], row[index * 2 + 1]]),
+    8 => row[index] as u16,
+    _ => {
+      let bit = index * depth as usize;
+      let shift = 8 - depth as usize - bit % 8;
+      ((row[bit / 8] >> shift) & ((1u8 << depth) - 1)) as u16
+    }
+  }
+}
+
+/// Decodes a PNG image into a new sprite.
+/// Returns error if the data is not a valid PNG image.
+pub fn decode(data: &[u8]) -> Result<Sprite, Error> {
+  if data.len() < 8 || data[..8] != SIGNATURE {
+    return Err(decode_error("missing PNG signature"));
+  }
+  let mut pos = 8;
+  let mut header: Option<Header> = None;
+  let mut palette: Vec<Pixel> = Vec::new();
+  let mut trns: Option<Vec<u8>> = None;
+  let mut idat = Vec::new();
+  let mut ended = false;
+  while pos < data.len() {
+    if pos + 12 > data.len() {
+      return Err(decode_error("truncated chunk"));
+    }
+    let len = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as usize;
+    let end = pos.checked_add(12 + len).filter(|e| *e <= data.len())
+      .ok_or_else(|| decode_error("truncated chunk"))?;
+    let kind = &data[pos + 4..pos + 8];
+    let body = &data[pos + 8..pos + 8 + len];
+    let crc = u32::from_be_bytes([data[end - 4], data[end - 3], data[end - 2], data[end - 1]]);
+    let name = String::from_utf8_lossy(kind).into_owned();
+    if crc32(&data[pos + 4..pos + 8 + len]) != crc {
+      return Err(decode_error(&format!("CRC mismatch in {} chunk", name)));
+    }
+    if header.is_none() && kind != b"IHDR" {
+      return Err(decode_error("IHDR must be the first chunk"));
+    }
+    match kind {
+      b"IHDR" => {
+        if header.is_some() {
+          return Err(decode_error("duplicate IHDR chunk"));
+        }
+        header = Some(Header::parse(body)?);
+      },
+      b"PLTE" => {
+        if !len.is_multiple_of(3) || len / 3 > 256 || len == 0 {
+          return Err(decode_error("invalid PLTE chunk"));
+        }
+        palette = body.chunks(3).map(|c| Pixel::rgb(c[0], c[1], c[2])).collect();
+      },
+      b"tRNS" => trns = Some(body.to_vec()),
+      b"IDAT" => idat.extend_from_slice(body),
+      b"IEND" => {
+        ended = true;
+        break;
+      },
+      _ => {
+        // Lower case first letter marks ancillary chunks, which can be skipped.
+        if kind[0] & 0x20 == 0 {
+          return Err(decode_error(&format!("unsupported critical chunk {}", name)));
+        }
+      }
+    }
+    pos = end;
+  }
+  let header = header.ok_or_else(|| decode_error("missing IHDR chunk"))?;
+  if !ended {
+    return Err(decode_error("missing IEND chunk"));
+  }
+  if idat.is_empty() {
+    return Err(decode_error("missing IDAT chunk"));
+  }
+  if header.colour == 3 {
+    if palette.is_empty() {
+      return Err(decode_error("missing PLTE chunk"));
+    }
+    if let Some(alpha) = &trns {
+      for (p, a) in palette.iter_mut().zip(alpha.iter()) {
+        p.a = *a;
+      }
+    }
+  }
+
+  let passes = header.passes();
+  let expected: usize = passes.iter().map(|p| (header.stride(p.4) + 1) * p.5).sum();
+  let mut raw = super::deflate::zlib_decompress(&idat, expected).map_err(decode_error)?;
+  if raw.len() < expected {
+    return Err(decode_error("image data is truncated"));
+  }
+
+  // Colour key from tRNS for greyscale and RGB images.
+  let key: Option<Vec<u16>> = match (header.colour, &trns) {
+    (0, Some(t)) if t.len() >= 2 => Some(vec![u16::from_be_bytes([t[0], t[1]])]),
+    (2, Some(t)) if t.len() >= 6 =>
+      Some((0..3).map(|i| u16::from_be_bytes([t[i * 2], t[i * 2 + 1]])).collect()),
+    _ => None
+  };
+  let depth = header.depth;
+  let max = ((1u32 << depth) - 1) as u16;
+  // 16-bit samples keep the high byte, lower depths are stretched to the full range.
+  let scale = |v: u16| if depth == 16 { (v >> 8) as u8 } else { (v as u32 * 255 / max as u32) as u8 };
+
+  let mut sprite = Sprite::with_dims(header.width as i32, header.height as i32);
+  let width = header.width;
+  let out = sprite.pixels_mut();
+  let mut offset = 0;
+  for (x0, y0, dx, dy, pw, ph) in passes {
+    let stride = header.stride(pw);
+    let pass = &mut raw[offset..offset + (stride + 1) * ph];
+    offset += (stride + 1) * ph;
+    unfilter(pass, stride, ph, header.filter_bpp())?;
+    for py in 0..ph {
+      let row = &pass[py * (stride + 1) + 1..(py + 1) * (stride + 1)];
+      for px in 0..pw {
+        let channels = header.channels();
+        let s = |c: usize| sample(row, px * channels + c, depth);
+        let p = match header.colour {
+          0 => {
+            let g = s(0);
+            let alpha = if key.as_ref().is_some_and(|k| k[0] == g) { 0 } else { 255 };
+            let v = scale(g);
+            Pixel::rgba(v, v, v, alpha)
+          },
+          2 => {
+            let rgb = [s(0), s(1), s(2)];
+            let alpha = if key.as_ref().is_some_and(|k| k[..] == rgb[..]) { 0 } else { 255 };
+            Pixel::rgba(scale(rgb[0]), scale(rgb[1]), scale(rgb[2]), alpha)
+          },
+          3 => *palette.get(s(0) as usize).ok_or_else(|| decode_error("palette index out of range"))?,
+          4 => {
+            let v = scale(s(0));
+            Pixel::rgba(v, v, v, scale(s(1)))
+          },
+          _ => Pixel::rgba(scale(s(0)), scale(s(1)), scale(s(2)), scale(s(3)))
+        };
+        out[(y0 + py * dy) * width + x0 + px * dx] = p;
+      }
+    }
+  }
+  Ok(sprite)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_crc32() {
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+  }
+
+  #[test]
+  fn test_encode_structure() {
+    let mut sprite = Sprite::with_dims(3, 2);
+    sprite.fill(crate::RED);
+    let png = encode(&sprite).unwrap();
+    assert_eq!(&png[..8], &SIGNATURE);
+    assert_eq!(&png[12..16], b"IHDR");
+    assert_eq!(&png[16..24], &[0, 0, 0, 3, 0, 0, 0, 2]);
+    assert_eq!(&png[24..29], &[8, 6, 0, 0, 0]);
+    assert_eq!(&png[png.len() - 12..], &[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]);
+  }
+
+  #[test]
+  fn test_encode_empty() {
+    assert!(encode(&Sprite::new()).is_err());
+    assert!(encode_pixels(2, 2, &[crate::RED]).is_err());
+  }
+
+  // Builds a PNG from unfiltered rows with the given header fields.
+  fn build(width: u32, height: u32, depth: u8, colour: u8, rows: &[&[u8]], extra: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+    let mut header = Vec::new();
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    header.extend_from_slice(&[depth, colour, 0, 0, 0]);
+    let mut raw = Vec::new();
+    for row in rows {
+      raw.push(0);
+      raw.extend_from_slice(row);
+    }
+    let mut out = SIGNATURE.to_vec();
+    chunk(&mut out, b"IHDR", &header);
+    for (kind, data) in extra {
+      chunk(&mut out, kind, data);
+    }
+    chunk(&mut out, b"IDAT", &deflate::zlib_compress(&raw));
+    chunk(&mut out, b"IEND", &[]);
+    out
+  }
+
+  #[test]
+  fn test_decode_roundtrip() {
+    let mut sprite = Sprite::with_dims(17, 9);
+    for (i, p) in sprite.pixels_mut().iter_mut().enumerate() {
+      *p = Pixel::rgba((i * 7) as u8, (i * 13) as u8, (i / 3) as u8, (255 - i) as u8);
+    }
+    let decoded = decode(&encode(&sprite).unwrap()).unwrap();
+    assert_eq!((decoded.width(), decoded.height()), (17, 9));
+    assert_eq!(decoded.pixels(), sprite.pixels());
+  }
+
+  #[test]
+  fn test_decode_colour_types() {
+    let grey = decode(&build(4, 1, 2, 0, &[&[0b00_01_10_11]], &[])).unwrap();
+    assert_eq!(grey.pixels(), &[
+      Pixel::rgb(0, 0, 0), Pixel::rgb(85, 85, 85), Pixel::rgb(170, 170, 170), Pixel::rgb(255, 255, 255)
+    ]);
+
+    let rgb16 = decode(&build(1, 1, 16, 2, &[&[0xFF, 0x00, 0x80, 0x00, 0x00, 0xFF]], &[])).unwrap();
+    assert_eq!(rgb16.get_pixel(0, 0), Pixel::rgb(255, 128, 0));
+
+    let plte = [255, 0, 0, 0, 0, 255];
+    let indexed = decode(&build(3, 1, 1, 3, &[&[0b010_00000]], &[(b"PLTE", &plte), (b"tRNS", &[128])])).unwrap();
+    assert_eq!(indexed.pixels(), &[Pixel::rgba(255, 0, 0, 128), Pixel::rgb(0, 0, 255), Pixel::rgba(255, 0, 0, 128)]);
+
+    let grey_alpha = decode(&build(1, 1, 8, 4, &[&[100, 50]], &[])).unwrap();
+    assert_eq!(grey_alpha.get_pixel(0, 0), Pixel::rgba(100, 100, 100, 50));
+
+    let keyed = decode(&build(2, 1, 8, 2, &[&[1, 2, 3, 4, 5, 6]], &[(b"tRNS", &[0, 1, 0, 2, 0, 3])])).unwrap();
+    assert_eq!(keyed.pixels(), &[Pixel::rgba(1, 2, 3, 0), Pixel::rgb(4, 5, 6)]);
+  }
+
+  #[test]
+  fn test_decode_interlaced() {
+    // 3x3 greyscale image, Adam7 places (0,0) in pass 1, (2,0) in pass 4, (0,2) and (2,2) in
+    // pass 5, (1,0), (1,2) in pass 6 and row 1 in pass 7.
+    let rows: [&[u8]; 7] = [&[0, 10], &[0, 20], &[0, 30, 40], &[0, 50], &[0, 60], &[0, 70, 80, 90], &[]];
+    let mut raw = Vec::new();
+    for r in rows.iter().filter(|r| !r.is_empty()) {
+      raw.extend_from_slice(r);
+    }
+    let mut header = Vec::new();
+    header.extend_from_slice(&3u32.to_be_bytes());
+    header.extend_from_slice(&3u32.to_be_bytes());
+    header.extend_from_slice(&[8, 0, 0, 0, 1]);
+    let mut png = SIGNATURE.to_vec();
+    chunk(&mut png, b"IHDR", &header);
+    chunk(&mut png, b"IDAT", &deflate::zlib_compress(&raw));
+    chunk(&mut png, b"IEND", &[]);
+    let sprite = decode(&png).unwrap();
+    let values: Vec<u8> = sprite.pixels().iter().map(|p| p.r).collect();
+    assert_eq!(values, vec![10, 50, 20, 70, 80, 90, 30, 60, 40]);
+  }
+
+  #[test]
+  fn test_decode_errors() {
+    let png = encode(&Sprite::with_dims(2, 2)).unwrap();
+    assert!(decode(&png[..7]).is_err());
+    assert!(decode(&png[..png.len() - 1]).is_err());
+    let mut corrupt = png.clone();
+    corrupt[20] ^= 0xFF;
+    let err = decode(&corrupt).unwrap_err();
+    assert_eq!(format!("{}", err), "Failed to decode PNG: CRC mismatch in IHDR chunk");
+    assert!(decode(&build(1, 1, 3, 2, &[&[0]], &[])).is_err());
+    assert!(decode(&build(1, 1, 8, 3, &[&[0]], &[])).is_err());
+    assert!(decode(&build(2, 1, 8, 0, &[&[0]], &[])).is_err());
+  }
+}
@@ -0,0 +1,159 @@
+//! PPM and PGM encoding and decoding.
+//!
+//! Sprites are written in the binary formats with 8-bit samples, alpha is dropped.
+//! The decoder supports the ASCII (P2, P3) and binary (P5, P6) variants with comments and
+//! sample values up to 65535, which are scaled to 8 bits.
+
+use crate::{Error, Pixel, Sprite};
+
+/// Encodes the sprite as a binary PPM image, alpha is dropped.
+pub fn encode_ppm(sprite: &Sprite) -> Result<Vec<u8>, Error> {
+  super::check_encodable(sprite, "PPM")?;
+  let mut out = format!("P6\n{} {}\n255\n", sprite.width(), sprite.height()).into_bytes();
+  for p in sprite.pixels() {
+    out.extend_from_slice(&[p.r, p.g, p.b]);
+  }
+  Ok(out)
+}
+
+/// Encodes the sprite as a binary PGM image of the luminance of each pixel, alpha is dropped.
+pub fn encode_pgm(sprite: &Sprite) -> Result<Vec<u8>, Error> {
+  super::check_encodable(sprite, "PGM")?;
+  let mut out = format!("P5\n{} {}\n255\n", sprite.width(), sprite.height()).into_bytes();
+  for p in sprite.pixels() {
+    // Rec. 601 weights in 8.8 fixed point.
+    out.push(((p.r as u32 * 77 + p.g as u32 * 150 + p.b as u32 * 29 + 128) >> 8) as u8);
+  }
+  Ok(out)
+}
+
+fn decode_error(msg: &str) -> Error {
+  Error { msg: format!("Failed to decode PNM: {}", msg) }
+}
+
+// Reads the whitespace separated header fields, skipping comments.
+struct Tokens<'a> {
+  data: &'a [u8],
+  pos: usize
+}
+
+impl<'a> Tokens<'a> {
+  fn skip_space(&mut self) {
+    while let Some(&c) = self.data.get(self.pos) {
+      if c == b'#' {
+        while self.data.get(self.pos).is_some_and(|&c| c != b'\n' && c != b'\r') {
+          self.pos += 1;
+        }
+      } else if c.is_ascii_whitespace() {
+        self.pos += 1;
+      } else {
+        break;
+      }
+    }
+  }
+
+  fn number(&mut self) -> Result<u32, Error> {
+    self.skip_space();
+    let start = self.pos;
+    while self.data.get(self.pos).is_some_and(|c| c.is_ascii_digit()) {
+      self.pos += 1;
+    }
+    std::str::from_utf8(&self.data[start..self.pos])
+      .ok()
+      .and_then(|s| s.parse().ok())
+      .ok_or_else(|| match self.data.get(self.pos) {
+        Some(c) => decode_error(&format!("expected a number, found '{}'", *c as char)),
+        None => decode_error("file is truncated")
+      })
+  }
+}
+
+/// Decodes PPM or PGM data into a new sprite.
+/// Returns error if the data is not a valid PPM or PGM image.
+pub fn decode(data: &[u8]) -> Result<Sprite, Error> {
+  let (binary, channels) = match data.get(..2) {
+    Some(b"P2") => (false, 1),
+    Some(b"P3") => (false, 3),
+    Some(b"P5") => (true, 1),
+    Some(b"P6") => (true, 3),
+    _ => return Err(decode_error("missing PPM or PGM signature"))
+  };
+  let mut tokens = Tokens { data, pos: 2 };
+  let width = tokens.number()?;
+  let height = tokens.number()?;
+  let max = tokens.number()?;
+  if max == 0 || max > 65535 {
+    return Err(decode_error(&format!("invalid maximum value {}", max)));
+  }
+  let mut sprite = super::new_sprite(width as u64, height as u64, "PNM")?;
+  let scale = |v: u32| -> Result<u8, Error> {
+    if v > max {
+      return Err(decode_error(&format!("sample {} exceeds the maximum value {}", v, max)));
+    }
+    Ok(((v * 255 + max / 2) / max) as u8)
+  };
+
+  let count = sprite.pixels().len() * channels;
+  let mut samples = Vec::with_capacity(count);
+  if binary {
+    // A single whitespace character separates the header from the samples.
+    let size = if max < 256 { 1 } else { 2 };
+    let start = tokens.pos + 1;
+    let bytes = data.get(start..start + count * size).ok_or_else(|| decode_error("file is truncated"))?;
+    for s in bytes.chunks_exact(size) {
+      let v = if size == 1 { s[0] as u32 } else { u16::from_be_bytes([s[0], s[1]]) as u32 };
+      samples.push(scale(v)?);
+    }
+  } else {
+    for _ in 0..count {
+      samples.push(scale(tokens.number()?)?);
+    }
+  }
+  for (p, s) in sprite.pixels_mut().iter_mut().zip(samples.chunks_exact(channels)) {
+    *p = if channels == 1 { Pixel::rgb(s[0], s[0], s[0]) } else { Pixel::rgb(s[0], s[1], s[2]) };
+  }
+  Ok(sprite)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_roundtrip() {
+    let mut sprite = Sprite::with_dims(4, 2);
+    for (i, p) in sprite.pixels_mut().iter_mut().enumerate() {
+      *p = Pixel::rgba(i as u8 * 30, 255 - i as u8, i as u8, 100);
+    }
+    let ppm = decode(&encode_ppm(&sprite).unwrap()).unwrap();
+    for (a, b) in ppm.pixels().iter().zip(sprite.pixels()) {
+      assert_eq!(*a, Pixel::rgb(b.r, b.g, b.b));
+    }
+    let pgm = decode(&encode_pgm(&sprite).unwrap()).unwrap();
+    assert_eq!(pgm.pixels()[0], Pixel::rgb(149, 149, 149));
+    assert!(pgm.pixels().iter().all(|p| p.r == p.g && p.g == p.b));
+  }
+
+  #[test]
+  fn test_decode_ascii_and_wide() {
+    let data = b"P3\n# comment\n2 1 # size\n15\n15 0 0   0 15 15\n";
+    let sprite = decode(data).unwrap();
+    assert_eq!(sprite.pixels(), &[crate::RED, crate::CYAN]);
+
+    let data = b"P2 3 1 4 0 2 4";
+    assert_eq!(decode(data).unwrap().pixels(), &[crate::BLACK, Pixel::rgb(128, 128, 128), crate::WHITE]);
+
+    let mut data = b"P5 2 1 65535\n".to_vec();
+    data.extend_from_slice(&[0xFF, 0xFF, 0x80, 0x00]);
+    assert_eq!(decode(&data).unwrap().pixels(), &[crate::WHITE, Pixel::rgb(128, 128, 128)]);
+  }
+
+  #[test]
+  fn test_decode_errors() {
+    assert!(decode(b"P6 2 1 255\n\0\0\0").is_err());
+    assert!(decode(b"P3 1 1 255 256 0 0").is_err());
+    assert!(decode(b"P3 1 x 255").is_err());
+    assert!(decode(b"P2 1 1 0 0").is_err());
+    assert!(decode(b"P4 1 1").is_err());
+  }
+}
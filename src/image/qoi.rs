@@ -0,0 +1,182 @@
+//! QOI encoding and decoding, see the [specification](https://qoiformat.org/qoi-specification.pdf).
+//!
+//! Sprites are written with 3 channels if every pixel is opaque and 4 channels otherwise.
+
+use crate::{Error, Pixel, Sprite};
+
+use super::Reader;
+
+const OP_INDEX: u8 = 0x00;
+const OP_DIFF: u8 = 0x40;
+const OP_LUMA: u8 = 0x80;
+const OP_RUN: u8 = 0xC0;
+const OP_RGB: u8 = 0xFE;
+const OP_RGBA: u8 = 0xFF;
+const MASK: u8 = 0xC0;
+const END: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 1];
+
+fn hash(p: Pixel) -> usize {
+  (p.r as usize * 3 + p.g as usize * 5 + p.b as usize * 7 + p.a as usize * 11) % 64
+}
+
+/// Encodes the sprite as a QOI image.
+pub fn encode(sprite: &Sprite) -> Result<Vec<u8>, Error> {
+  super::check_encodable(sprite, "QOI")?;
+  let pixels = sprite.pixels();
+  let channels = if pixels.iter().all(|p| p.a == 255) { 3 } else { 4 };
+  let mut out = Vec::with_capacity(14 + pixels.len() + END.len());
+  out.extend_from_slice(b"qoif");
+  out.extend_from_slice(&(sprite.width() as u32).to_be_bytes());
+  out.extend_from_slice(&(sprite.height() as u32).to_be_bytes());
+  out.extend_from_slice(&[channels, 0]);
+
+  let mut index = [Pixel::rgba(0, 0, 0, 0); 64];
+  let mut prev = Pixel::rgba(0, 0, 0, 255);
+  let mut run = 0u8;
+  for (i, &p) in pixels.iter().enumerate() {
+    if p == prev {
+      run += 1;
+      if run == 62 || i + 1 == pixels.len() {
+        out.push(OP_RUN | (run - 1));
+        run = 0;
+      }
+      continue;
+    }
+    if run > 0 {
+      out.push(OP_RUN | (run - 1));
+      run = 0;
+    }
+    let h = hash(p);
+    if index[h] == p {
+      out.push(OP_INDEX | h as u8);
+    } else {
+      index[h] = p;
+      if p.a == prev.a {
+        let dr = p.r.wrapping_sub(prev.r) as i8;
+        let dg = p.g.wrapping_sub(prev.g) as i8;
+        let db = p.b.wrapping_sub(prev.b) as i8;
+        let (dr_dg, db_dg) = (dr.wrapping_sub(dg), db.wrapping_sub(dg));
+        if (-2..2).contains(&dr) && (-2..2).contains(&dg) && (-2..2).contains(&db) {
+          out.push(OP_DIFF | ((dr + 2) as u8) << 4 | ((dg + 2) as u8) << 2 | (db + 2) as u8);
+        } else if (-32..32).contains(&dg) && (-8..8).contains(&dr_dg) && (-8..8).contains(&db_dg) {
+          out.push(OP_LUMA | (dg + 32) as u8);
+          out.push(((dr_dg + 8) as u8) << 4 | (db_dg + 8) as u8);
+        } else {
+          out.extend_from_slice(&[OP_RGB, p.r, p.g, p.b]);
+        }
+      } else {
+        out.extend_from_slice(&[OP_RGBA, p.r, p.g, p.b, p.a]);
+      }
+    }
+    prev = p;
+  }
+  out.extend_from_slice(&END);
+  Ok(out)
+}
+
+fn decode_error(msg: &str) -> Error {
+  Error { msg: format!("Failed to decode QOI: {}", msg) }
+}
+
+/// Decodes QOI data into a new sprite.
+/// Returns error if the data is not a valid QOI image.
+pub fn decode(data: &[u8]) -> Result<Sprite, Error> {
+  let truncated = || decode_error("file is truncated");
+  if !data.starts_with(b"qoif") {
+    return Err(decode_error("missing QOI signature"));
+  }
+  let mut r = Reader::at(data, 4);
+  let width = r.u32_be().ok_or_else(truncated)?;
+  let height = r.u32_be().ok_or_else(truncated)?;
+  let channels = r.u8().ok_or_else(truncated)?;
+  let colour_space = r.u8().ok_or_else(truncated)?;
+  if channels != 3 && channels != 4 {
+    return Err(decode_error(&format!("invalid channel count {}", channels)));
+  }
+  if colour_space > 1 {
+    return Err(decode_error(&format!("invalid colour space {}", colour_space)));
+  }
+  let mut sprite = super::new_sprite(width as u64, height as u64, "QOI")?;
+
+  let mut index = [Pixel::rgba(0, 0, 0, 0); 64];
+  let mut p = Pixel::rgba(0, 0, 0, 255);
+  let mut run = 0;
+  for out in sprite.pixels_mut() {
+    if run > 0 {
+      run -= 1;
+      *out = p;
+      continue;
+    }
+    let op = r.u8().ok_or_else(truncated)?;
+    match op {
+      OP_RGB => {
+        let b = r.bytes(3).ok_or_else(truncated)?;
+        p = Pixel::rgba(b[0], b[1], b[2], p.a);
+      }
+      OP_RGBA => {
+        let b = r.bytes(4).ok_or_else(truncated)?;
+        p = Pixel::rgba(b[0], b[1], b[2], b[3]);
+      }
+      _ => match op & MASK {
+        OP_INDEX => p = index[op as usize],
+        OP_DIFF => {
+          p.r = p.r.wrapping_add((op >> 4) & 3).wrapping_sub(2);
+          p.g = p.g.wrapping_add((op >> 2) & 3).wrapping_sub(2);
+          p.b = p.b.wrapping_add(op & 3).wrapping_sub(2);
+        }
+        OP_LUMA => {
+          let b = r.u8().ok_or_else(truncated)?;
+          let dg = (op & 0x3F).wrapping_sub(32);
+          p.r = p.r.wrapping_add(dg).wrapping_add(b >> 4).wrapping_sub(8);
+          p.g = p.g.wrapping_add(dg);
+          p.b = p.b.wrapping_add(dg).wrapping_add(b & 0x0F).wrapping_sub(8);
+        }
+        _ => run = op & 0x3F
+      }
+    }
+    index[hash(p)] = p;
+    *out = p;
+  }
+  Ok(sprite)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_roundtrip() {
+    let mut sprite = Sprite::with_dims(40, 30);
+    for (i, p) in sprite.pixels_mut().iter_mut().enumerate() {
+      // Runs, repeated colours, small and large differences and alpha changes.
+      *p = match i % 100 {
+        0..=69 => Pixel::rgb(10, 20, 30),
+        70..=79 => Pixel::rgb(10 + (i % 3) as u8, 20, 31),
+        80..=89 => Pixel::rgb(i as u8, (i * 3) as u8, (i * 7) as u8),
+        _ => Pixel::rgba(200, 100, 50, (i * 13) as u8)
+      };
+    }
+    let data = encode(&sprite).unwrap();
+    assert_eq!(data[12], 4);
+    assert!(data.len() < 40 * 30 * 4);
+    assert_eq!(decode(&data).unwrap().pixels(), sprite.pixels());
+
+    sprite.fill(crate::RED);
+    let data = encode(&sprite).unwrap();
+    assert_eq!(data[12], 3);
+    assert_eq!(decode(&data).unwrap().pixels(), sprite.pixels());
+  }
+
+  #[test]
+  fn test_decode() {
+    // 3x1: an RGB pixel, a diff of +1 red and a run of one.
+    let mut data = b"qoif\0\0\0\x03\0\0\0\x01\x03\0".to_vec();
+    data.extend_from_slice(&[OP_RGB, 100, 50, 25, OP_DIFF | 0b11_10_10, OP_RUN]);
+    data.extend_from_slice(&END);
+    let sprite = decode(&data).unwrap();
+    assert_eq!(sprite.pixels(), &[Pixel::rgb(100, 50, 25), Pixel::rgb(101, 50, 25), Pixel::rgb(101, 50, 25)]);
+    assert!(decode(&data[..16]).is_err());
+    data[12] = 5;
+    assert!(decode(&data).is_err());
+  }
+}
@@ -0,0 +1,262 @@
+//! TGA encoding and decoding.
+//!
+//! Sprites are written as run-length encoded 32-bit images with a top-left origin.
+//! The decoder supports colour-mapped, true colour and greyscale images, uncompressed or
+//! run-length encoded, at 8, 15, 16, 24 and 32 bits per pixel and with any origin.
+
+use crate::{Error, Pixel, Sprite};
+
+use super::Reader;
+
+const HEADER_SIZE: usize = 18;
+const FOOTER_SIGNATURE: &[u8; 18] = b"TRUEVISION-XFILE.\0";
+
+/// Encodes the sprite as a run-length encoded 32-bit TGA image.
+pub fn encode(sprite: &Sprite) -> Result<Vec<u8>, Error> {
+  super::check_encodable(sprite, "TGA")?;
+  let (width, height) = (sprite.width(), sprite.height());
+  if width > u16::MAX as i32 || height > u16::MAX as i32 {
+    return Err(Error { msg: format!("Failed to encode TGA: image size {}x{} is too large", width, height) });
+  }
+  let mut out = vec![0, 0, 10, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+  out.extend_from_slice(&(width as u16).to_le_bytes());
+  out.extend_from_slice(&(height as u16).to_le_bytes());
+  // 32 bits per pixel, 8 alpha bits and top-left origin.
+  out.extend_from_slice(&[32, 0x28]);
+  for row in sprite.rows() {
+    // Packets do not cross rows, as recommended by the specification.
+    let mut i = 0;
+    while i < row.len() {
+      let run = row[i..].iter().take(128).take_while(|&&p| p == row[i]).count();
+      if run > 1 {
+        out.push(0x80 | (run - 1) as u8);
+        push_pixel(&mut out, row[i]);
+        i += run;
+      } else {
+        // Raw packets end where the next run starts.
+        let start = i;
+        while i < row.len() && i - start < 128 && (i + 1 == row.len() || row[i] != row[i + 1]) {
+          i += 1;
+        }
+        out.push((i - start - 1) as u8);
+        for &p in &row[start..i] {
+          push_pixel(&mut out, p);
+        }
+      }
+    }
+  }
+  // TGA 2.0 footer without extension or developer areas.
+  out.extend_from_slice(&[0; 8]);
+  out.extend_from_slice(FOOTER_SIGNATURE);
+  Ok(out)
+}
+
+fn push_pixel(out: &mut Vec<u8>, p: Pixel) {
+  out.extend_from_slice(&[p.b, p.g, p.r, p.a]);
+}
+
+fn decode_error(msg: &str) -> Error {
+  Error { msg: format!("Failed to decode TGA: {}", msg) }
+}
+
+fn truncated() -> Error {
+  decode_error("file is truncated")
+}
+
+struct Header {
+  id_length: u8,
+  map_type: u8,
+  image_type: u8,
+  map_first: u16,
+  map_length: u16,
+  map_depth: u8,
+  width: u16,
+  height: u16,
+  depth: u8,
+  descriptor: u8
+}
+
+impl Header {
+  fn parse(data: &[u8]) -> Option<Self> {
+    let mut r = Reader::new(data);
+    let (id_length, map_type, image_type) = (r.u8()?, r.u8()?, r.u8()?);
+    let (map_first, map_length, map_depth) = (r.u16_le()?, r.u16_le()?, r.u8()?);
+    r.bytes(4)?;
+    let (width, height, depth, descriptor) = (r.u16_le()?, r.u16_le()?, r.u8()?, r.u8()?);
+    Some(Self { id_length, map_type, image_type, map_first, map_length, map_depth, width, height, depth, descriptor })
+  }
+
+  // Returns true if the combination of fields is one the decoder understands.
+  fn is_valid(&self) -> bool {
+    let depth_valid = match self.image_type & !8 {
+      1 => self.map_type == 1 && [8, 16].contains(&self.depth) && [15, 16, 24, 32].contains(&self.map_depth),
+      2 => [15, 16, 24, 32].contains(&self.depth),
+      3 => [8, 16].contains(&self.depth),
+      _ => false
+    };
+    self.map_type <= 1 && [1, 2, 3, 9, 10, 11].contains(&self.image_type) && depth_valid &&
+      self.width > 0 && self.height > 0 && self.descriptor & 0xC0 == 0
+  }
+
+  fn alpha_bits(&self) -> u8 {
+    self.descriptor & 0x0F
+  }
+}
+
+// TGA files have no signature, so accept only headers describing a supported image.
+pub(super) fn is_tga(data: &[u8]) -> bool {
+  data.len() > HEADER_SIZE && Header::parse(data).is_some_and(|h| h.is_valid())
+}
+
+// Converts a stored true colour value of `depth` bits into a pixel.
+fn colour(bytes: &[u8], depth: u8, alpha_bits: u8) -> Pixel {
+  match depth {
+    15 | 16 => {
+      let v = u16::from_le_bytes([bytes[0], bytes[1]]);
+      let scale = |c: u16| ((c & 0x1F) * 255 / 31) as u8;
+      let a = if depth == 16 && alpha_bits > 0 && v & 0x8000 == 0 { 0 } else { 255 };
+      Pixel::rgba(scale(v >> 10), scale(v >> 5), scale(v), a)
+    }
+    24 => Pixel::rgb(bytes[2], bytes[1], bytes[0]),
+    _ => Pixel::rgba(bytes[2], bytes[1], bytes[0], bytes[3])
+  }
+}
+
+/// Decodes TGA data into a new sprite.
+/// Returns error if the data is not a valid or supported TGA image.
+pub fn decode(data: &[u8]) -> Result<Sprite, Error> {
+  let header = Header::parse(data).ok_or_else(truncated)?;
+  if !header.is_valid() {
+    return Err(decode_error(&format!("unsupported image type {} with {} bits per pixel",
+      header.image_type, header.depth)));
+  }
+  let (width, height) = (header.width as usize, header.height as usize);
+  let mut sprite = super::new_sprite(width as u64, height as u64, "TGA")?;
+  let mut r = Reader::at(data, HEADER_SIZE);
+  r.bytes(header.id_length as usize).ok_or_else(truncated)?;
+
+  let mut palette = Vec::new();
+  if header.map_type == 1 {
+    let size = (header.map_depth as usize).div_ceil(8);
+    for _ in 0..header.map_length {
+      palette.push(colour(r.bytes(size).ok_or_else(truncated)?, header.map_depth, header.alpha_bits()));
+    }
+  }
+
+  let size = (header.depth as usize).div_ceil(8);
+  let kind = header.image_type & !8;
+  let read = |bytes: &[u8]| -> Result<Pixel, Error> {
+    match kind {
+      1 => {
+        let index = if size == 1 { bytes[0] as usize } else { u16::from_le_bytes([bytes[0], bytes[1]]) as usize };
+        index.checked_sub(header.map_first as usize)
+          .and_then(|i| palette.get(i).copied())
+          .ok_or_else(|| decode_error("colour map index out of range"))
+      }
+      3 if size == 2 => Ok(Pixel::rgba(bytes[0], bytes[0], bytes[0], bytes[1])),
+      3 => Ok(Pixel::rgb(bytes[0], bytes[0], bytes[0])),
+      _ => Ok(colour(bytes, header.depth, header.alpha_bits()))
+    }
+  };
+
+  let mut pixels = Vec::with_capacity(width * height);
+  if header.image_type & 8 == 0 {
+    for _ in 0..width * height {
+      pixels.push(read(r.bytes(size).ok_or_else(truncated)?)?);
+    }
+  } else {
+    while pixels.len() < width * height {
+      let packet = r.u8().ok_or_else(truncated)?;
+      let count = (packet & 0x7F) as usize + 1;
+      if packet & 0x80 != 0 {
+        let p = read(r.bytes(size).ok_or_else(truncated)?)?;
+        pixels.extend(std::iter::repeat_n(p, count));
+      } else {
+        for _ in 0..count {
+          pixels.push(read(r.bytes(size).ok_or_else(truncated)?)?);
+        }
+      }
+    }
+    pixels.truncate(width * height);
+  }
+  if header.depth == 32 || (kind == 1 && header.map_depth == 32) {
+    super::opaque_if_transparent(&mut pixels);
+  }
+
+  // Rows are stored bottom-up unless bit 5 is set, and right-to-left if bit 4 is set.
+  let top_down = header.descriptor & 0x20 != 0;
+  let right_to_left = header.descriptor & 0x10 != 0;
+  for (y, row) in sprite.rows_mut().enumerate() {
+    let src_y = if top_down { y } else { height - 1 - y };
+    let src = &pixels[src_y * width..(src_y + 1) * width];
+    row.copy_from_slice(src);
+    if right_to_left {
+      row.reverse();
+    }
+  }
+  Ok(sprite)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn header(image_type: u8, width: u16, height: u16, depth: u8, descriptor: u8) -> Vec<u8> {
+    let mut out = vec![0, 0, image_type, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    out.extend_from_slice(&width.to_le_bytes());
+    out.extend_from_slice(&height.to_le_bytes());
+    out.extend_from_slice(&[depth, descriptor]);
+    out
+  }
+
+  #[test]
+  fn test_roundtrip() {
+    let mut sprite = Sprite::with_dims(200, 3);
+    for (i, p) in sprite.pixels_mut().iter_mut().enumerate() {
+      // Mix of long runs, short runs and unique pixels.
+      let v = if i % 200 < 150 { 0 } else if i % 7 < 2 { 1 } else { i as u8 };
+      *p = Pixel::rgba(v, v / 2, 255 - v, 128);
+    }
+    let data = encode(&sprite).unwrap();
+    assert!(data.len() < 200 * 3 * 4);
+    assert!(data.ends_with(FOOTER_SIGNATURE));
+    assert_eq!(decode(&data).unwrap().pixels(), sprite.pixels());
+  }
+
+  #[test]
+  fn test_decode_formats() {
+    // Uncompressed bottom-up 24-bit, 2x2.
+    let mut data = header(2, 2, 2, 24, 0);
+    data.extend_from_slice(&[0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 255]);
+    let sprite = decode(&data).unwrap();
+    assert_eq!(sprite.pixels(), &[crate::BLUE, crate::WHITE, crate::RED, crate::GREEN]);
+
+    // RLE greyscale top-down and right-to-left, 3x1: a run of 2 and a raw packet of 1.
+    let mut data = header(11, 3, 1, 8, 0x30);
+    data.extend_from_slice(&[0x81, 10, 0x00, 20]);
+    let sprite = decode(&data).unwrap();
+    assert_eq!(sprite.pixels(), &[Pixel::rgb(20, 20, 20), Pixel::rgb(10, 10, 10), Pixel::rgb(10, 10, 10)]);
+
+    // Colour-mapped with 16-bit map entries, first entry 1.
+    let mut data = header(1, 2, 1, 8, 0x20);
+    data[1] = 1;
+    data[3..8].copy_from_slice(&[1, 0, 2, 0, 16]);
+    data.extend_from_slice(&[0x00, 0x7C, 0x1F, 0x00, 2, 1]);
+    let sprite = decode(&data).unwrap();
+    assert_eq!(sprite.pixels(), &[crate::BLUE, crate::RED]);
+  }
+
+  #[test]
+  fn test_decode_errors() {
+    let mut data = header(2, 2, 2, 24, 0);
+    data.extend_from_slice(&[0; 11]);
+    assert!(decode(&data).is_err());
+    assert!(decode(&header(4, 2, 2, 24, 0)).is_err());
+    assert!(!is_tga(&header(2, 0, 2, 24, 0)));
+    let mut data = header(1, 1, 1, 8, 0x20);
+    data[1] = 1;
+    data[3..8].copy_from_slice(&[0, 0, 1, 0, 24]);
+    data.extend_from_slice(&[0, 0, 0, 5]);
+    assert!(decode(&data).is_err());
+  }
+}
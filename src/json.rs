@@ -0,0 +1,315 @@
+//! Minimal JSON parser used to import descriptors exported by other tools.
+//!
+//! Objects keep the order of their members, which matters for formats that define frames by
+//! name. Numbers are parsed as `f64`.
+
+use crate::Error;
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Value {
+  Null,
+  Bool(bool),
+  Number(f64),
+  String(String),
+  Array(Vec<Value>),
+  Object(Vec<(String, Value)>)
+}
+
+impl Value {
+  // Returns the member of an object, `None` for other values or missing members.
+  pub fn get(&self, key: &str) -> Option<&Value> {
+    match self {
+      Value::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+      _ => None
+    }
+  }
+
+  pub fn as_f64(&self) -> Option<f64> {
+    match self {
+      Value::Number(n) => Some(*n),
+      _ => None
+    }
+  }
+
+  // Returns the number if it is an integer that fits into `i64`.
+  pub fn as_i64(&self) -> Option<i64> {
+    self.as_f64().filter(|n| n.fract() == 0.0 && n.abs() < 9.0e18).map(|n| n as i64)
+  }
+
+  pub fn as_str(&self) -> Option<&str> {
+    match self {
+      Value::String(s) => Some(s),
+      _ => None
+    }
+  }
+
+  pub fn as_array(&self) -> Option<&[Value]> {
+    match self {
+      Value::Array(a) => Some(a),
+      _ => None
+    }
+  }
+
+  pub fn as_object(&self) -> Option<&[(String, Value)]> {
+    match self {
+      Value::Object(o) => Some(o),
+      _ => None
+    }
+  }
+}
+
+// Nesting limit, guards against stack overflow on malicious input.
+const MAX_DEPTH: usize = 128;
+
+struct Parser<'a> {
+  text: &'a [u8],
+  pos: usize,
+  depth: usize
+}
+
+/// Parses the JSON document.
+/// Returns error with the line and column of the first invalid character.
+pub(crate) fn parse(text: &str) -> Result<Value, Error> {
+  let mut p = Parser { text: text.as_bytes(), pos: 0, depth: 0 };
+  let value = p.value()?;
+  p.skip_space();
+  if p.pos < p.text.len() {
+    return Err(p.error("unexpected trailing characters"));
+  }
+  Ok(value)
+}
+
+impl Parser<'_> {
+  fn error(&self, msg: &str) -> Error {
+    let before = &self.text[..self.pos.min(self.text.len())];
+    let line = before.iter().filter(|&&c| c == b'\n').count() + 1;
+    let column = before.iter().rev().take_while(|&&c| c != b'\n').count() + 1;
+    Error { msg: format!("Failed to parse JSON: {} at line {}, column {}", msg, line, column) }
+  }
+
+  fn skip_space(&mut self) {
+    while self.text.get(self.pos).is_some_and(|c| matches!(c, b' ' | b'\t' | b'\n' | b'\r')) {
+      self.pos += 1;
+    }
+  }
+
+  fn peek(&self) -> Option<u8> {
+    self.text.get(self.pos).copied()
+  }
+
+  fn expect(&mut self, c: u8) -> Result<(), Error> {
+    self.skip_space();
+    if self.peek() != Some(c) {
+      return Err(self.error(&format!("expected '{}'", c as char)));
+    }
+    self.pos += 1;
+    Ok(())
+  }
+
+  fn literal(&mut self, word: &str, value: Value) -> Result<Value, Error> {
+    if self.text[self.pos..].starts_with(word.as_bytes()) {
+      self.pos += word.len();
+      Ok(value)
+    } else {
+      Err(self.error("invalid literal"))
+    }
+  }
+
+  fn value(&mut self) -> Result<Value, Error> {
+    self.skip_space();
+    match self.peek() {
+      Some(b'{') | Some(b'[') => {
+        if self.depth == MAX_DEPTH {
+          return Err(self.error("nesting is too deep"));
+        }
+        self.depth += 1;
+        let res = if self.peek() == Some(b'{') { self.object() } else { self.array() };
+        self.depth -= 1;
+        res
+      }
+      Some(b'"') => self.string().map(Value::String),
+      Some(b't') => self.literal("true", Value::Bool(true)),
+      Some(b'f') => self.literal("false", Value::Bool(false)),
+      Some(b'n') => self.literal("null", Value::Null),
+      Some(b'-') | Some(b'0'..=b'9') => self.number(),
+      Some(_) => Err(self.error("unexpected character")),
+      None => Err(self.error("unexpected end of input"))
+    }
+  }
+
+  fn object(&mut self) -> Result<Value, Error> {
+    self.pos += 1;
+    let mut members = Vec::new();
+    self.skip_space();
+    if self.peek() == Some(b'}') {
+      self.pos += 1;
+      return Ok(Value::Object(members));
+    }
+    loop {
+      self.skip_space();
+      if self.peek() != Some(b'"') {
+        return Err(self.error("expected a member name"));
+      }
+      let key = self.string()?;
+      self.expect(b':')?;
+      members.push((key, self.value()?));
+      self.skip_space();
+      match self.peek() {
+        Some(b',') => self.pos += 1,
+        Some(b'}') => {
+          self.pos += 1;
+          return Ok(Value::Object(members));
+        }
+        _ => return Err(self.error("expected ',' or '}'"))
+      }
+    }
+  }
+
+  fn array(&mut self) -> Result<Value, Error> {
+    self.pos += 1;
+    let mut items = Vec::new();
+    self.skip_space();
+    if self.peek() == Some(b']') {
+      self.pos += 1;
+      return Ok(Value::Array(items));
+    }
+    loop {
+      items.push(self.value()?);
+      self.skip_space();
+      match self.peek() {
+        Some(b',') => self.pos += 1,
+        Some(b']') => {
+          self.pos += 1;
+          return Ok(Value::Array(items));
+        }
+        _ => return Err(self.error("expected ',' or ']'"))
+      }
+    }
+  }
+
+  fn number(&mut self) -> Result<Value, Error> {
+    let start = self.pos;
+    let digits = |p: &mut Self| {
+      let from = p.pos;
+      while p.peek().is_some_and(|c| c.is_ascii_digit()) {
+        p.pos += 1;
+      }
+      p.pos > from
+    };
+    if self.peek() == Some(b'-') {
+      self.pos += 1;
+    }
+    let leading_zero = self.peek() == Some(b'0');
+    if !digits(self) || (leading_zero && self.pos - start > 1 + (self.text[start] == b'-') as usize) {
+      return Err(self.error("invalid number"));
+    }
+    if self.peek() == Some(b'.') {
+      self.pos += 1;
+      if !digits(self) {
+        return Err(self.error("invalid number"));
+      }
+    }
+    if matches!(self.peek(), Some(b'e') | Some(b'E')) {
+      self.pos += 1;
+      if matches!(self.peek(), Some(b'+') | Some(b'-')) {
+        self.pos += 1;
+      }
+      if !digits(self) {
+        return Err(self.error("invalid number"));
+      }
+    }
+    // The slice only contains ASCII characters checked above.
+    let s = std::str::from_utf8(&self.text[start..self.pos]).unwrap_or_default();
+    s.parse().map(Value::Number).map_err(|_| self.error("invalid number"))
+  }
+
+  fn hex4(&mut self) -> Result<u32, Error> {
+    let digits = self.text.get(self.pos..self.pos + 4).ok_or_else(|| self.error("invalid escape"))?;
+    let s = std::str::from_utf8(digits).map_err(|_| self.error("invalid escape"))?;
+    let v = u32::from_str_radix(s, 16).map_err(|_| self.error("invalid escape"))?;
+    self.pos += 4;
+    Ok(v)
+  }
+
+  fn string(&mut self) -> Result<String, Error> {
+    self.pos += 1;
+    let mut out = Vec::new();
+    loop {
+      match self.peek() {
+        None => return Err(self.error("unterminated string")),
+        Some(b'"') => {
+          self.pos += 1;
+          // Input is a &str and escapes produce valid UTF-8, so this can't fail.
+          return String::from_utf8(out).map_err(|_| self.error("invalid UTF-8"));
+        }
+        Some(b'\\') => {
+          self.pos += 1;
+          let c = self.peek().ok_or_else(|| self.error("unterminated string"))?;
+          self.pos += 1;
+          let ch = match c {
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\u{8}',
+            b'f' => '\u{c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => {
+              let mut code = self.hex4()?;
+              // Surrogate pairs encode characters outside of the basic multilingual plane.
+              if (0xD800..0xDC00).contains(&code) && self.text[self.pos..].starts_with(b"\\u") {
+                self.pos += 2;
+                let low = self.hex4()?;
+                if !(0xDC00..0xE000).contains(&low) {
+                  return Err(self.error("invalid surrogate pair"));
+                }
+                code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+              }
+              char::from_u32(code).ok_or_else(|| self.error("invalid escape"))?
+            }
+            _ => return Err(self.error("invalid escape"))
+          };
+          let mut buf = [0; 4];
+          out.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+        }
+        Some(c) if c < 0x20 => return Err(self.error("control character in string")),
+        Some(c) => {
+          out.push(c);
+          self.pos += 1;
+        }
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse() {
+    let v = parse(r#" { "b": [1, -0.25e1, true, null], "a": {"s": "x\"\u00e9\ud83d\ude00"}, "e": [] } "#).unwrap();
+    let keys: Vec<&str> = v.as_object().unwrap().iter().map(|(k, _)| k.as_str()).collect();
+    assert_eq!(keys, ["b", "a", "e"]);
+    let b = v.get("b").unwrap().as_array().unwrap();
+    assert_eq!(b[0].as_i64(), Some(1));
+    assert_eq!(b[1].as_f64(), Some(-2.5));
+    assert_eq!(b[1].as_i64(), None);
+    assert_eq!(b[2], Value::Bool(true));
+    assert_eq!(b[3], Value::Null);
+    assert_eq!(v.get("a").and_then(|a| a.get("s")).and_then(Value::as_str), Some("x\"é😀"));
+    assert_eq!(v.get("e").and_then(Value::as_array).map(|e| e.len()), Some(0));
+    assert!(v.get("missing").is_none());
+  }
+
+  #[test]
+  fn test_parse_errors() {
+    for text in ["", "{", "[1,]", "{\"a\" 1}", "01", "1.", "\"a", "tru", "[1] x", "\"\\q\"", "-"].iter() {
+      assert!(parse(text).is_err(), "{}", text);
+    }
+    let err = parse("{\n  \"a\": x\n}").unwrap_err();
+    assert!(format!("{}", err).contains("line 2, column 8"), "{}", err);
+    assert!(parse(&"[".repeat(1000)).is_err());
+  }
+}
@@ -47,9 +47,28 @@
 //! ```
 
 mod cpp;
+pub mod animation;
+pub mod atlas;
+pub mod collision;
+pub mod fill;
+pub mod filters;
+pub mod geometry;
+pub mod image;
+mod json;
+pub mod nine_slice;
+pub mod noise;
+pub mod quantise;
+pub mod random;
+pub mod sprite_sheet;
+pub mod tilemap;
+pub mod transform;
+pub mod transformed_view;
+pub mod tween;
+mod xml;
 
 // Public export of cpp module structs and enums so they can be used as an API.
 pub use cpp::PixelMode;
+pub use cpp::DecalMode;
 pub use cpp::V2d;
 pub use cpp::Vi2d;
 pub use cpp::Vf2d;
@@ -122,8 +141,32 @@ impl<T> cpp::V2d<T> {
   }
 }
 
+impl<T: Copy + PartialOrd> cpp::V2d<T> {
+  /// Returns component-wise minimum of two vectors.
+  #[inline]
+  pub fn min(&self, rhs: Self) -> Self {
+    Self {
+      x: if rhs.x < self.x { rhs.x } else { self.x },
+      y: if rhs.y < self.y { rhs.y } else { self.y }
+    }
+  }
+
+  /// Returns component-wise maximum of two vectors.
+  #[inline]
+  pub fn max(&self, rhs: Self) -> Self {
+    Self {
+      x: if rhs.x > self.x { rhs.x } else { self.x },
+      y: if rhs.y > self.y { rhs.y } else { self.y }
+    }
+  }
+
+  /// Returns vector with each component clamped between the components of `lo` and `hi`.
+  #[inline]
+  pub fn clamp(&self, lo: Self, hi: Self) -> Self { self.max(lo).min(hi) }
+}
+
 impl Vi2d {
-  /// Returns magnitude (or length) of a vector.
+  /// Returns magnitude (or length) of a vector, truncated towards zero.
   #[inline]
   pub fn mag(&self) -> i32 { (self.mag2() as f32).sqrt() as i32 }
 
@@ -132,8 +175,10 @@ impl Vi2d {
   pub fn mag2(&self) -> i32 { self.x * self.x + self.y * self.y }
 
   /// Returns vector norm.
+  /// Integer vectors can't represent unit vectors exactly, so the normalised direction is rounded
+  /// to the nearest integer components, each in `-1..=1`. Zero vector is returned as is.
   #[inline]
-  pub fn norm(&self) -> Self { let r = 1 / self.mag(); Self { x: self.x * r, y: self.y * r } }
+  pub fn norm(&self) -> Self { Vf2d::from(*self).norm_or_zero().round().into() }
 
   /// Returns perpendicular vector.
   #[inline]
@@ -146,6 +191,22 @@ impl Vi2d {
   /// Returns cross product of two vectors.
   #[inline]
   pub fn cross(&self, rhs: Vi2d) -> i32 { self.x * rhs.y - self.y * rhs.x }
+
+  /// Returns vector with absolute values of the components.
+  #[inline]
+  pub fn abs(&self) -> Self { Self { x: self.x.abs(), y: self.y.abs() } }
+
+  /// Returns angle of the vector in radians, measured from the positive x axis.
+  #[inline]
+  pub fn angle(&self) -> f32 { (self.y as f32).atan2(self.x as f32) }
+
+  /// Returns vector rotated by `angle` radians, components are rounded to the nearest integer.
+  #[inline]
+  pub fn rotate(&self, angle: f32) -> Self { Vf2d::from(*self).rotate(angle).round().into() }
+
+  /// Linearly interpolates between two vectors, components are rounded to the nearest integer.
+  #[inline]
+  pub fn lerp(&self, rhs: Vi2d, t: f32) -> Self { Vf2d::from(*self).lerp(rhs.into(), t).round().into() }
 }
 
 impl Vf2d {
@@ -158,9 +219,17 @@ impl Vf2d {
   pub fn mag2(&self) -> f32 { self.x * self.x + self.y * self.y }
 
   /// Returns vector norm.
+  /// Zero vector has no direction and results in NaN components, same as C++ code; use
+  /// [`norm_or_zero`](V2d::norm_or_zero) when the vector can be zero.
   #[inline]
   pub fn norm(&self) -> Self { let r = 1.0 / self.mag(); Self { x: self.x * r, y: self.y * r } }
 
+  /// Returns vector norm, or zero vector if the vector is zero.
+  #[inline]
+  pub fn norm_or_zero(&self) -> Self {
+    if self.mag2() == 0.0 { Self::default() } else { self.norm() }
+  }
+
   /// Returns perpendicular vector.
   #[inline]
   pub fn perp(&self) -> Self { Self { x: -self.y, y: self.x } }
@@ -172,6 +241,59 @@ impl Vf2d {
   /// Returns cross product of two vectors.
   #[inline]
   pub fn cross(&self, rhs: Vf2d) -> f32 { self.x * rhs.y - self.y * rhs.x }
+
+  /// Returns vector with absolute values of the components.
+  #[inline]
+  pub fn abs(&self) -> Self { Self { x: self.x.abs(), y: self.y.abs() } }
+
+  /// Returns vector with components rounded down.
+  #[inline]
+  pub fn floor(&self) -> Self { Self { x: self.x.floor(), y: self.y.floor() } }
+
+  /// Returns vector with components rounded up.
+  #[inline]
+  pub fn ceil(&self) -> Self { Self { x: self.x.ceil(), y: self.y.ceil() } }
+
+  /// Returns vector with components rounded to the nearest integer, half way cases away from zero.
+  #[inline]
+  pub fn round(&self) -> Self { Self { x: self.x.round(), y: self.y.round() } }
+
+  /// Returns angle of the vector in radians, measured from the positive x axis.
+  #[inline]
+  pub fn angle(&self) -> f32 { self.y.atan2(self.x) }
+
+  /// Returns vector rotated by `angle` radians.
+  #[inline]
+  pub fn rotate(&self, angle: f32) -> Self {
+    let (sin, cos) = angle.sin_cos();
+    Self { x: self.x * cos - self.y * sin, y: self.x * sin + self.y * cos }
+  }
+
+  /// Linearly interpolates between two vectors, `t` is between 0.0 and 1.0.
+  #[inline]
+  pub fn lerp(&self, rhs: Vf2d, t: f32) -> Self { *self + (rhs - *self) * t }
+
+  /// Converts cartesian coordinates into polar coordinates (magnitude, angle).
+  #[inline]
+  pub fn polar(&self) -> Self { Self { x: self.mag(), y: self.angle() } }
+
+  /// Converts polar coordinates (magnitude, angle) into cartesian coordinates.
+  #[inline]
+  pub fn cart(&self) -> Self { Self { x: self.y.cos() * self.x, y: self.y.sin() * self.x } }
+}
+
+impl From<Vi2d> for Vf2d {
+  fn from(v: Vi2d) -> Self {
+    Self { x: v.x as f32, y: v.y as f32 }
+  }
+}
+
+/// Converts float vector into integer vector, truncating components towards zero
+/// similar to C++ `static_cast`.
+impl From<Vf2d> for Vi2d {
+  fn from(v: Vf2d) -> Self {
+    Self { x: v.x as i32, y: v.y as i32 }
+  }
 }
 
 impl<T> From<(T, T)> for cpp::V2d<T> {
@@ -240,6 +362,60 @@ impl<T: ops::DivAssign> ops::DivAssign for cpp::V2d<T> {
   }
 }
 
+impl<T: ops::Mul<Output = T> + Copy> ops::Mul<T> for cpp::V2d<T> {
+  type Output = Self;
+
+  fn mul(self, rhs: T) -> Self::Output {
+    Self { x: self.x * rhs, y: self.y * rhs }
+  }
+}
+
+impl<T: ops::MulAssign + Copy> ops::MulAssign<T> for cpp::V2d<T> {
+  fn mul_assign(&mut self, rhs: T) {
+    self.x *= rhs;
+    self.y *= rhs;
+  }
+}
+
+impl<T: ops::Div<Output = T> + Copy> ops::Div<T> for cpp::V2d<T> {
+  type Output = Self;
+
+  fn div(self, rhs: T) -> Self::Output {
+    Self { x: self.x / rhs, y: self.y / rhs }
+  }
+}
+
+impl<T: ops::DivAssign + Copy> ops::DivAssign<T> for cpp::V2d<T> {
+  fn div_assign(&mut self, rhs: T) {
+    self.x /= rhs;
+    self.y /= rhs;
+  }
+}
+
+impl ops::Mul<Vi2d> for i32 {
+  type Output = Vi2d;
+
+  fn mul(self, rhs: Vi2d) -> Self::Output {
+    rhs * self
+  }
+}
+
+impl ops::Mul<Vf2d> for f32 {
+  type Output = Vf2d;
+
+  fn mul(self, rhs: Vf2d) -> Self::Output {
+    rhs * self
+  }
+}
+
+impl<T: ops::Neg<Output = T>> ops::Neg for cpp::V2d<T> {
+  type Output = Self;
+
+  fn neg(self) -> Self::Output {
+    Self { x: -self.x, y: -self.y }
+  }
+}
+
 impl<T: fmt::Display + fmt::Debug> fmt::Display for cpp::V2d<T> {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     write!(f, "({:?}, {:?})", self.x, self.y)
@@ -258,6 +434,13 @@ impl Pixel {
   }
 }
 
+/// Linearly interpolates between two pixels, including alpha, `t` is between 0.0 and 1.0.
+/// Mirrors `olc::PixelLerp`.
+pub fn pixel_lerp(p1: Pixel, p2: Pixel, t: f32) -> Pixel {
+  let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round().clamp(0.0, 255.0) as u8;
+  Pixel::rgba(lerp(p1.r, p2.r), lerp(p1.g, p2.g), lerp(p1.b, p2.b), lerp(p1.a, p2.a))
+}
+
 impl fmt::Display for Pixel {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     write!(f, "(R: {} G: {} B: {} A: {})", self.r, self.g, self.b, self.a)
@@ -291,8 +474,17 @@ impl Sprite {
   }
 
   /// Loads a sprite from the image.
-  /// Returns error if the image could be loaded.
+  /// Returns error if the image could not be loaded.
+  /// PNG, BMP, TGA, QOI and PPM/PGM files are decoded in Rust, see [`image`](image), other
+  /// formats are left to the platform loader of the engine.
   pub fn from_image(path: &str) -> Result<Self, Error> {
+    let data = std::fs::read(path).map_err(|e| match e.kind() {
+      std::io::ErrorKind::NotFound => Error { msg: format!("Failed to load the sprite: No such file '{}': {}", path, e) },
+      _ => Error { msg: format!("Failed to load the sprite: '{}': {}", path, e) }
+    })?;
+    if image::Format::detect(&data).is_some() {
+      return image::decode(&data);
+    }
     let image = CString::new(path)?;
     let inner = unsafe { cpp::SpriteConstructor(0, 0) };
     let res = unsafe { cpp::SpriteLoadFromFile(&inner, image.as_ptr()) };
@@ -307,6 +499,76 @@ impl Sprite {
     }
   }
 
+  /// Decodes a sprite from PNG data in memory, for example embedded with `include_bytes!`.
+  /// Returns error if the data is not a valid PNG image, see [`image::png`](image::png).
+  pub fn from_png_bytes(data: &[u8]) -> Result<Self, Error> {
+    image::png::decode(data)
+  }
+
+  /// Decodes a sprite from image data in memory, the format is detected from its contents.
+  /// Returns error if the format is not supported or the data is invalid, see [`image`](image).
+  pub fn from_bytes(data: &[u8]) -> Result<Self, Error> {
+    image::decode(data)
+  }
+
+  /// Creates a sprite from `width * height` RGBA pixels, 4 bytes each, in row-major order.
+  /// Returns error if the length of `data` does not match the dimensions.
+  pub fn from_rgba(width: i32, height: i32, data: &[u8]) -> Result<Self, Error> {
+    if width < 0 || height < 0 || data.len() as u64 != width as u64 * height as u64 * 4 {
+      return Err(Error {
+        msg: format!("Failed to create the sprite: expected {}x{} RGBA pixels, received {} bytes",
+          width, height, data.len())
+      });
+    }
+    let mut sprite = Self::with_dims(width, height);
+    for (p, c) in sprite.pixels_mut().iter_mut().zip(data.chunks_exact(4)) {
+      *p = Pixel::rgba(c[0], c[1], c[2], c[3]);
+    }
+    Ok(sprite)
+  }
+
+  /// Loads a sprite from the PGE .spr file, an uncompressed dump of the sprite data.
+  /// Returns error if the file could not be loaded.
+  pub fn from_spr(path: &str) -> Result<Self, Error> {
+    let data = std::fs::read(path)
+      .map_err(|e| Error { msg: format!("Failed to load the sprite: '{}': {}", path, e) })?;
+    // Width and height as 32-bit integers followed by the RGBA pixels.
+    let size = data.get(..8).map(|h| {
+      (i32::from_le_bytes([h[0], h[1], h[2], h[3]]), i32::from_le_bytes([h[4], h[5], h[6], h[7]]))
+    });
+    match size {
+      Some((w, h)) if w >= 0 && h >= 0 && (data.len() - 8) as u64 == w as u64 * h as u64 * 4 =>
+        Self::from_rgba(w, h, &data[8..]),
+      _ => Err(Error { msg: format!("Failed to load the sprite: '{}' is not a valid .spr file", path) })
+    }
+  }
+
+  /// Saves the sprite into the PGE .spr file.
+  /// Returns error if the sprite has no data or the file could not be written.
+  pub fn save_spr(&self, path: &str) -> Result<(), Error> {
+    let file = CString::new(path)?;
+    match unsafe { cpp::SpriteSaveToPGESprFile(&self.inner, file.as_ptr()) } {
+      cpp::RCode::OK => Ok(()),
+      _ => Err(Error { msg: format!("Failed to save the sprite to '{}'", path) })
+    }
+  }
+
+  /// Saves the sprite as a PNG image, see [`image::png`](image::png).
+  /// Returns error if the sprite has no data or the file could not be written.
+  pub fn save_png(&self, path: &str) -> Result<(), Error> {
+    let data = image::png::encode(self)?;
+    std::fs::write(path, data)
+      .map_err(|e| Error { msg: format!("Failed to save the sprite to '{}': {}", path, e) })
+  }
+
+  /// Saves the sprite as an image in the format, see [`image`](image).
+  /// Returns error if the sprite has no data or the file could not be written.
+  pub fn save(&self, path: &str, format: image::Format) -> Result<(), Error> {
+    let data = image::encode(self, format)?;
+    std::fs::write(path, data)
+      .map_err(|e| Error { msg: format!("Failed to save the sprite to '{}': {}", path, e) })
+  }
+
   /// Returns width of the sprite.
   pub fn width(&self) -> i32 {
     unsafe { cpp::SpriteWidth(&self.inner) }
@@ -352,6 +614,198 @@ impl Sprite {
   pub fn sample_bl(&self, u: f32, v: f32) -> Pixel {
     unsafe { cpp::SpriteSampleBL(&self.inner, u, v) }
   }
+
+  // Returns data pointer and number of pixels, or `None` if the sprite has no data.
+  fn data(&self) -> Option<(*mut Pixel, usize)> {
+    let ptr = unsafe { cpp::SpriteGetData(&self.inner) };
+    let len = self.width().max(0) as usize * self.height().max(0) as usize;
+    if ptr.is_null() || len == 0 { None } else { Some((ptr, len)) }
+  }
+
+  /// Returns all pixels in row-major order, `width * height` long.
+  /// Returns an empty slice if the sprite has no data.
+  pub fn pixels(&self) -> &[Pixel] {
+    match self.data() {
+      // Pixel data is owned by the sprite and lives as long as it does.
+      Some((ptr, len)) => unsafe { std::slice::from_raw_parts(ptr, len) },
+      None => &[]
+    }
+  }
+
+  /// Returns all pixels in row-major order as a mutable slice, `width * height` long.
+  /// Returns an empty slice if the sprite has no data.
+  pub fn pixels_mut(&mut self) -> &mut [Pixel] {
+    match self.data() {
+      // Exclusive borrow of the sprite guarantees exclusive access to its pixel data.
+      Some((ptr, len)) => unsafe { std::slice::from_raw_parts_mut(ptr, len) },
+      None => &mut []
+    }
+  }
+
+  /// Returns iterator over rows of pixels, from top to bottom.
+  pub fn rows(&self) -> std::slice::ChunksExact<'_, Pixel> {
+    let width = self.width().max(1) as usize;
+    self.pixels().chunks_exact(width)
+  }
+
+  /// Returns iterator over mutable rows of pixels, from top to bottom.
+  pub fn rows_mut(&mut self) -> std::slice::ChunksExactMut<'_, Pixel> {
+    let width = self.width().max(1) as usize;
+    self.pixels_mut().chunks_exact_mut(width)
+  }
+
+  /// Copies all pixels from `src`, which is in row-major order.
+  ///
+  /// Panics if `src` length is not `width * height`.
+  pub fn copy_from_slice(&mut self, src: &[Pixel]) {
+    self.pixels_mut().copy_from_slice(src)
+  }
+
+  /// Sets every pixel of the sprite to `p`.
+  pub fn fill(&mut self, p: Pixel) {
+    self.pixels_mut().fill(p)
+  }
+
+  /// Returns a new sprite with the area from `pos` to `pos + size`.
+  /// Parts of the area outside of the sprite are [`BLANK`](BLANK).
+  pub fn crop(&self, pos: Vi2d, size: Vi2d) -> Sprite {
+    let mut res = Sprite::with_dims(size.x.max(0), size.y.max(0));
+    res.set_sample_mode(self.sample_mode());
+    res.fill(BLANK);
+    res.blit(self, Vi2d::new(0, 0), pos, size);
+    res
+  }
+
+  /// Copies the area from `src_pos` to `src_pos + src_size` of `src` into this sprite at `pos`.
+  /// Pixels are copied as they are, including alpha, and the area is clipped to both sprites.
+  pub fn blit(&mut self, src: &Sprite, pos: Vi2d, src_pos: Vi2d, src_size: Vi2d) {
+    // Clip the source area to the source sprite, then the destination area to this sprite.
+    let mut sx = src_pos.x.max(0);
+    let mut sy = src_pos.y.max(0);
+    let mut dx = pos.x + (sx - src_pos.x);
+    let mut dy = pos.y + (sy - src_pos.y);
+    let mut ex = (src_pos.x + src_size.x).min(src.width());
+    let mut ey = (src_pos.y + src_size.y).min(src.height());
+    if dx < 0 {
+      sx -= dx;
+      dx = 0;
+    }
+    if dy < 0 {
+      sy -= dy;
+      dy = 0;
+    }
+    ex = ex.min(sx + self.width() - dx);
+    ey = ey.min(sy + self.height() - dy);
+    if ex <= sx || ey <= sy || src.pixels().is_empty() || self.pixels().is_empty() {
+      return;
+    }
+    let (sw, dw) = (src.width() as usize, self.width() as usize);
+    let len = (ex - sx) as usize;
+    let src_pixels = src.pixels();
+    let dst_pixels = self.pixels_mut();
+    for row in 0..(ey - sy) as usize {
+      let s = (sy as usize + row) * sw + sx as usize;
+      let d = (dy as usize + row) * dw + dx as usize;
+      dst_pixels[d..d + len].copy_from_slice(&src_pixels[s..s + len]);
+    }
+  }
+
+  /// Returns a new sprite scaled to `width` x `height`.
+  pub fn resize(&self, width: i32, height: i32, filter: Filter) -> Sprite {
+    let mut res = Sprite::with_dims(width.max(0), height.max(0));
+    res.set_sample_mode(self.sample_mode());
+    let (sw, sh) = (self.width(), self.height());
+    let src = self.pixels();
+    if src.is_empty() {
+      return res;
+    }
+    let at = |x: i32, y: i32| src[(y.clamp(0, sh - 1) * sw + x.clamp(0, sw - 1)) as usize];
+    let (kx, ky) = (sw as f32 / width as f32, sh as f32 / height as f32);
+    for (y, row) in res.rows_mut().enumerate() {
+      // Position of the destination pixel centre in source pixels.
+      let v = (y as f32 + 0.5) * ky;
+      for (x, p) in row.iter_mut().enumerate() {
+        let u = (x as f32 + 0.5) * kx;
+        *p = match filter {
+          Filter::Nearest => at(u as i32, v as i32),
+          Filter::Bilinear => {
+            let (u, v) = (u - 0.5, v - 0.5);
+            let (x0, y0) = (u.floor() as i32, v.floor() as i32);
+            let (fu, fv) = (u - u.floor(), v - v.floor());
+            let top = pixel_lerp(at(x0, y0), at(x0 + 1, y0), fu);
+            let bottom = pixel_lerp(at(x0, y0 + 1), at(x0 + 1, y0 + 1), fu);
+            pixel_lerp(top, bottom, fv)
+          }
+        };
+      }
+    }
+    res
+  }
+
+  // Replaces the sprite with one of `width` x `height`, where pixel (x, y) comes from
+  // `src(x, y)` of the current sprite.
+  fn remap(&mut self, width: i32, height: i32, src: impl Fn(i32, i32) -> (i32, i32)) {
+    let mut res = Sprite::with_dims(width, height);
+    res.set_sample_mode(self.sample_mode());
+    let w = self.width();
+    let pixels = self.pixels();
+    if !pixels.is_empty() {
+      for (y, row) in res.rows_mut().enumerate() {
+        for (x, p) in row.iter_mut().enumerate() {
+          let (sx, sy) = src(x as i32, y as i32);
+          *p = pixels[(sy * w + sx) as usize];
+        }
+      }
+    }
+    *self = res;
+  }
+
+  /// Rotates the sprite by 90 degrees clockwise, swapping its width and height.
+  pub fn rotate90(&mut self) {
+    let (w, h) = (self.width(), self.height());
+    self.remap(h, w, |x, y| (y, h - 1 - x));
+  }
+
+  /// Rotates the sprite by 180 degrees.
+  pub fn rotate180(&mut self) {
+    self.pixels_mut().reverse();
+  }
+
+  /// Rotates the sprite by 270 degrees clockwise, swapping its width and height.
+  pub fn rotate270(&mut self) {
+    let (w, h) = (self.width(), self.height());
+    self.remap(h, w, |x, y| (w - 1 - y, x));
+  }
+
+  /// Mirrors the sprite horizontally.
+  pub fn flip_h(&mut self) {
+    for row in self.rows_mut() {
+      row.reverse();
+    }
+  }
+
+  /// Mirrors the sprite vertically.
+  pub fn flip_v(&mut self) {
+    let w = self.width().max(0) as usize;
+    let pixels = self.pixels_mut();
+    let rows = pixels.len().checked_div(w).unwrap_or(0);
+    for y in 0..rows / 2 {
+      let (top, bottom) = pixels.split_at_mut((rows - 1 - y) * w);
+      top[y * w..(y + 1) * w].swap_with_slice(&mut bottom[..w]);
+    }
+  }
+}
+
+impl Clone for Sprite {
+  fn clone(&self) -> Self {
+    if !self.has_data() {
+      return Sprite::new();
+    }
+    let mut res = Sprite::with_dims(self.width(), self.height());
+    res.set_sample_mode(self.sample_mode());
+    res.copy_from_slice(self.pixels());
+    res
+  }
 }
 
 impl fmt::Display for Sprite {
@@ -369,6 +823,17 @@ impl Drop for Sprite {
   }
 }
 
+/// Filtering used when a sprite is resampled, for example when it is drawn through a transform.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Filter {
+  /// Nearest neighbour sampling, see [`Sprite::sample`](Sprite::sample).
+  Nearest,
+  /// Bilinear sampling, see [`Sprite::sample_bl`](Sprite::sample_bl).
+  /// Transformed drawing uses `olc::Sprite::SampleBL`, so the drawn pixels are always opaque,
+  /// [`Sprite::resize`](Sprite::resize) interpolates alpha as well.
+  Bilinear
+}
+
 /// Mirror of `olc::Decal`.
 /// A GPU resident storage of an `olc::Sprite`.
 #[derive(Debug)]
@@ -404,6 +869,87 @@ impl Decal {
   pub fn sprite(&self) -> &Sprite {
     &self.sprite
   }
+
+  /// Returns mutable access to the sprite pixels, the decal is updated when the returned guard
+  /// is dropped. Use [`set_sprite`](Decal::set_sprite) to replace or resize the sprite.
+  pub fn sprite_mut(&mut self) -> DecalSprite<'_> {
+    DecalSprite { decal: self }
+  }
+
+  /// Replaces the sprite of the decal and uploads its pixels to the GPU.
+  /// Returns the previous sprite.
+  pub fn set_sprite(&mut self, sprite: Sprite) -> Sprite {
+    let previous = std::mem::replace(&mut self.sprite, sprite);
+    // The decal is pointed at the new sprite before the previous one can be freed.
+    self.update();
+    previous
+  }
+
+  /// Uploads the sprite pixels to the GPU, so that changes made to the sprite are drawn.
+  pub fn update(&mut self) {
+    unsafe { cpp::DecalUpdate(&self.inner, &self.sprite.inner) }
+  }
+}
+
+/// Mutable access to the pixels of a decal sprite, see [`Decal::sprite_mut`](Decal::sprite_mut).
+/// Only changes that keep the size of the sprite are possible.
+/// Calls [`Decal::update`](Decal::update) when dropped.
+#[derive(Debug)]
+pub struct DecalSprite<'a> {
+  decal: &'a mut Decal
+}
+
+impl DecalSprite<'_> {
+  /// See [`Sprite::set_pixel`](Sprite::set_pixel).
+  pub fn set_pixel(&mut self, x: i32, y: i32, p: Pixel) -> bool {
+    self.decal.sprite.set_pixel(x, y, p)
+  }
+
+  /// See [`Sprite::pixels_mut`](Sprite::pixels_mut).
+  pub fn pixels_mut(&mut self) -> &mut [Pixel] {
+    self.decal.sprite.pixels_mut()
+  }
+
+  /// See [`Sprite::rows_mut`](Sprite::rows_mut).
+  pub fn rows_mut(&mut self) -> std::slice::ChunksExactMut<'_, Pixel> {
+    self.decal.sprite.rows_mut()
+  }
+
+  /// See [`Sprite::copy_from_slice`](Sprite::copy_from_slice).
+  pub fn copy_from_slice(&mut self, src: &[Pixel]) {
+    self.decal.sprite.copy_from_slice(src)
+  }
+
+  /// See [`Sprite::fill`](Sprite::fill).
+  pub fn fill(&mut self, p: Pixel) {
+    self.decal.sprite.fill(p)
+  }
+}
+
+impl ops::Deref for DecalSprite<'_> {
+  type Target = Sprite;
+
+  fn deref(&self) -> &Sprite {
+    &self.decal.sprite
+  }
+}
+
+impl Drop for DecalSprite<'_> {
+  fn drop(&mut self) {
+    self.decal.update();
+  }
+}
+
+impl AsRef<Sprite> for Sprite {
+  fn as_ref(&self) -> &Sprite {
+    self
+  }
+}
+
+impl AsRef<Sprite> for Decal {
+  fn as_ref(&self) -> &Sprite {
+    &self.sprite
+  }
 }
 
 impl fmt::Display for Decal {
@@ -438,6 +984,12 @@ impl From<std::ffi::NulError> for Error {
   }
 }
 
+impl From<std::io::Error> for Error {
+  fn from(error: std::io::Error) -> Self {
+    Self { msg: format!("{}", error) }
+  }
+}
+
 /// Application trait, should be extended by an implementation and passed to [`start`](start)
 /// function.
 pub trait Application {
@@ -491,6 +1043,8 @@ pub fn start_with_full_screen_and_vsync(
       vsync
     )
   };
+  // Layers must not be accessed once the engine has stopped.
+  layer::invalidate();
 
   match res {
     cpp::RCode::FAIL =>
@@ -504,12 +1058,14 @@ pub fn start_with_full_screen_and_vsync(
 
 /// Sets seed for C++ rand function, equivalent of `srand(seed)`.
 /// See http://www.cplusplus.com/reference/cstdlib/srand for more information.
+/// Prefer [`random::srand`](random::srand), which is portable and reproducible.
 pub fn c_srand(seed: u32) {
   unsafe { cpp::c_srand(seed) }
 }
 
 /// Utility C++ rand function, equivalent of `rand()`.
 /// See http://www.cplusplus.com/reference/cstdlib/rand for more information.
+/// Prefer [`random::rand`](random::rand), which is portable and reproducible.
 pub fn c_rand() -> i32 {
   unsafe { cpp::c_rand() }
 }
@@ -600,9 +1156,54 @@ pub fn get_draw_target_height() -> i32 {
   unsafe { cpp::GetDrawTargetHeight() }
 }
 
+/// Calls `f` with all drawing routines redirected to the sprite, then restores the previous
+/// draw target. Decals are not affected, they are always drawn onto layers.
+/// Returns the result of `f`.
+///
+/// The layer that was the draw target must stay allocated, so
+/// [`set_screen_size`](crate::set_screen_size) panics if called from `f`.
+/// ```no_run
+/// # extern crate olc_pixel_game_engine;
+/// # use crate::olc_pixel_game_engine as olc;
+/// let mut minimap = olc::Sprite::with_dims(32, 32);
+/// olc::with_draw_target(&mut minimap, || {
+///   olc::clear(olc::BLACK);
+///   olc::fill_circle(16, 16, 3, olc::RED);
+/// });
+/// ```
+pub fn with_draw_target<R>(sprite: &mut Sprite, f: impl FnOnce() -> R) -> R {
+  let previous = unsafe { cpp::GetDrawTargetSprite() };
+  unsafe { cpp::SetDrawTargetSprite(&sprite.inner) };
+  // Restores the previous target even if `f` panics, the closure keeps targets nested.
+  let _guard = DrawTargetGuard { previous, _sprite: sprite, _layers: layer::borrow() };
+  f()
+}
+
+// Keeps the sprite borrowed while it is the draw target and restores the previous target on drop.
+// The layers are borrowed too, as the previous target may be one of them.
+struct DrawTargetGuard<'a> {
+  previous: cpp::Sprite,
+  _sprite: &'a mut Sprite,
+  _layers: layer::Borrow
+}
+
+impl Drop for DrawTargetGuard<'_> {
+  fn drop(&mut self) {
+    unsafe { cpp::SetDrawTargetSprite(&self.previous) }
+  }
+}
+
 /// Resizes the primary screen sprite.
+/// Layer descriptions returned before are no longer valid, see
+/// [`LayerDesc::is_valid`](layer::LayerDesc::is_valid).
+///
+/// Panics if called while layer pixels are borrowed by
+/// [`LayerDesc::with_pixels`](layer::LayerDesc::with_pixels) or a sprite is the draw target
+/// of [`with_draw_target`](crate::with_draw_target).
 pub fn set_screen_size(w: i32, h: i32) {
+  assert!(!layer::is_borrowed(), "Can't resize the screen while layer pixels are borrowed");
   unsafe { cpp::SetScreenSize(w, h) }
+  layer::invalidate();
 }
 
 /// Gets the current Frames Per Second.
@@ -625,8 +1226,11 @@ pub fn get_fps() -> u32 {
 /// // Will be drawn onto the layer 1.
 /// olc::draw(0, 0, olc::RED);
 ///
-/// // Returns the current layer description.
-/// let layer_desc = olc::layer::get_draw_target(1);
+/// // Returns the current layer description, `None` if the layer does not exist.
+/// if let Some(layer_desc) = olc::layer::get_draw_target(1) {
+///   // Reads and writes the pixels of the layer.
+///   layer_desc.with_pixels(|pixels| pixels.set_pixel(1, 1, olc::GREEN));
+/// }
 ///
 /// // Reset to the primary draw target.
 /// olc::layer::set_primary_draw_target();
@@ -634,12 +1238,171 @@ pub fn get_fps() -> u32 {
 pub mod layer {
   use super::*;
 
-  pub use cpp::LayerDesc;
+  use std::marker::PhantomData;
+  use std::mem::MaybeUninit;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+
+  // Incremented whenever the engine reallocates the layer sprites, invalidating descriptions
+  // returned before.
+  static GENERATION: AtomicUsize = AtomicUsize::new(0);
+  // Number of active pixel views and sprite draw targets, the layer sprites must not be
+  // reallocated while non-zero.
+  static BORROWED: AtomicUsize = AtomicUsize::new(0);
+
+  pub(crate) fn invalidate() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+  }
+
+  pub(crate) fn is_borrowed() -> bool {
+    BORROWED.load(Ordering::SeqCst) > 0
+  }
+
+  pub(crate) fn borrow() -> Borrow {
+    BORROWED.fetch_add(1, Ordering::SeqCst);
+    Borrow
+  }
+
+  /// Layer description, a snapshot of the layer state when it was returned.
+  /// Modifications are not propagated back to the engine, use the layer functions instead.
+  #[derive(Debug, PartialEq)]
+  pub struct LayerDesc {
+    /// Layer id.
+    pub id: u8,
+    /// Layer offset.
+    pub offset: Vf2d,
+    /// Layer scale.
+    pub scale: Vf2d,
+    /// Layer tint.
+    pub tint: Pixel,
+    /// Whether or not this layer is enabled to be rendered.
+    pub shown: bool,
+    // Layer backing sprite, owned by the engine.
+    sprite: cpp::Sprite,
+    generation: usize
+  }
 
   impl LayerDesc {
+    pub(crate) fn from_cpp(desc: cpp::LayerDesc) -> Self {
+      Self {
+        id: desc.id,
+        offset: desc.offset,
+        scale: desc.scale,
+        tint: desc.tint,
+        shown: desc.shown,
+        sprite: desc.sprite,
+        generation: GENERATION.load(Ordering::SeqCst)
+      }
+    }
+
+    /// Returns false if the layer pixels have been reallocated since the description was
+    /// returned, e.g. by [`set_screen_size`](crate::set_screen_size).
+    pub fn is_valid(&self) -> bool {
+      self.generation == GENERATION.load(Ordering::SeqCst)
+    }
+
     /// Returns the pixel set for (x, y) coordinates.
+    /// Returns `BLANK` if (x, y) is outside of the layer or the description is not valid.
     pub fn get_pixel(&self, x: i32, y: i32) -> Pixel {
-      unsafe { cpp::SpriteGetPixel(&self.sprite, x, y) }
+      self.with_pixels(|pixels| pixels.get_pixel(x, y)).unwrap_or(BLANK)
+    }
+
+    /// Calls `f` with read and write access to the layer pixels and returns its result.
+    /// Returns `None` if the description is not valid.
+    ///
+    /// Changes are visible to the drawing routines immediately. The screen can't be resized
+    /// while `f` runs, [`set_screen_size`](crate::set_screen_size) panics if called from `f`.
+    pub fn with_pixels<R>(&self, f: impl FnOnce(&mut LayerPixels<'_>) -> R) -> Option<R> {
+      if !self.is_valid() {
+        return None;
+      }
+      let mut pixels = LayerPixels::new(&self.sprite);
+      let _borrow = borrow();
+      Some(f(&mut pixels))
+    }
+  }
+
+  // Ends the borrow of the layer pixels, also when unwinding.
+  pub(crate) struct Borrow;
+
+  impl Drop for Borrow {
+    fn drop(&mut self) {
+      BORROWED.fetch_sub(1, Ordering::SeqCst);
+    }
+  }
+
+  /// Read and write access to the pixels of a layer, see
+  /// [`LayerDesc::with_pixels`](LayerDesc::with_pixels).
+  ///
+  /// The engine can draw into the layer at any time, so pixels are accessed one by one instead
+  /// of through slices.
+  pub struct LayerPixels<'a> {
+    data: *mut Pixel,
+    width: i32,
+    height: i32,
+    _layer: PhantomData<&'a LayerDesc>
+  }
+
+  impl LayerPixels<'_> {
+    fn new(sprite: &cpp::Sprite) -> Self {
+      let (width, height) = unsafe { (cpp::SpriteWidth(sprite), cpp::SpriteHeight(sprite)) };
+      let data = unsafe { cpp::SpriteGetData(sprite) };
+      if data.is_null() {
+        Self { data, width: 0, height: 0, _layer: PhantomData }
+      } else {
+        Self { data, width: width.max(0), height: height.max(0), _layer: PhantomData }
+      }
+    }
+
+    /// Returns the width of the layer.
+    pub fn width(&self) -> i32 {
+      self.width
+    }
+
+    /// Returns the height of the layer.
+    pub fn height(&self) -> i32 {
+      self.height
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+      if x < 0 || y < 0 || x >= self.width || y >= self.height {
+        None
+      } else {
+        Some(y as usize * self.width as usize + x as usize)
+      }
+    }
+
+    /// Returns the pixel at (x, y), `BLANK` if it is outside of the layer.
+    pub fn get_pixel(&self, x: i32, y: i32) -> Pixel {
+      // Index is within the layer sprite, which is alive until the screen is resized.
+      self.index(x, y).map_or(BLANK, |i| unsafe { self.data.add(i).read() })
+    }
+
+    /// Sets the pixel at (x, y).
+    /// Returns false if (x, y) is outside of the layer.
+    pub fn set_pixel(&mut self, x: i32, y: i32, p: Pixel) -> bool {
+      match self.index(x, y) {
+        Some(i) => {
+          unsafe { self.data.add(i).write(p) }
+          true
+        }
+        None => false
+      }
+    }
+
+    /// Sets all pixels of the layer.
+    pub fn fill(&mut self, p: Pixel) {
+      for i in 0..self.width as usize * self.height as usize {
+        unsafe { self.data.add(i).write(p) }
+      }
+    }
+
+    /// Returns a copy of the layer pixels.
+    pub fn to_sprite(&self) -> Sprite {
+      let mut sprite = Sprite::with_dims(self.width, self.height);
+      for (i, p) in sprite.pixels_mut().iter_mut().enumerate() {
+        *p = unsafe { self.data.add(i).read() };
+      }
+      sprite
     }
   }
 
@@ -662,15 +1425,27 @@ pub mod layer {
     unsafe { cpp::SetPrimaryDrawTarget() }
   }
 
-  /// Returns layer description for the selected layer.
-  pub fn get_draw_target(layer: u8) -> LayerDesc {
-    unsafe { cpp::GetDrawTarget(layer) }
+  /// Returns layer description for the selected layer, `None` if there is no such layer.
+  pub fn get_draw_target(layer: u8) -> Option<LayerDesc> {
+    let mut desc = MaybeUninit::uninit();
+    // The description is only initialised if the layer exists.
+    if unsafe { cpp::GetDrawTarget(layer, desc.as_mut_ptr()) } {
+      Some(LayerDesc::from_cpp(unsafe { desc.assume_init() }))
+    } else {
+      None
+    }
   }
 
   /// Returns description of the primary layer (index 0, the default layer).
   /// This is equivalent to `olc::GetDrawTarget(0)` in the pixel game engine.
-  pub fn get_primary_draw_target() -> LayerDesc {
-    unsafe { cpp::GetPrimaryDrawTarget() }
+  /// Returns `None` if the engine has not started.
+  pub fn get_primary_draw_target() -> Option<LayerDesc> {
+    let mut desc = MaybeUninit::uninit();
+    if unsafe { cpp::GetPrimaryDrawTarget(desc.as_mut_ptr()) } {
+      Some(LayerDesc::from_cpp(unsafe { desc.assume_init() }))
+    } else {
+      None
+    }
   }
 
   /// Enables/disables layer.
@@ -747,6 +1522,14 @@ pub fn fill_circle(x: i32, y: i32, radius: i32, p: Pixel) {
   unsafe { cpp::FillCircle(x, y, radius, p) }
 }
 
+/// Fills a circle located at (x, y) with radius using the fill style.
+pub fn fill_circle_with_style(x: i32, y: i32, radius: i32, style: &fill::FillStyle) {
+  match style {
+    fill::FillStyle::Solid(p) => fill_circle(x, y, radius, *p),
+    _ => fill::fill_circle(x, y, radius, style)
+  }
+}
+
 /// Draws a rectangle at (x, y) to (x+w, y+h).
 pub fn draw_rect(x: i32, y: i32, w: i32, h: i32, p: Pixel) {
   unsafe { cpp::DrawRect(x, y, w, h, p) }
@@ -757,6 +1540,14 @@ pub fn fill_rect(x: i32, y: i32, w: i32, h: i32, p: Pixel) {
   unsafe { cpp::FillRectangle(x, y, w, h, p) }
 }
 
+/// Fills a rectangle at (x, y) to (x+w, y+h) using the fill style.
+pub fn fill_rect_with_style(x: i32, y: i32, w: i32, h: i32, style: &fill::FillStyle) {
+  match style {
+    fill::FillStyle::Solid(p) => fill_rect(x, y, w, h, *p),
+    _ => fill::fill_rect(x, y, w, h, style)
+  }
+}
+
 /// Draws a triangle between points (x1, y1), (x2, y2) and (x3, y3).
 pub fn draw_triangle(x1: i32, y1: i32, x2: i32, y2: i32, x3: i32, y3: i32, p: Pixel) {
   unsafe { cpp::DrawTriangle(x1, y1, x2, y2, x3, y3, p) }
@@ -767,6 +1558,14 @@ pub fn fill_triangle(x1: i32, y1: i32, x2: i32, y2: i32, x3: i32, y3: i32, p: Pi
   unsafe { cpp::FillTriangle(x1, y1, x2, y2, x3, y3, p) }
 }
 
+/// Fills a triangle between points (x1, y1), (x2, y2) and (x3, y3) using the fill style.
+pub fn fill_triangle_with_style(x1: i32, y1: i32, x2: i32, y2: i32, x3: i32, y3: i32, style: &fill::FillStyle) {
+  match style {
+    fill::FillStyle::Solid(p) => fill_triangle(x1, y1, x2, y2, x3, y3, *p),
+    _ => fill::fill_triangle(x1, y1, x2, y2, x3, y3, style)
+  }
+}
+
 /// Draws an entire sprite at the location (x, y).
 #[inline]
 pub fn draw_sprite(x: i32, y: i32, sprite: &Sprite) {
@@ -790,6 +1589,62 @@ pub fn draw_partial_sprite_ext(x: i32, y: i32, sprite: &Sprite, ox: i32, oy: i32
   unsafe { cpp::DrawPartialSprite(x, y, &sprite.inner, ox, oy, w, h, scale, flip) }
 }
 
+/// Draws a sprite through the transform onto the current draw target with nearest neighbour
+/// sampling. Mirrors `olc::GFX2D::DrawSprite`.
+#[inline]
+pub fn draw_sprite_transformed(sprite: &Sprite, transform: &transform::Transform2D) {
+  draw_sprite_transformed_ext(sprite, transform, Filter::Nearest)
+}
+
+/// Draws a sprite through the transform onto the current draw target.
+/// Every covered pixel of the draw target is mapped back into the sprite and sampled with the
+/// filter. Nothing is drawn if the transform is not invertible.
+pub fn draw_sprite_transformed_ext(sprite: &Sprite, transform: &transform::Transform2D, filter: Filter) {
+  let (w, h) = (sprite.width() as f32, sprite.height() as f32);
+  let inverse = match transform.inverse() {
+    Some(inverse) if w > 0.0 && h > 0.0 => inverse,
+    _ => return
+  };
+
+  // Bounding box of the transformed sprite, clipped to the draw target.
+  let corners = [Vf2d::new(0.0, 0.0), Vf2d::new(w, 0.0), Vf2d::new(0.0, h), Vf2d::new(w, h)];
+  let mut lo = transform.forward(corners[0]);
+  let mut hi = lo;
+  for c in corners.iter().skip(1) {
+    let p = transform.forward(*c);
+    lo = lo.min(p);
+    hi = hi.max(p);
+  }
+  let sx = (lo.x.floor() as i32).max(0);
+  let sy = (lo.y.floor() as i32).max(0);
+  let ex = (hi.x.ceil() as i32).min(get_draw_target_width());
+  let ey = (hi.y.ceil() as i32).min(get_draw_target_height());
+
+  for y in sy..ey {
+    for x in sx..ex {
+      let src = inverse.forward(Vf2d::new(x as f32 + 0.5, y as f32 + 0.5));
+      if src.x >= 0.0 && src.x < w && src.y >= 0.0 && src.y < h {
+        let p = match filter {
+          Filter::Nearest => sprite.sample(src.x / w, src.y / h),
+          Filter::Bilinear => sprite.sample_bl(src.x / w, src.y / h)
+        };
+        draw(x, y, p);
+      }
+    }
+  }
+}
+
+/// Changes the blend mode of decals drawn after this call, see [`DecalMode`](DecalMode).
+/// The mode stays in effect until changed, each decal keeps the mode it was drawn with.
+pub fn set_decal_mode(mode: DecalMode) {
+  unsafe { cpp::SetDecalMode(mode) }
+}
+
+/// Returns the current decal mode.
+pub fn get_decal_mode() -> DecalMode {
+  unsafe { cpp::GetDecalMode() }
+}
+
 /// Draws a whole decal with default scale and tinting.
 #[inline]
 pub fn draw_decal(pos: &Vf2d, decal: &Decal) {
@@ -901,6 +1756,12 @@ pub fn clear_buffer(p: Pixel, depth: bool) {
 mod tests {
   use super::*;
 
+  // Serialises tests that change the draw target or other engine state.
+  pub(crate) fn engine_lock() -> std::sync::MutexGuard<'static, ()> {
+    static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    LOCK.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
   // Pixel tests
 
   #[test]
@@ -909,6 +1770,15 @@ mod tests {
     assert_eq!(&format!("{}", p), "(R: 1 G: 2 B: 3 A: 4)");
   }
 
+  #[test]
+  fn test_pixel_lerp() {
+    let a = Pixel::rgba(0, 100, 200, 0);
+    let b = Pixel::rgba(200, 100, 0, 255);
+    assert_eq!(pixel_lerp(a, b, 0.0), a);
+    assert_eq!(pixel_lerp(a, b, 1.0), b);
+    assert_eq!(pixel_lerp(a, b, 0.5), Pixel::rgba(100, 100, 100, 128));
+  }
+
   // Vi2d tests
 
   #[test]
@@ -958,6 +1828,49 @@ mod tests {
     assert_eq!(res, Vi2d::new(2, 1));
   }
 
+  #[test]
+  fn test_vi2d_scalar_ops() {
+    let mut res = Vi2d::new(2, -3) * 2;
+    assert_eq!(res, Vi2d::new(4, -6));
+    assert_eq!(3 * res, Vi2d::new(12, -18));
+    res /= 4;
+    assert_eq!(res, Vi2d::new(1, -1));
+    res *= 5;
+    assert_eq!(res / 2, Vi2d::new(2, -2));
+    assert_eq!(-res, Vi2d::new(-5, 5));
+  }
+
+  #[test]
+  fn test_vi2d_norm() {
+    assert_eq!(Vi2d::new(10, 0).norm(), Vi2d::new(1, 0));
+    assert_eq!(Vi2d::new(-3, 3).norm(), Vi2d::new(-1, 1));
+    assert_eq!(Vi2d::new(1, 10).norm(), Vi2d::new(0, 1));
+    assert_eq!(Vi2d::new(0, 0).norm(), Vi2d::new(0, 0));
+  }
+
+  #[test]
+  fn test_vi2d_min_max_clamp_abs() {
+    let a = Vi2d::new(1, 5);
+    let b = Vi2d::new(3, -2);
+    assert_eq!(a.min(b), Vi2d::new(1, -2));
+    assert_eq!(a.max(b), Vi2d::new(3, 5));
+    assert_eq!(Vi2d::new(-5, 10).clamp(Vi2d::new(0, 0), Vi2d::new(4, 4)), Vi2d::new(0, 4));
+    assert_eq!(b.abs(), Vi2d::new(3, 2));
+  }
+
+  #[test]
+  fn test_vi2d_rotate_lerp_angle() {
+    assert_eq!(Vi2d::new(10, 0).rotate(std::f32::consts::FRAC_PI_2), Vi2d::new(0, 10));
+    assert_eq!(Vi2d::new(0, 0).lerp(Vi2d::new(10, -5), 0.5), Vi2d::new(5, -3));
+    assert_eq!(Vi2d::new(0, 1).angle(), std::f32::consts::FRAC_PI_2);
+  }
+
+  #[test]
+  fn test_vi2d_conversion() {
+    assert_eq!(Vf2d::from(Vi2d::new(1, -2)), Vf2d::new(1.0, -2.0));
+    assert_eq!(Vi2d::from(Vf2d::new(1.9, -2.9)), Vi2d::new(1, -2));
+  }
+
   // Vf2d tests
 
   #[test]
@@ -1008,4 +1921,370 @@ mod tests {
     res /= Vf2d::new(2.0, 2.0);
     assert_eq!(res, Vf2d::new(2.0, 1.0));
   }
+
+  #[test]
+  fn test_vf2d_scalar_ops() {
+    let mut res = Vf2d::new(2.0, -3.0) * 2.0;
+    assert_eq!(res, Vf2d::new(4.0, -6.0));
+    assert_eq!(0.5 * res, Vf2d::new(2.0, -3.0));
+    res /= 4.0;
+    assert_eq!(res, Vf2d::new(1.0, -1.5));
+    res *= 2.0;
+    assert_eq!(res / 2.0, Vf2d::new(1.0, -1.5));
+    assert_eq!(-res, Vf2d::new(-2.0, 3.0));
+  }
+
+  #[test]
+  fn test_vf2d_norm() {
+    assert_eq!(Vf2d::new(3.0, 4.0).norm(), Vf2d::new(0.6, 0.8));
+    assert!(Vf2d::new(0.0, 0.0).norm().x.is_nan());
+    assert_eq!(Vf2d::new(0.0, 0.0).norm_or_zero(), Vf2d::new(0.0, 0.0));
+  }
+
+  #[test]
+  fn test_vf2d_rounding() {
+    let a = Vf2d::new(1.5, -1.5);
+    assert_eq!(a.floor(), Vf2d::new(1.0, -2.0));
+    assert_eq!(a.ceil(), Vf2d::new(2.0, -1.0));
+    assert_eq!(a.round(), Vf2d::new(2.0, -2.0));
+    assert_eq!(a.abs(), Vf2d::new(1.5, 1.5));
+  }
+
+  #[test]
+  fn test_vf2d_min_max_clamp() {
+    let a = Vf2d::new(1.0, 5.0);
+    let b = Vf2d::new(3.0, -2.0);
+    assert_eq!(a.min(b), Vf2d::new(1.0, -2.0));
+    assert_eq!(a.max(b), Vf2d::new(3.0, 5.0));
+    assert_eq!(a.clamp(Vf2d::new(2.0, 0.0), Vf2d::new(4.0, 4.0)), Vf2d::new(2.0, 4.0));
+  }
+
+  #[test]
+  fn test_vf2d_lerp() {
+    let a = Vf2d::new(0.0, 10.0);
+    let b = Vf2d::new(10.0, 0.0);
+    assert_eq!(a.lerp(b, 0.0), a);
+    assert_eq!(a.lerp(b, 1.0), b);
+    assert_eq!(a.lerp(b, 0.25), Vf2d::new(2.5, 7.5));
+  }
+
+  #[test]
+  fn test_vf2d_angle_rotate() {
+    let a = Vf2d::new(1.0, 0.0).rotate(std::f32::consts::FRAC_PI_2);
+    assert!((a - Vf2d::new(0.0, 1.0)).mag() < 1e-6);
+    assert_eq!(a.angle(), std::f32::consts::FRAC_PI_2);
+  }
+
+  #[test]
+  fn test_vf2d_polar_cart() {
+    let a = Vf2d::new(3.0, 4.0);
+    let polar = a.polar();
+    assert_eq!(polar.x, 5.0);
+    assert_eq!(polar.y, 4f32.atan2(3.0));
+    assert!((polar.cart() - a).mag() < 1e-5);
+  }
+
+  // Sprite tests
+
+  #[test]
+  fn test_sprite_pixels() {
+    let mut sprite = Sprite::with_dims(3, 2);
+    assert_eq!(sprite.pixels().len(), 6);
+    sprite.set_pixel(2, 1, RED);
+    assert_eq!(sprite.pixels()[5], RED);
+    sprite.pixels_mut()[1] = BLUE;
+    assert_eq!(sprite.get_pixel(1, 0), BLUE);
+
+    assert!(Sprite::new().pixels().is_empty());
+    assert!(Sprite::new().pixels_mut().is_empty());
+    assert_eq!(Sprite::new().rows().count(), 0);
+  }
+
+  #[test]
+  fn test_sprite_rows() {
+    let mut sprite = Sprite::with_dims(3, 2);
+    for (y, row) in sprite.rows_mut().enumerate() {
+      for (x, p) in row.iter_mut().enumerate() {
+        *p = Pixel::rgb(x as u8, y as u8, 0);
+      }
+    }
+    let rows: Vec<&[Pixel]> = sprite.rows().collect();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[1].len(), 3);
+    assert_eq!(rows[1][2], Pixel::rgb(2, 1, 0));
+    assert_eq!(sprite.get_pixel(2, 1), Pixel::rgb(2, 1, 0));
+  }
+
+  #[test]
+  fn test_sprite_fill_copy() {
+    let mut sprite = Sprite::with_dims(2, 2);
+    sprite.fill(GREEN);
+    assert!(sprite.pixels().iter().all(|p| *p == GREEN));
+    sprite.copy_from_slice(&[RED, GREEN, BLUE, WHITE]);
+    assert_eq!(sprite.get_pixel(0, 1), BLUE);
+    assert_eq!(sprite.get_pixel(1, 1), WHITE);
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_sprite_copy_wrong_len() {
+    Sprite::with_dims(2, 2).copy_from_slice(&[RED]);
+  }
+
+  fn numbered_sprite(w: i32, h: i32) -> Sprite {
+    let mut sprite = Sprite::with_dims(w, h);
+    for (i, p) in sprite.pixels_mut().iter_mut().enumerate() {
+      *p = Pixel::rgb(i as u8, 0, 0);
+    }
+    sprite
+  }
+
+  fn reds(sprite: &Sprite) -> Vec<u8> {
+    sprite.pixels().iter().map(|p| p.r).collect()
+  }
+
+  #[test]
+  fn test_sprite_clone() {
+    let mut a = numbered_sprite(3, 2);
+    a.set_sample_mode(SpriteMode::PERIODIC);
+    let b = a.clone();
+    a.fill(WHITE);
+    assert_eq!(reds(&b), vec![0, 1, 2, 3, 4, 5]);
+    assert_eq!(b.sample_mode(), SpriteMode::PERIODIC);
+    assert!(!Sprite::new().clone().has_data());
+  }
+
+  #[test]
+  fn test_sprite_crop_blit() {
+    let sprite = numbered_sprite(4, 3);
+    let c = sprite.crop(Vi2d::new(1, 1), Vi2d::new(2, 2));
+    assert_eq!((c.width(), c.height()), (2, 2));
+    assert_eq!(reds(&c), vec![5, 6, 9, 10]);
+
+    let c = sprite.crop(Vi2d::new(3, -1), Vi2d::new(2, 2));
+    assert_eq!(c.pixels(), &[BLANK, BLANK, Pixel::rgb(3, 0, 0), BLANK]);
+
+    let mut dst = Sprite::with_dims(3, 3);
+    dst.fill(BLACK);
+    dst.blit(&sprite, Vi2d::new(-1, 1), Vi2d::new(0, 0), Vi2d::new(3, 3));
+    assert_eq!(dst.get_pixel(0, 1), Pixel::rgb(1, 0, 0));
+    assert_eq!(dst.get_pixel(1, 2), Pixel::rgb(6, 0, 0));
+    assert_eq!(dst.get_pixel(2, 2), BLACK);
+    assert_eq!(dst.get_pixel(0, 0), BLACK);
+  }
+
+  #[test]
+  fn test_sprite_resize() {
+    let sprite = numbered_sprite(2, 2);
+    let big = sprite.resize(4, 4, Filter::Nearest);
+    assert_eq!(reds(&big), vec![0, 0, 1, 1, 0, 0, 1, 1, 2, 2, 3, 3, 2, 2, 3, 3]);
+    let small = big.resize(2, 2, Filter::Nearest);
+    assert_eq!(reds(&small), reds(&sprite));
+
+    let mut grad = Sprite::with_dims(2, 1);
+    grad.copy_from_slice(&[Pixel::rgba(0, 0, 0, 0), Pixel::rgba(200, 0, 0, 200)]);
+    let smooth = grad.resize(4, 1, Filter::Bilinear);
+    assert_eq!(smooth.get_pixel(0, 0), Pixel::rgba(0, 0, 0, 0));
+    assert_eq!(smooth.get_pixel(1, 0), Pixel::rgba(50, 0, 0, 50));
+    assert_eq!(smooth.get_pixel(2, 0), Pixel::rgba(150, 0, 0, 150));
+    assert_eq!(smooth.get_pixel(3, 0), Pixel::rgba(200, 0, 0, 200));
+  }
+
+  #[test]
+  fn test_sprite_rotate_flip() {
+    // 0 1 2
+    // 3 4 5
+    let mut s = numbered_sprite(3, 2);
+    s.rotate90();
+    assert_eq!((s.width(), s.height()), (2, 3));
+    assert_eq!(reds(&s), vec![3, 0, 4, 1, 5, 2]);
+    s.rotate270();
+    assert_eq!(reds(&s), vec![0, 1, 2, 3, 4, 5]);
+    s.rotate180();
+    assert_eq!(reds(&s), vec![5, 4, 3, 2, 1, 0]);
+
+    let mut s = numbered_sprite(3, 3);
+    s.flip_h();
+    assert_eq!(reds(&s), vec![2, 1, 0, 5, 4, 3, 8, 7, 6]);
+    s.flip_v();
+    assert_eq!(reds(&s), vec![8, 7, 6, 5, 4, 3, 2, 1, 0]);
+  }
+
+  fn temp_path(name: &str) -> String {
+    let dir = std::env::temp_dir().join(format!("olc-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join(name).to_str().unwrap().to_string()
+  }
+
+  #[test]
+  fn test_sprite_spr_roundtrip() {
+    let path = temp_path("roundtrip.spr");
+    let sprite = numbered_sprite(3, 2);
+    sprite.save_spr(&path).unwrap();
+    let loaded = Sprite::from_spr(&path).unwrap();
+    assert_eq!((loaded.width(), loaded.height()), (3, 2));
+    assert_eq!(loaded.pixels(), sprite.pixels());
+
+    assert!(Sprite::new().save_spr(&temp_path("empty.spr")).is_err());
+    assert!(Sprite::from_spr(&temp_path("missing.spr")).is_err());
+    std::fs::write(temp_path("truncated.spr"), [3, 0, 0, 0, 2, 0, 0, 0, 1]).unwrap();
+    assert!(Sprite::from_spr(&temp_path("truncated.spr")).is_err());
+  }
+
+  #[test]
+  fn test_sprite_save_png() {
+    let path = temp_path("sprite.png");
+    numbered_sprite(4, 4).save_png(&path).unwrap();
+    let data = std::fs::read(&path).unwrap();
+    assert_eq!(&data[1..4], b"PNG");
+    assert!(Sprite::new().save_png(&temp_path("empty.png")).is_err());
+    assert!(numbered_sprite(1, 1).save_png(&temp_path("missing/dir.png")).is_err());
+    assert_eq!(Sprite::from_image(&path).unwrap().pixels(), numbered_sprite(4, 4).pixels());
+    let err = Sprite::from_image(&temp_path("missing.png")).unwrap_err();
+    assert!(format!("{}", err).contains("No such file"), "{}", err);
+    // Other read errors are reported as they are.
+    let err = Sprite::from_image(&std::env::temp_dir().to_string_lossy()).unwrap_err();
+    assert!(!format!("{}", err).contains("No such file"), "{}", err);
+  }
+
+  #[test]
+  fn test_sprite_from_bytes() {
+    let sprite = Sprite::from_rgba(2, 1, &[1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+    assert_eq!(sprite.pixels(), &[Pixel::rgba(1, 2, 3, 4), Pixel::rgba(5, 6, 7, 8)]);
+    assert!(Sprite::from_rgba(2, 2, &[0; 8]).is_err());
+    assert!(Sprite::from_rgba(-1, 2, &[]).is_err());
+
+    let png = image::png::encode(&numbered_sprite(5, 3)).unwrap();
+    let decoded = Sprite::from_png_bytes(&png).unwrap();
+    assert_eq!(decoded.pixels(), numbered_sprite(5, 3).pixels());
+    assert!(Sprite::from_png_bytes(&png[..20]).is_err());
+    assert!(Sprite::from_png_bytes(b"not a png").is_err());
+  }
+
+  #[test]
+  fn test_draw_target_sprite() {
+    let _lock = engine_lock();
+    let mut outer = Sprite::with_dims(4, 4);
+    let mut inner = Sprite::with_dims(2, 2);
+    outer.fill(BLANK);
+    inner.fill(BLANK);
+    let res = with_draw_target(&mut outer, || {
+      assert_eq!(get_draw_target_width(), 4);
+      fill_rect(1, 1, 2, 2, RED);
+      with_draw_target(&mut inner, || {
+        assert_eq!(get_draw_target_height(), 2);
+        clear(GREEN);
+      });
+      // The outer target is restored after the nested one.
+      draw(0, 0, BLUE);
+      // Resizing would free the layer that was the target before.
+      assert!(std::panic::catch_unwind(|| set_screen_size(8, 8)).is_err());
+      7
+    });
+    assert_eq!(res, 7);
+    assert!(!layer::is_borrowed());
+    assert_eq!(outer.get_pixel(0, 0), BLUE);
+    assert_eq!(outer.get_pixel(2, 2), RED);
+    assert_eq!(outer.get_pixel(3, 3), BLANK);
+    assert!(inner.pixels().iter().all(|&p| p == GREEN));
+    // Without a window there is no primary screen to restore, drawing does nothing.
+    assert_eq!(get_draw_target_width(), 0);
+    assert!(!draw(0, 0, RED));
+    // Freeing the current target resets it to the primary screen.
+    let leaked = Sprite::with_dims(3, 3);
+    unsafe { cpp::SetDrawTargetSprite(&leaked.inner) };
+    assert_eq!(get_draw_target_width(), 3);
+    drop(leaked);
+    assert_eq!(get_draw_target_width(), 0);
+  }
+
+  #[test]
+  fn test_draw_sprite_transformed() {
+    let _lock = engine_lock();
+    let sprite = numbered_sprite(2, 2);
+    let mut t = transform::Transform2D::new();
+    t.scale(2.0, 2.0);
+    t.translate(1.0, 1.0);
+    let mut target = Sprite::with_dims(6, 6);
+    target.fill(BLANK);
+    with_draw_target(&mut target, || draw_sprite_transformed_ext(&sprite, &t, Filter::Nearest));
+    for y in 0..6 {
+      for x in 0..6 {
+        let expected = if (1..5).contains(&x) && (1..5).contains(&y) { sprite.get_pixel((x - 1) / 2, (y - 1) / 2) } else { BLANK };
+        assert_eq!(target.get_pixel(x, y), expected, "({}, {})", x, y);
+      }
+    }
+    // Bilinear sampling covers the same pixels.
+    let mut filtered = Sprite::with_dims(6, 6);
+    filtered.fill(BLANK);
+    with_draw_target(&mut filtered, || draw_sprite_transformed_ext(&sprite, &t, Filter::Bilinear));
+    let covered = |s: &Sprite| -> Vec<bool> { s.pixels().iter().map(|p| p.a > 0).collect() };
+    assert_eq!(covered(&filtered), covered(&target));
+    // Nothing is drawn through a transform that can't be inverted.
+    t.scale(0.0, 1.0);
+    target.fill(BLANK);
+    with_draw_target(&mut target, || draw_sprite_transformed_ext(&sprite, &t, Filter::Nearest));
+    assert!(target.pixels().iter().all(|&p| p == BLANK));
+  }
+
+  #[test]
+  fn test_decal_mode() {
+    let _lock = engine_lock();
+    assert_eq!(get_decal_mode(), DecalMode::NORMAL);
+    set_decal_mode(DecalMode::ADDITIVE);
+    assert_eq!(get_decal_mode(), DecalMode::ADDITIVE);
+    set_decal_mode(DecalMode::NORMAL);
+    assert_eq!(get_decal_mode(), DecalMode::NORMAL);
+  }
+
+  #[test]
+  fn test_layer_pixels() {
+    let _lock = engine_lock();
+    // Without a window there are no layers.
+    assert!(layer::get_draw_target(0).is_none());
+    assert!(layer::get_primary_draw_target().is_none());
+
+    let sprite = numbered_sprite(3, 2);
+    let desc = layer::LayerDesc::from_cpp(cpp::LayerDesc {
+      id: 1,
+      offset: Vf2d::new(0.0, 0.0),
+      scale: Vf2d::new(1.0, 1.0),
+      tint: WHITE,
+      shown: true,
+      // Shares the sprite, like the layer sprites owned by the engine.
+      sprite: unsafe { std::ptr::read(&sprite.inner) }
+    });
+    assert!(desc.is_valid());
+    assert_eq!(desc.get_pixel(2, 1), sprite.get_pixel(2, 1));
+    let copy = desc.with_pixels(|pixels| {
+      assert_eq!((pixels.width(), pixels.height()), (3, 2));
+      assert!(pixels.set_pixel(1, 0, RED));
+      assert!(!pixels.set_pixel(3, 0, RED));
+      assert_eq!(pixels.get_pixel(-1, 0), BLANK);
+      // Resizing would free the pixels.
+      assert!(std::panic::catch_unwind(|| set_screen_size(8, 8)).is_err());
+      pixels.to_sprite()
+    }).unwrap();
+    assert_eq!(sprite.get_pixel(1, 0), RED);
+    assert_eq!(copy.pixels(), sprite.pixels());
+    desc.with_pixels(|pixels| pixels.fill(BLUE));
+    assert!(sprite.pixels().iter().all(|&p| p == BLUE));
+
+    layer::invalidate();
+    assert!(!desc.is_valid());
+    assert!(desc.with_pixels(|pixels| pixels.fill(RED)).is_none());
+    assert_eq!(desc.get_pixel(0, 0), BLANK);
+  }
+
+  #[test]
+  fn test_sprite_save_and_detect() {
+    // The extension is deliberately misleading, the format comes from the contents.
+    let path = temp_path("sprite_qoi.bmp");
+    numbered_sprite(6, 2).save(&path, image::Format::Qoi).unwrap();
+    assert_eq!(Sprite::from_image(&path).unwrap().pixels(), numbered_sprite(6, 2).pixels());
+    let data = std::fs::read(&path).unwrap();
+    assert_eq!(Sprite::from_bytes(&data).unwrap().pixels(), numbered_sprite(6, 2).pixels());
+    assert!(Sprite::from_bytes(b"unknown").is_err());
+    assert!(Sprite::new().save(&temp_path("empty.tga"), image::Format::Tga).is_err());
+  }
 }
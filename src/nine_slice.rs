@@ -0,0 +1,319 @@
+//! Nine-slice drawing of bordered panels at any size.
+//!
+//! A [`NineSlice`](NineSlice) splits a region of a [`Sprite`](crate::Sprite) or a
+//! [`Decal`](crate::Decal) into a 3x3 grid by its four margins. When drawn to a destination
+//! rectangle the corners keep their size, the top and bottom edges fill the width, the left and
+//! right edges fill the height and the centre fills the rest. Edges and centre are either
+//! stretched or tiled, see [`SliceMode`](SliceMode). Corners shrink when the destination is
+//! smaller than the margins.
+//!
+//! ```no_run
+//! # extern crate olc_pixel_game_engine;
+//! # use crate::olc_pixel_game_engine as olc;
+//! use olc::nine_slice::{Margins, NineSlice, SliceMode};
+//!
+//! let image = olc::Sprite::from_image("panel.png").unwrap();
+//! let mut panel = NineSlice::new(image, Margins::uniform(6));
+//! panel.edge_mode = SliceMode::Tile;
+//!
+//! panel.draw(olc::Vi2d::new(10, 10), olc::Vi2d::new(120, 40));
+//! ```
+
+use crate::sprite_sheet::Frame;
+use crate::{Decal, Pixel, Sprite, Vf2d, Vi2d};
+
+/// How the edges and the centre fill the destination.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum SliceMode {
+  /// Scales the source to the destination.
+  #[default]
+  Stretch,
+  /// Repeats the source at its own size, cutting the last copy short.
+  Tile
+}
+
+/// Widths of the borders in pixels of the source.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct Margins {
+  pub left: i32,
+  pub top: i32,
+  pub right: i32,
+  pub bottom: i32
+}
+
+impl Margins {
+  /// Creates new margins.
+  pub fn new(left: i32, top: i32, right: i32, bottom: i32) -> Self {
+    Self { left, top, right, bottom }
+  }
+
+  /// Creates margins of the same width on all sides.
+  pub fn uniform(margin: i32) -> Self {
+    Self::new(margin, margin, margin, margin)
+  }
+}
+
+/// Part of the panel, a source rectangle drawn to a destination rectangle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Patch {
+  pub dst_pos: Vf2d,
+  pub dst_size: Vf2d,
+  pub src_pos: Vf2d,
+  pub src_size: Vf2d
+}
+
+// Part of one axis of a patch.
+#[derive(Clone, Copy, Debug)]
+struct Span {
+  dst: f32,
+  dst_len: f32,
+  src: f32,
+  src_len: f32
+}
+
+// Splits one axis into the spans of the start margin, the middle and the end margin.
+fn axis(dst: f32, dst_len: f32, src: f32, src_len: f32, start: f32, end: f32, scale: f32) -> [Span; 3] {
+  let start = start.clamp(0.0, src_len);
+  let end = end.clamp(0.0, src_len - start);
+  let borders = (start + end) * scale;
+  let shrink = if borders > dst_len { dst_len.max(0.0) / borders } else { 1.0 };
+  let (start_dst, end_dst) = (start * scale * shrink, end * scale * shrink);
+  let middle_dst = (dst_len - start_dst - end_dst).max(0.0);
+  [
+    Span { dst, dst_len: start_dst, src, src_len: start },
+    Span { dst: dst + start_dst, dst_len: middle_dst, src: src + start, src_len: src_len - start - end },
+    Span { dst: dst + start_dst + middle_dst, dst_len: end_dst, src: src + src_len - end, src_len: end }
+  ]
+}
+
+// Splits the span into copies of its source at `scale`.
+fn tile(span: Span, scale: f32) -> Vec<Span> {
+  let step = span.src_len * scale;
+  if step <= 0.0 {
+    return Vec::new();
+  }
+  // Loops over a whole number of tiles, as adding up `step` in f32 stops advancing on long spans.
+  let count = (span.dst_len / step).ceil() as usize;
+  (0..count)
+    .map(|i| {
+      let done = i as f32 * step;
+      let len = step.min(span.dst_len - done);
+      Span { dst: span.dst + done, dst_len: len, src: span.src, src_len: len / scale }
+    })
+    .filter(|s| s.dst_len > 0.0)
+    .collect()
+}
+
+/// Panel image split into nine parts by its margins, `T` is either [`Sprite`](crate::Sprite)
+/// or [`Decal`](crate::Decal).
+#[derive(Debug)]
+pub struct NineSlice<T> {
+  /// Mode of the edges, stretched or tiled along their length.
+  pub edge_mode: SliceMode,
+  /// Mode of the centre, stretched or tiled in both directions.
+  pub centre_mode: SliceMode,
+  image: T,
+  source: Frame,
+  margins: Margins
+}
+
+impl<T: AsRef<Sprite>> NineSlice<T> {
+  /// Creates a nine-slice of the whole image, with stretched edges and centre.
+  pub fn new(image: T, margins: Margins) -> Self {
+    let source = Frame::new(Vi2d::new(0, 0), Vi2d::new(image.as_ref().width(), image.as_ref().height()));
+    Self::from_frame(image, source, margins)
+  }
+
+  /// Creates a nine-slice of the region of the image, such as a frame of a sprite sheet or an
+  /// atlas.
+  pub fn from_frame(image: T, source: Frame, margins: Margins) -> Self {
+    Self { edge_mode: SliceMode::Stretch, centre_mode: SliceMode::Stretch, image, source, margins }
+  }
+
+  /// Returns the image.
+  pub fn image(&self) -> &T {
+    &self.image
+  }
+
+  /// Returns the region of the image.
+  pub fn source(&self) -> Frame {
+    self.source
+  }
+
+  pub fn margins(&self) -> Margins {
+    self.margins
+  }
+
+  /// Returns the smallest size that fits the margins at `scale` without shrinking the corners.
+  pub fn min_size(&self, scale: f32) -> Vf2d {
+    let m = self.margins;
+    Vf2d::new((m.left + m.right) as f32, (m.top + m.bottom) as f32) * scale
+  }
+
+  /// Returns the parts of the panel drawn to the rectangle at `pos` of `size`, with margins and
+  /// tiles scaled by `scale`. Empty parts are left out.
+  pub fn patches(&self, pos: Vf2d, size: Vf2d, scale: f32) -> Vec<Patch> {
+    let (src_pos, src_size, m) = (Vf2d::from(self.source.pos), Vf2d::from(self.source.size), self.margins);
+    let columns = axis(pos.x, size.x, src_pos.x, src_size.x, m.left as f32, m.right as f32, scale);
+    let rows = axis(pos.y, size.y, src_pos.y, src_size.y, m.top as f32, m.bottom as f32, scale);
+    let mut patches = Vec::new();
+    for (j, &row) in rows.iter().enumerate() {
+      for (i, &column) in columns.iter().enumerate() {
+        let mode = if i == 1 && j == 1 { self.centre_mode } else { self.edge_mode };
+        let split = |span: Span, middle: bool| if middle && mode == SliceMode::Tile { tile(span, scale) } else { vec![span] };
+        for y in split(row, j == 1) {
+          for x in split(column, i == 1) {
+            if x.dst_len > 0.0 && y.dst_len > 0.0 && x.src_len > 0.0 && y.src_len > 0.0 {
+              patches.push(Patch {
+                dst_pos: Vf2d::new(x.dst, y.dst),
+                dst_size: Vf2d::new(x.dst_len, y.dst_len),
+                src_pos: Vf2d::new(x.src, y.src),
+                src_size: Vf2d::new(x.src_len, y.src_len)
+              });
+            }
+          }
+        }
+      }
+    }
+    patches
+  }
+
+  /// Draws the panel filling the rectangle at `pos` of `size` on the screen.
+  pub fn draw(&self, pos: Vi2d, size: Vi2d) {
+    self.draw_ext(pos, size, 1)
+  }
+
+  /// Draws the panel with margins and tiles scaled by `scale`.
+  /// Parts that are not drawn at a whole multiple of their size use nearest sampling.
+  pub fn draw_ext(&self, pos: Vi2d, size: Vi2d, scale: u32) {
+    let sprite = self.image.as_ref();
+    let scale = scale.max(1);
+    for p in self.patches(pos.into(), size.into(), scale as f32) {
+      let (x0, y0) = (p.dst_pos.x.round() as i32, p.dst_pos.y.round() as i32);
+      let (w, h) = ((p.dst_pos.x + p.dst_size.x).round() as i32 - x0, (p.dst_pos.y + p.dst_size.y).round() as i32 - y0);
+      let (sx, sy) = (p.src_pos.x.round() as i32, p.src_pos.y.round() as i32);
+      let (sw, sh) = (p.src_size.x.round() as i32, p.src_size.y.round() as i32);
+      if w <= 0 || h <= 0 || sw <= 0 || sh <= 0 {
+        continue;
+      }
+      if w == sw * scale as i32 && h == sh * scale as i32 {
+        crate::draw_partial_sprite_ext(x0, y0, sprite, sx, sy, sw, sh, scale, crate::SpriteFlip::NONE);
+      } else {
+        // Only visits the pixels inside the draw target, samples in i64 as panels may be large.
+        let (tw, th) = (crate::get_draw_target_width(), crate::get_draw_target_height());
+        let (x_start, x_end) = (x0.saturating_neg().max(0), w.min(tw.saturating_sub(x0)));
+        let (y_start, y_end) = (y0.saturating_neg().max(0), h.min(th.saturating_sub(y0)));
+        for y in y_start..y_end {
+          let v = sy + (y as i64 * sh as i64 / h as i64) as i32;
+          for x in x_start..x_end {
+            let u = sx + (x as i64 * sw as i64 / w as i64) as i32;
+            crate::draw(x0 + x, y0 + y, sprite.get_pixel(u, v));
+          }
+        }
+      }
+    }
+  }
+}
+
+impl NineSlice<Sprite> {
+  /// Converts the image into a decal.
+  /// Must be called after the engine has started.
+  pub fn into_decal(self) -> NineSlice<Decal> {
+    NineSlice {
+      edge_mode: self.edge_mode,
+      centre_mode: self.centre_mode,
+      image: Decal::new(self.image),
+      source: self.source,
+      margins: self.margins
+    }
+  }
+}
+
+impl NineSlice<Decal> {
+  /// Draws the panel as decals filling the rectangle at `pos` of `size`.
+  pub fn draw_decal(&self, pos: &Vf2d, size: &Vf2d) {
+    self.draw_decal_ext(pos, size, 1.0, &crate::WHITE)
+  }
+
+  /// Draws the panel as tinted decals with margins and tiles scaled by `scale`.
+  pub fn draw_decal_ext(&self, pos: &Vf2d, size: &Vf2d, scale: f32, tint: &Pixel) {
+    for p in self.patches(*pos, *size, scale) {
+      crate::draw_partial_decal_ext(&p.dst_pos, &self.image, &p.src_pos, &p.src_size, &(p.dst_size / p.src_size), tint);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn rect(patch: &Patch) -> [f32; 8] {
+    [patch.dst_pos.x, patch.dst_pos.y, patch.dst_size.x, patch.dst_size.y, patch.src_pos.x, patch.src_pos.y, patch.src_size.x, patch.src_size.y]
+  }
+
+  #[test]
+  fn test_stretch() {
+    // 12x10 source at (4, 2) with 3 pixel sides and 2 pixel top and bottom.
+    let slice = NineSlice::from_frame(Sprite::with_dims(20, 20), Frame::new(Vi2d::new(4, 2), Vi2d::new(12, 10)), Margins::new(3, 2, 3, 2));
+    let patches = slice.patches(Vf2d::new(100.0, 50.0), Vf2d::new(40.0, 20.0), 1.0);
+    assert_eq!(patches.len(), 9);
+    assert_eq!(rect(&patches[0]), [100.0, 50.0, 3.0, 2.0, 4.0, 2.0, 3.0, 2.0]);
+    assert_eq!(rect(&patches[4]), [103.0, 52.0, 34.0, 16.0, 7.0, 4.0, 6.0, 6.0]);
+    assert_eq!(rect(&patches[8]), [137.0, 68.0, 3.0, 2.0, 13.0, 10.0, 3.0, 2.0]);
+    assert_eq!(slice.min_size(2.0), Vf2d::new(12.0, 8.0));
+
+    // Scaled corners, and corners shrinking to fit a destination smaller than the margins.
+    let patches = slice.patches(Vf2d::new(0.0, 0.0), Vf2d::new(40.0, 20.0), 2.0);
+    assert_eq!(rect(&patches[8]), [34.0, 16.0, 6.0, 4.0, 13.0, 10.0, 3.0, 2.0]);
+    let patches = slice.patches(Vf2d::new(0.0, 0.0), Vf2d::new(3.0, 4.0), 1.0);
+    assert_eq!(patches.len(), 4);
+    assert_eq!(rect(&patches[3]), [1.5, 2.0, 1.5, 2.0, 13.0, 10.0, 3.0, 2.0]);
+    assert!(slice.patches(Vf2d::new(0.0, 0.0), Vf2d::new(0.0, 10.0), 1.0).is_empty());
+  }
+
+  #[test]
+  fn test_tile() {
+    let mut slice = NineSlice::new(Sprite::with_dims(8, 8), Margins::uniform(2));
+    slice.edge_mode = SliceMode::Tile;
+    // Middle of 4 source pixels tiled over 10 destination pixels, 3 copies, the last 2 wide.
+    let patches = slice.patches(Vf2d::new(0.0, 0.0), Vf2d::new(14.0, 8.0), 1.0);
+    let top: Vec<_> = patches.iter().filter(|p| p.dst_pos.y == 0.0 && p.src_pos.x == 2.0).map(rect).collect();
+    assert_eq!(top, [
+      [2.0, 0.0, 4.0, 2.0, 2.0, 0.0, 4.0, 2.0],
+      [6.0, 0.0, 4.0, 2.0, 2.0, 0.0, 4.0, 2.0],
+      [10.0, 0.0, 2.0, 2.0, 2.0, 0.0, 2.0, 2.0]
+    ]);
+    // Stretched centre, tiled edges.
+    assert_eq!(patches.len(), 4 + 3 * 2 + 2 + 1);
+    slice.centre_mode = SliceMode::Tile;
+    assert_eq!(slice.patches(Vf2d::new(0.0, 0.0), Vf2d::new(14.0, 8.0), 1.0).len(), 4 + 3 * 2 + 2 + 3);
+    // Tiles scale with the margins.
+    let patches = slice.patches(Vf2d::new(0.0, 0.0), Vf2d::new(28.0, 16.0), 2.0);
+    assert_eq!(patches.len(), 4 + 3 * 2 + 2 + 3);
+    // Margins wider than the source leave no middle.
+    let wide = NineSlice::new(Sprite::with_dims(4, 4), Margins::uniform(3));
+    assert_eq!(wide.patches(Vf2d::new(0.0, 0.0), Vf2d::new(10.0, 10.0), 1.0).len(), 4);
+  }
+
+  #[test]
+  fn test_draw_clipped() {
+    let _lock = crate::tests::engine_lock();
+    let mut image = Sprite::with_dims(3, 3);
+    image.fill(crate::BLUE);
+    image.set_pixel(1, 1, crate::RED);
+    let slice = NineSlice::new(image, Margins::uniform(1));
+    let mut target = Sprite::with_dims(4, 4);
+    target.fill(crate::BLANK);
+    // Only the pixels of the stretched centre inside the draw target are visited.
+    crate::with_draw_target(&mut target, || slice.draw(Vi2d::new(-1_000_000, -1_000_000), Vi2d::new(2_000_000, 2_000_000)));
+    assert!(target.pixels().iter().all(|&p| p == crate::RED));
+    // Partially visible panel.
+    target.fill(crate::BLANK);
+    crate::with_draw_target(&mut target, || slice.draw(Vi2d::new(2, -5), Vi2d::new(100, 8)));
+    assert_eq!(target.get_pixel(1, 0), crate::BLANK);
+    assert_eq!(target.get_pixel(2, 0), crate::BLUE);
+    assert_eq!(target.get_pixel(3, 0), crate::RED);
+    assert_eq!(target.get_pixel(3, 2), crate::BLUE);
+    assert_eq!(target.get_pixel(3, 3), crate::BLANK);
+  }
+}
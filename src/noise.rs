@@ -0,0 +1,564 @@
+//! Coherent noise generators.
+//!
+//! Perlin, simplex, value and Worley noise in one, two and three dimensions, all implementing
+//! the [`Noise`](Noise) trait, and [`Fractal`](Fractal) which sums octaves of any of them.
+//! Generators are seeded, so the same seed always produces the same texture.
+//!
+//! [`fill_sprite`](fill_sprite) and [`fill_rect`](fill_rect) colour pixels by mapping noise onto a
+//! [`Gradient`](crate::fill::Gradient), so a generated texture can go straight into a decal:
+//!
+//! ```no_run
+//! # extern crate olc_pixel_game_engine;
+//! # use crate::olc_pixel_game_engine as olc;
+//! use olc::fill::{ColourStop, Gradient};
+//! use olc::noise::{self, Fractal, Perlin};
+//!
+//! let terrain = Gradient::with_stops(vec![
+//!   ColourStop::new(0.0, olc::DARK_BLUE),
+//!   ColourStop::new(0.5, olc::YELLOW),
+//!   ColourStop::new(0.6, olc::DARK_GREEN),
+//!   ColourStop::new(1.0, olc::WHITE)
+//! ]);
+//! let mut sprite = olc::Sprite::with_dims(256, 256);
+//! let noise = Fractal::new(Perlin::new(42), 5);
+//! noise::fill_sprite(&mut sprite, &noise, olc::Vf2d::new(0.0, 0.0), 1.0 / 64.0, &terrain);
+//! let decal = olc::Decal::new(sprite);
+//! ```
+
+use crate::fill::Gradient;
+use crate::random::Random;
+use crate::{Sprite, Vf2d};
+
+/// Source of coherent noise, values are in -1.0..=1.0.
+pub trait Noise {
+  /// Returns noise value at the 1D position.
+  fn sample1(&self, x: f32) -> f32;
+  /// Returns noise value at the 2D position.
+  fn sample2(&self, x: f32, y: f32) -> f32;
+  /// Returns noise value at the 3D position.
+  fn sample3(&self, x: f32, y: f32, z: f32) -> f32;
+}
+
+// Permutation table shared by the lattice based generators, doubled to avoid wrapping indices.
+#[derive(Clone)]
+struct Permutation {
+  perm: [u8; 512]
+}
+
+impl Permutation {
+  fn new(seed: u64) -> Self {
+    let mut values: Vec<u8> = (0..=255).collect();
+    Random::new(seed).shuffle(&mut values);
+    let mut perm = [0; 512];
+    for (i, p) in perm.iter_mut().enumerate() {
+      *p = values[i & 255];
+    }
+    Self { perm }
+  }
+
+  fn hash1(&self, i: i32) -> usize {
+    self.perm[(i & 255) as usize] as usize
+  }
+
+  fn hash2(&self, i: i32, j: i32) -> usize {
+    self.perm[self.hash1(i) + (j & 255) as usize] as usize
+  }
+
+  fn hash3(&self, i: i32, j: i32, k: i32) -> usize {
+    self.perm[self.hash2(i, j) + (k & 255) as usize] as usize
+  }
+}
+
+impl std::fmt::Debug for Permutation {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    write!(f, "Permutation")
+  }
+}
+
+fn fade(t: f32) -> f32 {
+  t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+  a + (b - a) * t
+}
+
+fn floor(v: f32) -> (i32, f32) {
+  let f = v.floor();
+  (f as i32, v - f)
+}
+
+fn grad1(hash: usize, x: f32) -> f32 {
+  let g = (1 + (hash & 7)) as f32 / 8.0;
+  if hash & 8 == 0 { g * x } else { -g * x }
+}
+
+fn grad2(hash: usize, x: f32, y: f32) -> f32 {
+  match hash & 7 {
+    0 => x + y,
+    1 => -x + y,
+    2 => x - y,
+    3 => -x - y,
+    4 => x,
+    5 => -x,
+    6 => y,
+    _ => -y
+  }
+}
+
+fn grad3(hash: usize, x: f32, y: f32, z: f32) -> f32 {
+  let h = hash & 15;
+  let u = if h < 8 { x } else { y };
+  let v = if h < 4 { y } else if h == 12 || h == 14 { x } else { z };
+  (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+//----------------------------------
+// Perlin
+//----------------------------------
+
+/// Improved Perlin gradient noise.
+#[derive(Clone, Debug)]
+pub struct Perlin {
+  perm: Permutation
+}
+
+impl Perlin {
+  /// Creates Perlin noise with the seed.
+  pub fn new(seed: u64) -> Self {
+    Self { perm: Permutation::new(seed) }
+  }
+}
+
+impl Noise for Perlin {
+  fn sample1(&self, x: f32) -> f32 {
+    let (i, fx) = floor(x);
+    let a = grad1(self.perm.hash1(i), fx);
+    let b = grad1(self.perm.hash1(i + 1), fx - 1.0);
+    (lerp(a, b, fade(fx)) * 2.0).clamp(-1.0, 1.0)
+  }
+
+  fn sample2(&self, x: f32, y: f32) -> f32 {
+    let (i, fx) = floor(x);
+    let (j, fy) = floor(y);
+    let p = &self.perm;
+    let (u, v) = (fade(fx), fade(fy));
+    let a = lerp(grad2(p.hash2(i, j), fx, fy), grad2(p.hash2(i + 1, j), fx - 1.0, fy), u);
+    let b = lerp(grad2(p.hash2(i, j + 1), fx, fy - 1.0), grad2(p.hash2(i + 1, j + 1), fx - 1.0, fy - 1.0), u);
+    lerp(a, b, v).clamp(-1.0, 1.0)
+  }
+
+  fn sample3(&self, x: f32, y: f32, z: f32) -> f32 {
+    let (i, fx) = floor(x);
+    let (j, fy) = floor(y);
+    let (k, fz) = floor(z);
+    let p = &self.perm;
+    let (u, v, w) = (fade(fx), fade(fy), fade(fz));
+    let corner = |di: i32, dj: i32, dk: i32| {
+      grad3(p.hash3(i + di, j + dj, k + dk), fx - di as f32, fy - dj as f32, fz - dk as f32)
+    };
+    let x00 = lerp(corner(0, 0, 0), corner(1, 0, 0), u);
+    let x10 = lerp(corner(0, 1, 0), corner(1, 1, 0), u);
+    let x01 = lerp(corner(0, 0, 1), corner(1, 0, 1), u);
+    let x11 = lerp(corner(0, 1, 1), corner(1, 1, 1), u);
+    lerp(lerp(x00, x10, v), lerp(x01, x11, v), w).clamp(-1.0, 1.0)
+  }
+}
+
+//----------------------------------
+// Simplex
+//----------------------------------
+
+/// Simplex noise, cheaper than Perlin noise in higher dimensions and without its grid artefacts.
+#[derive(Clone, Debug)]
+pub struct Simplex {
+  perm: Permutation
+}
+
+impl Simplex {
+  /// Creates simplex noise with the seed.
+  pub fn new(seed: u64) -> Self {
+    Self { perm: Permutation::new(seed) }
+  }
+}
+
+impl Noise for Simplex {
+  fn sample1(&self, x: f32) -> f32 {
+    let (i, x0) = floor(x);
+    let x1 = x0 - 1.0;
+    let contrib = |hash: usize, d: f32| {
+      let t = 1.0 - d * d;
+      t * t * t * t * grad1(hash, d) * 8.0
+    };
+    let n = contrib(self.perm.hash1(i), x0) + contrib(self.perm.hash1(i + 1), x1);
+    (n * 0.395).clamp(-1.0, 1.0)
+  }
+
+  fn sample2(&self, x: f32, y: f32) -> f32 {
+    let f2 = 0.5 * (3.0f32.sqrt() - 1.0);
+    let g2 = (3.0 - 3.0f32.sqrt()) / 6.0;
+    let s = (x + y) * f2;
+    let i = (x + s).floor() as i32;
+    let j = (y + s).floor() as i32;
+    let t = (i + j) as f32 * g2;
+    let x0 = x - (i as f32 - t);
+    let y0 = y - (j as f32 - t);
+    let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+    let corners = [
+      (0, 0, x0, y0),
+      (i1, j1, x0 - i1 as f32 + g2, y0 - j1 as f32 + g2),
+      (1, 1, x0 - 1.0 + 2.0 * g2, y0 - 1.0 + 2.0 * g2)
+    ];
+    let n: f32 = corners
+      .iter()
+      .map(|&(di, dj, cx, cy)| {
+        let t = 0.5 - cx * cx - cy * cy;
+        if t < 0.0 { 0.0 } else { t * t * t * t * grad2(self.perm.hash2(i + di, j + dj), cx, cy) }
+      })
+      .sum();
+    (70.0 * n).clamp(-1.0, 1.0)
+  }
+
+  fn sample3(&self, x: f32, y: f32, z: f32) -> f32 {
+    let f3 = 1.0 / 3.0;
+    let g3 = 1.0 / 6.0;
+    let s = (x + y + z) * f3;
+    let i = (x + s).floor() as i32;
+    let j = (y + s).floor() as i32;
+    let k = (z + s).floor() as i32;
+    let t = (i + j + k) as f32 * g3;
+    let x0 = x - (i as f32 - t);
+    let y0 = y - (j as f32 - t);
+    let z0 = z - (k as f32 - t);
+    // Offsets of the second and third corners of the simplex containing the point.
+    let (o1, o2) = if x0 >= y0 {
+      if y0 >= z0 {
+        ((1, 0, 0), (1, 1, 0))
+      } else if x0 >= z0 {
+        ((1, 0, 0), (1, 0, 1))
+      } else {
+        ((0, 0, 1), (1, 0, 1))
+      }
+    } else if y0 < z0 {
+      ((0, 0, 1), (0, 1, 1))
+    } else if x0 < z0 {
+      ((0, 1, 0), (0, 1, 1))
+    } else {
+      ((0, 1, 0), (1, 1, 0))
+    };
+    let corner = |(di, dj, dk): (i32, i32, i32), g: f32| {
+      let cx = x0 - di as f32 + g;
+      let cy = y0 - dj as f32 + g;
+      let cz = z0 - dk as f32 + g;
+      let t = 0.6 - cx * cx - cy * cy - cz * cz;
+      if t < 0.0 { 0.0 } else { t * t * t * t * grad3(self.perm.hash3(i + di, j + dj, k + dk), cx, cy, cz) }
+    };
+    let n = corner((0, 0, 0), 0.0) + corner(o1, g3) + corner(o2, 2.0 * g3) + corner((1, 1, 1), 3.0 * g3);
+    (32.0 * n).clamp(-1.0, 1.0)
+  }
+}
+
+//----------------------------------
+// Value
+//----------------------------------
+
+/// Value noise, smoothly interpolated random values at lattice points.
+#[derive(Clone, Debug)]
+pub struct Value {
+  perm: Permutation
+}
+
+impl Value {
+  /// Creates value noise with the seed.
+  pub fn new(seed: u64) -> Self {
+    Self { perm: Permutation::new(seed) }
+  }
+}
+
+fn lattice(hash: usize) -> f32 {
+  hash as f32 / 255.0 * 2.0 - 1.0
+}
+
+impl Noise for Value {
+  fn sample1(&self, x: f32) -> f32 {
+    let (i, fx) = floor(x);
+    lerp(lattice(self.perm.hash1(i)), lattice(self.perm.hash1(i + 1)), fade(fx))
+  }
+
+  fn sample2(&self, x: f32, y: f32) -> f32 {
+    let (i, fx) = floor(x);
+    let (j, fy) = floor(y);
+    let p = &self.perm;
+    let u = fade(fx);
+    let a = lerp(lattice(p.hash2(i, j)), lattice(p.hash2(i + 1, j)), u);
+    let b = lerp(lattice(p.hash2(i, j + 1)), lattice(p.hash2(i + 1, j + 1)), u);
+    lerp(a, b, fade(fy))
+  }
+
+  fn sample3(&self, x: f32, y: f32, z: f32) -> f32 {
+    let (i, fx) = floor(x);
+    let (j, fy) = floor(y);
+    let (k, fz) = floor(z);
+    let p = &self.perm;
+    let (u, v) = (fade(fx), fade(fy));
+    let layer = |dk: i32| {
+      let a = lerp(lattice(p.hash3(i, j, k + dk)), lattice(p.hash3(i + 1, j, k + dk)), u);
+      let b = lerp(lattice(p.hash3(i, j + 1, k + dk)), lattice(p.hash3(i + 1, j + 1, k + dk)), u);
+      lerp(a, b, v)
+    };
+    lerp(layer(0), layer(1), fade(fz))
+  }
+}
+
+//----------------------------------
+// Worley
+//----------------------------------
+
+/// Worley (cellular) noise based on the distance to the nearest of randomly placed feature
+/// points, one per lattice cell.
+#[derive(Clone, Debug)]
+pub struct Worley {
+  seed: u32
+}
+
+fn mix(mut h: u32) -> u32 {
+  h ^= h >> 16;
+  h = h.wrapping_mul(0x7feb_352d);
+  h ^= h >> 15;
+  h = h.wrapping_mul(0x846c_a68b);
+  h ^ (h >> 16)
+}
+
+impl Worley {
+  /// Creates Worley noise with the seed.
+  pub fn new(seed: u64) -> Self {
+    Self { seed: mix(seed as u32 ^ mix((seed >> 32) as u32)) }
+  }
+
+  // Returns feature point offset of the cell along each axis, in 0.0..1.0.
+  fn feature(&self, i: i32, j: i32, k: i32) -> [f32; 3] {
+    let h = mix(self.seed ^ mix(i as u32 ^ mix(j as u32 ^ mix(k as u32))));
+    let a = mix(h);
+    let b = mix(a);
+    [h as f32 / u32::MAX as f32, a as f32 / u32::MAX as f32, b as f32 / u32::MAX as f32]
+  }
+
+  /// Returns distance from the 1D position to the nearest feature point.
+  pub fn distance1(&self, x: f32) -> f32 {
+    let (i, fx) = floor(x);
+    (-1..=1)
+      .map(|di| (di as f32 + self.feature(i + di, 0, 0)[0] - fx).abs())
+      .fold(f32::MAX, f32::min)
+  }
+
+  /// Returns distance from the 2D position to the nearest feature point.
+  pub fn distance2(&self, x: f32, y: f32) -> f32 {
+    let (i, fx) = floor(x);
+    let (j, fy) = floor(y);
+    let mut best = f32::MAX;
+    for dj in -1..=1 {
+      for di in -1..=1 {
+        let f = self.feature(i + di, j + dj, 0);
+        let d = Vf2d::new(di as f32 + f[0] - fx, dj as f32 + f[1] - fy);
+        best = best.min(d.mag2());
+      }
+    }
+    best.sqrt()
+  }
+
+  /// Returns distance from the 3D position to the nearest feature point.
+  pub fn distance3(&self, x: f32, y: f32, z: f32) -> f32 {
+    let (i, fx) = floor(x);
+    let (j, fy) = floor(y);
+    let (k, fz) = floor(z);
+    let mut best = f32::MAX;
+    for dk in -1..=1 {
+      for dj in -1..=1 {
+        for di in -1..=1 {
+          let f = self.feature(i + di, j + dj, k + dk);
+          let dx = di as f32 + f[0] - fx;
+          let dy = dj as f32 + f[1] - fy;
+          let dz = dk as f32 + f[2] - fz;
+          best = best.min(dx * dx + dy * dy + dz * dz);
+        }
+      }
+    }
+    best.sqrt()
+  }
+}
+
+/// Distance to the nearest feature point is mapped so that -1.0 is on a feature point.
+impl Noise for Worley {
+  fn sample1(&self, x: f32) -> f32 {
+    (self.distance1(x) * 2.0 - 1.0).clamp(-1.0, 1.0)
+  }
+
+  fn sample2(&self, x: f32, y: f32) -> f32 {
+    (self.distance2(x, y) * 2.0 - 1.0).clamp(-1.0, 1.0)
+  }
+
+  fn sample3(&self, x: f32, y: f32, z: f32) -> f32 {
+    (self.distance3(x, y, z) * 2.0 - 1.0).clamp(-1.0, 1.0)
+  }
+}
+
+//----------------------------------
+// Fractal
+//----------------------------------
+
+/// Fractal noise, sum of octaves of the inner noise where each octave has `lacunarity` times
+/// the frequency and `gain` times the amplitude of the previous one.
+#[derive(Clone, Debug)]
+pub struct Fractal<N: Noise> {
+  pub noise: N,
+  pub octaves: u32,
+  pub lacunarity: f32,
+  pub gain: f32
+}
+
+impl<N: Noise> Fractal<N> {
+  /// Creates fractal noise with the number of octaves, lacunarity 2.0 and gain 0.5.
+  pub fn new(noise: N, octaves: u32) -> Self {
+    Self { noise, octaves, lacunarity: 2.0, gain: 0.5 }
+  }
+
+  // Sums octaves and normalises the result back into -1.0..=1.0.
+  fn sum(&self, sample: impl Fn(f32) -> f32) -> f32 {
+    let mut total = 0.0;
+    let mut norm = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    for _ in 0..self.octaves.max(1) {
+      total += sample(frequency) * amplitude;
+      norm += amplitude;
+      amplitude *= self.gain;
+      frequency *= self.lacunarity;
+    }
+    if norm > 0.0 { total / norm } else { 0.0 }
+  }
+}
+
+impl<N: Noise> Noise for Fractal<N> {
+  fn sample1(&self, x: f32) -> f32 {
+    self.sum(|f| self.noise.sample1(x * f))
+  }
+
+  fn sample2(&self, x: f32, y: f32) -> f32 {
+    self.sum(|f| self.noise.sample2(x * f, y * f))
+  }
+
+  fn sample3(&self, x: f32, y: f32, z: f32) -> f32 {
+    self.sum(|f| self.noise.sample3(x * f, y * f, z * f))
+  }
+}
+
+//----------------------------------
+// Filling
+//----------------------------------
+
+// Returns gradient colour for the noise at pixel (x, y).
+fn colour_at<N: Noise + ?Sized>(noise: &N, x: i32, y: i32, offset: Vf2d, frequency: f32, gradient: &Gradient) -> crate::Pixel {
+  let v = noise.sample2((offset.x + x as f32) * frequency, (offset.y + y as f32) * frequency);
+  gradient.colour_at((v + 1.0) * 0.5)
+}
+
+/// Fills the whole sprite with noise mapped onto the gradient, where -1.0 maps to offset 0.0
+/// and 1.0 to offset 1.0. Pixel (x, y) samples the noise at `(offset + (x, y)) * frequency`.
+pub fn fill_sprite<N: Noise + ?Sized>(sprite: &mut Sprite, noise: &N, offset: Vf2d, frequency: f32, gradient: &Gradient) {
+  for (y, row) in sprite.rows_mut().enumerate() {
+    for (x, p) in row.iter_mut().enumerate() {
+      *p = colour_at(noise, x as i32, y as i32, offset, frequency, gradient);
+    }
+  }
+}
+
+/// Fills the rectangle on the current draw target with noise mapped onto the gradient,
+/// same as [`fill_sprite`](fill_sprite) but respecting the current pixel mode.
+/// Noise is sampled at draw target coordinates, so adjacent rectangles line up seamlessly.
+#[allow(clippy::too_many_arguments)]
+pub fn fill_rect<N: Noise + ?Sized>(x: i32, y: i32, w: i32, h: i32, noise: &N, offset: Vf2d, frequency: f32, gradient: &Gradient) {
+  let sx = x.max(0);
+  let sy = y.max(0);
+  let ex = (x + w).min(crate::get_draw_target_width());
+  let ey = (y + h).min(crate::get_draw_target_height());
+  for j in sy..ey {
+    for i in sx..ex {
+      crate::draw(i, j, colour_at(noise, i, j, offset, frequency, gradient));
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn generators() -> Vec<Box<dyn Noise>> {
+    vec![
+      Box::new(Perlin::new(1)),
+      Box::new(Simplex::new(1)),
+      Box::new(Value::new(1)),
+      Box::new(Worley::new(1)),
+      Box::new(Fractal::new(Perlin::new(1), 4))
+    ]
+  }
+
+  #[test]
+  fn test_noise_range_and_continuity() {
+    for noise in generators() {
+      for i in 0..500 {
+        let x = i as f32 * 0.137 - 30.0;
+        let y = i as f32 * 0.071 + 5.0;
+        let z = i as f32 * -0.053;
+        for v in [noise.sample1(x), noise.sample2(x, y), noise.sample3(x, y, z)] {
+          assert!((-1.0..=1.0).contains(&v), "{} out of range", v);
+        }
+        assert!((noise.sample2(x, y) - noise.sample2(x + 0.001, y)).abs() < 0.05);
+        assert!((noise.sample3(x, y, z) - noise.sample3(x, y, z + 0.001)).abs() < 0.05);
+      }
+    }
+  }
+
+  #[test]
+  fn test_noise_seeded() {
+    let a = Simplex::new(7);
+    let b = Simplex::new(7);
+    let c = Simplex::new(8);
+    let pts: Vec<(f32, f32)> = (0..20).map(|i| (i as f32 * 0.37, i as f32 * 0.21)).collect();
+    assert!(pts.iter().all(|&(x, y)| a.sample2(x, y) == b.sample2(x, y)));
+    assert!(pts.iter().any(|&(x, y)| a.sample2(x, y) != c.sample2(x, y)));
+  }
+
+  #[test]
+  fn test_perlin_zero_at_lattice() {
+    let p = Perlin::new(3);
+    assert_eq!(p.sample1(4.0), 0.0);
+    assert_eq!(p.sample2(-2.0, 5.0), 0.0);
+    assert_eq!(p.sample3(1.0, 2.0, 3.0), 0.0);
+    assert!((0..100).any(|i| p.sample2(i as f32 * 0.31 + 0.1, 0.5).abs() > 0.1));
+  }
+
+  #[test]
+  fn test_worley_feature_points() {
+    let w = Worley::new(5);
+    let f = w.feature(2, 3, 0);
+    let d = w.distance2(2.0 + f[0], 3.0 + f[1]);
+    assert!(d < 1e-5);
+    assert!(w.sample2(2.0 + f[0], 3.0 + f[1]) < -0.999);
+  }
+
+  #[test]
+  fn test_fill_sprite() {
+    let mut sprite = Sprite::with_dims(16, 16);
+    let gradient = Gradient::new(crate::BLACK, crate::WHITE);
+    fill_sprite(&mut sprite, &Value::new(2), Vf2d::new(0.0, 0.0), 0.25, &gradient);
+    let mut seen = std::collections::HashSet::new();
+    for y in 0..16 {
+      for x in 0..16 {
+        let p = sprite.get_pixel(x, y);
+        assert!(p.r == p.g && p.g == p.b && p.a == 255);
+        seen.insert(p.r);
+      }
+    }
+    assert!(seen.len() > 10);
+  }
+}
@@ -0,0 +1,556 @@
+//! Colour quantisation and dithering for sprites.
+//!
+//! Reduces a [`Sprite`](crate::Sprite) to a limited palette, either generated from the sprite
+//! itself with median cut or octree quantisation, or provided by the caller. Results can be
+//! returned as a new sprite or as an [`IndexedSprite`](IndexedSprite), which stores one palette
+//! index per pixel and is suitable for indexed formats such as GIF.
+//!
+//! All four channels (including alpha) take part in the quantisation, so palettes may contain
+//! semi-transparent colours.
+//!
+//! ```no_run
+//! # extern crate olc_pixel_game_engine;
+//! # use crate::olc_pixel_game_engine as olc;
+//! use olc::quantise::{Dither, Quantiser};
+//!
+//! let sprite = olc::Sprite::from_image("examples/isometric_demo.png").unwrap();
+//! // Reduce to 16 colours with Floyd-Steinberg dithering.
+//! let retro = olc::quantise::quantise(&sprite, 16, Quantiser::MedianCut, Dither::FloydSteinberg);
+//! // Map onto a fixed two colour palette with ordered dithering.
+//! let palette = [olc::BLACK, olc::WHITE];
+//! let mono = olc::quantise::quantise_to_palette(&sprite, &palette, Dither::Bayer4x4);
+//! ```
+
+use std::collections::HashMap;
+
+use crate::{Pixel, Sprite};
+
+/// Method used to generate a palette from a sprite.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Quantiser {
+  /// Median cut: recursively splits the colour space at the median of the widest channel.
+  MedianCut,
+  /// Octree: builds a colour tree and merges the least populated leaves.
+  Octree
+}
+
+/// Dithering applied when mapping pixels onto a palette.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Dither {
+  /// Every pixel is mapped to the nearest palette colour.
+  None,
+  /// Floyd-Steinberg error diffusion.
+  FloydSteinberg,
+  /// Ordered dithering with a 2x2 Bayer matrix.
+  Bayer2x2,
+  /// Ordered dithering with a 4x4 Bayer matrix.
+  Bayer4x4,
+  /// Ordered dithering with an 8x8 Bayer matrix.
+  Bayer8x8
+}
+
+/// An image stored as palette indices, one `u8` per pixel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexedSprite {
+  width: i32,
+  height: i32,
+  palette: Vec<Pixel>,
+  indices: Vec<u8>
+}
+
+impl IndexedSprite {
+  /// Returns width of the image.
+  pub fn width(&self) -> i32 {
+    self.width
+  }
+
+  /// Returns height of the image.
+  pub fn height(&self) -> i32 {
+    self.height
+  }
+
+  /// Returns palette, at most 256 colours.
+  pub fn palette(&self) -> &[Pixel] {
+    &self.palette
+  }
+
+  /// Returns palette indices in row-major order.
+  pub fn indices(&self) -> &[u8] {
+    &self.indices
+  }
+
+  /// Returns palette index at (x, y), or `None` if the coordinates are out of bounds.
+  pub fn get_index(&self, x: i32, y: i32) -> Option<u8> {
+    if x >= 0 && x < self.width && y >= 0 && y < self.height {
+      Some(self.indices[(y * self.width + x) as usize])
+    } else {
+      None
+    }
+  }
+
+  /// Returns colour at (x, y), or [`BLANK`](crate::BLANK) if the coordinates are out of bounds.
+  pub fn get_pixel(&self, x: i32, y: i32) -> Pixel {
+    match self.get_index(x, y) {
+      Some(i) => self.palette[i as usize],
+      None => crate::BLANK
+    }
+  }
+
+  /// Expands the indexed image into a new sprite.
+  pub fn to_sprite(&self) -> Sprite {
+    let mut sprite = Sprite::with_dims(self.width, self.height);
+    for (p, i) in sprite.pixels_mut().iter_mut().zip(self.indices.iter()) {
+      *p = self.palette[*i as usize];
+    }
+    sprite
+  }
+}
+
+/// Generates a palette of at most `colours` colours for the sprite.
+/// The palette may be smaller if the sprite contains fewer distinct colours.
+///
+/// Panics if `colours` is not between 1 and 256.
+pub fn palette(sprite: &Sprite, colours: usize, method: Quantiser) -> Vec<Pixel> {
+  assert!((1..=256).contains(&colours), "Expected 1 to 256 colours, received {}", colours);
+  let histogram = histogram(sprite.pixels());
+  match method {
+    Quantiser::MedianCut => median_cut(histogram, colours),
+    Quantiser::Octree => octree(&histogram, colours)
+  }
+}
+
+/// Quantises the sprite to at most `colours` colours and returns a new sprite.
+///
+/// Panics if `colours` is not between 1 and 256.
+pub fn quantise(sprite: &Sprite, colours: usize, method: Quantiser, dither: Dither) -> Sprite {
+  quantise_indexed(sprite, colours, method, dither).to_sprite()
+}
+
+/// Quantises the sprite to at most `colours` colours and returns an indexed sprite.
+///
+/// Panics if `colours` is not between 1 and 256.
+pub fn quantise_indexed(sprite: &Sprite, colours: usize, method: Quantiser, dither: Dither) -> IndexedSprite {
+  let palette = palette(sprite, colours, method);
+  quantise_indexed_to_palette(sprite, &palette, dither)
+}
+
+/// Maps every pixel of the sprite onto the provided palette and returns a new sprite.
+///
+/// Panics if the palette is empty or has more than 256 colours.
+pub fn quantise_to_palette(sprite: &Sprite, palette: &[Pixel], dither: Dither) -> Sprite {
+  quantise_indexed_to_palette(sprite, palette, dither).to_sprite()
+}
+
+/// Maps every pixel of the sprite onto the provided palette and returns an indexed sprite.
+///
+/// Panics if the palette is empty or has more than 256 colours.
+pub fn quantise_indexed_to_palette(sprite: &Sprite, palette: &[Pixel], dither: Dither) -> IndexedSprite {
+  assert!(!palette.is_empty() && palette.len() <= 256,
+    "Expected 1 to 256 palette colours, received {}", palette.len());
+  let (width, height) = (sprite.width(), sprite.height());
+  let pixels = sprite.pixels();
+  let indices = match dither {
+    Dither::None => map_nearest(pixels, palette),
+    Dither::FloydSteinberg => map_floyd_steinberg(width, height, pixels, palette),
+    Dither::Bayer2x2 => map_ordered(width, height, pixels, palette, 2),
+    Dither::Bayer4x4 => map_ordered(width, height, pixels, palette, 4),
+    Dither::Bayer8x8 => map_ordered(width, height, pixels, palette, 8)
+  };
+  IndexedSprite { width, height, palette: palette.to_vec(), indices }
+}
+
+/// Returns index of the palette colour closest to `p`.
+pub fn nearest(palette: &[Pixel], p: Pixel) -> usize {
+  nearest_rgba(palette, [p.r as f32, p.g as f32, p.b as f32, p.a as f32])
+}
+
+//----------------------------------
+// Internal helpers
+//----------------------------------
+
+#[inline]
+fn pack(p: Pixel) -> u32 {
+  u32::from_le_bytes([p.r, p.g, p.b, p.a])
+}
+
+#[inline]
+fn unpack(n: u32) -> Pixel {
+  let [r, g, b, a] = n.to_le_bytes();
+  Pixel::rgba(r, g, b, a)
+}
+
+#[inline]
+fn channel(p: Pixel, c: usize) -> u8 {
+  match c {
+    0 => p.r,
+    1 => p.g,
+    2 => p.b,
+    _ => p.a
+  }
+}
+
+// Returns distinct colours with the number of their occurrences, sorted for deterministic output.
+fn histogram(pixels: &[Pixel]) -> Vec<(Pixel, u64)> {
+  let mut counts: HashMap<u32, u64> = HashMap::new();
+  for p in pixels {
+    *counts.entry(pack(*p)).or_insert(0) += 1;
+  }
+  let mut res: Vec<(Pixel, u64)> = counts.into_iter().map(|(k, v)| (unpack(k), v)).collect();
+  res.sort_by_key(|(p, _)| pack(*p));
+  res
+}
+
+fn average(colours: &[(Pixel, u64)]) -> Pixel {
+  let mut sum = [0u64; 4];
+  let mut total = 0u64;
+  for (p, n) in colours {
+    for (c, s) in sum.iter_mut().enumerate() {
+      *s += channel(*p, c) as u64 * n;
+    }
+    total += n;
+  }
+  if total == 0 {
+    return crate::BLANK;
+  }
+  let avg = |c: usize| ((sum[c] + total / 2) / total) as u8;
+  Pixel::rgba(avg(0), avg(1), avg(2), avg(3))
+}
+
+fn median_cut(histogram: Vec<(Pixel, u64)>, colours: usize) -> Vec<Pixel> {
+  if histogram.is_empty() {
+    return Vec::new();
+  }
+
+  // Returns the widest channel and its range for the box.
+  let widest = |b: &[(Pixel, u64)]| -> (usize, u8) {
+    let mut best = (0, 0);
+    for c in 0..4 {
+      let min = b.iter().map(|(p, _)| channel(*p, c)).min().unwrap_or(0);
+      let max = b.iter().map(|(p, _)| channel(*p, c)).max().unwrap_or(0);
+      if max - min > best.1 {
+        best = (c, max - min);
+      }
+    }
+    best
+  };
+
+  let mut boxes = vec![histogram];
+  while boxes.len() < colours {
+    // Split the box with the widest channel range, boxes of a single colour can't be split.
+    let candidate = boxes.iter().enumerate()
+      .filter(|(_, b)| b.len() > 1)
+      .map(|(i, b)| (i, widest(b)))
+      .max_by_key(|(_, (_, range))| *range);
+
+    let (i, (c, _)) = match candidate {
+      Some(candidate) => candidate,
+      None => break
+    };
+
+    let mut b = boxes.swap_remove(i);
+    b.sort_by_key(|(p, _)| (channel(*p, c), pack(*p)));
+    let total: u64 = b.iter().map(|(_, n)| n).sum();
+    let mut acc = 0;
+    let mut split = 1;
+    for (j, (_, n)) in b.iter().enumerate() {
+      acc += n;
+      if acc * 2 >= total {
+        split = j + 1;
+        break;
+      }
+    }
+    // Keep at least one colour on each side of the split.
+    let split = split.max(1).min(b.len() - 1);
+    let upper = b.split_off(split);
+    boxes.push(b);
+    boxes.push(upper);
+  }
+
+  boxes.iter().map(|b| average(b)).collect()
+}
+
+// Octree depth, one level per bit of each channel.
+const OCTREE_DEPTH: usize = 8;
+
+#[derive(Default)]
+struct OctreeNode {
+  children: [usize; 16], // 0 means no child, node 0 is the root
+  sum: [u64; 4],
+  count: u64,
+  leaf: bool,
+  reducible: bool
+}
+
+fn octree(histogram: &[(Pixel, u64)], colours: usize) -> Vec<Pixel> {
+  let mut nodes = vec![OctreeNode::default()];
+  // Nodes per level that have children and can be merged.
+  let mut levels: Vec<Vec<usize>> = vec![Vec::new(); OCTREE_DEPTH];
+  let mut leaves = 0;
+
+  for (p, n) in histogram {
+    let mut node = 0;
+    for (level, reducible) in levels.iter_mut().enumerate() {
+      if nodes[node].leaf {
+        break;
+      }
+      let shift = 7 - level;
+      let child = (((p.r >> shift) & 1) << 3 | ((p.g >> shift) & 1) << 2 |
+        ((p.b >> shift) & 1) << 1 | ((p.a >> shift) & 1)) as usize;
+      if nodes[node].children[child] == 0 {
+        nodes.push(OctreeNode { leaf: level == OCTREE_DEPTH - 1, ..OctreeNode::default() });
+        let id = nodes.len() - 1;
+        nodes[node].children[child] = id;
+        if nodes[id].leaf {
+          leaves += 1;
+        }
+        if !nodes[node].reducible {
+          nodes[node].reducible = true;
+          reducible.push(node);
+        }
+      }
+      node = nodes[node].children[child];
+    }
+    for c in 0..4 {
+      nodes[node].sum[c] += channel(*p, c) as u64 * n;
+    }
+    nodes[node].count += n;
+  }
+
+  // Merge the least populated nodes from the deepest level until the palette fits.
+  while leaves > colours {
+    let level = match levels.iter().rposition(|l| !l.is_empty()) {
+      Some(level) => level,
+      None => break
+    };
+    let weight = |nodes: &Vec<OctreeNode>, id: usize| -> u64 {
+      nodes[id].children.iter().filter(|c| **c != 0).map(|c| nodes[*c].count).sum()
+    };
+    let pos = (0..levels[level].len())
+      .min_by_key(|i| weight(&nodes, levels[level][*i]))
+      .unwrap();
+    let id = levels[level].swap_remove(pos);
+
+    let children = nodes[id].children;
+    let mut merged = 0;
+    for child in children.iter().filter(|c| **c != 0) {
+      for c in 0..4 {
+        nodes[id].sum[c] += nodes[*child].sum[c];
+      }
+      nodes[id].count += nodes[*child].count;
+      merged += 1;
+    }
+    nodes[id].children = [0; 16];
+    nodes[id].leaf = true;
+    leaves = leaves + 1 - merged;
+  }
+
+  let mut palette = Vec::with_capacity(leaves);
+  let mut stack = vec![0];
+  while let Some(id) = stack.pop() {
+    let node = &nodes[id];
+    if node.leaf {
+      if node.count > 0 {
+        let avg = |c: usize| ((node.sum[c] + node.count / 2) / node.count) as u8;
+        palette.push(Pixel::rgba(avg(0), avg(1), avg(2), avg(3)));
+      }
+    } else {
+      stack.extend(node.children.iter().rev().filter(|c| **c != 0));
+    }
+  }
+  palette
+}
+
+fn nearest_rgba(palette: &[Pixel], p: [f32; 4]) -> usize {
+  let mut best = (0, f32::MAX);
+  for (i, q) in palette.iter().enumerate() {
+    let mut dist = 0.0;
+    for (c, v) in p.iter().enumerate() {
+      let d = v - channel(*q, c) as f32;
+      dist += d * d;
+    }
+    if dist < best.1 {
+      best = (i, dist);
+    }
+  }
+  best.0
+}
+
+fn map_nearest(pixels: &[Pixel], palette: &[Pixel]) -> Vec<u8> {
+  let mut cache: HashMap<u32, u8> = HashMap::new();
+  pixels.iter()
+    .map(|p| *cache.entry(pack(*p)).or_insert_with(|| nearest(palette, *p) as u8))
+    .collect()
+}
+
+fn map_floyd_steinberg(width: i32, height: i32, pixels: &[Pixel], palette: &[Pixel]) -> Vec<u8> {
+  let (w, h) = (width as usize, height as usize);
+  let mut buf: Vec<[f32; 4]> = pixels.iter()
+    .map(|p| [p.r as f32, p.g as f32, p.b as f32, p.a as f32])
+    .collect();
+  let mut indices = vec![0u8; w * h];
+
+  for y in 0..h {
+    for x in 0..w {
+      let i = y * w + x;
+      let mut value = buf[i];
+      for v in value.iter_mut() {
+        *v = v.clamp(0.0, 255.0);
+      }
+      let idx = nearest_rgba(palette, value);
+      indices[i] = idx as u8;
+
+      let q = palette[idx];
+      let mut err = [0.0; 4];
+      for (c, e) in err.iter_mut().enumerate() {
+        *e = value[c] - channel(q, c) as f32;
+      }
+      let mut spread = |dx: isize, dy: usize, factor: f32| {
+        let nx = x as isize + dx;
+        let ny = y + dy;
+        if nx >= 0 && (nx as usize) < w && ny < h {
+          let j = ny * w + nx as usize;
+          for c in 0..4 {
+            buf[j][c] += err[c] * factor;
+          }
+        }
+      };
+      spread(1, 0, 7.0 / 16.0);
+      spread(-1, 1, 3.0 / 16.0);
+      spread(0, 1, 5.0 / 16.0);
+      spread(1, 1, 1.0 / 16.0);
+    }
+  }
+  indices
+}
+
+// Returns Bayer threshold matrix of size n x n (n is a power of 2) with values in 0..n*n.
+fn bayer_matrix(n: usize) -> Vec<u32> {
+  let mut m = vec![0u32];
+  let mut size = 1;
+  while size < n {
+    let mut next = vec![0u32; size * size * 4];
+    for y in 0..size {
+      for x in 0..size {
+        let v = m[y * size + x] * 4;
+        next[y * size * 2 + x] = v;
+        next[y * size * 2 + x + size] = v + 2;
+        next[(y + size) * size * 2 + x] = v + 3;
+        next[(y + size) * size * 2 + x + size] = v + 1;
+      }
+    }
+    m = next;
+    size *= 2;
+  }
+  m
+}
+
+fn map_ordered(width: i32, height: i32, pixels: &[Pixel], palette: &[Pixel], n: usize) -> Vec<u8> {
+  let matrix = bayer_matrix(n);
+  // Threshold amplitude roughly matches the distance between palette colours per channel.
+  let spread = 255.0 / (palette.len() as f32).cbrt().max(1.0);
+  let (w, h) = (width as usize, height as usize);
+  let mut indices = vec![0u8; w * h];
+  for y in 0..h {
+    for x in 0..w {
+      let p = pixels[y * w + x];
+      let t = (matrix[(y % n) * n + x % n] as f32 + 0.5) / (n * n) as f32 - 0.5;
+      let offset = t * spread;
+      let value = [
+        (p.r as f32 + offset).clamp(0.0, 255.0),
+        (p.g as f32 + offset).clamp(0.0, 255.0),
+        (p.b as f32 + offset).clamp(0.0, 255.0),
+        p.a as f32
+      ];
+      indices[y * w + x] = nearest_rgba(palette, value) as u8;
+    }
+  }
+  indices
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn gradient_sprite(width: i32, height: i32) -> Sprite {
+    let mut sprite = Sprite::with_dims(width, height);
+    for y in 0..height {
+      for x in 0..width {
+        let v = (x * 255 / (width - 1)) as u8;
+        sprite.set_pixel(x, y, Pixel::rgb(v, v, v));
+      }
+    }
+    sprite
+  }
+
+  #[test]
+  fn test_bayer_matrix() {
+    assert_eq!(bayer_matrix(2), vec![0, 2, 3, 1]);
+    let mut m = bayer_matrix(4);
+    m.sort();
+    assert_eq!(m, (0..16).collect::<Vec<u32>>());
+  }
+
+  #[test]
+  fn test_palette_median_cut() {
+    let sprite = gradient_sprite(16, 2);
+    let palette = palette(&sprite, 4, Quantiser::MedianCut);
+    assert_eq!(palette.len(), 4);
+    // Palette should cover both ends of the gradient.
+    assert!(palette.iter().any(|p| p.r < 64));
+    assert!(palette.iter().any(|p| p.r > 192));
+  }
+
+  #[test]
+  fn test_palette_octree() {
+    let sprite = gradient_sprite(16, 2);
+    let palette = palette(&sprite, 4, Quantiser::Octree);
+    assert!(!palette.is_empty() && palette.len() <= 4);
+  }
+
+  #[test]
+  fn test_palette_fewer_colours() {
+    let mut sprite = Sprite::with_dims(2, 1);
+    sprite.set_pixel(0, 0, crate::RED);
+    sprite.set_pixel(1, 0, crate::BLUE);
+    for method in [Quantiser::MedianCut, Quantiser::Octree].iter() {
+      let mut palette = palette(&sprite, 16, *method);
+      palette.sort_by_key(|p| pack(*p));
+      assert_eq!(palette, vec![crate::RED, crate::BLUE]);
+    }
+  }
+
+  #[test]
+  fn test_quantise_to_palette() {
+    let sprite = gradient_sprite(8, 1);
+    let palette = [crate::BLACK, crate::WHITE];
+    let res = quantise_indexed_to_palette(&sprite, &palette, Dither::None);
+    assert_eq!(res.indices(), &[0, 0, 0, 0, 1, 1, 1, 1]);
+    assert_eq!(res.get_pixel(7, 0), crate::WHITE);
+    assert_eq!(res.get_index(8, 0), None);
+  }
+
+  #[test]
+  fn test_quantise_dither_preserves_average() {
+    let mut sprite = Sprite::with_dims(8, 8);
+    for y in 0..8 {
+      for x in 0..8 {
+        sprite.set_pixel(x, y, Pixel::rgb(128, 128, 128));
+      }
+    }
+    let palette = [crate::BLACK, crate::WHITE];
+    for dither in [Dither::FloydSteinberg, Dither::Bayer2x2, Dither::Bayer8x8].iter() {
+      let res = quantise_indexed_to_palette(&sprite, &palette, *dither);
+      let white = res.indices().iter().filter(|i| **i == 1).count();
+      assert!((24..=40).contains(&white), "{:?} produced {} white pixels", dither, white);
+    }
+  }
+
+  #[test]
+  fn test_indexed_to_sprite() {
+    let sprite = gradient_sprite(4, 4);
+    let res = quantise(&sprite, 2, Quantiser::MedianCut, Dither::None);
+    assert_eq!(res.width(), 4);
+    assert_eq!(res.height(), 4);
+    assert_eq!(res.get_pixel(0, 0), res.get_pixel(0, 3));
+  }
+}
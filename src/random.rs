@@ -0,0 +1,305 @@
+//! Seedable pseudo-random number generation.
+//!
+//! [`Random`](Random) is a small PCG32 generator: fast, well distributed and producing the same
+//! sequence for the same seed on every platform, unlike the C `rand()` behind
+//! [`c_rand`](crate::c_rand). Each thread also has its own generator behind the free functions
+//! [`rand`](rand) and [`srand`](srand), so existing code can migrate one call at a time:
+//!
+//! ```no_run
+//! # extern crate olc_pixel_game_engine;
+//! # use crate::olc_pixel_game_engine as olc;
+//! olc::random::srand(42);
+//! let value = olc::random::rand() % 10; // was `olc::c_rand() % 10`
+//!
+//! let mut rng = olc::random::Random::new(1234);
+//! let speed = rng.range_f32(15.0, 65.0);
+//! let dir = rng.direction() * speed;
+//! ```
+
+use std::cell::RefCell;
+
+use crate::{Vf2d, Vi2d};
+
+const MULTIPLIER: u64 = 6364136223846793005;
+const INCREMENT: u64 = 1442695040888963407;
+
+/// PCG32 pseudo-random number generator.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Random {
+  state: u64
+}
+
+impl Default for Random {
+  fn default() -> Self {
+    Self::new(0)
+  }
+}
+
+impl Random {
+  /// Creates a generator with the seed.
+  pub fn new(seed: u64) -> Self {
+    let mut r = Self { state: 0 };
+    r.seed(seed);
+    r
+  }
+
+  /// Creates a generator seeded from the system time.
+  pub fn from_time() -> Self {
+    let nanos = std::time::SystemTime::now()
+      .duration_since(std::time::UNIX_EPOCH)
+      .map(|d| d.as_nanos() as u64)
+      .unwrap_or(0);
+    Self::new(nanos)
+  }
+
+  /// Restarts the sequence from the seed.
+  pub fn seed(&mut self, seed: u64) {
+    self.state = 0;
+    self.next_u32();
+    self.state = self.state.wrapping_add(seed);
+    self.next_u32();
+  }
+
+  /// Returns next random 32-bit value.
+  pub fn next_u32(&mut self) -> u32 {
+    let old = self.state;
+    self.state = old.wrapping_mul(MULTIPLIER).wrapping_add(INCREMENT);
+    let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+    let rot = (old >> 59) as u32;
+    xorshifted.rotate_right(rot)
+  }
+
+  /// Returns next random 64-bit value.
+  pub fn next_u64(&mut self) -> u64 {
+    ((self.next_u32() as u64) << 32) | self.next_u32() as u64
+  }
+
+  /// Returns random value in 0..=i32::MAX, drop-in replacement for `c_rand()`.
+  pub fn rand(&mut self) -> i32 {
+    (self.next_u32() >> 1) as i32
+  }
+
+  /// Returns random value in 0..n without modulo bias, returns 0 if `n` is 0.
+  pub fn below(&mut self, n: u32) -> u32 {
+    if n == 0 {
+      return 0;
+    }
+    // Lemire's multiply-and-reject method.
+    let mut m = self.next_u32() as u64 * n as u64;
+    if (m as u32) < n {
+      let threshold = n.wrapping_neg() % n;
+      while (m as u32) < threshold {
+        m = self.next_u32() as u64 * n as u64;
+      }
+    }
+    (m >> 32) as u32
+  }
+
+  /// Returns random integer in min..max, returns `min` if the range is empty.
+  pub fn range(&mut self, min: i32, max: i32) -> i32 {
+    if max <= min {
+      return min;
+    }
+    let span = (max as i64 - min as i64) as u32;
+    (min as i64 + self.below(span) as i64) as i32
+  }
+
+  /// Returns random integer in min..=max, returns `min` if the range is empty.
+  pub fn range_inclusive(&mut self, min: i32, max: i32) -> i32 {
+    if max <= min {
+      return min;
+    }
+    let span = max as i64 - min as i64 + 1;
+    if span > u32::MAX as i64 {
+      return self.next_u32() as i32;
+    }
+    (min as i64 + self.below(span as u32) as i64) as i32
+  }
+
+  /// Returns random float in 0.0..1.0.
+  pub fn next_f32(&mut self) -> f32 {
+    (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+  }
+
+  /// Returns random float in min..max.
+  pub fn range_f32(&mut self, min: f32, max: f32) -> f32 {
+    min + (max - min) * self.next_f32()
+  }
+
+  /// Returns true with the probability `p`.
+  pub fn chance(&mut self, p: f32) -> bool {
+    self.next_f32() < p
+  }
+
+  /// Returns random element of the slice, or `None` if it is empty.
+  pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+    if items.is_empty() {
+      None
+    } else {
+      items.get(self.below(items.len() as u32) as usize)
+    }
+  }
+
+  /// Shuffles the slice in place.
+  pub fn shuffle<T>(&mut self, items: &mut [T]) {
+    for i in (1..items.len()).rev() {
+      let j = self.below(i as u32 + 1) as usize;
+      items.swap(i, j);
+    }
+  }
+
+  /// Returns normally distributed float with the mean and standard deviation.
+  pub fn normal(&mut self, mean: f32, std_dev: f32) -> f32 {
+    // Box-Muller transform, u1 is kept away from 0 so that ln stays finite.
+    let u1 = 1.0 - self.next_f32();
+    let u2 = self.next_f32();
+    let z = (-2.0 * u1.ln()).sqrt() * (std::f32::consts::TAU * u2).cos();
+    mean + std_dev * z
+  }
+
+  /// Returns random angle in 0.0..TAU radians.
+  pub fn angle(&mut self) -> f32 {
+    self.next_f32() * std::f32::consts::TAU
+  }
+
+  /// Returns random unit vector.
+  pub fn direction(&mut self) -> Vf2d {
+    let (sin, cos) = self.angle().sin_cos();
+    Vf2d::new(cos, sin)
+  }
+
+  /// Returns random one of the four orthogonal unit directions.
+  pub fn direction4(&mut self) -> Vi2d {
+    const DIRS: [(i32, i32); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+    let (x, y) = DIRS[self.below(4) as usize];
+    Vi2d::new(x, y)
+  }
+
+  /// Returns random one of the eight orthogonal and diagonal directions.
+  pub fn direction8(&mut self) -> Vi2d {
+    const DIRS: [(i32, i32); 8] = [(1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1)];
+    let (x, y) = DIRS[self.below(8) as usize];
+    Vi2d::new(x, y)
+  }
+
+  /// Returns uniformly distributed random point inside the circle of `radius` around the origin.
+  pub fn in_circle(&mut self, radius: f32) -> Vf2d {
+    self.direction() * (radius * self.next_f32().sqrt())
+  }
+
+  /// Returns random point inside the rectangle from `pos` to `pos + size`.
+  pub fn in_rect(&mut self, pos: Vf2d, size: Vf2d) -> Vf2d {
+    Vf2d::new(pos.x + size.x * self.next_f32(), pos.y + size.y * self.next_f32())
+  }
+}
+
+thread_local! {
+  static GLOBAL: RefCell<Random> = RefCell::new(Random::default());
+}
+
+/// Sets seed of the current thread's generator, replacement for `c_srand(seed)`.
+pub fn srand(seed: u64) {
+  GLOBAL.with(|r| r.borrow_mut().seed(seed))
+}
+
+/// Returns random value in 0..=i32::MAX from the current thread's generator,
+/// replacement for `c_rand()`.
+pub fn rand() -> i32 {
+  GLOBAL.with(|r| r.borrow_mut().rand())
+}
+
+/// Calls the function with the current thread's generator.
+pub fn with<R>(f: impl FnOnce(&mut Random) -> R) -> R {
+  GLOBAL.with(|r| f(&mut r.borrow_mut()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_random_reproducible() {
+    let mut a = Random::new(7);
+    let mut b = Random::new(7);
+    let mut c = Random::new(8);
+    let xs: Vec<u32> = (0..16).map(|_| a.next_u32()).collect();
+    let ys: Vec<u32> = (0..16).map(|_| b.next_u32()).collect();
+    let zs: Vec<u32> = (0..16).map(|_| c.next_u32()).collect();
+    assert_eq!(xs, ys);
+    assert_ne!(xs, zs);
+
+    a.seed(7);
+    assert_eq!(a.next_u32(), xs[0]);
+  }
+
+  #[test]
+  fn test_random_ranges() {
+    let mut r = Random::new(1);
+    for _ in 0..1000 {
+      assert!(r.rand() >= 0);
+      let i = r.range(-3, 4);
+      assert!((-3..4).contains(&i));
+      let i = r.range_inclusive(-3, 4);
+      assert!((-3..=4).contains(&i));
+      let f = r.range_f32(2.0, 3.0);
+      assert!((2.0..3.0).contains(&f));
+      assert!(r.below(5) < 5);
+    }
+    assert_eq!(r.range(5, 5), 5);
+    assert_eq!(r.below(0), 0);
+
+    let mut seen = [false; 6];
+    for _ in 0..200 {
+      seen[r.range(0, 6) as usize] = true;
+    }
+    assert!(seen.iter().all(|s| *s));
+  }
+
+  #[test]
+  fn test_random_choose_shuffle() {
+    let mut r = Random::new(3);
+    let empty: [i32; 0] = [];
+    assert_eq!(r.choose(&empty), None);
+    let items = [1, 2, 3];
+    assert!(items.contains(r.choose(&items).unwrap()));
+
+    let mut v: Vec<i32> = (0..50).collect();
+    r.shuffle(&mut v);
+    assert_ne!(v, (0..50).collect::<Vec<_>>());
+    v.sort();
+    assert_eq!(v, (0..50).collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn test_random_normal() {
+    let mut r = Random::new(5);
+    let n = 10000;
+    let samples: Vec<f32> = (0..n).map(|_| r.normal(10.0, 2.0)).collect();
+    let mean = samples.iter().sum::<f32>() / n as f32;
+    let var = samples.iter().map(|s| (s - mean) * (s - mean)).sum::<f32>() / n as f32;
+    assert!((mean - 10.0).abs() < 0.1);
+    assert!((var.sqrt() - 2.0).abs() < 0.1);
+  }
+
+  #[test]
+  fn test_random_directions() {
+    let mut r = Random::new(9);
+    for _ in 0..100 {
+      assert!((r.direction().mag() - 1.0).abs() < 1e-5);
+      assert!(r.in_circle(3.0).mag() <= 3.0 + 1e-5);
+      let d = r.direction4();
+      assert_eq!(d.x.abs() + d.y.abs(), 1);
+      let d = r.direction8();
+      assert!(d.x.abs() <= 1 && d.y.abs() <= 1 && d != Vi2d::new(0, 0));
+    }
+  }
+
+  #[test]
+  fn test_random_global() {
+    srand(11);
+    let a = rand();
+    srand(11);
+    assert_eq!(rand(), a);
+    assert_eq!(with(|r| r.range(0, 1)), 0);
+  }
+}
@@ -0,0 +1,409 @@
+//! Sprite sheets with frames sliced from a grid or defined by name.
+//!
+//! A [`SpriteSheet`](SpriteSheet) wraps a [`Sprite`](crate::Sprite) or a [`Decal`](crate::Decal)
+//! and a list of frames, rectangles of the image that can be drawn by index or by name. Frames
+//! can be sliced from a uniform grid, added by hand or loaded from a descriptor.
+//!
+//! Text descriptors contain one command per line, `#` starts a comment:
+//! ```text
+//! # Slices 16x16 frames, 1 pixel from the edges of the image and 2 pixels apart.
+//! grid 16 16 1 1 2 2
+//! # Names the first frame of the grid.
+//! name 0 idle
+//! # Adds a frame at (0, 32) of size 32x16.
+//! frame door 0 32 32 16
+//! ```
+//! The margin and spacing of `grid` are optional. JSON descriptors use the hash or array format
+//! exported by TexturePacker and Aseprite, frames are read from the `frame` rectangle of each
+//! member of `frames`.
+//!
+//! ```no_run
+//! # extern crate olc_pixel_game_engine;
+//! # use crate::olc_pixel_game_engine as olc;
+//! use olc::sprite_sheet::SpriteSheet;
+//!
+//! let image = olc::Sprite::from_image("tiles.png").unwrap();
+//! let mut tiles = SpriteSheet::from_grid(image, olc::Vi2d::new(8, 8), olc::Vi2d::new(0, 0), olc::Vi2d::new(0, 0));
+//! tiles.set_name(9, "flag");
+//!
+//! tiles.draw_frame(olc::Vi2d::new(0, 0), 3);
+//! tiles.draw_frame(olc::Vi2d::new(8, 0), "flag");
+//! tiles.draw_frame(olc::Vi2d::new(16, 0), (1, 2));
+//! ```
+
+use std::collections::HashMap;
+
+use crate::json::{self, Value};
+use crate::{Decal, Error, Pixel, Sprite, SpriteFlip, Vf2d, Vi2d};
+
+/// Rectangle of the sheet image.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Frame {
+  pub pos: Vi2d,
+  pub size: Vi2d
+}
+
+impl Frame {
+  /// Creates a new frame.
+  pub fn new(pos: Vi2d, size: Vi2d) -> Self {
+    Self { pos, size }
+  }
+}
+
+/// Identifies a frame of a sheet, either by index, by name or by `(column, row)` of the first
+/// grid.
+pub trait FrameId {
+  /// Returns index of the frame in the sheet, `None` if there is no such frame.
+  fn index<T>(&self, sheet: &SpriteSheet<T>) -> Option<usize>;
+}
+
+impl FrameId for usize {
+  fn index<T>(&self, sheet: &SpriteSheet<T>) -> Option<usize> {
+    Some(*self).filter(|&i| i < sheet.frames.len())
+  }
+}
+
+impl FrameId for &str {
+  fn index<T>(&self, sheet: &SpriteSheet<T>) -> Option<usize> {
+    sheet.names.get(*self).copied()
+  }
+}
+
+impl FrameId for (usize, usize) {
+  fn index<T>(&self, sheet: &SpriteSheet<T>) -> Option<usize> {
+    let (column, row) = *self;
+    if column >= sheet.columns {
+      return None;
+    }
+    row.checked_mul(sheet.columns)?.checked_add(column)?.index(sheet)
+  }
+}
+
+impl FrameId for String {
+  fn index<T>(&self, sheet: &SpriteSheet<T>) -> Option<usize> {
+    self.as_str().index(sheet)
+  }
+}
+
+/// Image with a list of frames, `T` is either [`Sprite`](crate::Sprite) or
+/// [`Decal`](crate::Decal).
+#[derive(Debug)]
+pub struct SpriteSheet<T> {
+  image: T,
+  frames: Vec<Frame>,
+  names: HashMap<String, usize>,
+  columns: usize
+}
+
+impl<T: AsRef<Sprite>> SpriteSheet<T> {
+  /// Creates a sheet without frames.
+  pub fn new(image: T) -> Self {
+    Self { image, frames: Vec::new(), names: HashMap::new(), columns: 0 }
+  }
+
+  /// Creates a sheet sliced into frames of `frame_size`, in row-major order.
+  /// The grid starts `margin` pixels from the top left corner and frames are `spacing` pixels apart.
+  pub fn from_grid(image: T, frame_size: Vi2d, margin: Vi2d, spacing: Vi2d) -> Self {
+    let mut sheet = Self::new(image);
+    sheet.add_grid(frame_size, margin, spacing);
+    sheet
+  }
+
+  /// Creates a sheet with frames defined by the text or JSON descriptor, see the
+  /// [module documentation](self).
+  /// Returns error if the descriptor is invalid.
+  pub fn from_descriptor(image: T, descriptor: &str) -> Result<Self, Error> {
+    let mut sheet = Self::new(image);
+    if descriptor.trim_start().starts_with('{') {
+      sheet.load_json(descriptor)?;
+    } else {
+      sheet.load_text(descriptor)?;
+    }
+    Ok(sheet)
+  }
+
+  /// Creates a sheet with frames defined by the descriptor file, see
+  /// [`from_descriptor`](SpriteSheet::from_descriptor).
+  /// Returns error if the file could not be read or is invalid.
+  pub fn from_descriptor_file(image: T, path: &str) -> Result<Self, Error> {
+    let descriptor = std::fs::read_to_string(path)
+      .map_err(|e| Error { msg: format!("Failed to load the sprite sheet: '{}': {}", path, e) })?;
+    Self::from_descriptor(image, &descriptor)
+  }
+
+  /// Appends frames sliced from the grid, see [`from_grid`](SpriteSheet::from_grid).
+  /// Returns the number of frames added.
+  pub fn add_grid(&mut self, frame_size: Vi2d, margin: Vi2d, spacing: Vi2d) -> usize {
+    let (width, height) = (self.image.as_ref().width(), self.image.as_ref().height());
+    let count = |extent: i32, size: i32, margin: i32, spacing: i32| {
+      if size <= 0 || spacing < 0 || extent - margin < size { 0 } else { ((extent - margin + spacing) / (size + spacing)) as usize }
+    };
+    let columns = count(width, frame_size.x, margin.x, spacing.x);
+    let rows = count(height, frame_size.y, margin.y, spacing.y);
+    for y in 0..rows as i32 {
+      for x in 0..columns as i32 {
+        let pos = Vi2d::new(margin.x + x * (frame_size.x + spacing.x), margin.y + y * (frame_size.y + spacing.y));
+        self.frames.push(Frame::new(pos, frame_size));
+      }
+    }
+    if self.columns == 0 {
+      self.columns = columns;
+    }
+    columns * rows
+  }
+
+  /// Appends the frame and returns its index.
+  pub fn add_frame(&mut self, frame: Frame) -> usize {
+    self.frames.push(frame);
+    self.frames.len() - 1
+  }
+
+  /// Appends the frame under the name and returns its index.
+  pub fn add_named_frame(&mut self, name: &str, frame: Frame) -> usize {
+    let index = self.add_frame(frame);
+    self.names.insert(name.to_string(), index);
+    index
+  }
+
+  /// Names the frame at `index`, replacing any frame with the same name.
+  /// Returns false if there is no such frame.
+  pub fn set_name(&mut self, index: usize, name: &str) -> bool {
+    if index >= self.frames.len() {
+      return false;
+    }
+    self.names.insert(name.to_string(), index);
+    true
+  }
+
+  /// Returns index of the frame.
+  pub fn index_of(&self, id: impl FrameId) -> Option<usize> {
+    id.index(self)
+  }
+
+  /// Returns the frame.
+  pub fn frame(&self, id: impl FrameId) -> Option<Frame> {
+    id.index(self).map(|i| self.frames[i])
+  }
+
+  /// Returns the frame in `column` and `row` of the first grid.
+  pub fn grid_frame(&self, column: usize, row: usize) -> Option<Frame> {
+    self.frame((column, row))
+  }
+
+  /// Returns the number of columns of the first grid, 0 if the sheet has no grid.
+  pub fn columns(&self) -> usize {
+    self.columns
+  }
+
+  /// Returns all frames.
+  pub fn frames(&self) -> &[Frame] {
+    &self.frames
+  }
+
+  /// Returns the number of frames.
+  pub fn len(&self) -> usize {
+    self.frames.len()
+  }
+
+  /// Returns true if the sheet has no frames.
+  pub fn is_empty(&self) -> bool {
+    self.frames.is_empty()
+  }
+
+  /// Returns the sheet image.
+  pub fn image(&self) -> &T {
+    &self.image
+  }
+
+  /// Draws the frame with its top left corner at `pos`.
+  /// Returns false if there is no such frame.
+  pub fn draw_frame(&self, pos: Vi2d, id: impl FrameId) -> bool {
+    self.draw_frame_ext(pos, id, 1, SpriteFlip::NONE)
+  }
+
+  /// Draws the frame scaled and flipped.
+  /// Returns false if there is no such frame.
+  pub fn draw_frame_ext(&self, pos: Vi2d, id: impl FrameId, scale: u32, flip: SpriteFlip) -> bool {
+    match self.frame(id) {
+      Some(f) => {
+        crate::draw_partial_sprite_ext(pos.x, pos.y, self.image.as_ref(), f.pos.x, f.pos.y, f.size.x, f.size.y, scale, flip);
+        true
+      }
+      None => false
+    }
+  }
+
+  fn load_text(&mut self, text: &str) -> Result<(), Error> {
+    for (n, line) in text.lines().enumerate() {
+      let line = line.split('#').next().unwrap_or_default();
+      let words: Vec<&str> = line.split_whitespace().collect();
+      let error = |msg: &str| Error { msg: format!("Failed to parse the sprite sheet: {} on line {}", msg, n + 1) };
+      let numbers = |words: &[&str]| -> Result<Vec<i32>, Error> {
+        words.iter().map(|w| w.parse().map_err(|_| error(&format!("invalid number '{}'", w)))).collect()
+      };
+      match words.as_slice() {
+        [] => {}
+        ["grid", args @ ..] if [2, 4, 6].contains(&args.len()) => {
+          let v = numbers(args)?;
+          let arg = |i: usize| Vi2d::new(v.get(i).copied().unwrap_or(0), v.get(i + 1).copied().unwrap_or(0));
+          if v[0] <= 0 || v[1] <= 0 {
+            return Err(error("grid frame size must be positive"));
+          }
+          self.add_grid(arg(0), arg(2), arg(4));
+        }
+        ["frame", name, args @ ..] if args.len() == 4 => {
+          let v = numbers(args)?;
+          self.add_named_frame(name, Frame::new(Vi2d::new(v[0], v[1]), Vi2d::new(v[2], v[3])));
+        }
+        ["name", index, name] => {
+          let index = index.parse().map_err(|_| error(&format!("invalid number '{}'", index)))?;
+          if !self.set_name(index, name) {
+            return Err(error(&format!("no frame {}", index)));
+          }
+        }
+        [command, ..] => return Err(error(&format!("invalid '{}' command", command)))
+      }
+    }
+    Ok(())
+  }
+
+  fn load_json(&mut self, text: &str) -> Result<(), Error> {
+    let error = |msg: &str| Error { msg: format!("Failed to parse the sprite sheet: {}", msg) };
+    let root = json::parse(text)?;
+    let frames = root.get("frames").ok_or_else(|| error("missing 'frames'"))?;
+    let frames: Vec<(&str, &Value)> = if let Some(members) = frames.as_object() {
+      members.iter().map(|(k, v)| (k.as_str(), v)).collect()
+    } else if let Some(items) = frames.as_array() {
+      items.iter().map(|v| (v.get("filename").and_then(Value::as_str).unwrap_or_default(), v)).collect()
+    } else {
+      return Err(error("'frames' must be an object or an array"));
+    };
+    for (name, value) in frames {
+      let rect = value.get("frame").ok_or_else(|| error(&format!("frame '{}' has no 'frame' rectangle", name)))?;
+      let field = |key: &str| {
+        rect.get(key)
+          .and_then(Value::as_i64)
+          .map(|v| v as i32)
+          .ok_or_else(|| error(&format!("frame '{}' has an invalid '{}'", name, key)))
+      };
+      let frame = Frame::new(Vi2d::new(field("x")?, field("y")?), Vi2d::new(field("w")?, field("h")?));
+      if name.is_empty() {
+        self.add_frame(frame);
+      } else {
+        self.add_named_frame(name, frame);
+      }
+    }
+    Ok(())
+  }
+}
+
+impl SpriteSheet<Decal> {
+  /// Draws the frame as a decal with its top left corner at `pos`.
+  /// Returns false if there is no such frame.
+  pub fn draw_frame_decal(&self, pos: &Vf2d, id: impl FrameId) -> bool {
+    self.draw_frame_decal_ext(pos, id, &Vf2d::new(1.0, 1.0), &crate::WHITE)
+  }
+
+  /// Draws the frame as a scaled and tinted decal.
+  /// Returns false if there is no such frame.
+  pub fn draw_frame_decal_ext(&self, pos: &Vf2d, id: impl FrameId, scale: &Vf2d, tint: &Pixel) -> bool {
+    match self.frame(id) {
+      Some(f) => {
+        crate::draw_partial_decal_ext(pos, &self.image, &f.pos.into(), &f.size.into(), scale, tint);
+        true
+      }
+      None => false
+    }
+  }
+
+  /// Draws the frame as a decal warped to the 4 corners in `pos`, clockwise from the top left.
+  /// Returns false if there is no such frame.
+  ///
+  /// Panics if `pos` does not have 4 corners.
+  pub fn draw_frame_warped_decal(&self, pos: &[Vf2d], id: impl FrameId) -> bool {
+    match self.frame(id) {
+      Some(f) => {
+        crate::draw_partial_warped_decal(&self.image, pos, &f.pos.into(), &f.size.into());
+        true
+      }
+      None => false
+    }
+  }
+
+  /// Draws the frame as a decal rotated by `angle` radians around `center`, relative to the frame.
+  /// Returns false if there is no such frame.
+  pub fn draw_frame_rotated_decal(&self, pos: &Vf2d, id: impl FrameId, angle: f32, center: &Vf2d, scale: &Vf2d, tint: &Pixel) -> bool {
+    match self.frame(id) {
+      Some(f) => {
+        crate::draw_partial_rotated_decal_ext(pos, &self.image, angle, center, &f.pos.into(), &f.size.into(), scale, tint);
+        true
+      }
+      None => false
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_grid() {
+    // 2 + 3 * 16 + 2 * 4 = 58 pixels wide fits 3 columns, 2 rows in 40 pixels.
+    let sheet = SpriteSheet::from_grid(Sprite::with_dims(60, 40), Vi2d::new(16, 16), Vi2d::new(2, 1), Vi2d::new(4, 3));
+    assert_eq!(sheet.len(), 6);
+    assert_eq!(sheet.columns(), 3);
+    assert_eq!(sheet.frame(4usize), Some(Frame::new(Vi2d::new(22, 20), Vi2d::new(16, 16))));
+    assert_eq!(sheet.grid_frame(1, 1), sheet.frame(4usize));
+    assert_eq!(sheet.grid_frame(3, 0), None);
+    assert_eq!(sheet.index_of((2, 1)), Some(5));
+    assert_eq!(sheet.index_of((0, 2)), None);
+    assert_eq!(sheet.frame(6usize), None);
+    let empty = SpriteSheet::from_grid(Sprite::with_dims(8, 8), Vi2d::new(16, 16), Vi2d::new(0, 0), Vi2d::new(0, 0));
+    assert!(empty.is_empty());
+  }
+
+  #[test]
+  fn test_names() {
+    let mut sheet = SpriteSheet::from_grid(Sprite::with_dims(16, 8), Vi2d::new(8, 8), Vi2d::new(0, 0), Vi2d::new(0, 0));
+    assert!(sheet.set_name(1, "flag"));
+    assert!(!sheet.set_name(2, "mine"));
+    let door = sheet.add_named_frame("door", Frame::new(Vi2d::new(0, 0), Vi2d::new(16, 8)));
+    assert_eq!(sheet.index_of("flag"), Some(1));
+    assert_eq!(sheet.index_of(String::from("door")), Some(door));
+    assert_eq!(sheet.frame("flag").map(|f| f.pos), Some(Vi2d::new(8, 0)));
+    assert_eq!(sheet.index_of("mine"), None);
+    assert!(!sheet.draw_frame(Vi2d::new(0, 0), "mine"));
+  }
+
+  #[test]
+  fn test_text_descriptor() {
+    let text = "# tiles\ngrid 8 8\nname 1 flag  # second\n\nframe door 0 0 16 8\ngrid 4 4 0 4 0 0\n";
+    let sheet = SpriteSheet::from_descriptor(Sprite::with_dims(16, 8), text).unwrap();
+    assert_eq!(sheet.len(), 2 + 1 + 4);
+    assert_eq!(sheet.columns(), 2);
+    assert_eq!(sheet.index_of("flag"), Some(1));
+    assert_eq!(sheet.frame("door"), Some(Frame::new(Vi2d::new(0, 0), Vi2d::new(16, 8))));
+    assert_eq!(sheet.frame(6usize), Some(Frame::new(Vi2d::new(12, 4), Vi2d::new(4, 4))));
+    for bad in ["grid 8", "grid 0 8", "frame a 1 2 3", "name 5 x", "tile 1 2", "grid 8 x"].iter() {
+      assert!(SpriteSheet::from_descriptor(Sprite::with_dims(16, 8), bad).is_err(), "{}", bad);
+    }
+  }
+
+  #[test]
+  fn test_json_descriptor() {
+    let hash = r#"{ "frames": {
+      "run 0": { "frame": { "x": 0, "y": 0, "w": 8, "h": 8 }, "duration": 100 },
+      "run 1": { "frame": { "x": 8, "y": 0, "w": 8, "h": 8 }, "duration": 100 } } }"#;
+    let sheet = SpriteSheet::from_descriptor(Sprite::with_dims(16, 8), hash).unwrap();
+    assert_eq!(sheet.len(), 2);
+    assert_eq!(sheet.index_of("run 1"), Some(1));
+
+    let array = r#"{ "frames": [ { "filename": "a", "frame": { "x": 1, "y": 2, "w": 3, "h": 4 } } ] }"#;
+    let sheet = SpriteSheet::from_descriptor(Sprite::with_dims(16, 8), array).unwrap();
+    assert_eq!(sheet.frame("a"), Some(Frame::new(Vi2d::new(1, 2), Vi2d::new(3, 4))));
+
+    assert!(SpriteSheet::from_descriptor(Sprite::new(), "{}").is_err());
+    assert!(SpriteSheet::from_descriptor(Sprite::new(), r#"{"frames": [{"frame": {"x": 1}}]}"#).is_err());
+  }
+}
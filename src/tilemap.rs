@@ -0,0 +1,873 @@
+//! Tile maps with layers, tilesets and import of maps made with Tiled.
+//!
+//! A [`Tilemap`](Tilemap) is a grid of tiles in any number of [`TileLayer`](TileLayer)s, drawn
+//! in order, and [`ObjectLayer`](ObjectLayer)s with free placed shapes such as spawn points or
+//! triggers. Each [`Tile`](Tile) is a global tile id with flip flags. Tilesets are images sliced
+//! into a grid of tiles, with either a [`Sprite`](crate::Sprite) or a [`Decal`](crate::Decal) as
+//! the image, and are assigned consecutive ranges of global ids starting from 1. Tile id 0 is
+//! an empty tile.
+//!
+//! Drawing only draws the tiles that are visible on the screen, so large maps are as cheap to
+//! draw as small ones. Tiles are drawn with their bottom left corner at the bottom left corner
+//! of their cell, so tilesets can have tiles larger than the cells of the map, like trees that
+//! overlap the cell above. Orthogonal and isometric (diamond) maps are supported.
+//!
+//! Maps saved by [Tiled](https://www.mapeditor.org) as `.tmx` (XML) or `.tmj` (JSON) can be
+//! loaded with their tilesets, object layers and custom properties. Tile layer data must be
+//! stored as CSV or uncompressed base64, group layers are flattened into the layer list and
+//! image layers are skipped.
+//!
+//! ```no_run
+//! # extern crate olc_pixel_game_engine;
+//! # use crate::olc_pixel_game_engine as olc;
+//! use olc::tilemap::Tilemap;
+//!
+//! let map = Tilemap::from_tiled_file("level.tmx", olc::Sprite::from_image).unwrap();
+//! let spawn = map.object_layer("objects")
+//!   .and_then(|layer| layer.objects.iter().find(|o| o.name == "spawn"))
+//!   .map(|o| o.pos);
+//!
+//! // In `on_user_update`:
+//! # let camera = olc::Vi2d::new(0, 0);
+//! map.draw(-camera);
+//! ```
+
+use std::collections::HashMap;
+
+use crate::sprite_sheet::Frame;
+use crate::{Decal, Pixel, Sprite, SpriteFlip, Vf2d, Vi2d};
+
+mod tiled;
+
+// Flags stored in the top bits of a tile, using the same values as Tiled.
+const FLIP_HORIZONTAL: u32 = 0x8000_0000;
+const FLIP_VERTICAL: u32 = 0x4000_0000;
+const FLIP_DIAGONAL: u32 = 0x2000_0000;
+const GID_MASK: u32 = 0x0fff_ffff;
+
+/// Tile of a tile layer, a global tile id with flip flags.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Tile(u32);
+
+impl Tile {
+  /// Tile that is not drawn.
+  pub const EMPTY: Tile = Tile(0);
+
+  /// Creates a tile with the global id that is not flipped.
+  /// Ids are limited to 28 bits, the upper bits are ignored.
+  pub fn new(gid: u32) -> Self {
+    Tile(gid & GID_MASK)
+  }
+
+  /// Creates a tile from the value stored by Tiled, a global id with the flip flags in the
+  /// upper bits. The hexagonal rotation flag is not supported and ignored.
+  pub fn from_raw(raw: u32) -> Self {
+    Tile(raw & (GID_MASK | FLIP_HORIZONTAL | FLIP_VERTICAL | FLIP_DIAGONAL))
+  }
+
+  /// Returns the value stored by Tiled, the global id with the flip flags in the upper bits.
+  pub fn raw(self) -> u32 {
+    self.0
+  }
+
+  /// Returns the global tile id, 0 for an empty tile.
+  pub fn gid(self) -> u32 {
+    self.0 & GID_MASK
+  }
+
+  /// Returns true if the tile is empty.
+  pub fn is_empty(self) -> bool {
+    self.gid() == 0
+  }
+
+  /// Returns the tile with the flip flags set. The diagonal flip swaps the x and y axes of the
+  /// tile and is applied before the horizontal and vertical flips, so a diagonal and a
+  /// horizontal flip rotate the tile by 90 degrees clockwise.
+  pub fn with_flip(self, horizontal: bool, vertical: bool, diagonal: bool) -> Self {
+    let flags = [(horizontal, FLIP_HORIZONTAL), (vertical, FLIP_VERTICAL), (diagonal, FLIP_DIAGONAL)];
+    Tile(flags.iter().filter(|(set, _)| *set).fold(self.gid(), |raw, (_, flag)| raw | flag))
+  }
+
+  /// Returns true if the tile is flipped horizontally.
+  pub fn flip_horizontal(self) -> bool {
+    self.0 & FLIP_HORIZONTAL != 0
+  }
+
+  /// Returns true if the tile is flipped vertically.
+  pub fn flip_vertical(self) -> bool {
+    self.0 & FLIP_VERTICAL != 0
+  }
+
+  /// Returns true if the x and y axes of the tile are swapped.
+  pub fn flip_diagonal(self) -> bool {
+    self.0 & FLIP_DIAGONAL != 0
+  }
+
+  // Returns the pixel of the tileset frame shown at `pos` of the flipped tile of `size`.
+  fn source_pixel(self, pos: Vi2d, size: Vi2d) -> Vi2d {
+    let x = if self.flip_horizontal() { size.x - 1 - pos.x } else { pos.x };
+    let y = if self.flip_vertical() { size.y - 1 - pos.y } else { pos.y };
+    if self.flip_diagonal() { Vi2d::new(y, x) } else { Vi2d::new(x, y) }
+  }
+
+  // Returns the position in a tile of `size` that the corner `uv` of the frame is flipped to.
+  fn flip_corner(self, uv: Vf2d, size: Vf2d) -> Vf2d {
+    let uv = if self.flip_diagonal() { Vf2d::new(uv.y, uv.x) } else { uv };
+    let x = if self.flip_horizontal() { 1.0 - uv.x } else { uv.x };
+    let y = if self.flip_vertical() { 1.0 - uv.y } else { uv.y };
+    Vf2d::new(x * size.x, y * size.y)
+  }
+}
+
+/// Custom property of a map, layer, tileset, tile or object.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Property {
+  Bool(bool),
+  Int(i64),
+  Float(f64),
+  String(String),
+  Colour(Pixel),
+  /// Path of a file, relative to the map.
+  File(String),
+  /// Id of an object, 0 if not set.
+  Object(u32)
+}
+
+impl Property {
+  /// Returns the value of a `Bool` property.
+  pub fn as_bool(&self) -> Option<bool> {
+    match self {
+      Property::Bool(b) => Some(*b),
+      _ => None
+    }
+  }
+
+  /// Returns the value of an `Int` property.
+  pub fn as_i64(&self) -> Option<i64> {
+    match self {
+      Property::Int(i) => Some(*i),
+      _ => None
+    }
+  }
+
+  /// Returns the value of a `Float` or an `Int` property.
+  pub fn as_f64(&self) -> Option<f64> {
+    match self {
+      Property::Float(f) => Some(*f),
+      Property::Int(i) => Some(*i as f64),
+      _ => None
+    }
+  }
+
+  /// Returns the value of a `String` or a `File` property.
+  pub fn as_str(&self) -> Option<&str> {
+    match self {
+      Property::String(s) | Property::File(s) => Some(s),
+      _ => None
+    }
+  }
+}
+
+/// Custom properties by name.
+pub type Properties = HashMap<String, Property>;
+
+/// Projection of the map grid onto the screen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Orientation {
+  /// Cells are rectangles in rows and columns.
+  #[default]
+  Orthogonal,
+  /// Cells are diamonds, the x axis runs to the bottom right and the y axis to the bottom left
+  /// of the screen. Cell (0, 0) is at the top.
+  Isometric
+}
+
+/// Grid of tiles.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileLayer {
+  pub name: String,
+  pub visible: bool,
+  /// Opacity from 0 to 1, applied when drawing decals.
+  pub opacity: f32,
+  /// Offset of the layer in pixels.
+  pub offset: Vf2d,
+  pub properties: Properties,
+  size: Vi2d,
+  tiles: Vec<Tile>
+}
+
+impl TileLayer {
+  /// Creates a visible layer of empty tiles.
+  pub fn new(name: &str, size: Vi2d) -> Self {
+    let size = size.max(Vi2d::new(0, 0));
+    Self {
+      name: name.to_string(),
+      visible: true,
+      opacity: 1.0,
+      offset: Vf2d::new(0.0, 0.0),
+      properties: Properties::new(),
+      size,
+      tiles: vec![Tile::EMPTY; size.x as usize * size.y as usize]
+    }
+  }
+
+  /// Returns the size of the layer in tiles.
+  pub fn size(&self) -> Vi2d {
+    self.size
+  }
+
+  /// Returns the tile at `pos`, an empty tile if `pos` is outside of the layer.
+  pub fn get(&self, pos: Vi2d) -> Tile {
+    self.index(pos).map_or(Tile::EMPTY, |i| self.tiles[i])
+  }
+
+  /// Sets the tile at `pos`.
+  /// Returns false if `pos` is outside of the layer.
+  pub fn set(&mut self, pos: Vi2d, tile: Tile) -> bool {
+    match self.index(pos) {
+      Some(i) => {
+        self.tiles[i] = tile;
+        true
+      }
+      None => false
+    }
+  }
+
+  /// Sets all tiles of the layer.
+  pub fn fill(&mut self, tile: Tile) {
+    self.tiles.fill(tile);
+  }
+
+  /// Returns the tiles row by row.
+  pub fn tiles(&self) -> &[Tile] {
+    &self.tiles
+  }
+
+  fn index(&self, pos: Vi2d) -> Option<usize> {
+    if pos.x < 0 || pos.y < 0 || pos.x >= self.size.x || pos.y >= self.size.y {
+      None
+    } else {
+      Some((pos.y * self.size.x + pos.x) as usize)
+    }
+  }
+}
+
+/// Shape of an object.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Shape {
+  /// Rectangle of the object size, or a tile if the object has one.
+  #[default]
+  Rectangle,
+  /// Ellipse inside of the object rectangle.
+  Ellipse,
+  /// Point at the object position.
+  Point,
+  /// Closed polygon, points are relative to the object position.
+  Polygon(Vec<Vf2d>),
+  /// Open polyline, points are relative to the object position.
+  Polyline(Vec<Vf2d>),
+  /// Text inside of the object rectangle.
+  Text(String)
+}
+
+/// Object of an object layer.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Object {
+  /// Unique id of the object within the map, 0 if not set.
+  pub id: u32,
+  pub name: String,
+  /// User defined class, named type in older versions of Tiled.
+  pub class: String,
+  /// Position in pixels. Tile objects are positioned by their bottom left corner.
+  pub pos: Vf2d,
+  pub size: Vf2d,
+  /// Rotation in degrees, clockwise around `pos`.
+  pub rotation: f32,
+  pub visible: bool,
+  /// Tile shown by the object.
+  pub tile: Option<Tile>,
+  pub shape: Shape,
+  pub properties: Properties
+}
+
+/// List of objects.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjectLayer {
+  pub name: String,
+  pub visible: bool,
+  /// Offset of the layer in pixels.
+  pub offset: Vf2d,
+  pub properties: Properties,
+  pub objects: Vec<Object>
+}
+
+impl ObjectLayer {
+  /// Creates a visible layer without objects.
+  pub fn new(name: &str) -> Self {
+    Self {
+      name: name.to_string(),
+      visible: true,
+      offset: Vf2d::new(0.0, 0.0),
+      properties: Properties::new(),
+      objects: Vec::new()
+    }
+  }
+
+  /// Returns the first object with the name.
+  pub fn object(&self, name: &str) -> Option<&Object> {
+    self.objects.iter().find(|o| o.name == name)
+  }
+}
+
+/// Layer of a tilemap.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Layer {
+  Tiles(TileLayer),
+  Objects(ObjectLayer)
+}
+
+impl Layer {
+  /// Returns the name of the layer.
+  pub fn name(&self) -> &str {
+    match self {
+      Layer::Tiles(layer) => &layer.name,
+      Layer::Objects(layer) => &layer.name
+    }
+  }
+}
+
+/// Image sliced into a grid of tiles.
+#[derive(Clone, Debug)]
+pub struct Tileset<T> {
+  pub name: String,
+  /// Offset of the tiles in pixels.
+  pub offset: Vi2d,
+  pub properties: Properties,
+  image: T,
+  first_gid: u32,
+  tile_size: Vi2d,
+  margin: Vi2d,
+  spacing: Vi2d,
+  columns: usize,
+  len: usize,
+  tile_properties: HashMap<usize, Properties>
+}
+
+impl<T: AsRef<Sprite>> Tileset<T> {
+  /// Creates a tileset of all tiles of `tile_size` that fit into the image, starting `margin`
+  /// pixels from the top left corner of the image and `spacing` pixels apart.
+  pub fn new(name: &str, image: T, tile_size: Vi2d, margin: Vi2d, spacing: Vi2d) -> Self {
+    let (width, height) = (image.as_ref().width(), image.as_ref().height());
+    let count = |extent: i32, size: i32, margin: i32, spacing: i32| {
+      if size <= 0 || spacing < 0 || extent - margin < size { 0 } else { ((extent - margin + spacing) / (size + spacing)) as usize }
+    };
+    let columns = count(width, tile_size.x, margin.x, spacing.x);
+    let rows = count(height, tile_size.y, margin.y, spacing.y);
+    Self {
+      name: name.to_string(),
+      offset: Vi2d::new(0, 0),
+      properties: Properties::new(),
+      image,
+      first_gid: 0,
+      tile_size,
+      margin,
+      spacing,
+      columns,
+      len: columns * rows,
+      tile_properties: HashMap::new()
+    }
+  }
+}
+
+impl<T> Tileset<T> {
+  /// Returns the image of the tileset.
+  pub fn image(&self) -> &T {
+    &self.image
+  }
+
+  /// Returns the global id of the first tile, assigned when the tileset is added to a map.
+  /// Returns 0 if the tileset is not part of a map.
+  pub fn first_gid(&self) -> u32 {
+    self.first_gid
+  }
+
+  /// Returns the size of a tile in pixels.
+  pub fn tile_size(&self) -> Vi2d {
+    self.tile_size
+  }
+
+  /// Returns the number of tiles in a row of the image.
+  pub fn columns(&self) -> usize {
+    self.columns
+  }
+
+  /// Returns the number of tiles.
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  /// Returns true if the tileset has no tiles.
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Returns the map tile showing the tile at `index` in the tileset.
+  pub fn tile(&self, index: usize) -> Tile {
+    Tile::new(self.first_gid + index as u32)
+  }
+
+  /// Returns the rectangle of the image covered by the tile at `index`.
+  pub fn frame(&self, index: usize) -> Option<Frame> {
+    if index >= self.len || self.columns == 0 {
+      return None;
+    }
+    let (column, row) = ((index % self.columns) as i32, (index / self.columns) as i32);
+    let pos = self.margin + Vi2d::new(column, row) * (self.tile_size + self.spacing);
+    Some(Frame::new(pos, self.tile_size))
+  }
+
+  /// Returns the custom properties of the tile at `index`.
+  pub fn tile_properties(&self, index: usize) -> Option<&Properties> {
+    self.tile_properties.get(&index)
+  }
+
+  /// Sets the custom properties of the tile at `index`.
+  pub fn set_tile_properties(&mut self, index: usize, properties: Properties) {
+    self.tile_properties.insert(index, properties);
+  }
+
+  fn map_image<U>(self, f: impl FnOnce(T) -> U) -> Tileset<U> {
+    Tileset {
+      name: self.name,
+      offset: self.offset,
+      properties: self.properties,
+      image: f(self.image),
+      first_gid: self.first_gid,
+      tile_size: self.tile_size,
+      margin: self.margin,
+      spacing: self.spacing,
+      columns: self.columns,
+      len: self.len,
+      tile_properties: self.tile_properties
+    }
+  }
+}
+
+/// Layers of tiles and objects with the tilesets the tiles are drawn from.
+#[derive(Clone, Debug)]
+pub struct Tilemap<T> {
+  pub properties: Properties,
+  orientation: Orientation,
+  size: Vi2d,
+  tile_size: Vi2d,
+  tilesets: Vec<Tileset<T>>,
+  layers: Vec<Layer>
+}
+
+impl<T> Tilemap<T> {
+  /// Creates a map without layers and tilesets, `size` is the number of cells and `tile_size` the
+  /// size of a cell in pixels.
+  pub fn new(size: Vi2d, tile_size: Vi2d, orientation: Orientation) -> Self {
+    Self {
+      properties: Properties::new(),
+      orientation,
+      size: size.max(Vi2d::new(0, 0)),
+      tile_size: tile_size.max(Vi2d::new(1, 1)),
+      tilesets: Vec::new(),
+      layers: Vec::new()
+    }
+  }
+
+  /// Returns the number of cells in a row and a column.
+  pub fn size(&self) -> Vi2d {
+    self.size
+  }
+
+  /// Returns the size of a cell in pixels.
+  pub fn tile_size(&self) -> Vi2d {
+    self.tile_size
+  }
+
+  pub fn orientation(&self) -> Orientation {
+    self.orientation
+  }
+
+  /// Adds the tileset and returns the global id of its first tile.
+  pub fn add_tileset(&mut self, mut tileset: Tileset<T>) -> u32 {
+    tileset.first_gid = self.tilesets.last().map_or(1, |last| last.first_gid + last.len as u32);
+    self.tilesets.push(tileset);
+    self.tilesets[self.tilesets.len() - 1].first_gid
+  }
+
+  /// Returns the tilesets ordered by their first global id.
+  pub fn tilesets(&self) -> &[Tileset<T>] {
+    &self.tilesets
+  }
+
+  /// Returns the tileset of the tile and the index of the tile in the tileset.
+  pub fn tileset_of(&self, tile: Tile) -> Option<(&Tileset<T>, usize)> {
+    let gid = tile.gid();
+    let i = self.tilesets.partition_point(|t| t.first_gid <= gid);
+    let tileset = self.tilesets.get(i.checked_sub(1)?)?;
+    let index = (gid - tileset.first_gid) as usize;
+    if gid == 0 || index >= tileset.len { None } else { Some((tileset, index)) }
+  }
+
+  /// Returns the custom properties of the tile from its tileset.
+  pub fn tile_properties(&self, tile: Tile) -> Option<&Properties> {
+    self.tileset_of(tile).and_then(|(tileset, index)| tileset.tile_properties(index))
+  }
+
+  /// Appends a layer of empty tiles of the map size and returns it.
+  pub fn add_tile_layer(&mut self, name: &str) -> &mut TileLayer {
+    self.layers.push(Layer::Tiles(TileLayer::new(name, self.size)));
+    match self.layers.last_mut() {
+      Some(Layer::Tiles(layer)) => layer,
+      _ => unreachable!()
+    }
+  }
+
+  /// Appends a layer without objects and returns it.
+  pub fn add_object_layer(&mut self, name: &str) -> &mut ObjectLayer {
+    self.layers.push(Layer::Objects(ObjectLayer::new(name)));
+    match self.layers.last_mut() {
+      Some(Layer::Objects(layer)) => layer,
+      _ => unreachable!()
+    }
+  }
+
+  /// Returns the layers in drawing order.
+  pub fn layers(&self) -> &[Layer] {
+    &self.layers
+  }
+
+  /// Returns the layers for adding, removing or reordering.
+  pub fn layers_mut(&mut self) -> &mut Vec<Layer> {
+    &mut self.layers
+  }
+
+  /// Returns the first tile layer with the name.
+  pub fn tile_layer(&self, name: &str) -> Option<&TileLayer> {
+    self.layers.iter().find_map(|layer| match layer {
+      Layer::Tiles(layer) if layer.name == name => Some(layer),
+      _ => None
+    })
+  }
+
+  /// Returns the first tile layer with the name.
+  pub fn tile_layer_mut(&mut self, name: &str) -> Option<&mut TileLayer> {
+    self.layers.iter_mut().find_map(|layer| match layer {
+      Layer::Tiles(layer) if layer.name == name => Some(layer),
+      _ => None
+    })
+  }
+
+  /// Returns the first object layer with the name.
+  pub fn object_layer(&self, name: &str) -> Option<&ObjectLayer> {
+    self.layers.iter().find_map(|layer| match layer {
+      Layer::Objects(layer) if layer.name == name => Some(layer),
+      _ => None
+    })
+  }
+
+  /// Returns the top left corner of the bounding box of the cell, in pixels relative to the
+  /// top left corner of the map.
+  pub fn tile_to_world(&self, cell: Vi2d) -> Vi2d {
+    match self.orientation {
+      Orientation::Orthogonal => cell * self.tile_size,
+      Orientation::Isometric => Vi2d::new(
+        (cell.x - cell.y + self.size.y - 1) * self.tile_size.x / 2,
+        (cell.x + cell.y) * self.tile_size.y / 2
+      )
+    }
+  }
+
+  /// Returns the cell at the position in pixels relative to the top left corner of the map.
+  /// The cell can be outside of the map.
+  pub fn world_to_tile(&self, pos: Vf2d) -> Vi2d {
+    let size = Vf2d::from(self.tile_size);
+    match self.orientation {
+      Orientation::Orthogonal => (pos / size).floor().into(),
+      Orientation::Isometric => {
+        let x = (pos.x - self.size.y as f32 * size.x / 2.0) / size.x;
+        let y = pos.y / size.y;
+        Vf2d::new(y + x, y - x).floor().into()
+      }
+    }
+  }
+
+  /// Returns the range of cells, from the first inclusive to the last exclusive, with tiles that
+  /// can be visible in the area of `size` at `pos`, in pixels relative to the map.
+  pub fn visible_tiles(&self, pos: Vf2d, size: Vf2d) -> (Vi2d, Vi2d) {
+    let corners = [pos, pos + Vf2d::new(size.x, 0.0), pos + Vf2d::new(0.0, size.y), pos + size];
+    let first = corners.iter().map(|&c| self.world_to_tile(c)).fold(Vi2d::new(i32::MAX, i32::MAX), |a, b| a.min(b));
+    let last = corners.iter().map(|&c| self.world_to_tile(c)).fold(Vi2d::new(i32::MIN, i32::MIN), |a, b| a.max(b));
+    // Tiles larger than a cell or with an offset overhang the neighbouring cells.
+    let overhang = self.tilesets.iter()
+      .map(|t| t.tile_size.x.max(t.tile_size.y) + t.offset.x.abs().max(t.offset.y.abs()))
+      .max()
+      .unwrap_or(0);
+    let cell = self.tile_size.x.min(self.tile_size.y);
+    let mut extra = ((overhang - cell).max(0) + cell - 1) / cell;
+    // The bounding box of an isometric cell overlaps its neighbours.
+    if self.orientation == Orientation::Isometric {
+      extra += 1;
+    }
+    let zero = Vi2d::new(0, 0);
+    let first = Vi2d::new(first.x.saturating_sub(extra), first.y.saturating_sub(extra)).clamp(zero, self.size);
+    let last = Vi2d::new(last.x.saturating_add(extra + 1), last.y.saturating_add(extra + 1)).clamp(zero, self.size);
+    (first, last)
+  }
+
+  // Returns the tileset frame of the tile and its position relative to the cell.
+  fn tile_frame(&self, tile: Tile) -> Option<(&Tileset<T>, Frame, Vi2d)> {
+    let (tileset, index) = self.tileset_of(tile)?;
+    let frame = tileset.frame(index)?;
+    let height = if tile.flip_diagonal() { frame.size.x } else { frame.size.y };
+    Some((tileset, frame, tileset.offset + Vi2d::new(0, self.tile_size.y - height)))
+  }
+
+  // Returns the cells of the layer that can be visible in the area of `size` at `pos` on screen.
+  fn visible_cells(&self, layer: &TileLayer, pos: Vf2d, size: Vf2d) -> impl Iterator<Item = Vi2d> {
+    let (first, last) = self.visible_tiles(-pos, size);
+    let last = last.min(layer.size);
+    (first.y..last.y).flat_map(move |y| (first.x..last.x).map(move |x| Vi2d::new(x, y)))
+  }
+}
+
+impl Tilemap<Sprite> {
+  /// Converts the tileset images into decals.
+  /// Must be called after the engine has started.
+  pub fn into_decals(self) -> Tilemap<Decal> {
+    Tilemap {
+      properties: self.properties,
+      orientation: self.orientation,
+      size: self.size,
+      tile_size: self.tile_size,
+      tilesets: self.tilesets.into_iter().map(|t| t.map_image(Decal::new)).collect(),
+      layers: self.layers
+    }
+  }
+}
+
+impl<T: AsRef<Sprite>> Tilemap<T> {
+  /// Draws the visible tile layers with the top left corner of the map at `pos` on the draw
+  /// target.
+  pub fn draw(&self, pos: Vi2d) {
+    for layer in self.layers.iter() {
+      if let Layer::Tiles(layer) = layer {
+        if layer.visible {
+          self.draw_tiles(layer, pos);
+        }
+      }
+    }
+  }
+
+  /// Draws the tile layer at `index`, even if it is hidden.
+  /// Returns false if there is no tile layer at `index`.
+  pub fn draw_layer(&self, index: usize, pos: Vi2d) -> bool {
+    match self.layers.get(index) {
+      Some(Layer::Tiles(layer)) => {
+        self.draw_tiles(layer, pos);
+        true
+      }
+      _ => false
+    }
+  }
+
+  fn draw_tiles(&self, layer: &TileLayer, pos: Vi2d) {
+    let origin = pos + Vi2d::from(layer.offset.round());
+    // Maps may be drawn into sprites, so cull against the draw target rather than the screen.
+    let target = Vf2d::new(crate::get_draw_target_width() as f32, crate::get_draw_target_height() as f32);
+    for cell in self.visible_cells(layer, origin.into(), target) {
+      let tile = layer.get(cell);
+      if let Some((tileset, frame, offset)) = self.tile_frame(tile) {
+        let at = origin + self.tile_to_world(cell) + offset;
+        draw_tile(tileset.image.as_ref(), frame, at, tile);
+      }
+    }
+  }
+}
+
+fn draw_tile(image: &Sprite, frame: Frame, pos: Vi2d, tile: Tile) {
+  let flip = match (tile.flip_horizontal(), tile.flip_vertical(), tile.flip_diagonal()) {
+    (false, false, false) => SpriteFlip::NONE,
+    (true, false, false) => SpriteFlip::HORIZ,
+    (false, true, false) => SpriteFlip::VERT,
+    _ => {
+      // The engine can't combine flips, so draw the pixels one by one.
+      let size = if tile.flip_diagonal() { Vi2d::new(frame.size.y, frame.size.x) } else { frame.size };
+      for y in 0..size.y {
+        for x in 0..size.x {
+          let src = frame.pos + tile.source_pixel(Vi2d::new(x, y), size);
+          crate::draw(pos.x + x, pos.y + y, image.get_pixel(src.x, src.y));
+        }
+      }
+      return;
+    }
+  };
+  crate::draw_partial_sprite_ext(pos.x, pos.y, image, frame.pos.x, frame.pos.y, frame.size.x, frame.size.y, 1, flip);
+}
+
+impl Tilemap<Decal> {
+  /// Draws the visible tile layers as decals with the top left corner of the map at `pos` on
+  /// the screen, scaled by `scale`.
+  pub fn draw_decal(&self, pos: &Vf2d, scale: &Vf2d) {
+    for layer in self.layers.iter() {
+      if let Layer::Tiles(layer) = layer {
+        if layer.visible {
+          self.draw_tiles_decal(layer, pos, scale);
+        }
+      }
+    }
+  }
+
+  /// Draws the tile layer at `index` as decals, even if it is hidden.
+  /// Returns false if there is no tile layer at `index`.
+  pub fn draw_layer_decal(&self, index: usize, pos: &Vf2d, scale: &Vf2d) -> bool {
+    match self.layers.get(index) {
+      Some(Layer::Tiles(layer)) => {
+        self.draw_tiles_decal(layer, pos, scale);
+        true
+      }
+      _ => false
+    }
+  }
+
+  fn draw_tiles_decal(&self, layer: &TileLayer, pos: &Vf2d, scale: &Vf2d) {
+    let origin = *pos + layer.offset * *scale;
+    let screen = Vf2d::new(crate::screen_width() as f32, crate::screen_height() as f32);
+    let tint = Pixel::rgba(255, 255, 255, (layer.opacity.clamp(0.0, 1.0) * 255.0).round() as u8);
+    for cell in self.visible_cells(layer, origin / *scale, screen / *scale) {
+      let tile = layer.get(cell);
+      if let Some((tileset, frame, offset)) = self.tile_frame(tile) {
+        let at = origin + Vf2d::from(self.tile_to_world(cell) + offset) * *scale;
+        draw_tile_decal(&tileset.image, frame, at, tile, scale, &tint);
+      }
+    }
+  }
+}
+
+fn draw_tile_decal(decal: &Decal, frame: Frame, pos: Vf2d, tile: Tile, scale: &Vf2d, tint: &Pixel) {
+  if !(tile.flip_horizontal() || tile.flip_vertical() || tile.flip_diagonal()) {
+    crate::draw_partial_decal_ext(&pos, decal, &frame.pos.into(), &frame.size.into(), scale, tint);
+    return;
+  }
+  let size = if tile.flip_diagonal() { Vf2d::new(frame.size.y as f32, frame.size.x as f32) } else { frame.size.into() };
+  // Warped decals take the corners in the order top left, bottom left, bottom right, top right.
+  let corners = [(0.0, 0.0), (0.0, 1.0), (1.0, 1.0), (1.0, 0.0)]
+    .map(|(u, v)| pos + tile.flip_corner(Vf2d::new(u, v), size * *scale));
+  crate::draw_partial_warped_decal_ext(decal, &corners, &frame.pos.into(), &frame.size.into(), tint);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn tileset(name: &str, width: i32, height: i32) -> Tileset<Sprite> {
+    Tileset::new(name, Sprite::with_dims(width, height), Vi2d::new(8, 8), Vi2d::new(0, 0), Vi2d::new(0, 0))
+  }
+
+  #[test]
+  fn test_tile_flags() {
+    let tile = Tile::new(5).with_flip(true, false, true);
+    assert_eq!(tile.gid(), 5);
+    assert_eq!(tile.raw(), 0xa000_0005);
+    assert!(tile.flip_horizontal() && !tile.flip_vertical() && tile.flip_diagonal());
+    assert_eq!(Tile::from_raw(0x5000_0003), Tile::new(3).with_flip(false, true, false));
+    assert!(Tile::EMPTY.is_empty());
+    assert!(Tile::new(0).with_flip(true, true, true).is_empty());
+
+    // Diagonal and horizontal flip rotates clockwise: the top left pixel of the frame ends up in
+    // the top right corner, the bottom left pixel in the top left corner.
+    let size = Vi2d::new(4, 2);
+    assert_eq!(tile.source_pixel(Vi2d::new(3, 0), size), Vi2d::new(0, 0));
+    assert_eq!(tile.source_pixel(Vi2d::new(0, 0), size), Vi2d::new(0, 3));
+    let size = Vf2d::new(4.0, 2.0);
+    assert_eq!(tile.flip_corner(Vf2d::new(0.0, 0.0), size), Vf2d::new(4.0, 0.0));
+    assert_eq!(tile.flip_corner(Vf2d::new(0.0, 1.0), size), Vf2d::new(0.0, 0.0));
+  }
+
+  #[test]
+  fn test_layers() {
+    let mut map: Tilemap<Sprite> = Tilemap::new(Vi2d::new(4, 3), Vi2d::new(8, 8), Orientation::Orthogonal);
+    let ground = map.add_tile_layer("ground");
+    ground.fill(Tile::new(1));
+    assert!(ground.set(Vi2d::new(3, 2), Tile::new(2)));
+    assert!(!ground.set(Vi2d::new(4, 0), Tile::new(2)));
+    map.add_object_layer("objects").objects.push(Object { name: "spawn".to_string(), ..Object::default() });
+
+    assert_eq!(map.layers().len(), 2);
+    assert_eq!(map.layers()[1].name(), "objects");
+    let ground = map.tile_layer("ground").unwrap();
+    assert_eq!(ground.size(), Vi2d::new(4, 3));
+    assert_eq!(ground.get(Vi2d::new(3, 2)), Tile::new(2));
+    assert_eq!(ground.get(Vi2d::new(0, 0)), Tile::new(1));
+    assert_eq!(ground.get(Vi2d::new(-1, 0)), Tile::EMPTY);
+    assert_eq!(ground.tiles().len(), 12);
+    assert!(map.tile_layer("objects").is_none());
+    assert!(map.object_layer("objects").unwrap().object("spawn").is_some());
+    map.tile_layer_mut("ground").unwrap().visible = false;
+    assert!(!map.tile_layer("ground").unwrap().visible);
+  }
+
+  #[test]
+  fn test_tilesets() {
+    let mut map = Tilemap::new(Vi2d::new(4, 4), Vi2d::new(8, 8), Orientation::Orthogonal);
+    assert_eq!(map.add_tileset(tileset("a", 16, 16)), 1);
+    let mut b = tileset("b", 24, 8);
+    b.set_tile_properties(1, Properties::from([("solid".to_string(), Property::Bool(true))]));
+    assert_eq!(map.add_tileset(b), 5);
+
+    assert!(map.tileset_of(Tile::EMPTY).is_none());
+    let (a, index) = map.tileset_of(Tile::new(4).with_flip(true, false, false)).unwrap();
+    assert_eq!((a.name.as_str(), index), ("a", 3));
+    assert_eq!(a.frame(index), Some(Frame::new(Vi2d::new(8, 8), Vi2d::new(8, 8))));
+    let (b, index) = map.tileset_of(Tile::new(6)).unwrap();
+    assert_eq!((b.name.as_str(), index, b.columns()), ("b", 1, 3));
+    assert_eq!(b.tile(index), Tile::new(6));
+    assert!(map.tileset_of(Tile::new(8)).is_none());
+    assert_eq!(map.tile_properties(Tile::new(6)).and_then(|p| p["solid"].as_bool()), Some(true));
+    assert!(map.tile_properties(Tile::new(5)).is_none());
+  }
+
+  #[test]
+  fn test_coordinates() {
+    let mut map: Tilemap<Sprite> = Tilemap::new(Vi2d::new(10, 8), Vi2d::new(16, 16), Orientation::Orthogonal);
+    assert_eq!(map.tile_to_world(Vi2d::new(2, 3)), Vi2d::new(32, 48));
+    assert_eq!(map.world_to_tile(Vf2d::new(47.9, 48.0)), Vi2d::new(2, 3));
+    assert_eq!(map.world_to_tile(Vf2d::new(-0.5, 0.0)), Vi2d::new(-1, 0));
+    assert_eq!(map.visible_tiles(Vf2d::new(20.0, 20.0), Vf2d::new(40.0, 30.0)), (Vi2d::new(1, 1), Vi2d::new(4, 4)));
+    assert_eq!(map.visible_tiles(Vf2d::new(-100.0, 0.0), Vf2d::new(50.0, 50.0)).1.x, 0);
+    map.add_tileset(Tileset::new("tall", Sprite::with_dims(16, 48), Vi2d::new(16, 48), Vi2d::new(0, 0), Vi2d::new(0, 0)));
+    // Tiles three cells high can overhang two cells.
+    assert_eq!(map.visible_tiles(Vf2d::new(64.0, 64.0), Vf2d::new(16.0, 16.0)), (Vi2d::new(2, 2), Vi2d::new(8, 8)));
+
+    let map: Tilemap<Sprite> = Tilemap::new(Vi2d::new(4, 4), Vi2d::new(40, 20), Orientation::Isometric);
+    assert_eq!(map.tile_to_world(Vi2d::new(0, 0)), Vi2d::new(60, 0));
+    assert_eq!(map.tile_to_world(Vi2d::new(0, 3)), Vi2d::new(0, 30));
+    assert_eq!(map.tile_to_world(Vi2d::new(1, 1)), Vi2d::new(60, 20));
+    // Centre of a cell and points near its corners.
+    assert_eq!(map.world_to_tile(Vf2d::new(80.0, 30.0)), Vi2d::new(1, 1));
+    assert_eq!(map.world_to_tile(Vf2d::new(61.0, 22.0)), Vi2d::new(0, 1));
+    assert_eq!(map.world_to_tile(Vf2d::new(99.0, 22.0)), Vi2d::new(1, 0));
+    assert_eq!(map.visible_tiles(Vf2d::new(0.0, 0.0), Vf2d::new(160.0, 80.0)), (Vi2d::new(0, 0), Vi2d::new(4, 4)));
+  }
+
+  #[test]
+  fn test_draw_into_sprite() {
+    let _lock = crate::tests::engine_lock();
+    let mut image = Sprite::with_dims(2, 1);
+    image.copy_from_slice(&[crate::RED, crate::BLUE]);
+    let mut map = Tilemap::new(Vi2d::new(3, 1), Vi2d::new(1, 1), Orientation::Orthogonal);
+    map.add_tileset(Tileset::new("colours", image, Vi2d::new(1, 1), Vi2d::new(0, 0), Vi2d::new(0, 0)));
+    let layer = map.add_tile_layer("ground");
+    layer.fill(Tile::new(1));
+    layer.set(Vi2d::new(2, 0), Tile::new(2));
+    // Without a window the screen has no size, the target sprite decides what is visible.
+    let mut target = Sprite::with_dims(4, 1);
+    target.fill(crate::BLANK);
+    crate::with_draw_target(&mut target, || map.draw(Vi2d::new(1, 0)));
+    assert_eq!(target.pixels(), [crate::BLANK, crate::RED, crate::RED, crate::BLUE]);
+  }
+}
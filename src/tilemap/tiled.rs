@@ -0,0 +1,787 @@
+// Import of maps and tilesets saved by Tiled, in the XML (.tmx, .tsx) and JSON (.tmj, .tsj)
+// formats.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::path::Path;
+
+use super::{Layer, Object, ObjectLayer, Orientation, Properties, Property, Shape, Tile, TileLayer, Tilemap, Tileset};
+use crate::json::{self, Value};
+use crate::xml::{self, Element};
+use crate::{Error, Pixel, Sprite, Vf2d, Vi2d};
+
+// Largest number of tiles in a map, guards against huge allocations for invalid sizes.
+const MAX_TILES: i64 = 1 << 26;
+
+fn error(msg: impl fmt::Display) -> Error {
+  Error { msg: format!("Failed to load the tilemap: {}", msg) }
+}
+
+fn read_file(path: &Path) -> Result<String, Error> {
+  std::fs::read_to_string(path).map_err(|e| error(format!("'{}': {}", path.display(), e)))
+}
+
+// Returns `path` relative to `dir` as passed to the image loader.
+fn join(dir: &Path, path: &str) -> String {
+  dir.join(path).to_string_lossy().into_owned()
+}
+
+impl<T: AsRef<Sprite>> Tilemap<T> {
+  /// Creates a map from the contents of a Tiled `.tmx` file. Tileset images and external
+  /// tilesets are resolved relative to `dir`, images are loaded by calling `load` with the path,
+  /// e.g. [`Sprite::from_image`](crate::Sprite::from_image).
+  /// Returns error if the map is invalid or uses unsupported features.
+  pub fn from_tmx(text: &str, dir: &str, mut load: impl FnMut(&str) -> Result<T, Error>) -> Result<Self, Error> {
+    xml_map(&xml::parse(text)?, Path::new(dir), &mut load)
+  }
+
+  /// Creates a map from the contents of a Tiled `.tmj` file, see
+  /// [`from_tmx`](Tilemap::from_tmx).
+  /// Returns error if the map is invalid or uses unsupported features.
+  pub fn from_tmj(text: &str, dir: &str, mut load: impl FnMut(&str) -> Result<T, Error>) -> Result<Self, Error> {
+    json_map(&json::parse(text)?, Path::new(dir), &mut load)
+  }
+
+  /// Loads a Tiled map file, either `.tmx` or `.tmj` detected from the contents. Paths are
+  /// resolved relative to the directory of the file, see [`from_tmx`](Tilemap::from_tmx).
+  /// Returns error if the file could not be read, is invalid or uses unsupported features.
+  pub fn from_tiled_file(path: &str, load: impl FnMut(&str) -> Result<T, Error>) -> Result<Self, Error> {
+    let text = read_file(Path::new(path))?;
+    let dir = Path::new(path).parent().map(|d| d.to_string_lossy().into_owned()).unwrap_or_default();
+    if text.trim_start().starts_with('<') {
+      Self::from_tmx(&text, &dir, load)
+    } else {
+      Self::from_tmj(&text, &dir, load)
+    }
+  }
+}
+
+// Layer attributes inherited from group layers.
+#[derive(Clone, Copy)]
+struct Group {
+  offset: Vf2d,
+  visible: bool,
+  opacity: f32
+}
+
+impl Group {
+  fn root() -> Self {
+    Group { offset: Vf2d::new(0.0, 0.0), visible: true, opacity: 1.0 }
+  }
+
+  fn nest(self, offset: Vf2d, visible: bool, opacity: f32) -> Self {
+    Group { offset: self.offset + offset, visible: self.visible && visible, opacity: self.opacity * opacity }
+  }
+}
+
+fn orientation(name: &str) -> Result<Orientation, Error> {
+  match name {
+    "orthogonal" => Ok(Orientation::Orthogonal),
+    "isometric" => Ok(Orientation::Isometric),
+    _ => Err(error(format!("orientation '{}' is not supported", name)))
+  }
+}
+
+fn map_size(width: i64, height: i64) -> Result<Vi2d, Error> {
+  if width < 0 || height < 0 || width.saturating_mul(height) > MAX_TILES {
+    return Err(error(format!("invalid map size {}x{}", width, height)));
+  }
+  Ok(Vi2d::new(width as i32, height as i32))
+}
+
+fn tile_size(width: i64, height: i64) -> Result<Vi2d, Error> {
+  if !(1..=1 << 16).contains(&width) || !(1..=1 << 16).contains(&height) {
+    return Err(error(format!("invalid tile size {}x{}", width, height)));
+  }
+  Ok(Vi2d::new(width as i32, height as i32))
+}
+
+// Parses colours in the "#AARRGGBB" or "#RRGGBB" format, the empty string is transparent.
+fn colour(s: &str) -> Result<Pixel, Error> {
+  let hex = s.trim_start_matches('#');
+  let value = u32::from_str_radix(hex, 16);
+  match (hex.len(), value) {
+    (0, _) => Ok(crate::BLANK),
+    (6, Ok(v)) => Ok(Pixel::rgb((v >> 16) as u8, (v >> 8) as u8, v as u8)),
+    (8, Ok(v)) => Ok(Pixel::rgba((v >> 16) as u8, (v >> 8) as u8, v as u8, (v >> 24) as u8)),
+    _ => Err(error(format!("invalid colour '{}'", s)))
+  }
+}
+
+fn base64(text: &str) -> Result<Vec<u8>, Error> {
+  let mut out = Vec::with_capacity(text.len() / 4 * 3);
+  let (mut acc, mut bits) = (0u32, 0);
+  for c in text.bytes() {
+    let v = match c {
+      b'A'..=b'Z' => c - b'A',
+      b'a'..=b'z' => c - b'a' + 26,
+      b'0'..=b'9' => c - b'0' + 52,
+      b'+' => 62,
+      b'/' => 63,
+      b'=' => break,
+      b' ' | b'\t' | b'\n' | b'\r' => continue,
+      _ => return Err(error("invalid base64 data"))
+    };
+    acc = (acc << 6 | v as u32) & 0xffff;
+    bits += 6;
+    if bits >= 8 {
+      bits -= 8;
+      out.push((acc >> bits) as u8);
+    }
+  }
+  Ok(out)
+}
+
+// Decodes tile data stored as little endian 32-bit values.
+fn base64_tiles(text: &str) -> Result<Vec<Tile>, Error> {
+  let data = base64(text)?;
+  if data.len() % 4 != 0 {
+    return Err(error("base64 tile data is not a multiple of 4 bytes"));
+  }
+  Ok(data.chunks_exact(4).map(|b| Tile::from_raw(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))).collect())
+}
+
+fn check_compression(compression: Option<&str>) -> Result<(), Error> {
+  match compression {
+    None | Some("") => Ok(()),
+    Some(c) => Err(error(format!("'{}' compressed tile data is not supported", c)))
+  }
+}
+
+fn fill_layer(layer: &mut TileLayer, tiles: Vec<Tile>) -> Result<(), Error> {
+  if tiles.len() != layer.tiles.len() {
+    return Err(error(format!("layer '{}' has {} tiles, expected {}", layer.name, tiles.len(), layer.tiles.len())));
+  }
+  layer.tiles = tiles;
+  Ok(())
+}
+
+// Loads the tileset image and creates the tileset. Tiled stores the number of tiles and
+// columns, older versions only the image, so the grid of the image is the fallback.
+#[allow(clippy::too_many_arguments)]
+fn tileset<T: AsRef<Sprite>>(
+  name: &str, first_gid: i64, tile_size: Vi2d, margin: i64, spacing: i64, columns: Option<i64>, count: Option<i64>,
+  image: &str, load: &mut impl FnMut(&str) -> Result<T, Error>
+) -> Result<Tileset<T>, Error> {
+  if !(1..=i64::from(super::GID_MASK)).contains(&first_gid) {
+    return Err(error(format!("invalid first tile id {} of tileset '{}'", first_gid, name)));
+  }
+  if !(0..=1 << 16).contains(&margin) || !(0..=1 << 16).contains(&spacing) {
+    return Err(error(format!("invalid margin or spacing of tileset '{}'", name)));
+  }
+  let (margin, spacing) = (Vi2d::new(margin as i32, margin as i32), Vi2d::new(spacing as i32, spacing as i32));
+  let mut tileset = Tileset::new(name, load(image)?, tile_size, margin, spacing);
+  tileset.first_gid = first_gid as u32;
+  if let (Some(columns), Some(count)) = (columns, count) {
+    if columns < 0 || !(0..=MAX_TILES).contains(&count) {
+      return Err(error(format!("invalid number of tiles of tileset '{}'", name)));
+    }
+    tileset.columns = columns as usize;
+    tileset.len = count as usize;
+  }
+  Ok(tileset)
+}
+
+fn sort_tilesets<T>(map: &mut Tilemap<T>) -> Result<(), Error> {
+  map.tilesets.sort_by_key(|t| t.first_gid);
+  for pair in map.tilesets.windows(2) {
+    if pair[0].first_gid + pair[0].len as u32 > pair[1].first_gid {
+      return Err(error(format!("tilesets '{}' and '{}' overlap", pair[0].name, pair[1].name)));
+    }
+  }
+  Ok(())
+}
+
+//----------------------------------
+// XML
+//----------------------------------
+
+fn attr<'a>(el: &'a Element, name: &str) -> Result<&'a str, Error> {
+  el.attr(name).ok_or_else(|| error(format!("<{}> has no '{}' attribute", el.name, name)))
+}
+
+fn parse_attr<V: std::str::FromStr>(el: &Element, name: &str, default: Option<V>) -> Result<V, Error> {
+  match (el.attr(name), default) {
+    (Some(s), _) => s.trim().parse().map_err(|_| error(format!("invalid '{}' attribute of <{}>: '{}'", name, el.name, s))),
+    (None, Some(default)) => Ok(default),
+    (None, None) => Err(error(format!("<{}> has no '{}' attribute", el.name, name)))
+  }
+}
+
+fn xml_properties(el: &Element) -> Result<Properties, Error> {
+  let mut properties = Properties::new();
+  for p in el.child("properties").into_iter().flat_map(|list| list.children_named("property")) {
+    let name = attr(p, "name")?;
+    // Multi-line strings are stored as the text of the element.
+    let value = p.attr("value").unwrap_or(&p.text);
+    let invalid = || error(format!("invalid value of property '{}': '{}'", name, value));
+    let property = match p.attr("type").unwrap_or("string") {
+      "string" => Property::String(value.to_string()),
+      "int" => Property::Int(value.parse().map_err(|_| invalid())?),
+      "float" => Property::Float(value.parse().map_err(|_| invalid())?),
+      "bool" => Property::Bool(value == "true"),
+      "color" => Property::Colour(colour(value)?),
+      "file" => Property::File(value.to_string()),
+      "object" => Property::Object(value.parse().map_err(|_| invalid())?),
+      // Class properties have nested members, which are not supported.
+      _ => continue
+    };
+    properties.insert(name.to_string(), property);
+  }
+  Ok(properties)
+}
+
+fn xml_map<T: AsRef<Sprite>>(root: &Element, dir: &Path, load: &mut impl FnMut(&str) -> Result<T, Error>) -> Result<Tilemap<T>, Error> {
+  if root.name != "map" {
+    return Err(error("expected a <map> element"));
+  }
+  if root.attr("infinite") == Some("1") {
+    return Err(error("infinite maps are not supported"));
+  }
+  let orientation = orientation(root.attr("orientation").unwrap_or("orthogonal"))?;
+  let size = map_size(parse_attr(root, "width", None)?, parse_attr(root, "height", None)?)?;
+  let tile_size = tile_size(parse_attr(root, "tilewidth", None)?, parse_attr(root, "tileheight", None)?)?;
+  let mut map = Tilemap::new(size, tile_size, orientation);
+  map.properties = xml_properties(root)?;
+  for el in root.children_named("tileset") {
+    let first_gid = parse_attr(el, "firstgid", None)?;
+    let tileset = match el.attr("source") {
+      Some(source) => external_tileset(dir, source, first_gid, load)?,
+      None => xml_tileset(el, first_gid, dir, load)?
+    };
+    map.tilesets.push(tileset);
+  }
+  sort_tilesets(&mut map)?;
+  xml_layers(&mut map, root, Group::root())?;
+  Ok(map)
+}
+
+fn xml_tileset<T: AsRef<Sprite>>(
+  el: &Element, first_gid: i64, dir: &Path, load: &mut impl FnMut(&str) -> Result<T, Error>
+) -> Result<Tileset<T>, Error> {
+  let name = el.attr("name").unwrap_or_default();
+  let image = el.child("image")
+    .ok_or_else(|| error(format!("tileset '{}' has no image, image collections are not supported", name)))?;
+  let tile_size = tile_size(parse_attr(el, "tilewidth", None)?, parse_attr(el, "tileheight", None)?)?;
+  let mut tileset = tileset(
+    name, first_gid, tile_size, parse_attr(el, "margin", Some(0))?, parse_attr(el, "spacing", Some(0))?,
+    parse_attr(el, "columns", None).ok(), parse_attr(el, "tilecount", None).ok(),
+    &join(dir, attr(image, "source")?), load
+  )?;
+  if let Some(offset) = el.child("tileoffset") {
+    tileset.offset = Vi2d::new(parse_attr(offset, "x", Some(0))?, parse_attr(offset, "y", Some(0))?);
+  }
+  tileset.properties = xml_properties(el)?;
+  for tile in el.children_named("tile") {
+    let properties = xml_properties(tile)?;
+    if !properties.is_empty() {
+      tileset.tile_properties.insert(parse_attr(tile, "id", None)?, properties);
+    }
+  }
+  Ok(tileset)
+}
+
+// Returns the offset, visibility and opacity of a layer element.
+fn xml_group(el: &Element, parent: Group) -> Result<Group, Error> {
+  let offset = Vf2d::new(parse_attr(el, "offsetx", Some(0.0))?, parse_attr(el, "offsety", Some(0.0))?);
+  Ok(parent.nest(offset, el.attr("visible") != Some("0"), parse_attr(el, "opacity", Some(1.0))?))
+}
+
+fn xml_layers<T>(map: &mut Tilemap<T>, parent: &Element, group: Group) -> Result<(), Error> {
+  for el in parent.children.iter() {
+    let name = el.attr("name").unwrap_or_default();
+    match el.name.as_str() {
+      "layer" => {
+        let g = xml_group(el, group)?;
+        let mut layer = TileLayer::new(name, map.size);
+        layer.visible = g.visible;
+        layer.opacity = g.opacity;
+        layer.offset = g.offset;
+        layer.properties = xml_properties(el)?;
+        fill_layer(&mut layer, xml_tiles(el)?)?;
+        map.layers.push(Layer::Tiles(layer));
+      }
+      "objectgroup" => {
+        let g = xml_group(el, group)?;
+        let mut layer = ObjectLayer::new(name);
+        layer.visible = g.visible;
+        layer.offset = g.offset;
+        layer.properties = xml_properties(el)?;
+        layer.objects = el.children_named("object").map(xml_object).collect::<Result<_, _>>()?;
+        map.layers.push(Layer::Objects(layer));
+      }
+      "group" => xml_layers(map, el, xml_group(el, group)?)?,
+      _ => {}
+    }
+  }
+  Ok(())
+}
+
+fn xml_tiles(layer: &Element) -> Result<Vec<Tile>, Error> {
+  let name = layer.attr("name").unwrap_or_default();
+  let data = layer.child("data").ok_or_else(|| error(format!("layer '{}' has no data", name)))?;
+  if data.child("chunk").is_some() {
+    return Err(error("infinite maps are not supported"));
+  }
+  check_compression(data.attr("compression"))?;
+  match data.attr("encoding") {
+    Some("csv") => data.text.split(',').map(|s| {
+      s.trim().parse().map(Tile::from_raw).map_err(|_| error(format!("invalid tile '{}' in layer '{}'", s.trim(), name)))
+    }).collect(),
+    Some("base64") => base64_tiles(&data.text),
+    None => data.children_named("tile").map(|t| parse_attr(t, "gid", Some(0)).map(Tile::from_raw)).collect(),
+    Some(encoding) => Err(error(format!("'{}' encoded tile data is not supported", encoding)))
+  }
+}
+
+fn xml_points(el: &Element) -> Result<Vec<Vf2d>, Error> {
+  attr(el, "points")?.split_whitespace().map(|point| {
+    let mut xy = point.split(',').map(|v| v.parse::<f32>());
+    match (xy.next(), xy.next(), xy.next()) {
+      (Some(Ok(x)), Some(Ok(y)), None) => Ok(Vf2d::new(x, y)),
+      _ => Err(error(format!("invalid point '{}'", point)))
+    }
+  }).collect()
+}
+
+fn xml_object(el: &Element) -> Result<Object, Error> {
+  let shape = if el.child("ellipse").is_some() {
+    Shape::Ellipse
+  } else if el.child("point").is_some() {
+    Shape::Point
+  } else if let Some(polygon) = el.child("polygon") {
+    Shape::Polygon(xml_points(polygon)?)
+  } else if let Some(polyline) = el.child("polyline") {
+    Shape::Polyline(xml_points(polyline)?)
+  } else if let Some(text) = el.child("text") {
+    Shape::Text(text.text.clone())
+  } else {
+    Shape::Rectangle
+  };
+  Ok(Object {
+    id: parse_attr(el, "id", Some(0))?,
+    name: el.attr("name").unwrap_or_default().to_string(),
+    class: el.attr("class").or_else(|| el.attr("type")).unwrap_or_default().to_string(),
+    pos: Vf2d::new(parse_attr(el, "x", Some(0.0))?, parse_attr(el, "y", Some(0.0))?),
+    size: Vf2d::new(parse_attr(el, "width", Some(0.0))?, parse_attr(el, "height", Some(0.0))?),
+    rotation: parse_attr(el, "rotation", Some(0.0))?,
+    visible: el.attr("visible") != Some("0"),
+    tile: el.attr("gid").map(|_| parse_attr(el, "gid", None).map(Tile::from_raw)).transpose()?,
+    shape,
+    properties: xml_properties(el)?
+  })
+}
+
+fn external_tileset<T: AsRef<Sprite>>(
+  dir: &Path, source: &str, first_gid: i64, load: &mut impl FnMut(&str) -> Result<T, Error>
+) -> Result<Tileset<T>, Error> {
+  let path = dir.join(source);
+  let text = read_file(&path)?;
+  // Images of the tileset are relative to the tileset file.
+  let dir = path.parent().unwrap_or(dir);
+  if text.trim_start().starts_with('<') {
+    let root = xml::parse(&text)?;
+    if root.name != "tileset" {
+      return Err(error(format!("'{}' is not a tileset", source)));
+    }
+    xml_tileset(&root, first_gid, dir, load)
+  } else {
+    json_tileset(&json::parse(&text)?, first_gid, dir, load)
+  }
+}
+
+//----------------------------------
+// JSON
+//----------------------------------
+
+fn json_i64(v: &Value, key: &str, default: Option<i64>) -> Result<i64, Error> {
+  match (v.get(key), default) {
+    (Some(n), _) => n.as_i64().ok_or_else(|| error(format!("'{}' is not an integer", key))),
+    (None, Some(default)) => Ok(default),
+    (None, None) => Err(error(format!("missing '{}'", key)))
+  }
+}
+
+fn json_f32(v: &Value, key: &str) -> Result<f32, Error> {
+  match v.get(key) {
+    Some(n) => n.as_f64().map(|n| n as f32).ok_or_else(|| error(format!("'{}' is not a number", key))),
+    None => Ok(0.0)
+  }
+}
+
+fn json_str<'a>(v: &'a Value, key: &str) -> &'a str {
+  v.get(key).and_then(Value::as_str).unwrap_or_default()
+}
+
+fn json_bool(v: &Value, key: &str, default: bool) -> bool {
+  match v.get(key) {
+    Some(Value::Bool(b)) => *b,
+    _ => default
+  }
+}
+
+fn json_properties(v: &Value) -> Result<Properties, Error> {
+  let mut properties = Properties::new();
+  for p in v.get("properties").and_then(Value::as_array).unwrap_or_default() {
+    let name = json_str(p, "name");
+    let value = p.get("value").unwrap_or(&Value::Null);
+    let invalid = || error(format!("invalid value of property '{}'", name));
+    let property = match json_str(p, "type") {
+      "" | "string" => Property::String(value.as_str().ok_or_else(invalid)?.to_string()),
+      "int" => Property::Int(value.as_i64().ok_or_else(invalid)?),
+      "float" => Property::Float(value.as_f64().ok_or_else(invalid)?),
+      "bool" => Property::Bool(matches!(value, Value::Bool(true))),
+      "color" => Property::Colour(colour(value.as_str().ok_or_else(invalid)?)?),
+      "file" => Property::File(value.as_str().ok_or_else(invalid)?.to_string()),
+      "object" => Property::Object(value.as_i64().and_then(|id| u32::try_from(id).ok()).ok_or_else(invalid)?),
+      _ => continue
+    };
+    properties.insert(name.to_string(), property);
+  }
+  Ok(properties)
+}
+
+fn json_map<T: AsRef<Sprite>>(root: &Value, dir: &Path, load: &mut impl FnMut(&str) -> Result<T, Error>) -> Result<Tilemap<T>, Error> {
+  if json_bool(root, "infinite", false) {
+    return Err(error("infinite maps are not supported"));
+  }
+  let orientation = orientation(root.get("orientation").and_then(Value::as_str).unwrap_or("orthogonal"))?;
+  let size = map_size(json_i64(root, "width", None)?, json_i64(root, "height", None)?)?;
+  let tile_size = tile_size(json_i64(root, "tilewidth", None)?, json_i64(root, "tileheight", None)?)?;
+  let mut map = Tilemap::new(size, tile_size, orientation);
+  map.properties = json_properties(root)?;
+  for v in root.get("tilesets").and_then(Value::as_array).unwrap_or_default() {
+    let first_gid = json_i64(v, "firstgid", None)?;
+    let tileset = match v.get("source").and_then(Value::as_str) {
+      Some(source) => external_tileset(dir, source, first_gid, load)?,
+      None => json_tileset(v, first_gid, dir, load)?
+    };
+    map.tilesets.push(tileset);
+  }
+  sort_tilesets(&mut map)?;
+  json_layers(&mut map, root, Group::root())?;
+  Ok(map)
+}
+
+fn json_tileset<T: AsRef<Sprite>>(
+  v: &Value, first_gid: i64, dir: &Path, load: &mut impl FnMut(&str) -> Result<T, Error>
+) -> Result<Tileset<T>, Error> {
+  let name = json_str(v, "name");
+  let image = v.get("image").and_then(Value::as_str)
+    .ok_or_else(|| error(format!("tileset '{}' has no image, image collections are not supported", name)))?;
+  let tile_size = tile_size(json_i64(v, "tilewidth", None)?, json_i64(v, "tileheight", None)?)?;
+  let mut tileset = tileset(
+    name, first_gid, tile_size, json_i64(v, "margin", Some(0))?, json_i64(v, "spacing", Some(0))?,
+    json_i64(v, "columns", None).ok(), json_i64(v, "tilecount", None).ok(), &join(dir, image), load
+  )?;
+  if let Some(offset) = v.get("tileoffset") {
+    tileset.offset = Vi2d::new(json_i64(offset, "x", Some(0))? as i32, json_i64(offset, "y", Some(0))? as i32);
+  }
+  tileset.properties = json_properties(v)?;
+  let mut tile_properties = HashMap::new();
+  for tile in v.get("tiles").and_then(Value::as_array).unwrap_or_default() {
+    let properties = json_properties(tile)?;
+    if !properties.is_empty() {
+      let id = usize::try_from(json_i64(tile, "id", None)?).map_err(|_| error("invalid tile id"))?;
+      tile_properties.insert(id, properties);
+    }
+  }
+  tileset.tile_properties = tile_properties;
+  Ok(tileset)
+}
+
+fn json_group(v: &Value, parent: Group) -> Result<Group, Error> {
+  let opacity = match v.get("opacity") {
+    Some(o) => o.as_f64().ok_or_else(|| error("'opacity' is not a number"))? as f32,
+    None => 1.0
+  };
+  Ok(parent.nest(Vf2d::new(json_f32(v, "offsetx")?, json_f32(v, "offsety")?), json_bool(v, "visible", true), opacity))
+}
+
+fn json_layers<T>(map: &mut Tilemap<T>, parent: &Value, group: Group) -> Result<(), Error> {
+  for v in parent.get("layers").and_then(Value::as_array).unwrap_or_default() {
+    let name = json_str(v, "name");
+    match json_str(v, "type") {
+      "tilelayer" => {
+        let g = json_group(v, group)?;
+        let mut layer = TileLayer::new(name, map.size);
+        layer.visible = g.visible;
+        layer.opacity = g.opacity;
+        layer.offset = g.offset;
+        layer.properties = json_properties(v)?;
+        fill_layer(&mut layer, json_tiles(v)?)?;
+        map.layers.push(Layer::Tiles(layer));
+      }
+      "objectgroup" => {
+        let g = json_group(v, group)?;
+        let mut layer = ObjectLayer::new(name);
+        layer.visible = g.visible;
+        layer.offset = g.offset;
+        layer.properties = json_properties(v)?;
+        layer.objects = v.get("objects").and_then(Value::as_array).unwrap_or_default()
+          .iter()
+          .map(json_object)
+          .collect::<Result<_, _>>()?;
+        map.layers.push(Layer::Objects(layer));
+      }
+      "group" => json_layers(map, v, json_group(v, group)?)?,
+      _ => {}
+    }
+  }
+  Ok(())
+}
+
+fn json_tiles(layer: &Value) -> Result<Vec<Tile>, Error> {
+  let name = json_str(layer, "name");
+  if layer.get("chunks").is_some() {
+    return Err(error("infinite maps are not supported"));
+  }
+  check_compression(layer.get("compression").and_then(Value::as_str))?;
+  match layer.get("data") {
+    Some(Value::Array(data)) => data.iter().map(|v| {
+      v.as_i64()
+        .and_then(|gid| u32::try_from(gid).ok())
+        .map(Tile::from_raw)
+        .ok_or_else(|| error(format!("invalid tile in layer '{}'", name)))
+    }).collect(),
+    Some(Value::String(data)) if json_str(layer, "encoding") == "base64" => base64_tiles(data),
+    _ => Err(error(format!("layer '{}' has no data", name)))
+  }
+}
+
+fn json_points(v: &Value) -> Result<Vec<Vf2d>, Error> {
+  v.as_array().unwrap_or_default().iter().map(|p| Ok(Vf2d::new(json_f32(p, "x")?, json_f32(p, "y")?))).collect()
+}
+
+fn json_object(v: &Value) -> Result<Object, Error> {
+  let shape = if json_bool(v, "ellipse", false) {
+    Shape::Ellipse
+  } else if json_bool(v, "point", false) {
+    Shape::Point
+  } else if let Some(polygon) = v.get("polygon") {
+    Shape::Polygon(json_points(polygon)?)
+  } else if let Some(polyline) = v.get("polyline") {
+    Shape::Polyline(json_points(polyline)?)
+  } else if let Some(text) = v.get("text") {
+    Shape::Text(json_str(text, "text").to_string())
+  } else {
+    Shape::Rectangle
+  };
+  let tile = match v.get("gid") {
+    Some(gid) => Some(gid.as_i64().and_then(|gid| u32::try_from(gid).ok()).map(Tile::from_raw).ok_or_else(|| error("invalid object tile"))?),
+    None => None
+  };
+  Ok(Object {
+    id: u32::try_from(json_i64(v, "id", Some(0))?).map_err(|_| error("invalid object id"))?,
+    name: json_str(v, "name").to_string(),
+    class: v.get("class").or_else(|| v.get("type")).and_then(Value::as_str).unwrap_or_default().to_string(),
+    pos: Vf2d::new(json_f32(v, "x")?, json_f32(v, "y")?),
+    size: Vf2d::new(json_f32(v, "width")?, json_f32(v, "height")?),
+    rotation: json_f32(v, "rotation")?,
+    visible: json_bool(v, "visible", true),
+    tile,
+    shape,
+    properties: json_properties(v)?
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Creates images of the size encoded in the file name, e.g. "tiles_32x16.png".
+  fn load(path: &str) -> Result<Sprite, Error> {
+    let size = path.rsplit('_').next().and_then(|s| s.strip_suffix(".png")).unwrap_or_default();
+    let (w, h) = size.split_once('x').ok_or_else(|| error(path))?;
+    Ok(Sprite::with_dims(w.parse().unwrap(), h.parse().unwrap()))
+  }
+
+  const TMX: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" renderorder="right-down" width="3" height="2" tilewidth="8" tileheight="8" infinite="0">
+ <properties>
+  <property name="title" value="Level 1"/>
+  <property name="gravity" type="float" value="9.5"/>
+  <property name="tint" type="color" value="#80ff0000"/>
+  <property name="notes">two
+lines</property>
+ </properties>
+ <tileset firstgid="1" name="ground" tilewidth="8" tileheight="8" tilecount="4" columns="2">
+  <image source="img/ground_16x16.png" width="16" height="16"/>
+  <tile id="2"><properties><property name="solid" type="bool" value="true"/></properties></tile>
+ </tileset>
+ <tileset firstgid="5" name="trees" tilewidth="8" tileheight="16" spacing="1" tilecount="2" columns="2">
+  <tileoffset x="0" y="2"/>
+  <image source="trees_17x16.png" width="17" height="16"/>
+ </tileset>
+ <layer id="1" name="ground" width="3" height="2">
+  <data encoding="csv">
+1,2,3,
+2147483652,0,5
+</data>
+ </layer>
+ <group id="4" name="details" offsetx="4" opacity="0.5" visible="0">
+  <layer id="2" name="decor" width="3" height="2" offsetx="1" offsety="2" opacity="0.5">
+   <properties><property name="depth" type="int" value="3"/></properties>
+   <data encoding="base64">BQAAAAAAAAAAAAAAAAAAAAAAAAAGAABA</data>
+  </layer>
+ </group>
+ <imagelayer id="5" name="sky"/>
+ <objectgroup id="3" name="objects">
+  <object id="1" name="spawn" type="player" x="8" y="4.5"><point/></object>
+  <object id="2" name="zone" x="0" y="0" width="16" height="8" rotation="45" visible="0"><ellipse/></object>
+  <object id="3" x="1" y="2"><polygon points="0,0 8,0 8,-8"/></object>
+  <object id="4" x="0" y="16" width="8" height="16" gid="5"/>
+  <object id="5" name="sign"><text wrap="1">Hello &amp; welcome</text>
+   <properties><property name="target" type="object" value="1"/></properties>
+  </object>
+ </objectgroup>
+</map>
+"##;
+
+  const TMJ: &str = r##"{
+  "orientation": "isometric", "width": 3, "height": 2, "tilewidth": 16, "tileheight": 8, "infinite": false,
+  "properties": [{"name": "title", "type": "string", "value": "Level 1"}, {"name": "tint", "type": "color", "value": "#ff0000"}],
+  "tilesets": [
+    {"firstgid": 5, "name": "trees", "tilewidth": 16, "tileheight": 16, "image": "trees_32x16.png", "tileoffset": {"x": 1, "y": -2}},
+    {"firstgid": 1, "name": "ground", "tilewidth": 16, "tileheight": 8, "margin": 0, "spacing": 0, "tilecount": 4, "columns": 2,
+     "image": "ground_32x16.png", "tiles": [{"id": 3, "properties": [{"name": "cost", "type": "int", "value": 2}]}]}
+  ],
+  "layers": [
+    {"type": "tilelayer", "name": "ground", "width": 3, "height": 2, "data": [1, 2, 3, 4, 1073741825, 0], "opacity": 0.25},
+    {"type": "group", "name": "g", "offsetx": 2, "layers": [
+      {"type": "tilelayer", "name": "decor", "width": 3, "height": 2, "encoding": "base64", "data": "BQAAAAAAAAAAAAAAAAAAAAAAAAAGAABA", "visible": false}
+    ]},
+    {"type": "objectgroup", "name": "objects", "objects": [
+      {"id": 1, "name": "spawn", "type": "player", "x": 8, "y": 4.5, "point": true},
+      {"id": 2, "x": 1, "y": 2, "polyline": [{"x": 0, "y": 0}, {"x": 8, "y": -8}], "properties": [{"name": "loop", "type": "bool", "value": true}]},
+      {"id": 3, "x": 0, "y": 16, "width": 16, "height": 16, "gid": 2147483653},
+      {"id": 4, "class": "label", "text": {"text": "Hi", "wrap": true}}
+    ]}
+  ]
+}"##;
+
+  #[test]
+  fn test_tmx() {
+    let mut paths = Vec::new();
+    let map = Tilemap::from_tmx(TMX, "maps", |path| {
+      paths.push(path.to_string());
+      load(path)
+    }).unwrap();
+    assert_eq!(paths, [join(Path::new("maps"), "img/ground_16x16.png"), join(Path::new("maps"), "trees_17x16.png")]);
+    assert_eq!((map.size(), map.tile_size(), map.orientation()), (Vi2d::new(3, 2), Vi2d::new(8, 8), Orientation::Orthogonal));
+    assert_eq!(map.properties["title"], Property::String("Level 1".to_string()));
+    assert_eq!(map.properties["gravity"].as_f64(), Some(9.5));
+    assert_eq!(map.properties["tint"], Property::Colour(Pixel::rgba(255, 0, 0, 128)));
+    assert_eq!(map.properties["notes"].as_str(), Some("two\nlines"));
+
+    let (ground, trees) = (&map.tilesets()[0], &map.tilesets()[1]);
+    assert_eq!((ground.first_gid(), ground.len(), ground.columns()), (1, 4, 2));
+    assert_eq!(map.tile_properties(Tile::new(3)).and_then(|p| p["solid"].as_bool()), Some(true));
+    assert_eq!((trees.first_gid(), trees.len(), trees.offset), (5, 2, Vi2d::new(0, 2)));
+    assert_eq!(trees.frame(1).map(|f| f.pos), Some(Vi2d::new(9, 0)));
+
+    let names: Vec<&str> = map.layers().iter().map(Layer::name).collect();
+    assert_eq!(names, ["ground", "decor", "objects"]);
+    let layer = map.tile_layer("ground").unwrap();
+    assert_eq!(layer.get(Vi2d::new(2, 0)), Tile::new(3));
+    assert_eq!(layer.get(Vi2d::new(0, 1)), Tile::new(4).with_flip(true, false, false));
+    assert_eq!(layer.get(Vi2d::new(1, 1)), Tile::EMPTY);
+    let decor = map.tile_layer("decor").unwrap();
+    assert_eq!((decor.visible, decor.opacity, decor.offset), (false, 0.25, Vf2d::new(5.0, 2.0)));
+    assert_eq!(decor.properties["depth"].as_i64(), Some(3));
+    assert_eq!(decor.tiles()[0], Tile::new(5));
+    assert_eq!(decor.get(Vi2d::new(2, 1)), Tile::new(6).with_flip(false, true, false));
+
+    let objects = &map.object_layer("objects").unwrap().objects;
+    assert_eq!(objects.len(), 5);
+    assert_eq!((objects[0].class.as_str(), objects[0].pos, &objects[0].shape), ("player", Vf2d::new(8.0, 4.5), &Shape::Point));
+    assert_eq!((objects[1].rotation, objects[1].visible, &objects[1].shape), (45.0, false, &Shape::Ellipse));
+    assert_eq!(objects[2].shape, Shape::Polygon(vec![Vf2d::new(0.0, 0.0), Vf2d::new(8.0, 0.0), Vf2d::new(8.0, -8.0)]));
+    assert_eq!((objects[3].tile, &objects[3].shape), (Some(Tile::new(5)), &Shape::Rectangle));
+    assert_eq!(objects[4].shape, Shape::Text("Hello & welcome".to_string()));
+    assert_eq!(objects[4].properties["target"], Property::Object(1));
+  }
+
+  #[test]
+  fn test_tmj() {
+    let map = Tilemap::from_tmj(TMJ, "", load).unwrap();
+    assert_eq!((map.size(), map.tile_size(), map.orientation()), (Vi2d::new(3, 2), Vi2d::new(16, 8), Orientation::Isometric));
+    assert_eq!(map.properties["tint"], Property::Colour(Pixel::rgb(255, 0, 0)));
+
+    // Tilesets are sorted, the grid of the image is used without a tile count.
+    let names: Vec<&str> = map.tilesets().iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, ["ground", "trees"]);
+    assert_eq!((map.tilesets()[1].len(), map.tilesets()[1].offset), (2, Vi2d::new(1, -2)));
+    assert_eq!(map.tile_properties(Tile::new(4)).and_then(|p| p["cost"].as_i64()), Some(2));
+
+    let ground = map.tile_layer("ground").unwrap();
+    assert_eq!(ground.opacity, 0.25);
+    assert_eq!(ground.get(Vi2d::new(1, 1)), Tile::new(1).with_flip(false, true, false));
+    let decor = map.tile_layer("decor").unwrap();
+    assert_eq!((decor.visible, decor.offset), (false, Vf2d::new(2.0, 0.0)));
+    assert_eq!(decor.get(Vi2d::new(2, 1)), Tile::new(6).with_flip(false, true, false));
+
+    let objects = &map.object_layer("objects").unwrap().objects;
+    assert_eq!((objects[0].name.as_str(), objects[0].class.as_str(), &objects[0].shape), ("spawn", "player", &Shape::Point));
+    assert_eq!(objects[1].shape, Shape::Polyline(vec![Vf2d::new(0.0, 0.0), Vf2d::new(8.0, -8.0)]));
+    assert_eq!(objects[1].properties["loop"], Property::Bool(true));
+    assert_eq!(objects[2].tile, Some(Tile::new(5).with_flip(true, false, false)));
+    assert_eq!((objects[3].class.as_str(), &objects[3].shape), ("label", &Shape::Text("Hi".to_string())));
+  }
+
+  #[test]
+  fn test_external_tileset() {
+    let dir = std::env::temp_dir().join(format!("olc_tilemap_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("sets")).unwrap();
+    std::fs::write(dir.join("sets/ground.tsx"), r#"<tileset name="ground" tilewidth="8" tileheight="8" tilecount="4" columns="2">
+ <image source="ground_16x16.png"/>
+</tileset>"#).unwrap();
+    std::fs::write(dir.join("sets/trees.tsj"), r#"{"name": "trees", "tilewidth": 8, "tileheight": 16, "image": "../trees_16x16.png"}"#).unwrap();
+    let tmx = TMX.replace(
+      r#"<tileset firstgid="5" name="trees""#,
+      r#"<tileset firstgid="5" source="sets/trees.tsj"/><tileset firstgid="9" name="unused""#
+    );
+    std::fs::write(dir.join("level.tmx"), tmx).unwrap();
+
+    let mut paths = Vec::new();
+    let map = Tilemap::from_tiled_file(&dir.join("level.tmx").to_string_lossy(), |path| {
+      paths.push(path.to_string());
+      load(path)
+    }).unwrap();
+    assert_eq!(paths[1], join(&dir.join("sets"), "../trees_16x16.png"));
+    assert_eq!(map.tilesets()[1].name, "trees");
+    assert_eq!(map.tilesets()[1].len(), 2);
+
+    let tmx = TMX.replacen(r#"<tileset firstgid="1" name="ground" tilewidth="8" tileheight="8" tilecount="4" columns="2">
+  <image source="img/ground_16x16.png" width="16" height="16"/>"#, r#"<tileset firstgid="1" source="sets/ground.tsx"><image source="x"/>"#, 1);
+    let map = Tilemap::from_tmx(&tmx, &dir.to_string_lossy(), load).unwrap();
+    assert_eq!(map.tilesets()[0].len(), 4);
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_errors() {
+    let cases = [
+      TMX.replace("infinite=\"0\"", "infinite=\"1\""),
+      TMX.replace("orthogonal", "hexagonal"),
+      TMX.replace("encoding=\"base64\"", "encoding=\"base64\" compression=\"zlib\""),
+      TMX.replace("2147483652,0,5", "2147483652,0"),
+      TMX.replace("2147483652,0,5", "2147483652,x,5"),
+      TMX.replace("firstgid=\"5\"", "firstgid=\"3\""),
+      TMX.replace("type=\"int\" value=\"3\"", "type=\"int\" value=\"three\""),
+      TMX.replace("width=\"3\" height=\"2\" tilewidth", "width=\"-3\" height=\"2\" tilewidth"),
+      TMX.replace("</map>", "")
+    ];
+    for tmx in cases.iter() {
+      assert!(Tilemap::from_tmx(tmx, "", load).is_err());
+    }
+    let err = Tilemap::from_tmx(&TMX.replace("trees_17x16", "trees"), "", load).unwrap_err();
+    assert!(format!("{}", err).contains("trees.png"), "{}", err);
+    assert!(Tilemap::from_tmj(&TMJ.replace("\"infinite\": false", "\"infinite\": true"), "", load).is_err());
+    assert!(Tilemap::from_tmj(&TMJ.replace("1073741825, 0]", "1073741825]"), "", load).is_err());
+    assert!(Tilemap::<Sprite>::from_tiled_file("missing.tmx", load).is_err());
+    assert_eq!(base64("TWFu\nTWE=").unwrap(), b"ManMa");
+    assert!(base64("TW!u").is_err());
+  }
+}
@@ -0,0 +1,217 @@
+//! Affine 2D transforms and transformed sprite drawing.
+//!
+//! [`Transform2D`](Transform2D) mirrors `olc::GFX2D::Transform2D` from the Graphics2D extension: a
+//! 3x3 matrix that accumulates translation, rotation, scale and shear, applied in the order they
+//! are called. [`draw_sprite_transformed`](crate::draw_sprite_transformed) draws a sprite through
+//! the transform onto the current draw target in software, so the result can be rendered into a
+//! layer or a sprite, unlike decals.
+//!
+//! ```no_run
+//! # extern crate olc_pixel_game_engine;
+//! # use crate::olc_pixel_game_engine as olc;
+//! let sprite = olc::Sprite::from_image("examples/cursor.png").unwrap();
+//!
+//! // Rotate the sprite around its centre and place it at (100, 50) at double size.
+//! let mut t = olc::transform::Transform2D::new();
+//! t.translate(-sprite.width() as f32 / 2.0, -sprite.height() as f32 / 2.0);
+//! t.rotate(std::f32::consts::FRAC_PI_4);
+//! t.scale(2.0, 2.0);
+//! t.translate(100.0, 50.0);
+//! olc::draw_sprite_transformed_ext(&sprite, &t, olc::Filter::Bilinear);
+//! ```
+
+use std::ops;
+
+use crate::Vf2d;
+
+/// Affine transform represented by a 3x3 matrix, points are treated as column vectors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform2D {
+  m: [[f32; 3]; 3]
+}
+
+impl Default for Transform2D {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Transform2D {
+  /// Creates identity transform.
+  pub fn new() -> Self {
+    Self { m: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]] }
+  }
+
+  /// Creates a transform from a row-major matrix.
+  pub fn from_matrix(m: [[f32; 3]; 3]) -> Self {
+    Self { m }
+  }
+
+  /// Returns row-major matrix of the transform.
+  pub fn matrix(&self) -> [[f32; 3]; 3] {
+    self.m
+  }
+
+  /// Resets the transform to identity.
+  pub fn reset(&mut self) {
+    *self = Self::new();
+  }
+
+  /// Appends translation by (ox, oy).
+  pub fn translate(&mut self, ox: f32, oy: f32) {
+    self.then(&Self::from_matrix([[1.0, 0.0, ox], [0.0, 1.0, oy], [0.0, 0.0, 1.0]]));
+  }
+
+  /// Appends rotation by `theta` radians around the origin.
+  pub fn rotate(&mut self, theta: f32) {
+    let (sin, cos) = theta.sin_cos();
+    self.then(&Self::from_matrix([[cos, -sin, 0.0], [sin, cos, 0.0], [0.0, 0.0, 1.0]]));
+  }
+
+  /// Appends scale by (sx, sy) relative to the origin.
+  pub fn scale(&mut self, sx: f32, sy: f32) {
+    self.then(&Self::from_matrix([[sx, 0.0, 0.0], [0.0, sy, 0.0], [0.0, 0.0, 1.0]]));
+  }
+
+  /// Appends shear by (sx, sy).
+  pub fn shear(&mut self, sx: f32, sy: f32) {
+    self.then(&Self::from_matrix([[1.0, sx, 0.0], [sy, 1.0, 0.0], [0.0, 0.0, 1.0]]));
+  }
+
+  /// Appends another transform, which is applied after this one.
+  pub fn then(&mut self, other: &Transform2D) {
+    *self = *other * *self;
+  }
+
+  /// Returns the inverse transform, or `None` if the transform is not invertible.
+  pub fn inverse(&self) -> Option<Self> {
+    let m = &self.m;
+    let det = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1]) -
+      m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0]) +
+      m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+    // Small scales are still invertible, only reject determinants that can't be divided by.
+    if !det.is_normal() {
+      return None;
+    }
+    let idet = 1.0 / det;
+    Some(Self::from_matrix([
+      [
+        (m[1][1] * m[2][2] - m[1][2] * m[2][1]) * idet,
+        (m[0][2] * m[2][1] - m[0][1] * m[2][2]) * idet,
+        (m[0][1] * m[1][2] - m[0][2] * m[1][1]) * idet
+      ],
+      [
+        (m[1][2] * m[2][0] - m[1][0] * m[2][2]) * idet,
+        (m[0][0] * m[2][2] - m[0][2] * m[2][0]) * idet,
+        (m[0][2] * m[1][0] - m[0][0] * m[1][2]) * idet
+      ],
+      [
+        (m[1][0] * m[2][1] - m[1][1] * m[2][0]) * idet,
+        (m[0][1] * m[2][0] - m[0][0] * m[2][1]) * idet,
+        (m[0][0] * m[1][1] - m[0][1] * m[1][0]) * idet
+      ]
+    ]))
+  }
+
+  /// Transforms a point from source space into destination space.
+  pub fn forward(&self, p: Vf2d) -> Vf2d {
+    let m = &self.m;
+    let x = m[0][0] * p.x + m[0][1] * p.y + m[0][2];
+    let y = m[1][0] * p.x + m[1][1] * p.y + m[1][2];
+    let z = m[2][0] * p.x + m[2][1] * p.y + m[2][2];
+    if z != 0.0 && z != 1.0 { Vf2d::new(x / z, y / z) } else { Vf2d::new(x, y) }
+  }
+
+  /// Transforms a point from destination space back into source space,
+  /// returns `None` if the transform is not invertible.
+  pub fn backward(&self, p: Vf2d) -> Option<Vf2d> {
+    self.inverse().map(|inv| inv.forward(p))
+  }
+}
+
+/// Composes two transforms: `a * b` applies `b` first and then `a`.
+impl ops::Mul for Transform2D {
+  type Output = Self;
+
+  fn mul(self, rhs: Self) -> Self::Output {
+    let mut m = [[0.0; 3]; 3];
+    for (r, row) in m.iter_mut().enumerate() {
+      for (c, v) in row.iter_mut().enumerate() {
+        *v = (0..3).map(|k| self.m[r][k] * rhs.m[k][c]).sum();
+      }
+    }
+    Self { m }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn assert_close(a: Vf2d, b: Vf2d) {
+    assert!((a - b).mag() < 1e-4, "{} != {}", a, b);
+  }
+
+  #[test]
+  fn test_transform_identity() {
+    let t = Transform2D::new();
+    assert_eq!(t.forward(Vf2d::new(3.0, 4.0)), Vf2d::new(3.0, 4.0));
+    assert_eq!(t, Transform2D::default());
+  }
+
+  #[test]
+  fn test_transform_order() {
+    let mut t = Transform2D::new();
+    t.scale(2.0, 3.0);
+    t.translate(10.0, 20.0);
+    assert_close(t.forward(Vf2d::new(1.0, 1.0)), Vf2d::new(12.0, 23.0));
+
+    let mut t = Transform2D::new();
+    t.translate(10.0, 20.0);
+    t.scale(2.0, 3.0);
+    assert_close(t.forward(Vf2d::new(1.0, 1.0)), Vf2d::new(22.0, 63.0));
+  }
+
+  #[test]
+  fn test_transform_rotate_shear() {
+    let mut t = Transform2D::new();
+    t.rotate(std::f32::consts::FRAC_PI_2);
+    assert_close(t.forward(Vf2d::new(1.0, 0.0)), Vf2d::new(0.0, 1.0));
+
+    let mut t = Transform2D::new();
+    t.shear(1.0, 0.0);
+    assert_close(t.forward(Vf2d::new(1.0, 2.0)), Vf2d::new(3.0, 2.0));
+  }
+
+  #[test]
+  fn test_transform_inverse() {
+    let mut t = Transform2D::new();
+    t.translate(-5.0, 3.0);
+    t.rotate(0.7);
+    t.scale(2.0, 0.5);
+    t.shear(0.2, 0.1);
+    let p = Vf2d::new(7.0, -2.0);
+    assert_close(t.backward(t.forward(p)).unwrap(), p);
+    assert_close((t * t.inverse().unwrap()).forward(p), p);
+
+    let mut small = Transform2D::new();
+    small.scale(0.001, 0.001);
+    assert_close(small.backward(Vf2d::new(0.002, 0.003)).unwrap(), Vf2d::new(2.0, 3.0));
+
+    let mut singular = Transform2D::new();
+    singular.scale(0.0, 1.0);
+    assert_eq!(singular.inverse(), None);
+  }
+
+  #[test]
+  fn test_transform_compose() {
+    let mut a = Transform2D::new();
+    a.translate(1.0, 2.0);
+    let mut b = Transform2D::new();
+    b.scale(3.0, 3.0);
+    let mut c = a;
+    c.then(&b);
+    assert_eq!(c, b * a);
+    assert_close(c.forward(Vf2d::new(0.0, 0.0)), Vf2d::new(3.0, 6.0));
+  }
+}
@@ -0,0 +1,460 @@
+//! Pan-and-zoom world camera.
+//!
+//! [`TransformedView`](TransformedView) mirrors `olc::TransformedView` from the TransformedView
+//! extension. It owns a world offset and a zoom level, converts between world and screen space
+//! and wraps the drawing routines so they accept world coordinates. Objects that are entirely
+//! outside of the view are culled before drawing.
+//!
+//! ```no_run
+//! # extern crate olc_pixel_game_engine;
+//! # use crate::olc_pixel_game_engine as olc;
+//! # fn on_user_update(tv: &mut olc::transformed_view::TransformedView) {
+//! // Pan with the middle mouse button and zoom with the mouse wheel.
+//! tv.handle_pan_and_zoom(2, 0.1, true, true);
+//!
+//! olc::clear(olc::BLACK);
+//! // Tile at world position (10, 10), scaled and offset to screen space.
+//! tv.fill_rect(&olc::Vf2d::new(10.0, 10.0), &olc::Vf2d::new(1.0, 1.0), olc::GREEN);
+//! # }
+//! ```
+
+use crate::{Decal, Error, Pixel, Sprite, Vf2d};
+
+/// World camera with panning and zooming.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransformedView {
+  world_offset: Vf2d,
+  world_scale: Vf2d,
+  view_area: Vf2d,
+  start_pan: Vf2d,
+  panning: bool
+}
+
+impl TransformedView {
+  /// Creates a view covering `view_area` screen pixels, where one world unit is `pixel_scale`
+  /// screen pixels. Mirrors `olc::TransformedView::Initialise`.
+  pub fn new(view_area: Vf2d, pixel_scale: Vf2d) -> Self {
+    Self {
+      world_offset: Vf2d::new(0.0, 0.0),
+      world_scale: pixel_scale,
+      view_area,
+      start_pan: Vf2d::new(0.0, 0.0),
+      panning: false
+    }
+  }
+
+  /// Sets world position shown at the top left corner of the view.
+  pub fn set_world_offset(&mut self, offset: &Vf2d) {
+    self.world_offset = *offset;
+  }
+
+  /// Moves the world offset by `delta` world units.
+  pub fn move_world_offset(&mut self, delta: &Vf2d) {
+    self.world_offset += *delta;
+  }
+
+  /// Returns world position shown at the top left corner of the view.
+  pub fn world_offset(&self) -> Vf2d {
+    self.world_offset
+  }
+
+  /// Sets the number of screen pixels per world unit.
+  pub fn set_world_scale(&mut self, scale: &Vf2d) {
+    self.world_scale = *scale;
+  }
+
+  /// Returns the number of screen pixels per world unit.
+  pub fn world_scale(&self) -> Vf2d {
+    self.world_scale
+  }
+
+  /// Sets size of the view in screen pixels.
+  pub fn set_view_area(&mut self, view_area: &Vf2d) {
+    self.view_area = *view_area;
+  }
+
+  /// Returns size of the view in screen pixels.
+  pub fn view_area(&self) -> Vf2d {
+    self.view_area
+  }
+
+  /// Returns world position of the top left corner of the view.
+  pub fn world_tl(&self) -> Vf2d {
+    self.screen_to_world(&Vf2d::new(0.0, 0.0))
+  }
+
+  /// Returns world position of the bottom right corner of the view.
+  pub fn world_br(&self) -> Vf2d {
+    self.screen_to_world(&self.view_area)
+  }
+
+  /// Returns size of the visible area in world units.
+  pub fn world_visible_area(&self) -> Vf2d {
+    self.world_br() - self.world_tl()
+  }
+
+  /// Converts world position into screen position.
+  pub fn world_to_screen(&self, pos: &Vf2d) -> Vf2d {
+    (*pos - self.world_offset) * self.world_scale
+  }
+
+  /// Converts screen position into world position.
+  pub fn screen_to_world(&self, pos: &Vf2d) -> Vf2d {
+    *pos / self.world_scale + self.world_offset
+  }
+
+  /// Converts size in screen pixels into world units.
+  pub fn scale_to_world(&self, size: &Vf2d) -> Vf2d {
+    *size / self.world_scale
+  }
+
+  /// Converts size in world units into screen pixels.
+  pub fn scale_to_screen(&self, size: &Vf2d) -> Vf2d {
+    *size * self.world_scale
+  }
+
+  /// Multiplies the zoom by `delta_zoom`, keeping the world position under the screen position
+  /// `pos` in place.
+  pub fn zoom_at_screen_pos(&mut self, delta_zoom: f32, pos: &Vf2d) {
+    let before = self.screen_to_world(pos);
+    self.world_scale *= delta_zoom;
+    let after = self.screen_to_world(pos);
+    self.world_offset += before - after;
+  }
+
+  /// Sets the zoom, keeping the world position under the screen position `pos` in place.
+  pub fn set_zoom(&mut self, zoom: f32, pos: &Vf2d) {
+    let before = self.screen_to_world(pos);
+    self.world_scale = Vf2d::new(zoom, zoom);
+    let after = self.screen_to_world(pos);
+    self.world_offset += before - after;
+  }
+
+  /// Starts panning from the screen position.
+  pub fn start_pan(&mut self, pos: &Vf2d) {
+    self.panning = true;
+    self.start_pan = *pos;
+  }
+
+  /// Moves the world so that the point grabbed by [`start_pan`](TransformedView::start_pan)
+  /// follows the screen position.
+  pub fn update_pan(&mut self, pos: &Vf2d) {
+    if self.panning {
+      self.world_offset -= (*pos - self.start_pan) / self.world_scale;
+      self.start_pan = *pos;
+    }
+  }
+
+  /// Finishes panning at the screen position.
+  pub fn end_pan(&mut self, pos: &Vf2d) {
+    self.update_pan(pos);
+    self.panning = false;
+  }
+
+  /// Returns true if the view is being panned.
+  pub fn is_panning(&self) -> bool {
+    self.panning
+  }
+
+  /// Pans the view while the mouse button is held and zooms around the mouse cursor with the
+  /// mouse wheel, each wheel step changes the zoom by `zoom_rate`.
+  pub fn handle_pan_and_zoom(&mut self, mouse_button: u32, zoom_rate: f32, pan: bool, zoom: bool) {
+    let mouse = Vf2d::new(crate::get_mouse_x() as f32, crate::get_mouse_y() as f32);
+    if pan {
+      let button = crate::get_mouse(mouse_button);
+      if button.pressed {
+        self.start_pan(&mouse);
+      }
+      if button.held {
+        self.update_pan(&mouse);
+      }
+      if button.released {
+        self.end_pan(&mouse);
+      }
+    }
+    if zoom {
+      let wheel = crate::get_mouse_wheel();
+      if wheel > 0 {
+        self.zoom_at_screen_pos(1.0 + zoom_rate, &mouse);
+      } else if wheel < 0 {
+        self.zoom_at_screen_pos(1.0 - zoom_rate, &mouse);
+      }
+    }
+  }
+
+  /// Returns true if the world position is inside the view.
+  pub fn is_point_visible(&self, pos: &Vf2d) -> bool {
+    let p = self.world_to_screen(pos);
+    p.x >= 0.0 && p.x < self.view_area.x && p.y >= 0.0 && p.y < self.view_area.y
+  }
+
+  /// Returns true if any part of the world rectangle is inside the view.
+  pub fn is_rect_visible(&self, pos: &Vf2d, size: &Vf2d) -> bool {
+    let p = self.world_to_screen(pos);
+    let s = self.scale_to_screen(size);
+    p.x < self.view_area.x && p.x + s.x >= 0.0 && p.y < self.view_area.y && p.y + s.y >= 0.0
+  }
+
+  // Returns true if the bounding box of the points is visible.
+  fn is_bounds_visible(&self, points: &[Vf2d]) -> bool {
+    let lo = points.iter().fold(points[0], |acc, p| acc.min(*p));
+    let hi = points.iter().fold(points[0], |acc, p| acc.max(*p));
+    self.is_rect_visible(&lo, &(hi - lo))
+  }
+
+  // Returns true if the text at the world position is visible, `scale` is in world units per
+  // font pixel. Characters are 8x8 font pixels and each newline starts a row.
+  fn is_text_visible(&self, pos: &Vf2d, text: &str, scale: &Vf2d) -> bool {
+    let columns = text.split('\n').map(str::len).max().unwrap_or(0);
+    let rows = text.split('\n').count();
+    self.is_rect_visible(pos, &(Vf2d::new(columns as f32 * 8.0, rows as f32 * 8.0) * *scale))
+  }
+
+  // Converts world position into integer screen coordinates.
+  fn screen_i(&self, pos: &Vf2d) -> (i32, i32) {
+    let p = self.world_to_screen(pos).floor();
+    (p.x as i32, p.y as i32)
+  }
+
+  //----------------------------------
+  // Drawing routines in world space
+  //----------------------------------
+
+  /// Draws a world unit sized pixel at the world position.
+  pub fn draw(&self, pos: &Vf2d, p: Pixel) {
+    self.fill_rect(pos, &Vf2d::new(1.0, 1.0), p)
+  }
+
+  /// Draws a line between two world positions.
+  pub fn draw_line(&self, pos1: &Vf2d, pos2: &Vf2d, p: Pixel) {
+    if self.is_bounds_visible(&[*pos1, *pos2]) {
+      let (x1, y1) = self.screen_i(pos1);
+      let (x2, y2) = self.screen_i(pos2);
+      crate::draw_line(x1, y1, x2, y2, p);
+    }
+  }
+
+  /// Draws a circle at the world position, `radius` is in world units along the x axis.
+  pub fn draw_circle(&self, pos: &Vf2d, radius: f32, p: Pixel) {
+    let r = Vf2d::new(radius, radius);
+    if self.is_rect_visible(&(*pos - r), &(r * 2.0)) {
+      let (x, y) = self.screen_i(pos);
+      crate::draw_circle(x, y, (radius * self.world_scale.x) as i32, p);
+    }
+  }
+
+  /// Fills a circle at the world position, `radius` is in world units along the x axis.
+  pub fn fill_circle(&self, pos: &Vf2d, radius: f32, p: Pixel) {
+    let r = Vf2d::new(radius, radius);
+    if self.is_rect_visible(&(*pos - r), &(r * 2.0)) {
+      let (x, y) = self.screen_i(pos);
+      crate::fill_circle(x, y, (radius * self.world_scale.x) as i32, p);
+    }
+  }
+
+  /// Draws a rectangle at the world position with the world size.
+  pub fn draw_rect(&self, pos: &Vf2d, size: &Vf2d, p: Pixel) {
+    if self.is_rect_visible(pos, size) {
+      let (x1, y1) = self.screen_i(pos);
+      let (x2, y2) = self.screen_i(&(*pos + *size));
+      crate::draw_rect(x1, y1, x2 - x1, y2 - y1, p);
+    }
+  }
+
+  /// Fills a rectangle at the world position with the world size.
+  pub fn fill_rect(&self, pos: &Vf2d, size: &Vf2d, p: Pixel) {
+    if self.is_rect_visible(pos, size) {
+      let (x1, y1) = self.screen_i(pos);
+      let (x2, y2) = self.screen_i(&(*pos + *size));
+      crate::fill_rect(x1, y1, x2 - x1, y2 - y1, p);
+    }
+  }
+
+  /// Draws a triangle between three world positions.
+  pub fn draw_triangle(&self, pos1: &Vf2d, pos2: &Vf2d, pos3: &Vf2d, p: Pixel) {
+    if self.is_bounds_visible(&[*pos1, *pos2, *pos3]) {
+      let (x1, y1) = self.screen_i(pos1);
+      let (x2, y2) = self.screen_i(pos2);
+      let (x3, y3) = self.screen_i(pos3);
+      crate::draw_triangle(x1, y1, x2, y2, x3, y3, p);
+    }
+  }
+
+  /// Fills a triangle between three world positions.
+  pub fn fill_triangle(&self, pos1: &Vf2d, pos2: &Vf2d, pos3: &Vf2d, p: Pixel) {
+    if self.is_bounds_visible(&[*pos1, *pos2, *pos3]) {
+      let (x1, y1) = self.screen_i(pos1);
+      let (x2, y2) = self.screen_i(pos2);
+      let (x3, y3) = self.screen_i(pos3);
+      crate::fill_triangle(x1, y1, x2, y2, x3, y3, p);
+    }
+  }
+
+  /// Draws an entire sprite at the world position, where one sprite pixel covers `scale`
+  /// world units.
+  pub fn draw_sprite(&self, pos: &Vf2d, sprite: &Sprite, scale: &Vf2d) {
+    let size = Vf2d::new(sprite.width() as f32, sprite.height() as f32);
+    self.draw_partial_sprite(pos, sprite, &Vf2d::new(0.0, 0.0), &size, scale)
+  }
+
+  /// Draws an area of a sprite at the world position, where the selected area is `source_pos` to
+  /// `source_pos + source_size` and one sprite pixel covers `scale` world units.
+  /// The sprite is resampled with nearest neighbour filtering, so any zoom level is supported.
+  pub fn draw_partial_sprite(&self, pos: &Vf2d, sprite: &Sprite, source_pos: &Vf2d, source_size: &Vf2d, scale: &Vf2d) {
+    let world_size = *source_size * *scale;
+    if !self.is_rect_visible(pos, &world_size) {
+      return;
+    }
+    let tl = self.world_to_screen(pos);
+    let br = self.world_to_screen(&(*pos + world_size));
+    let sx = (tl.x.floor() as i32).max(0);
+    let sy = (tl.y.floor() as i32).max(0);
+    let ex = (br.x.ceil() as i32).min(crate::get_draw_target_width());
+    let ey = (br.y.ceil() as i32).min(crate::get_draw_target_height());
+    let step = *source_size / (br - tl);
+    for y in sy..ey {
+      let v = (y as f32 + 0.5 - tl.y) * step.y;
+      if v < 0.0 || v >= source_size.y {
+        continue;
+      }
+      for x in sx..ex {
+        let u = (x as f32 + 0.5 - tl.x) * step.x;
+        if u < 0.0 || u >= source_size.x {
+          continue;
+        }
+        let p = sprite.get_pixel((source_pos.x + u) as i32, (source_pos.y + v) as i32);
+        crate::draw(x, y, p);
+      }
+    }
+  }
+
+  /// Draws string at the world position, `scale` is in world units per font pixel.
+  /// The software font only supports integer scale, so the resulting scale is rounded.
+  pub fn draw_string(&self, pos: &Vf2d, text: &str, col: Pixel, scale: &Vf2d) -> Result<(), Error> {
+    let s = (scale.x * self.world_scale.x).round().max(1.0) as u32;
+    if !self.is_text_visible(pos, text, &(Vf2d::new(s as f32, s as f32) / self.world_scale)) {
+      return Ok(());
+    }
+    let (x, y) = self.screen_i(pos);
+    crate::draw_string_with_scale(x, y, text, col, s)
+  }
+
+  /// Draws a whole decal at the world position with scale and tinting.
+  pub fn draw_decal(&self, pos: &Vf2d, decal: &Decal, scale: &Vf2d, tint: &Pixel) {
+    let sprite = decal.sprite();
+    let size = Vf2d::new(sprite.width() as f32, sprite.height() as f32) * *scale;
+    if self.is_rect_visible(pos, &size) {
+      crate::draw_decal_ext(&self.world_to_screen(pos), decal, &(*scale * self.world_scale), tint);
+    }
+  }
+
+  /// Draws a region of a decal at the world position with scale and tinting.
+  pub fn draw_partial_decal(&self, pos: &Vf2d, decal: &Decal, source_pos: &Vf2d, source_size: &Vf2d, scale: &Vf2d, tint: &Pixel) {
+    if self.is_rect_visible(pos, &(*source_size * *scale)) {
+      let screen_scale = *scale * self.world_scale;
+      crate::draw_partial_decal_ext(&self.world_to_screen(pos), decal, source_pos, source_size, &screen_scale, tint);
+    }
+  }
+
+  /// Draws rotated decal at the world position, `center` is in decal pixels.
+  pub fn draw_rotated_decal(&self, pos: &Vf2d, decal: &Decal, angle: f32, center: &Vf2d, scale: &Vf2d, tint: &Pixel) {
+    // Rotation may extend the decal in any direction, cull against its bounding circle.
+    let sprite = decal.sprite();
+    let size = Vf2d::new(sprite.width() as f32, sprite.height() as f32) * *scale;
+    let reach = size.mag();
+    if self.is_rect_visible(&(*pos - Vf2d::new(reach, reach)), &Vf2d::new(reach * 2.0, reach * 2.0)) {
+      let screen_scale = *scale * self.world_scale;
+      crate::draw_rotated_decal_ext(&self.world_to_screen(pos), decal, angle, center, &screen_scale, tint);
+    }
+  }
+
+  /// Draws warped decal with corners at four world positions.
+  pub fn draw_warped_decal(&self, decal: &Decal, pos: &[Vf2d], tint: &Pixel) {
+    assert_eq!(pos.len(), 4, "Expected 4 positions, received {}", pos.len());
+    if self.is_bounds_visible(pos) {
+      let screen: Vec<Vf2d> = pos.iter().map(|p| self.world_to_screen(p)).collect();
+      crate::draw_warped_decal_ext(decal, &screen, tint);
+    }
+  }
+
+  /// Draws string decal at the world position, `scale` is in world units per font pixel.
+  pub fn draw_string_decal(&self, pos: &Vf2d, text: &str, col: Pixel, scale: &Vf2d) -> Result<(), Error> {
+    if !self.is_text_visible(pos, text, scale) {
+      return Ok(());
+    }
+    crate::draw_string_decal_ext(&self.world_to_screen(pos), text, col, &(*scale * self.world_scale))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn view() -> TransformedView {
+    TransformedView::new(Vf2d::new(200.0, 100.0), Vf2d::new(10.0, 10.0))
+  }
+
+  #[test]
+  fn test_world_screen_conversion() {
+    let mut tv = view();
+    tv.set_world_offset(&Vf2d::new(5.0, 2.0));
+    let screen = tv.world_to_screen(&Vf2d::new(6.0, 4.0));
+    assert_eq!(screen, Vf2d::new(10.0, 20.0));
+    assert_eq!(tv.screen_to_world(&screen), Vf2d::new(6.0, 4.0));
+    assert_eq!(tv.world_tl(), Vf2d::new(5.0, 2.0));
+    assert_eq!(tv.world_br(), Vf2d::new(25.0, 12.0));
+    assert_eq!(tv.world_visible_area(), Vf2d::new(20.0, 10.0));
+  }
+
+  #[test]
+  fn test_zoom_at_screen_pos() {
+    let mut tv = view();
+    let cursor = Vf2d::new(50.0, 40.0);
+    let before = tv.screen_to_world(&cursor);
+    tv.zoom_at_screen_pos(2.0, &cursor);
+    assert_eq!(tv.world_scale(), Vf2d::new(20.0, 20.0));
+    assert!((tv.screen_to_world(&cursor) - before).mag() < 1e-5);
+
+    tv.set_zoom(5.0, &cursor);
+    assert_eq!(tv.world_scale(), Vf2d::new(5.0, 5.0));
+    assert!((tv.screen_to_world(&cursor) - before).mag() < 1e-5);
+  }
+
+  #[test]
+  fn test_pan() {
+    let mut tv = view();
+    tv.start_pan(&Vf2d::new(100.0, 50.0));
+    assert!(tv.is_panning());
+    tv.update_pan(&Vf2d::new(80.0, 50.0));
+    assert_eq!(tv.world_offset(), Vf2d::new(2.0, 0.0));
+    tv.end_pan(&Vf2d::new(80.0, 40.0));
+    assert_eq!(tv.world_offset(), Vf2d::new(2.0, 1.0));
+    assert!(!tv.is_panning());
+    tv.update_pan(&Vf2d::new(0.0, 0.0));
+    assert_eq!(tv.world_offset(), Vf2d::new(2.0, 1.0));
+  }
+
+  #[test]
+  fn test_visibility() {
+    let tv = view();
+    assert!(tv.is_point_visible(&Vf2d::new(0.0, 0.0)));
+    assert!(!tv.is_point_visible(&Vf2d::new(20.0, 5.0)));
+    assert!(tv.is_rect_visible(&Vf2d::new(-1.0, -1.0), &Vf2d::new(2.0, 2.0)));
+    assert!(!tv.is_rect_visible(&Vf2d::new(-3.0, -3.0), &Vf2d::new(2.0, 2.0)));
+    assert!(!tv.is_rect_visible(&Vf2d::new(21.0, 0.0), &Vf2d::new(2.0, 2.0)));
+  }
+
+  #[test]
+  fn test_text_visibility() {
+    let tv = view();
+    let scale = Vf2d::new(0.1, 0.1);
+    assert!(tv.is_text_visible(&Vf2d::new(1.0, 1.0), "A", &scale));
+    assert!(!tv.is_text_visible(&Vf2d::new(21.0, 1.0), "A", &scale));
+    // Long lines reach into the view from the left.
+    assert!(!tv.is_text_visible(&Vf2d::new(-1.7, 1.0), "AB", &scale));
+    assert!(tv.is_text_visible(&Vf2d::new(-1.7, 1.0), "ABC", &scale));
+    // The first rows are above the view, the last one is inside.
+    assert!(!tv.is_text_visible(&Vf2d::new(1.0, -1.6), "A", &scale));
+    assert!(tv.is_text_visible(&Vf2d::new(1.0, -1.6), "\n\nA", &scale));
+  }
+}
@@ -0,0 +1,629 @@
+//! Tweening and easing.
+//!
+//! A [`Tween`](Tween) animates an `f32`, [`Vf2d`](crate::Vf2d) or [`Pixel`](crate::Pixel)
+//! between two values along an [`Easing`](Easing) curve. Tweens, [`Delay`](Delay)s and
+//! [`Callback`](Callback)s implement [`Playable`](Playable) and can be chained into a
+//! [`Sequence`](Sequence) or run together in a [`Parallel`](Parallel) group. Everything is
+//! advanced by the frame's `elapsed_time`.
+//!
+//! Tweens can write into a shared `Rc<Cell<T>>`, so the animated value can be read while the
+//! tween itself is owned by a sequence:
+//!
+//! ```no_run
+//! # extern crate olc_pixel_game_engine;
+//! # use crate::olc_pixel_game_engine as olc;
+//! use std::cell::Cell;
+//! use std::rc::Rc;
+//! use olc::tween::{Easing, Playable, Sequence, Tween};
+//!
+//! let pos = Rc::new(Cell::new(olc::Vf2d::new(0.0, 0.0)));
+//! let fade = Rc::new(Cell::new(olc::WHITE));
+//! let mut intro = Sequence::new()
+//!   .then(Tween::new(olc::Vf2d::new(0.0, 0.0), olc::Vf2d::new(100.0, 50.0), 1.0)
+//!     .easing(Easing::BackOut)
+//!     .bind(&pos))
+//!   .delay(0.5)
+//!   .then(Tween::new(olc::WHITE, olc::BLANK, 0.25).bind(&fade))
+//!   .call(|| println!("intro finished"));
+//!
+//! // In `on_user_update`:
+//! # let elapsed_time = 0.016;
+//! intro.update(elapsed_time);
+//! let p = pos.get();
+//! ```
+
+use std::cell::Cell;
+use std::f32::consts::PI;
+use std::rc::Rc;
+
+use crate::{Pixel, Vf2d};
+
+/// Easing curve mapping linear progress 0.0..=1.0 onto eased progress.
+/// All curves start at 0.0 and end at 1.0, `Back` and `Elastic` overshoot in between.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Easing {
+  #[default]
+  Linear,
+  QuadIn,
+  QuadOut,
+  QuadInOut,
+  CubicIn,
+  CubicOut,
+  CubicInOut,
+  QuartIn,
+  QuartOut,
+  QuartInOut,
+  QuintIn,
+  QuintOut,
+  QuintInOut,
+  SineIn,
+  SineOut,
+  SineInOut,
+  ExpoIn,
+  ExpoOut,
+  ExpoInOut,
+  CircIn,
+  CircOut,
+  CircInOut,
+  BackIn,
+  BackOut,
+  BackInOut,
+  ElasticIn,
+  ElasticOut,
+  ElasticInOut,
+  BounceIn,
+  BounceOut,
+  BounceInOut
+}
+
+// Builds ease out and ease in-out from an ease in curve.
+fn out(f: fn(f32) -> f32, t: f32) -> f32 {
+  1.0 - f(1.0 - t)
+}
+
+fn in_out(f: fn(f32) -> f32, t: f32) -> f32 {
+  if t < 0.5 { f(2.0 * t) / 2.0 } else { 1.0 - f(2.0 - 2.0 * t) / 2.0 }
+}
+
+fn sine(t: f32) -> f32 {
+  1.0 - (t * PI / 2.0).cos()
+}
+
+fn expo(t: f32) -> f32 {
+  if t <= 0.0 { 0.0 } else { 2f32.powf(10.0 * t - 10.0) }
+}
+
+fn circ(t: f32) -> f32 {
+  1.0 - (1.0 - t * t).max(0.0).sqrt()
+}
+
+fn back(t: f32) -> f32 {
+  const C1: f32 = 1.70158;
+  (C1 + 1.0) * t * t * t - C1 * t * t
+}
+
+fn elastic(t: f32) -> f32 {
+  if t <= 0.0 || t >= 1.0 {
+    return t.clamp(0.0, 1.0);
+  }
+  -(2f32.powf(10.0 * t - 10.0)) * ((t * 10.0 - 10.75) * (2.0 * PI / 3.0)).sin()
+}
+
+fn bounce(t: f32) -> f32 {
+  out(bounce_out, t)
+}
+
+fn bounce_out(t: f32) -> f32 {
+  const N1: f32 = 7.5625;
+  const D1: f32 = 2.75;
+  if t < 1.0 / D1 {
+    N1 * t * t
+  } else if t < 2.0 / D1 {
+    let t = t - 1.5 / D1;
+    N1 * t * t + 0.75
+  } else if t < 2.5 / D1 {
+    let t = t - 2.25 / D1;
+    N1 * t * t + 0.9375
+  } else {
+    let t = t - 2.625 / D1;
+    N1 * t * t + 0.984375
+  }
+}
+
+impl Easing {
+  /// Returns eased progress for linear progress `t`, which is clamped to 0.0..=1.0.
+  pub fn apply(self, t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    match self {
+      Easing::Linear => t,
+      Easing::QuadIn => t * t,
+      Easing::QuadOut => out(|t| t * t, t),
+      Easing::QuadInOut => in_out(|t| t * t, t),
+      Easing::CubicIn => t * t * t,
+      Easing::CubicOut => out(|t| t * t * t, t),
+      Easing::CubicInOut => in_out(|t| t * t * t, t),
+      Easing::QuartIn => t.powi(4),
+      Easing::QuartOut => out(|t| t.powi(4), t),
+      Easing::QuartInOut => in_out(|t| t.powi(4), t),
+      Easing::QuintIn => t.powi(5),
+      Easing::QuintOut => out(|t| t.powi(5), t),
+      Easing::QuintInOut => in_out(|t| t.powi(5), t),
+      Easing::SineIn => sine(t),
+      Easing::SineOut => out(sine, t),
+      Easing::SineInOut => in_out(sine, t),
+      Easing::ExpoIn => expo(t),
+      Easing::ExpoOut => if t >= 1.0 { 1.0 } else { out(expo, t) },
+      Easing::ExpoInOut => if t >= 1.0 { 1.0 } else { in_out(expo, t) },
+      Easing::CircIn => circ(t),
+      Easing::CircOut => out(circ, t),
+      Easing::CircInOut => in_out(circ, t),
+      Easing::BackIn => back(t),
+      Easing::BackOut => out(back, t),
+      Easing::BackInOut => in_out(back, t),
+      Easing::ElasticIn => elastic(t),
+      Easing::ElasticOut => out(elastic, t),
+      Easing::ElasticInOut => in_out(elastic, t),
+      Easing::BounceIn => bounce(t),
+      Easing::BounceOut => bounce_out(t),
+      Easing::BounceInOut => in_out(bounce, t)
+    }
+  }
+}
+
+/// Value that can be interpolated by a tween, `t` may be outside of 0.0..=1.0 for overshooting
+/// easing curves.
+pub trait Tweenable: Copy {
+  /// Returns value between `a` and `b` at `t`.
+  fn tween(a: Self, b: Self, t: f32) -> Self;
+}
+
+impl Tweenable for f32 {
+  fn tween(a: Self, b: Self, t: f32) -> Self {
+    a + (b - a) * t
+  }
+}
+
+impl Tweenable for Vf2d {
+  fn tween(a: Self, b: Self, t: f32) -> Self {
+    a.lerp(b, t)
+  }
+}
+
+/// Colour channels are clamped, so overshooting curves saturate instead of wrapping.
+impl Tweenable for Pixel {
+  fn tween(a: Self, b: Self, t: f32) -> Self {
+    crate::pixel_lerp(a, b, t)
+  }
+}
+
+/// Anything that is advanced by elapsed time.
+pub trait Playable {
+  /// Advances by `elapsed_time` seconds and returns the time left over after finishing,
+  /// which is 0.0 while still running.
+  fn update(&mut self, elapsed_time: f32) -> f32;
+  /// Returns true once finished.
+  fn is_finished(&self) -> bool;
+  /// Rewinds to the start.
+  fn reset(&mut self);
+}
+
+/// Animates a value from `from` to `to` over `duration` seconds.
+pub struct Tween<T: Tweenable> {
+  from: T,
+  to: T,
+  duration: f32,
+  elapsed: f32,
+  easing: Easing,
+  target: Option<Rc<Cell<T>>>,
+  on_complete: Option<Box<dyn FnMut()>>,
+  completed: bool
+}
+
+impl<T: Tweenable> Tween<T> {
+  /// Creates linear tween between two values.
+  pub fn new(from: T, to: T, duration: f32) -> Self {
+    Self {
+      from,
+      to,
+      duration: duration.max(0.0),
+      elapsed: 0.0,
+      easing: Easing::Linear,
+      target: None,
+      on_complete: None,
+      completed: false
+    }
+  }
+
+  /// Sets easing curve.
+  pub fn easing(mut self, easing: Easing) -> Self {
+    self.easing = easing;
+    self
+  }
+
+  /// Writes the value into `target` on every update.
+  pub fn bind(mut self, target: &Rc<Cell<T>>) -> Self {
+    self.target = Some(target.clone());
+    self
+  }
+
+  /// Sets function called once when the tween finishes.
+  pub fn on_complete(mut self, f: impl FnMut() + 'static) -> Self {
+    self.on_complete = Some(Box::new(f));
+    self
+  }
+
+  /// Returns the current value.
+  pub fn value(&self) -> T {
+    T::tween(self.from, self.to, self.easing.apply(self.progress()))
+  }
+
+  /// Returns linear progress in 0.0..=1.0.
+  pub fn progress(&self) -> f32 {
+    if self.duration <= 0.0 { 1.0 } else { (self.elapsed / self.duration).min(1.0) }
+  }
+
+  /// Returns duration in seconds.
+  pub fn duration(&self) -> f32 {
+    self.duration
+  }
+}
+
+impl<T: Tweenable> Playable for Tween<T> {
+  fn update(&mut self, elapsed_time: f32) -> f32 {
+    if self.completed {
+      return elapsed_time;
+    }
+    self.elapsed += elapsed_time;
+    let left = (self.elapsed - self.duration).max(0.0);
+    self.elapsed = self.elapsed.min(self.duration);
+    if let Some(target) = &self.target {
+      target.set(self.value());
+    }
+    if self.elapsed >= self.duration {
+      self.completed = true;
+      if let Some(f) = &mut self.on_complete {
+        f();
+      }
+    }
+    left
+  }
+
+  fn is_finished(&self) -> bool {
+    self.completed
+  }
+
+  fn reset(&mut self) {
+    self.elapsed = 0.0;
+    self.completed = false;
+  }
+}
+
+/// Waits for `duration` seconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Delay {
+  duration: f32,
+  elapsed: f32
+}
+
+impl Delay {
+  /// Creates delay of `duration` seconds.
+  pub fn new(duration: f32) -> Self {
+    Self { duration: duration.max(0.0), elapsed: 0.0 }
+  }
+}
+
+impl Playable for Delay {
+  fn update(&mut self, elapsed_time: f32) -> f32 {
+    self.elapsed += elapsed_time;
+    let left = (self.elapsed - self.duration).max(0.0);
+    self.elapsed = self.elapsed.min(self.duration);
+    left
+  }
+
+  fn is_finished(&self) -> bool {
+    self.elapsed >= self.duration
+  }
+
+  fn reset(&mut self) {
+    self.elapsed = 0.0;
+  }
+}
+
+/// Calls a function once, taking no time.
+pub struct Callback {
+  f: Box<dyn FnMut()>,
+  called: bool
+}
+
+impl Callback {
+  /// Creates callback from the function.
+  pub fn new(f: impl FnMut() + 'static) -> Self {
+    Self { f: Box::new(f), called: false }
+  }
+}
+
+impl Playable for Callback {
+  fn update(&mut self, elapsed_time: f32) -> f32 {
+    if !self.called {
+      self.called = true;
+      (self.f)();
+    }
+    elapsed_time
+  }
+
+  fn is_finished(&self) -> bool {
+    self.called
+  }
+
+  fn reset(&mut self) {
+    self.called = false;
+  }
+}
+
+/// Plays items one after another, time left over by one item is passed on to the next.
+#[derive(Default)]
+pub struct Sequence {
+  items: Vec<Box<dyn Playable>>,
+  current: usize,
+  repeats: u32,
+  played: u32
+}
+
+impl Sequence {
+  /// Creates empty sequence.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Appends an item.
+  pub fn then(mut self, item: impl Playable + 'static) -> Self {
+    self.items.push(Box::new(item));
+    self
+  }
+
+  /// Appends a delay of `duration` seconds.
+  pub fn delay(self, duration: f32) -> Self {
+    self.then(Delay::new(duration))
+  }
+
+  /// Appends a function call.
+  pub fn call(self, f: impl FnMut() + 'static) -> Self {
+    self.then(Callback::new(f))
+  }
+
+  /// Plays the sequence `count` more times after the first play.
+  pub fn repeat(mut self, count: u32) -> Self {
+    self.repeats = count;
+    self
+  }
+
+  /// Plays the sequence forever.
+  pub fn looped(self) -> Self {
+    self.repeat(u32::MAX)
+  }
+}
+
+impl Playable for Sequence {
+  fn update(&mut self, elapsed_time: f32) -> f32 {
+    let mut left = elapsed_time;
+    loop {
+      let pass_start = left;
+      while self.current < self.items.len() {
+        left = self.items[self.current].update(left);
+        if !self.items[self.current].is_finished() {
+          return 0.0;
+        }
+        self.current += 1;
+      }
+      if self.played >= self.repeats {
+        return left;
+      }
+      if self.repeats != u32::MAX {
+        self.played += 1;
+      }
+      self.current = 0;
+      self.items.iter_mut().for_each(|i| i.reset());
+      // A pass that takes no time would otherwise repeat forever within one update.
+      if left <= 0.0 || left >= pass_start {
+        return 0.0;
+      }
+    }
+  }
+
+  fn is_finished(&self) -> bool {
+    self.current >= self.items.len() && self.played >= self.repeats
+  }
+
+  fn reset(&mut self) {
+    self.current = 0;
+    self.played = 0;
+    self.items.iter_mut().for_each(|i| i.reset());
+  }
+}
+
+/// Plays items at the same time, finishes when all of them have finished.
+#[derive(Default)]
+pub struct Parallel {
+  items: Vec<Box<dyn Playable>>
+}
+
+impl Parallel {
+  /// Creates empty group.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Adds an item.
+  pub fn with(mut self, item: impl Playable + 'static) -> Self {
+    self.items.push(Box::new(item));
+    self
+  }
+}
+
+impl Playable for Parallel {
+  fn update(&mut self, elapsed_time: f32) -> f32 {
+    let mut left = elapsed_time;
+    for item in self.items.iter_mut() {
+      if !item.is_finished() {
+        left = left.min(item.update(elapsed_time));
+      }
+    }
+    if self.is_finished() { left } else { 0.0 }
+  }
+
+  fn is_finished(&self) -> bool {
+    self.items.iter().all(|i| i.is_finished())
+  }
+
+  fn reset(&mut self) {
+    self.items.iter_mut().for_each(|i| i.reset());
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const ALL: [Easing; 31] = [
+    Easing::Linear,
+    Easing::QuadIn,
+    Easing::QuadOut,
+    Easing::QuadInOut,
+    Easing::CubicIn,
+    Easing::CubicOut,
+    Easing::CubicInOut,
+    Easing::QuartIn,
+    Easing::QuartOut,
+    Easing::QuartInOut,
+    Easing::QuintIn,
+    Easing::QuintOut,
+    Easing::QuintInOut,
+    Easing::SineIn,
+    Easing::SineOut,
+    Easing::SineInOut,
+    Easing::ExpoIn,
+    Easing::ExpoOut,
+    Easing::ExpoInOut,
+    Easing::CircIn,
+    Easing::CircOut,
+    Easing::CircInOut,
+    Easing::BackIn,
+    Easing::BackOut,
+    Easing::BackInOut,
+    Easing::ElasticIn,
+    Easing::ElasticOut,
+    Easing::ElasticInOut,
+    Easing::BounceIn,
+    Easing::BounceOut,
+    Easing::BounceInOut
+  ];
+
+  #[test]
+  fn test_easing_endpoints() {
+    for e in ALL.iter() {
+      assert!(e.apply(0.0).abs() < 1e-3, "{:?} at 0 = {}", e, e.apply(0.0));
+      assert!((e.apply(1.0) - 1.0).abs() < 1e-3, "{:?} at 1 = {}", e, e.apply(1.0));
+      assert!((e.apply(-1.0) - e.apply(0.0)).abs() < 1e-6);
+    }
+    assert_eq!(Easing::QuadIn.apply(0.5), 0.25);
+    assert_eq!(Easing::QuadOut.apply(0.5), 0.75);
+    assert_eq!(Easing::CubicInOut.apply(0.5), 0.5);
+    assert!(Easing::BackIn.apply(0.2) < 0.0);
+    assert!(Easing::BackOut.apply(0.8) > 1.0);
+    assert!((Easing::BounceOut.apply(1.0 / 2.75) - 1.0).abs() < 1e-5);
+  }
+
+  #[test]
+  fn test_tween_values() {
+    let mut t = Tween::new(0.0f32, 10.0, 2.0);
+    assert_eq!(t.value(), 0.0);
+    assert_eq!(t.update(1.0), 0.0);
+    assert_eq!(t.value(), 5.0);
+    assert!(!t.is_finished());
+    assert_eq!(t.update(1.5), 0.5);
+    assert_eq!(t.value(), 10.0);
+    assert!(t.is_finished());
+    t.reset();
+    assert_eq!(t.value(), 0.0);
+
+    let v = Tween::new(Vf2d::new(0.0, 0.0), Vf2d::new(4.0, 8.0), 1.0).easing(Easing::QuadIn);
+    let mut v = v;
+    v.update(0.5);
+    assert_eq!(v.value(), Vf2d::new(1.0, 2.0));
+
+    let mut p = Tween::new(crate::BLACK, crate::WHITE, 1.0);
+    p.update(0.5);
+    assert_eq!(p.value(), Pixel::rgb(128, 128, 128));
+  }
+
+  #[test]
+  fn test_tween_bind_and_complete() {
+    let target = Rc::new(Cell::new(1.0f32));
+    let done = Rc::new(Cell::new(0));
+    let d = done.clone();
+    let mut t = Tween::new(2.0f32, 4.0, 1.0).bind(&target).on_complete(move || d.set(d.get() + 1));
+    assert_eq!(target.get(), 1.0);
+    t.update(0.5);
+    assert_eq!(target.get(), 3.0);
+    t.update(1.0);
+    t.update(1.0);
+    assert_eq!(target.get(), 4.0);
+    assert_eq!(done.get(), 1);
+  }
+
+  #[test]
+  fn test_sequence() {
+    let a = Rc::new(Cell::new(0.0f32));
+    let calls = Rc::new(Cell::new(0));
+    let c = calls.clone();
+    let mut s = Sequence::new()
+      .then(Tween::new(0.0f32, 1.0, 1.0).bind(&a))
+      .delay(1.0)
+      .call(move || c.set(c.get() + 1))
+      .then(Tween::new(1.0f32, 3.0, 1.0).bind(&a));
+    s.update(1.5);
+    assert_eq!(a.get(), 1.0);
+    assert_eq!(calls.get(), 0);
+    s.update(1.0);
+    assert_eq!(calls.get(), 1);
+    assert_eq!(a.get(), 2.0);
+    assert!(!s.is_finished());
+    assert_eq!(s.update(1.0), 0.5);
+    assert!(s.is_finished());
+    assert_eq!(a.get(), 3.0);
+    assert_eq!(calls.get(), 1);
+  }
+
+  #[test]
+  fn test_sequence_repeat() {
+    let calls = Rc::new(Cell::new(0));
+    let c = calls.clone();
+    let mut s = Sequence::new().delay(1.0).call(move || c.set(c.get() + 1)).repeat(2);
+    s.update(2.5);
+    assert_eq!(calls.get(), 2);
+    assert!(!s.is_finished());
+    s.update(1.0);
+    assert_eq!(calls.get(), 3);
+    assert!(s.is_finished());
+
+    let c = calls.clone();
+    let mut forever = Sequence::new().call(move || c.set(c.get() + 1)).looped();
+    forever.update(1.0);
+    assert!(!forever.is_finished());
+  }
+
+  #[test]
+  fn test_parallel() {
+    let a = Rc::new(Cell::new(0.0f32));
+    let b = Rc::new(Cell::new(0.0f32));
+    let mut p = Parallel::new()
+      .with(Tween::new(0.0f32, 1.0, 1.0).bind(&a))
+      .with(Tween::new(0.0f32, 1.0, 2.0).bind(&b));
+    assert_eq!(p.update(1.0), 0.0);
+    assert_eq!(a.get(), 1.0);
+    assert_eq!(b.get(), 0.5);
+    assert!(!p.is_finished());
+    assert_eq!(p.update(1.5), 0.5);
+    assert!(p.is_finished());
+  }
+}
@@ -0,0 +1,287 @@
+//! Minimal XML parser used to import documents exported by other tools.
+//!
+//! Parses elements, attributes, character data, CDATA sections and the predefined and numeric
+//! character references. The XML declaration, comments, processing instructions and the
+//! document type declaration are skipped, namespaces are not interpreted.
+
+use crate::Error;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Element {
+  pub name: String,
+  pub attributes: Vec<(String, String)>,
+  pub children: Vec<Element>,
+  // Character data of the element, without the text of its children.
+  pub text: String
+}
+
+impl Element {
+  pub fn attr(&self, name: &str) -> Option<&str> {
+    self.attributes.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+  }
+
+  // Returns the first child element with the name.
+  pub fn child(&self, name: &str) -> Option<&Element> {
+    self.children.iter().find(|c| c.name == name)
+  }
+
+  pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+    self.children.iter().filter(move |c| c.name == name)
+  }
+}
+
+// Nesting limit, guards against stack overflow on malicious input.
+const MAX_DEPTH: usize = 128;
+
+struct Parser<'a> {
+  text: &'a str,
+  pos: usize,
+  depth: usize
+}
+
+/// Parses the XML document and returns its root element.
+/// Returns error with the line and column of the first invalid character.
+pub(crate) fn parse(text: &str) -> Result<Element, Error> {
+  let mut p = Parser { text, pos: 0, depth: 0 };
+  p.skip_misc()?;
+  if !p.rest().starts_with('<') {
+    return Err(p.error("expected the root element"));
+  }
+  let root = p.element()?;
+  p.skip_misc()?;
+  if p.pos < p.text.len() {
+    return Err(p.error("unexpected content after the root element"));
+  }
+  Ok(root)
+}
+
+fn is_name_char(c: char) -> bool {
+  c.is_alphanumeric() || matches!(c, '_' | ':' | '-' | '.')
+}
+
+impl<'a> Parser<'a> {
+  fn error(&self, msg: &str) -> Error {
+    let before = &self.text[..self.pos.min(self.text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|&c| c != '\n').count() + 1;
+    Error { msg: format!("Failed to parse XML: {} at line {}, column {}", msg, line, column) }
+  }
+
+  fn rest(&self) -> &'a str {
+    &self.text[self.pos..]
+  }
+
+  fn skip_space(&mut self) {
+    let rest = self.rest();
+    self.pos += rest.len() - rest.trim_start_matches([' ', '\t', '\n', '\r']).len();
+  }
+
+  // Skips past the terminator, returns the skipped text.
+  fn skip_past(&mut self, terminator: &str, what: &str) -> Result<&'a str, Error> {
+    match self.rest().find(terminator) {
+      Some(i) => {
+        let skipped = &self.text[self.pos..self.pos + i];
+        self.pos += i + terminator.len();
+        Ok(skipped)
+      }
+      None => Err(self.error(&format!("unterminated {}", what)))
+    }
+  }
+
+  // Skips white space, comments, processing instructions and the document type declaration.
+  fn skip_misc(&mut self) -> Result<(), Error> {
+    loop {
+      self.skip_space();
+      if self.rest().starts_with("<?") {
+        self.skip_past("?>", "processing instruction")?;
+      } else if self.rest().starts_with("<!--") {
+        self.skip_past("-->", "comment")?;
+      } else if self.rest().starts_with("<!DOCTYPE") {
+        self.doctype()?;
+      } else {
+        return Ok(());
+      }
+    }
+  }
+
+  fn doctype(&mut self) -> Result<(), Error> {
+    // The internal subset may contain '>' inside of brackets.
+    let mut bracket = false;
+    for (i, c) in self.rest().char_indices() {
+      match c {
+        '[' => bracket = true,
+        ']' => bracket = false,
+        '>' if !bracket => {
+          self.pos += i + 1;
+          return Ok(());
+        }
+        _ => {}
+      }
+    }
+    Err(self.error("unterminated document type declaration"))
+  }
+
+  fn name(&mut self) -> Result<String, Error> {
+    let rest = self.rest();
+    let len = rest.len() - rest.trim_start_matches(is_name_char).len();
+    if len == 0 {
+      return Err(self.error("expected a name"));
+    }
+    self.pos += len;
+    Ok(rest[..len].to_string())
+  }
+
+  fn expect(&mut self, s: &str) -> Result<(), Error> {
+    if !self.rest().starts_with(s) {
+      return Err(self.error(&format!("expected '{}'", s)));
+    }
+    self.pos += s.len();
+    Ok(())
+  }
+
+  fn element(&mut self) -> Result<Element, Error> {
+    if self.depth == MAX_DEPTH {
+      return Err(self.error("nesting is too deep"));
+    }
+    self.pos += 1;
+    let mut element = Element { name: self.name()?, ..Element::default() };
+    loop {
+      self.skip_space();
+      if self.rest().starts_with("/>") {
+        self.pos += 2;
+        return Ok(element);
+      }
+      if self.rest().starts_with('>') {
+        self.pos += 1;
+        break;
+      }
+      let name = self.name()?;
+      self.skip_space();
+      self.expect("=")?;
+      self.skip_space();
+      let quote = match self.rest().chars().next() {
+        Some(q) if q == '"' || q == '\'' => q,
+        _ => return Err(self.error("expected a quoted attribute value"))
+      };
+      self.pos += 1;
+      let start = self.pos;
+      let raw = self.skip_past(&quote.to_string(), "attribute value")?;
+      if raw.contains('<') {
+        self.pos = start + raw.find('<').unwrap_or_default();
+        return Err(self.error("'<' in attribute value"));
+      }
+      let value = self.unescape(raw, start)?;
+      if element.attr(&name).is_some() {
+        self.pos = start;
+        return Err(self.error(&format!("duplicate attribute '{}'", name)));
+      }
+      element.attributes.push((name, value));
+    }
+    self.depth += 1;
+    loop {
+      let start = self.pos;
+      let text = match self.rest().find('<') {
+        Some(i) => &self.text[start..start + i],
+        None => return Err(self.error(&format!("unterminated element '{}'", element.name)))
+      };
+      self.pos += text.len();
+      element.text += &self.unescape(text, start)?;
+      if self.rest().starts_with("</") {
+        self.pos += 2;
+        let name = self.name()?;
+        if name != element.name {
+          return Err(self.error(&format!("expected '</{}>'", element.name)));
+        }
+        self.skip_space();
+        self.expect(">")?;
+        self.depth -= 1;
+        return Ok(element);
+      } else if self.rest().starts_with("<!--") {
+        self.skip_past("-->", "comment")?;
+      } else if self.rest().starts_with("<![CDATA[") {
+        self.pos += 9;
+        let data = self.skip_past("]]>", "CDATA section")?;
+        element.text += data;
+      } else if self.rest().starts_with("<?") {
+        self.skip_past("?>", "processing instruction")?;
+      } else {
+        element.children.push(self.element()?);
+      }
+    }
+  }
+
+  // Replaces character references, `start` is the position of `s` for error messages.
+  fn unescape(&mut self, s: &str, start: usize) -> Result<String, Error> {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find('&') {
+      out += &rest[..i];
+      let reference = &rest[i + 1..];
+      let end = match reference.find(';') {
+        Some(end) => end,
+        None => {
+          self.pos = start + (s.len() - rest.len()) + i;
+          return Err(self.error("unterminated character reference"));
+        }
+      };
+      let c = match &reference[..end] {
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "amp" => Some('&'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        r if r.starts_with("#x") => u32::from_str_radix(&r[2..], 16).ok().and_then(char::from_u32),
+        r if r.starts_with('#') => r[1..].parse().ok().and_then(char::from_u32),
+        _ => None
+      };
+      match c {
+        Some(c) => out.push(c),
+        None => {
+          self.pos = start + (s.len() - rest.len()) + i;
+          return Err(self.error("invalid character reference"));
+        }
+      }
+      rest = &reference[end + 1..];
+    }
+    out += rest;
+    Ok(out)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse() {
+    let doc = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE map [ <!ENTITY x "y"> ]>
+<!-- comment -->
+<map a="1" b='&lt;&#65;&#x42;&amp;'>
+  <layer name="one"/>
+  <layer name="two">text<![CDATA[<raw>]]><!-- skipped --> more</layer>
+  <other />
+</map>
+"#;
+    let root = parse(doc).unwrap();
+    assert_eq!(root.name, "map");
+    assert_eq!(root.attr("a"), Some("1"));
+    assert_eq!(root.attr("b"), Some("<AB&"));
+    assert_eq!(root.attr("c"), None);
+    let names: Vec<_> = root.children_named("layer").filter_map(|l| l.attr("name")).collect();
+    assert_eq!(names, ["one", "two"]);
+    assert_eq!(root.children_named("layer").nth(1).unwrap().text, "text<raw> more");
+    assert!(root.child("other").is_some());
+    assert!(root.child("missing").is_none());
+  }
+
+  #[test]
+  fn test_parse_errors() {
+    for text in ["", "text", "<a>", "<a></b>", "<a b=1/>", "<a b=\"1\" b=\"2\"/>", "<a>&bad;</a>", "<a/><b/>", "<a><!-- </a>"].iter() {
+      assert!(parse(text).is_err(), "{}", text);
+    }
+    let err = parse("<a>\n  <b c=\"x\" d></b>\n</a>").unwrap_err();
+    assert!(format!("{}", err).contains("line 2, column 13"), "{}", err);
+    assert!(parse(&"<a>".repeat(1000)).is_err());
+  }
+}